@@ -70,13 +70,17 @@ fn demonstrate_custom_physics() {
     let physics = PhysicsConfig {
         gravity: 160, // Double the default gravity
         ground_level: 18000,
-        momentum_decay_percent: 70, // Faster decay (less slidey)
+        ground_friction_percent: 70, // Faster decay (less slidey)
+        air_drag_percent: 70,
         knockback_threshold: -100,
     };
 
     println!("  Custom High Gravity, Fast Decay:");
     println!("    - Gravity: {}", physics.gravity);
-    println!("    - Momentum decay: {}%", physics.momentum_decay_percent);
+    println!(
+        "    - Ground friction: {}%, air drag: {}%",
+        physics.ground_friction_percent, physics.air_drag_percent
+    );
     println!("    - Effect: Faster falling, less momentum carry");
 
     // Can also use preset physics
@@ -91,6 +95,7 @@ fn demonstrate_custom_input() {
     let input = InputConfig {
         buffer_size: 30,
         detection_window: 25, // Very large window for easier specials
+        ..Default::default()
     };
 
     println!("  Lenient Motion Detection:");
@@ -102,6 +107,15 @@ fn demonstrate_custom_input() {
     println!("  Strict Motion Detection:");
     println!("    - Detection window: {} frames", strict.detection_window);
     println!("    - Effect: Requires precise input timing");
+
+    // Offline setup where player 2's pad reports 2 frames faster
+    let offline = InputConfig {
+        latency_offset: [0, 2],
+        ..Default::default()
+    };
+    println!("  Device Latency Compensation:");
+    println!("    - P2 offset: {} frames", offline.latency_offset[1]);
+    println!("    - Effect: Both players' inputs land on the same frame");
 }
 
 fn demonstrate_custom_rules() {
@@ -110,6 +124,7 @@ fn demonstrate_custom_rules() {
         starting_health: 500,
         time_limit_frames: 1800, // 30 seconds at 60 FPS
         rounds_to_win: 1,
+        ..Default::default()
     };
 
     println!("  Quick Match:");
@@ -137,6 +152,7 @@ fn demonstrate_complete_custom() {
         starting_health: 1500,
         time_limit_frames: 5400, // 90 seconds
         rounds_to_win: 2,
+        ..Default::default()
     };
 
     let config = EngineConfig::new(custom_physics, custom_input, custom_game);
@@ -150,19 +166,17 @@ fn demonstrate_complete_custom() {
         config.input.detection_window
     );
 
-    // Note: In the current engine implementation, configs are for reference only.
-    // A future version would allow passing config to Engine::new() to apply these settings.
-    println!("\n  Note: Configuration system is ready for future integration with Engine.");
-    println!("  Currently serves as a blueprint for custom game modes.");
+    // Note: Engine::with_config() accepts this config, but only some settings
+    // (physics tuning and the wakeup delay) are applied by the current engine.
+    println!("\n  Note: Configuration system is partially integrated with Engine.");
+    println!("  Pass it to Engine::with_config() to apply the supported settings.");
 }
 
 #[allow(dead_code)]
-fn run_match_with_config(_config: EngineConfig) {
-    // This demonstrates how configs could be used in a future version
+fn run_match_with_config(config: EngineConfig) {
     println!("\nRunning match with custom config...");
 
-    let mut engine = Engine::new();
-    // Future: let mut engine = Engine::with_config(config);
+    let mut engine = Engine::with_config(config);
 
     engine.init_match();
 
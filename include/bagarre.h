@@ -0,0 +1,74 @@
+/* Generated by bagarre::ffi::c_header(); do not edit. */
+#ifndef BAGARRE_H
+#define BAGARRE_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define BAGARRE_API_VERSION 1
+
+typedef struct BagarreEngine BagarreEngine;
+typedef struct BagarreSnapshot BagarreSnapshot;
+
+typedef struct BagarreFighter {
+    int32_t x;
+    int32_t y;
+    int32_t health;
+    int32_t max_health;
+    int32_t meter;
+    int32_t facing;
+    uint32_t state;
+    uint32_t state_frame;
+    uint32_t combo_hits;
+} BagarreFighter;
+
+typedef struct BagarreMatch {
+    uint64_t frame;
+    uint32_t result;
+    uint32_t round;
+    uint32_t p1_rounds;
+    uint32_t p2_rounds;
+    BagarreFighter fighters[2];
+} BagarreMatch;
+
+typedef struct BagarreLimits {
+    uint32_t max_players;
+    uint32_t max_entities;
+    uint32_t max_projectiles;
+    uint32_t max_hitboxes;
+    uint32_t max_hurtboxes;
+    uint32_t max_collisions_per_frame;
+    uint32_t max_states;
+    uint32_t max_resources;
+    uint32_t input_buffer_size;
+    uint32_t motion_detection_window;
+    uint32_t max_input_delay;
+    uint32_t rollback_window;
+    uint32_t max_rewind_frames;
+    int32_t units_per_pixel;
+    int32_t max_coordinate;
+    uint32_t ticks_per_second;
+    uint32_t engine_data_version;
+    uint32_t replay_format_version;
+} BagarreLimits;
+
+uint32_t bagarre_api_version(void);
+int32_t bagarre_get_limits(BagarreLimits *out);
+BagarreEngine *bagarre_engine_new(void);
+void bagarre_engine_free(BagarreEngine *engine);
+void bagarre_init_match(BagarreEngine *engine, uint64_t seed);
+void bagarre_tick(BagarreEngine *engine, uint8_t p1_input, uint8_t p2_input);
+int32_t bagarre_get_match(const BagarreEngine *engine, BagarreMatch *out);
+BagarreSnapshot *bagarre_snapshot_save(const BagarreEngine *engine);
+int32_t bagarre_snapshot_load(BagarreEngine *engine, const BagarreSnapshot *snapshot);
+void bagarre_snapshot_free(BagarreSnapshot *snapshot);
+uint64_t bagarre_state_hash(const BagarreEngine *engine);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BAGARRE_H */
@@ -0,0 +1,213 @@
+//! Attract mode
+//!
+//! Loops a playlist of replays for demo screens. Replays are either scripted
+//! and added up front, or recorded from live matches and offered once the
+//! match is over; only finished matches long enough to be worth watching are
+//! kept. Playback holds on the last frame for a moment, then resets to a
+//! fresh engine and moves on to the next replay, wrapping around forever.
+
+use crate::config::EngineConfig;
+use crate::constants::*;
+use crate::engine::{Engine, GameResult};
+use crate::replay::{Replay, ReplayPlayer};
+
+/// Demo-screen playlist player
+pub struct AttractMode {
+    config: EngineConfig,
+    playlist: Vec<Replay>,
+    /// Replay being shown
+    current: usize,
+    player: Option<ReplayPlayer>,
+    /// Frames left holding the final frame of the current replay
+    hold_remaining: u32,
+}
+
+impl Default for AttractMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AttractMode {
+    pub fn new() -> Self {
+        Self::with_config(EngineConfig::default())
+    }
+
+    /// Play back with the configuration the replays were recorded under
+    pub fn with_config(config: EngineConfig) -> Self {
+        Self {
+            config,
+            playlist: Vec::new(),
+            current: 0,
+            player: None,
+            hold_remaining: ATTRACT_END_HOLD_FRAMES,
+        }
+    }
+
+    /// Add a scripted replay to the playlist
+    pub fn add(&mut self, replay: Replay) {
+        self.playlist.push(replay);
+    }
+
+    /// Offer a recorded match; keeps it if the match is over and long enough
+    ///
+    /// Once the playlist is full, a kept match replaces the oldest entry.
+    /// Returns whether the replay was kept.
+    pub fn offer(&mut self, replay: Replay, engine: &Engine) -> bool {
+        if engine.game_result == GameResult::InProgress || replay.len() < ATTRACT_MIN_FRAMES {
+            return false;
+        }
+        if self.playlist.len() >= MAX_ATTRACT_REPLAYS {
+            self.playlist.remove(0);
+            // Keep showing the same replay; restart if it was the one dropped
+            match self.current.checked_sub(1) {
+                Some(current) => self.current = current,
+                None => self.player = None,
+            }
+        }
+        self.playlist.push(replay);
+        true
+    }
+
+    /// Replays in the playlist
+    pub fn playlist(&self) -> &[Replay] {
+        &self.playlist
+    }
+
+    /// Index of the replay being shown
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Engine showing the demo, once playback has started
+    pub fn engine(&self) -> Option<&Engine> {
+        self.player.as_ref().map(ReplayPlayer::engine)
+    }
+
+    /// Advance the demo by one frame; returns false with an empty playlist
+    pub fn tick(&mut self) -> bool {
+        if self.playlist.is_empty() {
+            return false;
+        }
+        if self.player.is_none() {
+            self.player = Some(self.start(self.current));
+        }
+        if self.player.as_mut().is_some_and(ReplayPlayer::tick) {
+            return true;
+        }
+
+        if self.hold_remaining > 0 {
+            self.hold_remaining -= 1;
+        } else {
+            self.current = (self.current + 1) % self.playlist.len();
+            self.player = Some(self.start(self.current));
+        }
+        true
+    }
+
+    /// Fresh playback of a playlist entry
+    fn start(&mut self, index: usize) -> ReplayPlayer {
+        self.hold_remaining = ATTRACT_END_HOLD_FRAMES;
+        let replay = self.playlist[index].clone();
+        let config = EngineConfig {
+            stage: replay.stage,
+            ..self.config
+        };
+        ReplayPlayer::with_engine(replay, Engine::with_config(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputState;
+    use crate::setup::MatchSetup;
+
+    fn idle_replay(frames: usize) -> Replay {
+        let mut replay = Replay::new(MatchSetup::default());
+        for _ in 0..frames {
+            replay.push(InputState::neutral(), InputState::neutral());
+        }
+        replay
+    }
+
+    /// Short one-round match that P1 wins on time
+    fn finished_match() -> (Replay, Engine) {
+        let mut config = EngineConfig::default();
+        config.game.time_limit_frames = ATTRACT_MIN_FRAMES as u64;
+        config.game.rounds_to_win = 1;
+        let setup = MatchSetup {
+            distance: Some(20000),
+            distance_variation: 0,
+            ..MatchSetup::default()
+        };
+        let mut engine = Engine::with_config(config);
+        engine.init_match_with(setup);
+
+        let mut replay = Replay::new(setup);
+        for frame in 0..ATTRACT_MIN_FRAMES {
+            let mut p1 = InputState::neutral();
+            p1.light = frame % 20 == 0;
+            engine.tick(p1, InputState::neutral());
+            replay.record(&engine);
+        }
+        (replay, engine)
+    }
+
+    #[test]
+    fn test_playlist_loops_with_reset() {
+        let mut attract = AttractMode::new();
+        assert!(!attract.tick());
+        attract.add(idle_replay(10));
+        attract.add(idle_replay(20));
+
+        for _ in 0..10 + ATTRACT_END_HOLD_FRAMES {
+            attract.tick();
+        }
+        assert_eq!(attract.current(), 0);
+        assert_eq!(attract.engine().unwrap().frame.0, 10);
+
+        attract.tick();
+        assert_eq!(attract.current(), 1);
+        assert_eq!(attract.engine().unwrap().frame.0, 0);
+
+        for _ in 0..20 + 1 + ATTRACT_END_HOLD_FRAMES {
+            attract.tick();
+        }
+        assert_eq!(attract.current(), 0);
+        assert_eq!(attract.engine().unwrap().frame.0, 0);
+    }
+
+    #[test]
+    fn test_offer_keeps_finished_matches() {
+        let mut attract = AttractMode::new();
+        let mut live = Engine::new();
+        live.init_match();
+        assert!(!attract.offer(idle_replay(ATTRACT_MIN_FRAMES), &live));
+
+        let (replay, engine) = finished_match();
+        assert_eq!(engine.game_result, GameResult::Player1Wins);
+        let mut short = replay.clone();
+        short.truncate(ATTRACT_MIN_FRAMES - 1);
+        assert!(!attract.offer(short, &engine));
+        for _ in 0..MAX_ATTRACT_REPLAYS + 1 {
+            assert!(attract.offer(replay.clone(), &engine));
+        }
+        assert_eq!(attract.playlist().len(), MAX_ATTRACT_REPLAYS);
+    }
+
+    #[test]
+    fn test_recorded_match_replays_to_same_result() {
+        let (replay, engine) = finished_match();
+        let mut attract = AttractMode::with_config(engine.config);
+        assert!(attract.offer(replay.clone(), &engine));
+
+        for _ in 0..replay.len() {
+            attract.tick();
+        }
+        assert_eq!(
+            attract.engine().unwrap().game_result,
+            GameResult::Player1Wins
+        );
+    }
+}
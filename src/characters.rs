@@ -0,0 +1,547 @@
+//! Reference character archetypes
+//!
+//! Each archetype is a `StateSet` built from the default character with a
+//! few states replaced, so it can be handed to `Entity::with_states` or
+//! swapped into a `StateMachine`. They double as worked examples of the
+//! engine's systems:
+//!
+//! - **Rushdown**: faster walk and normals, lunging special
+//! - **Grappler**: command grab (hit-grab into a linked throw)
+//! - **Zoner**: fireball projectile that travels across the screen, with a
+//!   faster EX version paid for with meter
+//! - **Charge**: hold Heavy to charge an unblockable heavy
+//!
+//! `CharacterDef` pairs a state set with the stats that aren't part of any
+//! state (health, weight, gravity); `CharacterDef::rushdown` and friends
+//! give each archetype its own. Start a matchup with
+//! `Engine::init_match_with_characters`.
+
+use std::sync::Arc;
+
+use crate::constants::{DEFAULT_HEALTH, DEFAULT_WEIGHT, EX_METER_COST};
+use crate::hitbox::AttackData;
+use crate::projectile::ProjectileDef;
+use crate::state::{
+    states, FrameData, State, StateAction, StateId, StateSet, StateType, SPECIAL_CANCELS,
+};
+use crate::types::{Rect, Vec2};
+
+/// Grappler's throw sequence, entered when the command grab connects
+pub const GRAPPLER_THROW: StateId = StateId::Custom(100);
+
+/// A playable character: its states and stats
+///
+/// Walk and jump speeds are the takeoff velocities of the movement states,
+/// so they're read from and written to `states`. The other stats are given
+/// to the fighter at the start of each round.
+#[derive(Clone)]
+pub struct CharacterDef {
+    /// Starting and maximum health
+    pub health: i32,
+    /// Pushbox weight (`DEFAULT_WEIGHT` is standard)
+    pub weight: i32,
+    /// Gravity, in percent of the stage's
+    pub gravity_percent: i32,
+    /// Movement, attacks and reactions
+    pub states: Arc<StateSet>,
+}
+
+impl Default for CharacterDef {
+    fn default() -> Self {
+        Self::new(states::default_set())
+    }
+}
+
+impl CharacterDef {
+    /// Character with standard stats
+    pub fn new(states: StateSet) -> Self {
+        Self {
+            health: DEFAULT_HEALTH,
+            weight: DEFAULT_WEIGHT,
+            gravity_percent: 100,
+            states: Arc::new(states),
+        }
+    }
+
+    /// Light and fast, but fragile
+    pub fn rushdown() -> Self {
+        Self::new(rushdown())
+            .with_health(900)
+            .with_weight(90)
+            .with_back_walk_speed(250)
+    }
+
+    /// Heavy and durable, with a short, fast-falling jump
+    pub fn grappler() -> Self {
+        Self::new(grappler())
+            .with_health(1150)
+            .with_weight(130)
+            .with_gravity_percent(120)
+            .with_jump_velocity(1250)
+    }
+
+    /// Keeps away, slightly below standard health
+    pub fn zoner() -> Self {
+        Self::new(zoner())
+            .with_health(950)
+            .with_back_walk_speed(250)
+    }
+
+    /// Standard stats
+    pub fn charge() -> Self {
+        Self::new(charge())
+    }
+
+    pub fn with_health(mut self, health: i32) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub fn with_weight(mut self, weight: i32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_gravity_percent(mut self, percent: i32) -> Self {
+        self.gravity_percent = percent;
+        self
+    }
+
+    /// Forward walk speed, per frame
+    pub fn walk_speed(&self) -> i32 {
+        self.takeoff(StateId::Walk).x
+    }
+
+    /// Backward walk speed, per frame
+    pub fn back_walk_speed(&self) -> i32 {
+        -self.takeoff(StateId::WalkBack).x
+    }
+
+    /// Upward takeoff speed of a jump
+    pub fn jump_velocity(&self) -> i32 {
+        -self.takeoff(StateId::Jump).y
+    }
+
+    pub fn with_walk_speed(mut self, speed: i32) -> Self {
+        self.set_takeoff(StateId::Walk, Vec2::new(speed, 0));
+        self
+    }
+
+    pub fn with_back_walk_speed(mut self, speed: i32) -> Self {
+        self.set_takeoff(StateId::WalkBack, Vec2::new(-speed, 0));
+        self
+    }
+
+    pub fn with_jump_velocity(mut self, velocity: i32) -> Self {
+        self.set_takeoff(StateId::Jump, Vec2::new(0, -velocity));
+        self
+    }
+
+    /// Damage of a move's first hitbox (`None` if it has no hitbox)
+    pub fn move_damage(&self, id: StateId) -> Option<i32> {
+        self.states
+            .find(id)?
+            .frame_data
+            .iter()
+            .find_map(|data| match data.action {
+                StateAction::Hitbox { attack, .. } => Some(attack.damage),
+                _ => None,
+            })
+    }
+
+    /// Set the damage of every hitbox of a move; moves without hitboxes are
+    /// left alone
+    pub fn with_move_damage(mut self, id: StateId, damage: i32) -> Self {
+        if self.move_damage(id).is_none() {
+            return self;
+        }
+        let Some(mut state) = self.states.find(id).cloned() else {
+            return self;
+        };
+        for data in &mut state.frame_data {
+            if let StateAction::Hitbox { attack, .. } = &mut data.action {
+                attack.damage = damage;
+            }
+        }
+        Arc::make_mut(&mut self.states).register(state);
+        self
+    }
+
+    /// Velocity a state sets on its first frame (zero if it sets none)
+    fn takeoff(&self, id: StateId) -> Vec2 {
+        self.states
+            .find(id)
+            .and_then(|state| {
+                state.frame_data.iter().find_map(|data| match data.action {
+                    StateAction::SetVelocity { x, y } if data.frame == 0 => Some(Vec2::new(x, y)),
+                    _ => None,
+                })
+            })
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    /// Replace the velocity a state sets on its first frame; states the
+    /// character doesn't have are left alone
+    fn set_takeoff(&mut self, id: StateId, velocity: Vec2) {
+        let Some(mut state) = self.states.find(id).cloned() else {
+            return;
+        };
+        let action = StateAction::SetVelocity {
+            x: velocity.x,
+            y: velocity.y,
+        };
+        let existing = state
+            .frame_data
+            .iter_mut()
+            .find(|data| data.frame == 0 && matches!(data.action, StateAction::SetVelocity { .. }));
+        match existing {
+            Some(data) => data.action = action,
+            None => state.frame_data.push(FrameData::new(0, action)),
+        }
+        Arc::make_mut(&mut self.states).register(state);
+    }
+}
+
+/// Rushdown: fast movement and pressure
+pub fn rushdown() -> StateSet {
+    let mut set = states::default_set();
+    set.register(
+        State::new(StateId::Walk, StateType::Normal, 1)
+            .add_frame_data(FrameData::new(0, StateAction::SetVelocity { x: 450, y: 0 })),
+    );
+    set.register(
+        State::new(StateId::LightAttack, StateType::Attack, 14)
+            .grounded_only()
+            .cancels_on_contact(&states::LIGHT_CHAINS, 3, 10)
+            .cancels_on_contact(&SPECIAL_CANCELS, 3, 10)
+            .add_frame_data(FrameData::new(
+                3,
+                StateAction::Hitbox {
+                    x: 14000,
+                    y: 10000,
+                    width: 11000,
+                    height: 8000,
+                    attack: AttackData::new(40)
+                        .with_stun(8, 6)
+                        .with_knockback(300, 0)
+                        .with_block_pushback(150),
+                },
+            )),
+    );
+    // Lunge: QCF + Special closes distance before striking
+    set.register(
+        State::new(StateId::SpecialMove, StateType::Attack, 30)
+            .add_frame_data(FrameData::new(
+                2,
+                StateAction::AddMomentum { x: 1500, y: 0 },
+            ))
+            .add_frame_data(FrameData::new(
+                10,
+                StateAction::Hitbox {
+                    x: 16000,
+                    y: 10000,
+                    width: 14000,
+                    height: 10000,
+                    attack: AttackData::new(90)
+                        .with_stun(14, 10)
+                        .with_knockback(900, 0)
+                        .with_block_pushback(500),
+                },
+            )),
+    );
+    set
+}
+
+/// Grappler: slow, with a command grab on QCF + Special
+pub fn grappler() -> StateSet {
+    let mut set = states::default_set();
+    set.register(
+        State::new(StateId::Walk, StateType::Normal, 1)
+            .add_frame_data(FrameData::new(0, StateAction::SetVelocity { x: 200, y: 0 })),
+    );
+    set.register(
+        State::new(StateId::SpecialMove, StateType::Attack, 40).add_frame_data(FrameData::new(
+            5,
+            StateAction::Hitbox {
+                x: 14000,
+                y: 10000,
+                width: 12000,
+                height: 12000,
+                attack: AttackData::new(60).unblockable().hit_grab(GRAPPLER_THROW),
+            },
+        )),
+    );
+    set.register(
+        State::new(GRAPPLER_THROW, StateType::Attack, 40)
+            .add_frame_data(FrameData::new(0, StateAction::MoveVictim { x: 6000, y: 0 }))
+            .add_frame_data(FrameData::new(
+                10,
+                StateAction::MoveVictim { x: 0, y: -12000 },
+            ))
+            .add_frame_data(FrameData::new(
+                20,
+                StateAction::MoveVictim { x: -8000, y: 0 },
+            ))
+            .add_frame_data(FrameData::new(24, StateAction::ReleaseVictim)),
+    );
+    set
+}
+
+/// Zoner: keeps opponents out with a slow-travelling fireball
+pub fn zoner() -> StateSet {
+    let mut set = states::default_set();
+    set.register(zoner_fireball());
+    set.register(zoner_ex_fireball());
+    set
+}
+
+/// Charge character: Heavy is held to charge an unblockable release
+pub fn charge() -> StateSet {
+    let mut set = states::default_set();
+    set.register(states::heavy_charge());
+    set.register(states::charged_heavy());
+    set
+}
+
+/// Fireball launched on frame 12, crossing the screen in 48 frames
+fn zoner_fireball() -> State {
+    let fireball = ProjectileDef::new(
+        Rect::new(0, 8000, 8000, 6000),
+        AttackData::new(70)
+            .with_stun(16, 12)
+            .with_knockback(600, 0)
+            .with_block_pushback(400),
+        Vec2::new(2000, 0),
+        48,
+    );
+    State::new(StateId::SpecialMove, StateType::Attack, 40).add_frame_data(FrameData::new(
+        12,
+        StateAction::SpawnProjectile {
+            x: 20000,
+            y: 0,
+            projectile: fireball,
+        },
+    ))
+}
+
+/// EX fireball (QCF + Special with Heavy held): faster and stronger,
+/// costs `EX_METER_COST`
+fn zoner_ex_fireball() -> State {
+    let fireball = ProjectileDef::new(
+        Rect::new(0, 8000, 8000, 6000),
+        AttackData::new(100)
+            .with_stun(20, 14)
+            .with_knockback(800, 0)
+            .with_block_pushback(500),
+        Vec2::new(3000, 0),
+        32,
+    );
+    State::new(StateId::ExSpecial, StateType::Attack, 36)
+        .requires_meter(EX_METER_COST)
+        .add_frame_data(FrameData::new(
+            0,
+            StateAction::ConsumeMeter {
+                amount: EX_METER_COST,
+            },
+        ))
+        .add_frame_data(FrameData::new(
+            8,
+            StateAction::SpawnProjectile {
+                x: 20000,
+                y: 0,
+                projectile: fireball,
+            },
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use crate::events::HitOutcome;
+    use crate::input::InputState;
+    use crate::state::StateMachine;
+
+    fn engine_with(p1: StateSet) -> Engine {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let fighter = engine.entities[0].as_mut().unwrap();
+        fighter.state_machine = StateMachine::with_states(Arc::new(p1));
+        engine
+    }
+
+    fn place(engine: &mut Engine, p1_x: i32, p2_x: i32) {
+        engine.entities[0].as_mut().unwrap().physics.position.x = p1_x;
+        engine.entities[1].as_mut().unwrap().physics.position.x = p2_x;
+    }
+
+    fn run_special(engine: &mut Engine, frames: u32) -> Vec<HitOutcome> {
+        let fighter = engine.entities[0].as_mut().unwrap();
+        fighter.state_machine.transition(StateId::SpecialMove);
+        let mut outcomes = Vec::new();
+        for _ in 0..frames {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            outcomes.extend(engine.events().iter().map(|e| e.outcome));
+        }
+        outcomes
+    }
+
+    #[test]
+    fn test_archetypes_override_default_states() {
+        for set in [rushdown(), grappler(), zoner(), charge()] {
+            assert!(set.find(StateId::Idle).is_some());
+            assert!(set.len() <= states::default_set().len() + 2);
+        }
+        assert!(charge().find(StateId::HeavyCharge).is_some());
+        assert!(states::default_set().find(StateId::SpecialMove).is_none());
+    }
+
+    #[test]
+    fn test_grappler_can_add_every_win_pose() {
+        let mut set = grappler();
+        for pose in 1..=4 {
+            set.register(states::win_pose(pose));
+        }
+        for pose in 0..=4 {
+            assert!(set.find(StateId::WinPose(pose)).is_some());
+        }
+    }
+
+    #[test]
+    fn test_character_def_movement_stats_edit_states() {
+        let standard = CharacterDef::default();
+        assert_eq!(standard.walk_speed(), 300);
+        assert_eq!(standard.back_walk_speed(), 200);
+        assert_eq!(standard.jump_velocity(), 1200);
+
+        let edited = standard
+            .clone()
+            .with_walk_speed(380)
+            .with_jump_velocity(1000);
+        assert_eq!(edited.walk_speed(), 380);
+        assert_eq!(edited.jump_velocity(), 1000);
+        let jump = edited.states.find(StateId::Jump).unwrap();
+        assert_eq!(jump.frame_data.len(), 1);
+        assert!(jump.can_cancel);
+        // The original is shared, not edited
+        assert_eq!(standard.walk_speed(), 300);
+
+        assert_eq!(CharacterDef::rushdown().walk_speed(), 450);
+        assert_eq!(CharacterDef::grappler().walk_speed(), 200);
+    }
+
+    #[test]
+    fn test_rushdown_lunge_closes_distance() {
+        let mut engine = engine_with(rushdown());
+        place(&mut engine, 0, 30000);
+
+        let outcomes = run_special(&mut engine, 15);
+        assert_eq!(outcomes, vec![HitOutcome::Hit]);
+    }
+
+    #[test]
+    fn test_grappler_command_grab_links_and_releases() {
+        let mut engine = engine_with(grappler());
+        place(&mut engine, 0, 15000);
+
+        let outcomes = run_special(&mut engine, 8);
+        assert_eq!(outcomes, vec![HitOutcome::Hit]);
+        assert!(engine.link.is_some());
+        let victim = engine.entities[1].as_ref().unwrap();
+        assert_eq!(victim.state_machine.current_state(), StateId::Held);
+
+        for _ in 0..30 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        assert!(engine.link.is_none());
+    }
+
+    #[test]
+    fn test_zoner_special_reaches_full_screen() {
+        let mut engine = engine_with(zoner());
+        place(&mut engine, -50000, 50000);
+
+        // The fireball keeps flying after the zoner has recovered
+        let outcomes = run_special(&mut engine, 45);
+        assert!(outcomes.is_empty());
+        assert!(engine.projectiles.iter().any(Option::is_some));
+        let fighter = engine.entities[0].as_ref().unwrap();
+        assert_eq!(fighter.state_machine.current_state(), StateId::Idle);
+
+        let mut outcomes = Vec::new();
+        for _ in 0..15 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            outcomes.extend(engine.events().iter().map(|e| e.outcome));
+        }
+        assert_eq!(outcomes, vec![HitOutcome::Hit]);
+        assert!(engine.projectiles.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_zoner_ex_fireball_needs_meter() {
+        use crate::constants::EX_METER_COST;
+        use crate::input::Direction;
+
+        let qcf_ex = |engine: &mut Engine| {
+            for direction in [Direction::Down, Direction::DownForward] {
+                let input = InputState {
+                    direction,
+                    ..InputState::neutral()
+                };
+                engine.tick(input, InputState::neutral());
+            }
+            let input = InputState {
+                direction: Direction::Forward,
+                special: true,
+                heavy: true,
+                ..InputState::neutral()
+            };
+            engine.tick(input, InputState::neutral());
+            let fighter = engine.entities[0].as_ref().unwrap();
+            (fighter.state_machine.current_state(), fighter.meter)
+        };
+
+        let mut engine = engine_with(zoner());
+        assert_eq!(qcf_ex(&mut engine), (StateId::SpecialMove, 0));
+
+        let mut engine = engine_with(zoner());
+        engine.entities[0].as_mut().unwrap().meter = EX_METER_COST + 5;
+        assert_eq!(qcf_ex(&mut engine), (StateId::ExSpecial, 5));
+    }
+
+    #[test]
+    fn test_charge_character_releases_charged_heavy() {
+        let mut engine = engine_with(charge());
+        let mut heavy = InputState::neutral();
+        heavy.heavy = true;
+
+        engine.tick(heavy, InputState::neutral());
+        let fighter = engine.entities[0].as_ref().unwrap();
+        assert_eq!(fighter.state_machine.current_state(), StateId::HeavyCharge);
+
+        for _ in 0..40 {
+            engine.tick(heavy, InputState::neutral());
+        }
+        engine.tick(InputState::neutral(), InputState::neutral());
+        let fighter = engine.entities[0].as_ref().unwrap();
+        assert_eq!(fighter.state_machine.current_state(), StateId::ChargedHeavy);
+    }
+
+    #[test]
+    fn test_full_charge_cue_reaches_the_engine() {
+        use crate::events::Cue;
+
+        let mut engine = engine_with(charge());
+        let id = engine.entities[0].as_ref().unwrap().id;
+        let mut heavy = InputState::neutral();
+        heavy.heavy = true;
+
+        let mut cues = Vec::new();
+        for _ in 0..40 {
+            engine.tick(heavy, InputState::neutral());
+            cues.extend_from_slice(engine.cues());
+        }
+        assert_eq!(cues.len(), 1);
+        assert_eq!((cues[0].entity, cues[0].cue), (id, Cue::FullyCharged));
+    }
+}
@@ -0,0 +1,836 @@
+//! Plain-text character definitions
+//!
+//! A character file lists the stats and states that differ from the default
+//! character; everything it doesn't mention keeps the default definition,
+//! like the archetypes in `characters`. Load one with
+//! `Engine::load_character` or turn it into a `CharacterDef` with
+//! `parse_character`. The default character itself is the bundled
+//! `DEFAULT_CHARACTER` file. `write_state` goes the other way, for tools
+//! that edit characters (see `tuning`).
+//!
+//! One statement per line; `#` starts a comment. Numbers are world units
+//! and frames, as in the Rust definitions. The first statement is the
+//! `ENGINE_DATA_VERSION` the file was written for; files for any other
+//! version are refused, since their frame data would play differently
+//! under replays of this one.
+//!
+//! ```text
+//! version 1                        # ENGINE_DATA_VERSION
+//! body stand 0 0 10000 25000       # collision body: stand|crouch, x y width height
+//! health 900                       # also: weight <n>, gravity <percent of the stage's>
+//!
+//! state LightAttack attack 14      # id, normal|attack|hurt|invincible, duration
+//!   grounded                       # or: airborne
+//!   cancel MediumAttack 3 10       # contact cancel window
+//!   cancel SpecialMove 3 10 whiff  # also on whiff
+//!   frame 3 hitbox 14000 10000 11000 8000 damage 40 stun 8 6 knockback 300 0 pushback 150
+//! end
+//! ```
+//!
+//! State properties: `free` (cancels into anything), `grounded`,
+//! `airborne`, `back_turned`, `meter <n>` (required to start),
+//! `contact_recovery <n>`, `mash <per press> <min duration>`,
+//! `cancel <state> <start> <end> [whiff]`.
+//!
+//! Frame actions (`frame <n> <action>`):
+//!
+//! | Action                                  | Effect                       |
+//! |-----------------------------------------|------------------------------|
+//! | `hitbox <x> <y> <w> <h> damage <n> ...` | Hitbox, attack options below |
+//! | `velocity <x> <y>`                      | Set velocity                 |
+//! | `momentum <x> <y>`                      | Add momentum                 |
+//! | `gravity <percent>`                     | Scale gravity for the state  |
+//! | `halt`                                  | Stop vertical movement       |
+//! | `pin <frames>`                          | Hold in place                |
+//! | `freeze_vertical <frames>`              | Suspend vertical movement    |
+//! | `invincible <frames>`                   | Full invincibility           |
+//! | `armor <hits> <frames> [<percent>]`     | Armor, `forever` hits: hyper |
+//! | `spend_meter <n>`                       | Consume super meter          |
+//! | `flash <frames>`                        | Super flash                  |
+//! | `install <frames> <startup> <recovery>` | Frame data install           |
+//! | `goto <state>`                          | Transition                   |
+//!
+//! Attack options follow `damage`: `stun <hit> <block>`,
+//! `knockback <x> <y>`, `pushback <n>`, `hitstop <n>`, `dizzy <n>`,
+//! `proration <percent>`, `group <n>` and the flags `overhead`, `low`,
+//! `unblockable`, `launcher`, `otg`, `sweep`, `soft_knockdown`,
+//! `hard_knockdown`, `super`.
+//!
+//! State IDs are the `StateId` variant names, with `Custom(<n>)` for
+//! character-specific states and `WinPose(<n>)` for win poses. A duration
+//! of `forever` never ends on its own (crouching, stances left through
+//! input).
+
+use std::sync::Arc;
+
+use crate::characters::CharacterDef;
+use crate::constants::{ENGINE_DATA_VERSION, MAX_STATES};
+use crate::hitbox::{AttackData, Invulnerability, KnockdownType};
+use crate::state::{
+    CancelRoute, Footing, FrameData, FrameDataModifier, State, StateAction, StateId, StateType,
+};
+use crate::types::Rect;
+
+/// The default character, which other character files build on
+pub const DEFAULT_CHARACTER: &str = include_str!("../data/default.chr");
+
+/// Why a character definition couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharacterError {
+    /// 1-based line the error was found on
+    pub line: usize,
+    pub kind: CharacterErrorKind,
+}
+
+/// What was wrong with a line of a character definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharacterErrorKind {
+    /// Word that isn't a statement, property, action or attack option
+    UnknownKeyword(String),
+    /// Name that isn't a `StateId`
+    UnknownState(String),
+    /// State type other than normal, attack, hurt or invincible
+    UnknownStateType(String),
+    /// Value that isn't a number in range
+    BadNumber(String),
+    /// Line ended before a required value
+    MissingValue,
+    /// Words left over after a complete statement
+    TrailingInput(String),
+    /// Property or action outside a `state` block
+    OutsideState,
+    /// Character-wide statement (`body`, stats) inside a `state` block
+    InsideState,
+    /// `state` inside another state's block
+    NestedState,
+    /// File ended inside a `state` block
+    UnclosedState,
+    /// New state when the character already has `MAX_STATES`
+    TooManyStates,
+    /// First statement isn't `version <n>`
+    MissingVersion,
+    /// Written for another `ENGINE_DATA_VERSION`
+    EngineVersion(u16),
+}
+
+/// Parse a character definition into the default character with the
+/// file's stats and states applied over it
+pub fn parse_character(text: &str) -> Result<CharacterDef, CharacterError> {
+    let mut character = CharacterDef::default();
+    parse_into(text, &mut character)?;
+    Ok(character)
+}
+
+/// Apply a character definition to `character`; on error `character` may
+/// be partly changed
+pub(crate) fn parse_into(text: &str, character: &mut CharacterDef) -> Result<(), CharacterError> {
+    let mut current: Option<State> = None;
+    let mut versioned = false;
+
+    for (index, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let mut line = Line {
+            number: index + 1,
+            words: content.split_whitespace(),
+        };
+        let Some(keyword) = line.words.next() else {
+            continue;
+        };
+
+        if !versioned {
+            if keyword != "version" {
+                return Err(line.error(CharacterErrorKind::MissingVersion));
+            }
+            let version = line.number()?;
+            if version != ENGINE_DATA_VERSION {
+                return Err(line.error(CharacterErrorKind::EngineVersion(version)));
+            }
+            versioned = true;
+            line.finish()?;
+            continue;
+        }
+
+        match (keyword, current.take()) {
+            ("version", _) => return Err(line.unknown(keyword)),
+            ("state", Some(_)) => return Err(line.error(CharacterErrorKind::NestedState)),
+            ("state", None) => {
+                let id = line.state_id()?;
+                let states = &character.states;
+                if states.find(id).is_none() && states.len() >= MAX_STATES {
+                    return Err(line.error(CharacterErrorKind::TooManyStates));
+                }
+                let state_type = line.state_type()?;
+                let duration = line.duration()?;
+                current = Some(State::new(id, state_type, duration));
+            }
+            ("end", Some(state)) => Arc::make_mut(&mut character.states).register(state),
+            ("body" | "health" | "weight" | "gravity", Some(_)) => {
+                return Err(line.error(CharacterErrorKind::InsideState))
+            }
+            ("health", None) => character.health = line.number()?,
+            ("weight", None) => character.weight = line.number()?,
+            ("gravity", None) => character.gravity_percent = line.number()?,
+            ("body", None) => {
+                let posture = line.word()?;
+                let rect = Rect::new(
+                    line.number()?,
+                    line.number()?,
+                    line.number()?,
+                    line.number()?,
+                );
+                let body = &mut Arc::make_mut(&mut character.states).body;
+                match posture {
+                    "stand" => body.stand = rect,
+                    "crouch" => body.crouch = rect,
+                    other => return Err(line.unknown(other)),
+                }
+            }
+            (_, None) => return Err(line.error(CharacterErrorKind::OutsideState)),
+            (keyword, Some(state)) => current = Some(line.property(keyword, state)?),
+        }
+        line.finish()?;
+    }
+
+    let unfinished = match current {
+        Some(_) => CharacterErrorKind::UnclosedState,
+        None if !versioned => CharacterErrorKind::MissingVersion,
+        None => return Ok(()),
+    };
+    Err(CharacterError {
+        line: text.lines().count(),
+        kind: unfinished,
+    })
+}
+
+/// Words of one line, consumed left to right
+struct Line<'a> {
+    number: usize,
+    words: std::str::SplitWhitespace<'a>,
+}
+
+impl<'a> Line<'a> {
+    fn error(&self, kind: CharacterErrorKind) -> CharacterError {
+        CharacterError {
+            line: self.number,
+            kind,
+        }
+    }
+
+    fn word(&mut self) -> Result<&'a str, CharacterError> {
+        self.words
+            .next()
+            .ok_or_else(|| self.error(CharacterErrorKind::MissingValue))
+    }
+
+    fn number<T: core::str::FromStr>(&mut self) -> Result<T, CharacterError> {
+        let word = self.word()?;
+        word.parse()
+            .map_err(|_| self.error(CharacterErrorKind::BadNumber(word.into())))
+    }
+
+    /// Number that can't be negative
+    fn amount(&mut self) -> Result<i32, CharacterError> {
+        let word = self.word()?;
+        match word.parse() {
+            Ok(amount) if amount >= 0 => Ok(amount),
+            _ => Err(self.error(CharacterErrorKind::BadNumber(word.into()))),
+        }
+    }
+
+    fn state_id(&mut self) -> Result<StateId, CharacterError> {
+        let word = self.word()?;
+        state_id(word).ok_or_else(|| self.error(CharacterErrorKind::UnknownState(word.into())))
+    }
+
+    /// Frame count, or `forever`
+    fn duration(&mut self) -> Result<u32, CharacterError> {
+        match self.words.clone().next() {
+            Some("forever") => {
+                self.words.next();
+                Ok(u32::MAX)
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn state_type(&mut self) -> Result<StateType, CharacterError> {
+        match self.word()? {
+            "normal" => Ok(StateType::Normal),
+            "attack" => Ok(StateType::Attack),
+            "hurt" => Ok(StateType::Hurt),
+            "invincible" => Ok(StateType::Invincible),
+            other => Err(self.error(CharacterErrorKind::UnknownStateType(other.into()))),
+        }
+    }
+
+    /// Error on anything left after a complete statement
+    fn finish(&mut self) -> Result<(), CharacterError> {
+        match self.words.next() {
+            Some(word) => Err(self.error(CharacterErrorKind::TrailingInput(word.into()))),
+            None => Ok(()),
+        }
+    }
+
+    fn unknown(&self, word: &str) -> CharacterError {
+        self.error(CharacterErrorKind::UnknownKeyword(word.into()))
+    }
+
+    /// Apply a state property line
+    fn property(&mut self, keyword: &str, state: State) -> Result<State, CharacterError> {
+        Ok(match keyword {
+            "free" => state.with_cancel(),
+            "grounded" => state.grounded_only(),
+            "airborne" => state.airborne_only(),
+            "back_turned" => state.with_back_turned(),
+            "meter" => state.requires_meter(self.number()?),
+            "contact_recovery" => state.with_contact_recovery(self.number()?),
+            "mash" => state.with_mash(self.number()?, self.number()?),
+            "cancel" => {
+                let mut route = CancelRoute::new(self.state_id()?, self.number()?, self.number()?);
+                // The optional flag ends the line, so take it only if it's there
+                match self.words.next() {
+                    Some("whiff") => route = route.on_whiff(),
+                    Some(other) => return Err(self.unknown(other)),
+                    None => {}
+                }
+                state.with_cancel_route(route)
+            }
+            "frame" => {
+                let frame = self.number()?;
+                state.add_frame_data(FrameData::new(frame, self.action()?))
+            }
+            other => return Err(self.unknown(other)),
+        })
+    }
+
+    fn action(&mut self) -> Result<StateAction, CharacterError> {
+        Ok(match self.word()? {
+            "hitbox" => StateAction::Hitbox {
+                x: self.number()?,
+                y: self.number()?,
+                width: self.number()?,
+                height: self.number()?,
+                attack: self.attack()?,
+            },
+            "velocity" => StateAction::SetVelocity {
+                x: self.number()?,
+                y: self.number()?,
+            },
+            "momentum" => StateAction::AddMomentum {
+                x: self.number()?,
+                y: self.number()?,
+            },
+            "gravity" => StateAction::SetGravityScale {
+                percent: self.number()?,
+            },
+            "halt" => StateAction::HaltMomentum,
+            "pin" => StateAction::PinPosition {
+                frames: self.number()?,
+            },
+            "freeze_vertical" => StateAction::FreezeVertical {
+                frames: self.number()?,
+            },
+            "invincible" => StateAction::SetInvincible {
+                frames: self.number()?,
+                invulnerability: Invulnerability::FULL,
+            },
+            "armor" => StateAction::SetArmor {
+                hits: self.duration()?,
+                frames: self.number()?,
+                damage_percent: match self.words.clone().next() {
+                    Some(_) => self.number()?,
+                    None => 100,
+                },
+            },
+            "spend_meter" => StateAction::ConsumeMeter {
+                amount: self.number()?,
+            },
+            "flash" => StateAction::SuperFlash {
+                frames: self.number()?,
+            },
+            "install" => StateAction::Install {
+                frames: self.number()?,
+                modifier: FrameDataModifier::new(self.number()?, self.number()?),
+            },
+            "goto" => StateAction::Transition {
+                target: self.state_id()?,
+            },
+            other => return Err(self.unknown(other)),
+        })
+    }
+
+    /// `damage <n>` followed by attack options up to the end of the line
+    fn attack(&mut self) -> Result<AttackData, CharacterError> {
+        match self.word()? {
+            "damage" => {}
+            other => return Err(self.unknown(other)),
+        }
+        let mut attack = AttackData::new(self.number()?);
+        while let Some(option) = self.words.next() {
+            attack = match option {
+                "stun" => attack.with_stun(self.number()?, self.number()?),
+                "knockback" => attack.with_knockback(self.number()?, self.number()?),
+                "pushback" => attack.with_block_pushback(self.number()?),
+                "hitstop" => attack.with_hitstop(self.number()?),
+                "dizzy" => attack.with_dizzy(self.amount()?),
+                "proration" => attack.with_proration(self.number()?),
+                "group" => attack.with_hit_group(self.number()?),
+                "overhead" => attack.overhead(),
+                "low" => attack.low(),
+                "unblockable" => attack.unblockable(),
+                "launcher" => attack.launcher(),
+                "otg" => attack.otg(),
+                "sweep" => attack.sweep(),
+                "soft_knockdown" => attack.soft_knockdown(),
+                "hard_knockdown" => attack.hard_knockdown(),
+                "super" => attack.super_move(),
+                other => return Err(self.unknown(other)),
+            };
+        }
+        Ok(attack)
+    }
+}
+
+/// Write a state as a `state` block that parses back to the same state, or
+/// `None` if it uses something the format can't express (projectiles,
+/// grabs, animations, ...)
+pub fn write_state(state: &State) -> Option<String> {
+    if state.charge.is_some()
+        || state.hold.is_some()
+        || state.counter.is_some()
+        || state.projectile_guard.is_some()
+        || state.animation.is_some()
+        || state.use_limit.is_some()
+        || state.resource_cost.is_some()
+    {
+        return None;
+    }
+    let state_type = match state.state_type {
+        StateType::Normal => "normal",
+        StateType::Attack => "attack",
+        StateType::Hurt => "hurt",
+        StateType::Invincible => "invincible",
+    };
+    let duration = match state.duration {
+        u32::MAX => "forever".to_string(),
+        frames => frames.to_string(),
+    };
+
+    let mut lines = vec![format!("state {:?} {state_type} {duration}", state.id)];
+    if state.can_cancel {
+        lines.push("  free".into());
+    }
+    match state.footing {
+        Footing::Anywhere => {}
+        Footing::Grounded => lines.push("  grounded".into()),
+        Footing::Airborne => lines.push("  airborne".into()),
+    }
+    if state.back_turned {
+        lines.push("  back_turned".into());
+    }
+    if state.meter_required != 0 {
+        lines.push(format!("  meter {}", state.meter_required));
+    }
+    if state.contact_duration != 0 {
+        lines.push(format!("  contact_recovery {}", state.contact_duration));
+    }
+    if state.mash_reduction != 0 {
+        lines.push(format!(
+            "  mash {} {}",
+            state.mash_reduction, state.mash_min_duration
+        ));
+    }
+    for route in &state.cancels {
+        let whiff = if route.on_contact { "" } else { " whiff" };
+        lines.push(format!(
+            "  cancel {:?} {} {}{whiff}",
+            route.target, route.start, route.end
+        ));
+    }
+    for data in &state.frame_data {
+        lines.push(format!(
+            "  frame {} {}",
+            data.frame,
+            write_action(&data.action)?
+        ));
+    }
+    lines.push("end".into());
+    Some(lines.join("\n") + "\n")
+}
+
+fn write_action(action: &StateAction) -> Option<String> {
+    Some(match *action {
+        StateAction::Hitbox {
+            x,
+            y,
+            width,
+            height,
+            attack,
+        } => format!("hitbox {x} {y} {width} {height} {}", write_attack(&attack)?),
+        StateAction::SetVelocity { x, y } => format!("velocity {x} {y}"),
+        StateAction::AddMomentum { x, y } => format!("momentum {x} {y}"),
+        StateAction::SetGravityScale { percent } => format!("gravity {percent}"),
+        StateAction::HaltMomentum => "halt".into(),
+        StateAction::PinPosition { frames } => format!("pin {frames}"),
+        StateAction::FreezeVertical { frames } => format!("freeze_vertical {frames}"),
+        StateAction::SetInvincible {
+            frames,
+            invulnerability: Invulnerability::FULL,
+        } => format!("invincible {frames}"),
+        StateAction::SetArmor {
+            hits,
+            frames,
+            damage_percent,
+        } => {
+            let hits = match hits {
+                u32::MAX => "forever".to_string(),
+                hits => hits.to_string(),
+            };
+            match damage_percent {
+                100 => format!("armor {hits} {frames}"),
+                percent => format!("armor {hits} {frames} {percent}"),
+            }
+        }
+        StateAction::ConsumeMeter { amount } => format!("spend_meter {amount}"),
+        StateAction::SuperFlash { frames } => format!("flash {frames}"),
+        StateAction::Install { frames, modifier } => {
+            format!(
+                "install {frames} {} {}",
+                modifier.startup, modifier.recovery
+            )
+        }
+        StateAction::Transition { target } => format!("goto {target:?}"),
+        _ => return None,
+    })
+}
+
+/// `damage <n>` and the options that differ from `AttackData::new`
+fn write_attack(attack: &AttackData) -> Option<String> {
+    if attack.hit_cue.is_some()
+        || attack.grab_state.is_some()
+        || attack.team.is_some()
+        || attack.techable
+    {
+        return None;
+    }
+    let standard = AttackData::new(attack.damage);
+    let mut words = vec![format!("damage {}", attack.damage)];
+    if (attack.hitstun, attack.blockstun) != (standard.hitstun, standard.blockstun) {
+        words.push(format!("stun {} {}", attack.hitstun, attack.blockstun));
+    }
+    if (attack.pushback_x, attack.pushback_y) != (standard.pushback_x, standard.pushback_y) {
+        words.push(format!(
+            "knockback {} {}",
+            attack.pushback_x, attack.pushback_y
+        ));
+    }
+    if attack.block_pushback != standard.block_pushback {
+        words.push(format!("pushback {}", attack.block_pushback));
+    }
+    if let Some(frames) = attack.hitstop {
+        words.push(format!("hitstop {frames}"));
+    }
+    if let Some(stun) = attack.dizzy {
+        words.push(format!("dizzy {stun}"));
+    }
+    if attack.proration != standard.proration {
+        words.push(format!("proration {}", attack.proration));
+    }
+    if attack.hit_group != standard.hit_group {
+        words.push(format!("group {}", attack.hit_group));
+    }
+    let flags = [
+        (attack.is_overhead, "overhead"),
+        (attack.is_low, "low"),
+        (!attack.can_block, "unblockable"),
+        (attack.can_otg, "otg"),
+        (attack.is_super, "super"),
+    ];
+    words.extend(
+        flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, word)| word.to_string()),
+    );
+    match attack.knockdown {
+        KnockdownType::None => {}
+        KnockdownType::Soft => words.push("soft_knockdown".into()),
+        KnockdownType::Hard => words.push("hard_knockdown".into()),
+        KnockdownType::Sweep => words.push("sweep".into()),
+        KnockdownType::Launcher => words.push("launcher".into()),
+    }
+    Some(words.join(" "))
+}
+
+/// `StateId` from its variant name, or `Custom(<n>)` and `WinPose(<n>)`
+fn state_id(name: &str) -> Option<StateId> {
+    if let Some(number) = name
+        .strip_prefix("Custom(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return number.parse().ok().map(StateId::Custom);
+    }
+    if let Some(number) = name
+        .strip_prefix("WinPose(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return number.parse().ok().map(StateId::WinPose);
+    }
+    Some(match name {
+        "Idle" => StateId::Idle,
+        "Walk" => StateId::Walk,
+        "WalkBack" => StateId::WalkBack,
+        "Crouch" => StateId::Crouch,
+        "Jump" => StateId::Jump,
+        "LightAttack" => StateId::LightAttack,
+        "MediumAttack" => StateId::MediumAttack,
+        "HeavyAttack" => StateId::HeavyAttack,
+        "Sweep" => StateId::Sweep,
+        "SpecialMove" => StateId::SpecialMove,
+        "Hitstun" => StateId::Hitstun,
+        "Blockstun" => StateId::Blockstun,
+        "Knockdown" => StateId::Knockdown,
+        "TechNeutral" => StateId::TechNeutral,
+        "TechBack" => StateId::TechBack,
+        "TechForward" => StateId::TechForward,
+        "HeavyCharge" => StateId::HeavyCharge,
+        "ChargedHeavy" => StateId::ChargedHeavy,
+        "Held" => StateId::Held,
+        "Throw" => StateId::Throw,
+        "Throwing" => StateId::Throwing,
+        "ThrowWhiff" => StateId::ThrowWhiff,
+        "BackHitstun" => StateId::BackHitstun,
+        "ThrowTech" => StateId::ThrowTech,
+        "ExSpecial" => StateId::ExSpecial,
+        "SuperMove" => StateId::SuperMove,
+        "JumpLight" => StateId::JumpLight,
+        "JumpMedium" => StateId::JumpMedium,
+        "JumpHeavy" => StateId::JumpHeavy,
+        "Landing" => StateId::Landing,
+        "Dizzy" => StateId::Dizzy,
+        "LosePose" => StateId::LosePose,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::states;
+
+    const RUSHDOWN: &str = include_str!("../data/rushdown.chr");
+
+    #[test]
+    fn test_data_file_matches_rust_archetype() {
+        let parsed = parse_character(RUSHDOWN).unwrap();
+        let built = CharacterDef::rushdown();
+        assert_eq!(
+            (parsed.health, parsed.weight, parsed.gravity_percent),
+            (built.health, built.weight, built.gravity_percent)
+        );
+        let (parsed, built) = (parsed.states, built.states);
+        assert_eq!(parsed.len(), built.len());
+        for id in [
+            StateId::Walk,
+            StateId::WalkBack,
+            StateId::LightAttack,
+            StateId::SpecialMove,
+        ] {
+            let (a, b) = (parsed.find(id).unwrap(), built.find(id).unwrap());
+            assert_eq!(a.state_type, b.state_type);
+            assert_eq!(a.duration, b.duration);
+            assert_eq!(a.footing, b.footing);
+            assert_eq!(a.cancels, b.cancels);
+            assert_eq!(format!("{:?}", a.frame_data), format!("{:?}", b.frame_data));
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_the_line() {
+        let error = |text: &str| parse_character(text).err().unwrap();
+
+        assert_eq!(
+            error("version 1\nstate Idle normal 1\nend\nstate Nope attack 3"),
+            CharacterError {
+                line: 4,
+                kind: CharacterErrorKind::UnknownState("Nope".into())
+            }
+        );
+        assert_eq!(
+            error("version 1\nstate Custom(7) attack 10\n  frame 2 hitbox 1 2 3 x damage 5").kind,
+            CharacterErrorKind::BadNumber("x".into())
+        );
+        assert_eq!(
+            error("version 1\nstate Idle normal 1\n  frame 0 velocity 1 2 3\nend").kind,
+            CharacterErrorKind::TrailingInput("3".into())
+        );
+        assert_eq!(
+            error("version 1\nframe 0 halt").kind,
+            CharacterErrorKind::OutsideState
+        );
+        assert_eq!(
+            error("version 1\nstate Idle normal 1\n# comment\n").kind,
+            CharacterErrorKind::UnclosedState
+        );
+        assert_eq!(
+            error("version 1\nstate Idle normal").kind,
+            CharacterErrorKind::MissingValue
+        );
+        assert_eq!(
+            error("version 1\nstate Idle normal 1\n  body stand 0 0 1 1\nend").kind,
+            CharacterErrorKind::InsideState
+        );
+        assert_eq!(
+            error("version 1\nstate Idle attack 9\n  frame 2 hitbox 1 2 3 4 damage 5 dizzy -20")
+                .kind,
+            CharacterErrorKind::BadNumber("-20".into())
+        );
+    }
+
+    #[test]
+    fn test_states_past_the_limit_are_refused() {
+        let free = MAX_STATES - CharacterDef::default().states.len();
+        let mut text = String::from("version 1\n");
+        for id in 0..=free {
+            text += &format!("state Custom({id}) normal 1\nend\n");
+        }
+        assert_eq!(
+            parse_character(&text).err(),
+            Some(CharacterError {
+                line: 2 + 2 * free,
+                kind: CharacterErrorKind::TooManyStates
+            })
+        );
+
+        // Redefining a state doesn't need a free slot
+        text.truncate(text.rfind("state").unwrap());
+        text += "state Idle normal 1\nend\n";
+        assert_eq!(parse_character(&text).unwrap().states.len(), MAX_STATES);
+    }
+
+    #[test]
+    fn test_files_carry_the_engine_data_version() {
+        let error = |text: &str| parse_character(text).err().unwrap().kind;
+        let current = format!("# comment\n\nversion {}\nhealth 800", ENGINE_DATA_VERSION);
+        assert_eq!(parse_character(&current).unwrap().health, 800);
+
+        let other = ENGINE_DATA_VERSION + 1;
+        assert_eq!(
+            error(&format!("version {}\nhealth 800", other)),
+            CharacterErrorKind::EngineVersion(other)
+        );
+        assert_eq!(error("health 800"), CharacterErrorKind::MissingVersion);
+        assert_eq!(error(""), CharacterErrorKind::MissingVersion);
+        assert_eq!(
+            error("version 1\nversion 1"),
+            CharacterErrorKind::UnknownKeyword("version".into())
+        );
+    }
+
+    #[test]
+    fn test_custom_states_and_options() {
+        let character = parse_character(
+            "
+            version 1
+            body crouch 0 12000 9000 13000
+            gravity 80
+
+            state Custom(41) normal forever
+            end
+
+            state WinPose(3) normal forever
+            end
+
+            state Custom(42) attack 30   # command overhead
+              meter 500
+              frame 0 spend_meter 500
+              frame 2 armor forever 6 50
+              frame 8 hitbox 0 0 100 100 damage 90 overhead hard_knockdown hitstop 12 dizzy 150
+              frame 20 goto Idle
+            end
+            ",
+        )
+        .unwrap();
+        assert_eq!(character.gravity_percent, 80);
+        let set = &character.states;
+        let state = set.find(StateId::Custom(42)).unwrap();
+        assert_eq!(state.meter_required, 500);
+        assert_eq!(state.first_active_frame(), Some(8));
+        assert!(matches!(
+            state.frame_data[1].action,
+            StateAction::SetArmor {
+                hits: u32::MAX,
+                frames: 6,
+                damage_percent: 50
+            }
+        ));
+        assert!(write_state(state)
+            .unwrap()
+            .contains("frame 2 armor forever 6 50\n"));
+        let StateAction::Hitbox { attack, .. } = state.frame_data[2].action else {
+            panic!("expected a hitbox");
+        };
+        assert_eq!(
+            attack,
+            AttackData::new(90)
+                .overhead()
+                .hard_knockdown()
+                .with_hitstop(12)
+                .with_dizzy(150)
+        );
+        assert_eq!(set.find(StateId::Custom(41)).unwrap().duration, u32::MAX);
+        assert!(set.find(StateId::WinPose(3)).is_some());
+        assert_eq!(set.body.crouch, Rect::new(0, 12000, 9000, 13000));
+        // Default states and the default standing body are still there
+        assert!(set.find(StateId::Hitstun).is_some());
+        assert_eq!(set.body.stand, states::default_set().body.stand);
+    }
+
+    #[test]
+    fn test_written_states_parse_back() {
+        let rushdown = parse_character(RUSHDOWN).unwrap().states;
+        let defaults = states::default_set();
+        let mut written = 0;
+        for set in [&defaults, &rushdown] {
+            for id in [
+                StateId::Walk,
+                StateId::Crouch,
+                StateId::Jump,
+                StateId::JumpHeavy,
+                StateId::LightAttack,
+                StateId::Sweep,
+                StateId::SpecialMove,
+                StateId::Landing,
+            ] {
+                let Some(text) = set.find(id).and_then(write_state) else {
+                    continue;
+                };
+                let versioned = format!("version {}\n{}", ENGINE_DATA_VERSION, text);
+                let parsed = parse_character(&versioned).unwrap().states;
+                let (a, b) = (set.find(id).unwrap(), parsed.find(id).unwrap());
+                assert_eq!(a.duration, b.duration);
+                assert_eq!(a.can_cancel, b.can_cancel);
+                assert_eq!(a.footing, b.footing);
+                assert_eq!(a.cancels, b.cancels);
+                assert_eq!(format!("{:?}", a.frame_data), format!("{:?}", b.frame_data));
+                assert_eq!(write_state(b), Some(text));
+                written += 1;
+            }
+        }
+        assert!(written >= 14);
+
+        // Grabs have no text form
+        let grab =
+            State::new(StateId::Custom(3), StateType::Attack, 20).add_frame_data(FrameData::new(
+                2,
+                StateAction::Hitbox {
+                    x: 0,
+                    y: 0,
+                    width: 1,
+                    height: 1,
+                    attack: AttackData::new(10).hit_grab(StateId::Throwing),
+                },
+            ));
+        assert_eq!(write_state(&grab), None);
+    }
+}
@@ -0,0 +1,518 @@
+//! Deterministic state checksums for desync detection
+//!
+//! Netplay peers hash their simulation every frame and compare digests;
+//! the first mismatch marks the frame the simulations diverged, and
+//! `EngineSnapshot::diff` can then say which fields differ.
+//!
+//! The digest is FNV-1a over an explicit list of gameplay fields, each
+//! written at a fixed width in little endian, so it's identical across
+//! platforms (native and wasm32 alike) and doesn't depend on std's
+//! `Hash` implementations, which hash `usize` at the platform width.
+//! Every field a snapshot captures goes in, except display-only state
+//! (presentation offsets, lifebar trails, audiovisual cues) and the
+//! character definitions the state machines point at, which are data
+//! rather than state.
+
+use crate::engine::Engine;
+use crate::entity::{Entity, Physics};
+use crate::hitbox::{AttackData, Invulnerability, Team};
+use crate::projectile::{Motion, Projectile, ProjectileDef};
+use crate::state::StateId;
+use crate::types::{EntityId, Rect, Vec2};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a digest builder
+#[derive(Debug, Clone, Copy)]
+pub struct StateHasher {
+    hash: u64,
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self {
+            hash: FNV_OFFSET_BASIS,
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.write_bytes(&[value]);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_vec2(&mut self, value: Vec2) {
+        self.write_i32(value.x);
+        self.write_i32(value.y);
+    }
+
+    pub fn write_rect(&mut self, value: Rect) {
+        self.write_i32(value.x);
+        self.write_i32(value.y);
+        self.write_i32(value.width);
+        self.write_i32(value.height);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl Engine {
+    /// Digest of the gameplay state: equal on every peer running the same
+    /// inputs, different as soon as anything that affects play diverges
+    pub fn state_hash(&self) -> u64 {
+        let mut h = StateHasher::new();
+        h.write_u64(self.frame.0);
+        h.write_u32(self.entity_count as u32);
+        h.write_u32(self.game_result.to_u32());
+        h.write_u32(self.outro_remaining);
+        h.write_u32(self.round);
+        for wins in self.round_wins {
+            h.write_u32(wins);
+        }
+        h.write_u64(self.round_start_frame.0);
+        for bonus in self.round_bonus {
+            h.write_i32(bonus);
+        }
+
+        match &self.link {
+            Some(link) => {
+                h.write_bool(true);
+                write_id(&mut h, link.attacker);
+                write_id(&mut h, link.victim);
+                h.write_u32(link.attacker_state.to_u32());
+                h.write_u32(link.victim_state.to_u32());
+                h.write_bool(link.is_super);
+                match link.tech {
+                    Some(tech) => {
+                        h.write_bool(true);
+                        h.write_u32(tech.frames);
+                        h.write_i32(tech.damage);
+                        write_cue(&mut h, tech.cue);
+                        h.write_vec2(tech.contact_point);
+                    }
+                    None => h.write_bool(false),
+                }
+            }
+            None => h.write_bool(false),
+        }
+
+        for entity in &self.entities {
+            match entity {
+                Some(entity) => {
+                    h.write_bool(true);
+                    hash_entity(&mut h, entity);
+                }
+                None => h.write_bool(false),
+            }
+        }
+        for projectile in &self.projectiles {
+            match projectile {
+                Some(projectile) => {
+                    h.write_bool(true);
+                    hash_projectile(&mut h, projectile);
+                }
+                None => h.write_bool(false),
+            }
+        }
+        self.input_manager.hash_state(&mut h);
+        h.finish()
+    }
+}
+
+fn write_id(h: &mut StateHasher, id: EntityId) {
+    h.write_u32(id.0);
+}
+
+fn write_state(h: &mut StateHasher, state: Option<StateId>) {
+    h.write_u32(state.map_or(u32::MAX, StateId::to_u32));
+}
+
+fn write_cue(h: &mut StateHasher, cue: Option<u16>) {
+    h.write_u32(cue.map_or(u32::MAX, u32::from));
+}
+
+fn write_team(h: &mut StateHasher, team: Team) {
+    match team {
+        Team::Neutral => h.write_bool(false),
+        Team::Side(side) => {
+            h.write_bool(true);
+            h.write_u8(side);
+        }
+    }
+}
+
+fn write_invuln(h: &mut StateHasher, invuln: Invulnerability) {
+    h.write_bool(invuln.strike);
+    h.write_bool(invuln.throw);
+    h.write_bool(invuln.projectile);
+}
+
+fn hash_physics(h: &mut StateHasher, p: &Physics) {
+    h.write_vec2(p.position);
+    h.write_vec2(p.velocity);
+    h.write_vec2(p.momentum);
+    h.write_i32(p.gravity);
+    h.write_bool(p.on_ground);
+    h.write_i32(p.ground_friction);
+    h.write_i32(p.air_drag);
+    h.write_i32(p.gravity_scale);
+    h.write_i32(p.max_fall_speed);
+    h.write_u32(p.pinned_frames);
+    h.write_u32(p.vertical_freeze);
+}
+
+fn hash_entity(h: &mut StateHasher, e: &Entity) {
+    write_id(h, e.id);
+    h.write_u8(e.player_id.0);
+    write_team(h, e.team);
+    h.write_i32(e.facing.sign());
+    h.write_i32(e.health.current);
+    h.write_i32(e.health.maximum);
+    hash_physics(h, &e.physics);
+    e.state_machine.hash_state(h);
+    h.write_u32(e.hitstun_remaining);
+    h.write_u32(e.blockstun_remaining);
+    h.write_u32(e.knockdown_remaining);
+    h.write_bool(e.can_tech);
+    h.write_u8(e.tech_direction as u8);
+    h.write_u32(e.invuln_remaining);
+    write_invuln(h, e.invuln);
+    h.write_u32(e.armor_remaining);
+    h.write_u32(e.armor_hits);
+    h.write_i32(e.armor_damage_percent);
+    h.write_u32(e.attack_delay);
+    h.write_u32(e.wakeup_delay_frames);
+    h.write_u32(e.wakeup_delay_applied);
+    h.write_i32(e.weight);
+    h.write_i32(e.meter);
+    h.write_i32(e.meter_gain);
+    h.write_i32(e.guard);
+    h.write_u32(e.crush_protection_remaining);
+    h.write_i32(e.dizzy);
+    h.write_u32(e.dizzy_remaining);
+    for resource in &e.resources {
+        match resource {
+            Some(resource) => {
+                h.write_u8(resource.remaining);
+                h.write_u8(resource.uses);
+                h.write_u8(resource.scope as u8);
+            }
+            None => h.write_u8(u8::MAX),
+        }
+    }
+    h.write_u32(e.combo_hits);
+    h.write_i32(e.combo_damage);
+    h.write_i32(e.combo_proration);
+    h.write_i32(e.defense);
+    e.stale_moves.hash_state(h);
+    e.blockstring.hash_state(h);
+    h.write_u32(e.trajectory_state.to_u32());
+    h.write_i32(e.opponent_distance.unwrap_or(i32::MIN));
+    h.write_bool(e.opponent_in_combo);
+    match e.spawn {
+        Some((state, index)) => {
+            h.write_bool(true);
+            h.write_u32(state.to_u32());
+            h.write_u32(index as u32);
+        }
+        None => h.write_bool(false),
+    }
+    h.write_u32(e.pusher.map_or(u32::MAX, |id| id.0));
+    h.write_bool(e.air_attacked);
+    h.write_u32(e.frames_since_action);
+    h.write_u32(e.frozen_frames);
+    h.write_u32(e.flash.unwrap_or(u32::MAX));
+    match e.install {
+        Some(install) => {
+            h.write_bool(true);
+            h.write_u32(install.remaining);
+            h.write_i32(install.modifier.startup);
+            h.write_i32(install.modifier.recovery);
+        }
+        None => h.write_bool(false),
+    }
+}
+
+fn hash_attack(h: &mut StateHasher, a: &AttackData) {
+    h.write_i32(a.damage);
+    h.write_u32(a.hitstun);
+    h.write_u32(a.blockstun);
+    h.write_i32(a.pushback_x);
+    h.write_i32(a.pushback_y);
+    h.write_i32(a.block_pushback);
+    h.write_bool(a.can_block);
+    h.write_bool(a.is_overhead);
+    h.write_bool(a.is_low);
+    h.write_u8(a.knockdown as u8);
+    h.write_bool(a.can_otg);
+    h.write_u8(a.hit_group);
+    h.write_u32(a.hitstop.unwrap_or(u32::MAX));
+    write_cue(h, a.hit_cue);
+    write_state(h, a.grab_state);
+    h.write_i32(a.proration);
+    match a.team {
+        Some(team) => {
+            h.write_bool(true);
+            write_team(h, team);
+        }
+        None => h.write_bool(false),
+    }
+    h.write_bool(a.techable);
+    h.write_bool(a.is_super);
+    h.write_i32(a.dizzy.unwrap_or(i32::MIN));
+}
+
+fn hash_projectile_def(h: &mut StateHasher, d: &ProjectileDef) {
+    h.write_rect(d.hitbox);
+    hash_attack(h, &d.attack);
+    h.write_vec2(d.velocity);
+    match d.motion {
+        Motion::Straight => h.write_u8(0),
+        Motion::Arc { gravity } => {
+            h.write_u8(1);
+            h.write_i32(gravity);
+        }
+        Motion::Homing { speed, turn_rate } => {
+            h.write_u8(2);
+            h.write_i32(speed);
+            h.write_i32(turn_rate);
+        }
+    }
+    h.write_u32(d.lifetime);
+    match &d.residual {
+        Some(residual) => {
+            h.write_bool(true);
+            h.write_rect(residual.hitbox);
+            hash_attack(h, &residual.attack);
+            h.write_u32(residual.frames);
+        }
+        None => h.write_bool(false),
+    }
+    h.write_bool(d.reflectable);
+    h.write_bool(d.absorbable);
+}
+
+fn hash_projectile(h: &mut StateHasher, p: &Projectile) {
+    write_id(h, p.id);
+    write_id(h, p.owner);
+    write_team(h, p.team);
+    h.write_i32(p.facing.sign());
+    h.write_vec2(p.position);
+    h.write_vec2(p.velocity);
+    hash_projectile_def(h, &p.def);
+    h.write_u32(p.age);
+    h.write_u32(p.residual_remaining);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Direction, InputState};
+    use crate::link::{PairLink, TechWindow};
+    use crate::state::{FrameDataModifier, State, StateType};
+    use crate::types::{Facing, Frame};
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        assert_eq!(StateHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+        let mut h = StateHasher::new();
+        h.write_bytes(b"a");
+        assert_eq!(h.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_state_hash_tracks_divergence() {
+        let run = |p2_light_on: u64| {
+            let mut engine = Engine::new();
+            engine.init_match();
+            let mut hashes = Vec::new();
+            for frame in 0..30 {
+                let p2 = InputState {
+                    light: frame == p2_light_on,
+                    ..InputState::neutral()
+                };
+                engine.tick(InputState::neutral(), p2);
+                hashes.push(engine.state_hash());
+            }
+            hashes
+        };
+
+        let reference = run(20);
+        assert_eq!(reference, run(20));
+        let diverged = run(10);
+        assert_eq!(reference[..10], diverged[..10]);
+        assert!(reference[10..]
+            .iter()
+            .zip(&diverged[10..])
+            .all(|(a, b)| a != b));
+
+        // Restoring a snapshot restores the hash
+        let mut engine = Engine::new();
+        engine.init_match();
+        let snapshot = engine.save_snapshot();
+        let hash = engine.state_hash();
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_ne!(engine.state_hash(), hash);
+        snapshot.restore(&mut engine);
+        assert_eq!(engine.state_hash(), hash);
+    }
+
+    #[test]
+    fn test_state_hash_covers_every_gameplay_field() {
+        let mut base = Engine::new();
+        base.init_match();
+        let id = |engine: &Engine, i: usize| engine.entities[i].as_ref().unwrap().id;
+        let (p1, p2) = (id(&base, 0), id(&base, 1));
+        // Player 2 stands in a mashable state, so presses get counted
+        let stagger = StateId::Custom(1);
+        let victim = base.entities[1].as_mut().unwrap();
+        let state = State::new(stagger, StateType::Hurt, 60).with_mash(5, 20);
+        victim.state_machine.register_state(state);
+        victim.state_machine.transition(stagger);
+        // States match the fighters' so restoring keeps the link
+        base.link = Some(PairLink {
+            attacker: p1,
+            victim: p2,
+            attacker_state: StateId::Idle,
+            victim_state: stagger,
+            tech: Some(TechWindow {
+                frames: 5,
+                damage: 100,
+                cue: None,
+                contact_point: Vec2::ZERO,
+            }),
+            is_super: false,
+        });
+        let def = ProjectileDef::new(
+            Rect::new(0, 0, 1000, 1000),
+            AttackData::new(50),
+            Vec2::new(500, 0),
+            60,
+        );
+        base.projectiles[0] = Some(Projectile::new(
+            EntityId(10),
+            p1,
+            Facing::Right,
+            Vec2::ZERO,
+            def,
+        ));
+        let hash = base.state_hash();
+        let snapshot = base.save_snapshot();
+
+        type Change = (&'static str, fn(&mut Engine));
+        let changes: &[Change] = &[
+            ("entity_count", |e| e.entity_count += 1),
+            ("round_bonus", |e| e.round_bonus[0] += 1),
+            ("link.tech.frames", |e| {
+                e.link.as_mut().unwrap().tech.as_mut().unwrap().frames += 1
+            }),
+            ("link.is_super", |e| {
+                e.link.as_mut().unwrap().is_super = true
+            }),
+            ("pinned_frames", |e| fighter(e).physics.pinned_frames += 1),
+            ("vertical_freeze", |e| {
+                fighter(e).physics.vertical_freeze += 1
+            }),
+            ("gravity_scale", |e| fighter(e).physics.gravity_scale += 1),
+            ("max_fall_speed", |e| fighter(e).physics.max_fall_speed += 1),
+            ("crush_protection", |e| {
+                fighter(e).crush_protection_remaining += 1
+            }),
+            ("stale_moves", |e| {
+                fighter(e).stale_moves.push(StateId::LightAttack, 5)
+            }),
+            ("team", |e| fighter(e).team = Team::Neutral),
+            ("invuln", |e| fighter(e).invuln.throw = false),
+            ("can_tech", |e| fighter(e).can_tech = !fighter(e).can_tech),
+            ("tech_direction", |e| {
+                fighter(e).tech_direction = Direction::Back
+            }),
+            ("wakeup_delay_applied", |e| {
+                fighter(e).wakeup_delay_applied += 1
+            }),
+            ("wakeup_delay_frames", |e| {
+                fighter(e).wakeup_delay_frames += 1
+            }),
+            ("weight", |e| fighter(e).weight += 1),
+            ("meter_gain", |e| fighter(e).meter_gain += 1),
+            ("defense", |e| fighter(e).defense += 1),
+            ("blockstring", |e| fighter(e).blockstring.record_block(true)),
+            ("trajectory_state", |e| {
+                fighter(e).trajectory_state = StateId::Jump
+            }),
+            ("pusher", |e| fighter(e).pusher = Some(EntityId(99))),
+            ("air_attacked", |e| fighter(e).air_attacked = true),
+            ("hit_groups", |e| fighter(e).state_machine.mark_hit_group(1)),
+            ("mash_presses", |e| {
+                e.entities[1]
+                    .as_mut()
+                    .unwrap()
+                    .state_machine
+                    .register_mash(1)
+            }),
+            ("held", |e| fighter(e).state_machine.set_held(true)),
+            ("modifier", |e| {
+                let modifier = FrameDataModifier::new(-1, 0);
+                fighter(e).state_machine.set_modifier(modifier)
+            }),
+            ("projectile.team", |e| projectile(e).team = Team::Side(1)),
+            ("projectile.damage", |e| {
+                projectile(e).def.attack.damage += 1
+            }),
+            ("projectile.lifetime", |e| projectile(e).def.lifetime += 1),
+            ("latency_offset", |e| {
+                e.input_manager.set_latency_offset(0, 2)
+            }),
+            ("action_buffer", |e| e.input_manager.set_action_buffer(1)),
+            ("last_frame", |e| {
+                let neutral = InputState::neutral();
+                let _ = e.input_manager.update_player_input(0, Frame(0), neutral);
+            }),
+        ];
+        for (field, change) in changes {
+            let mut engine = Engine::new();
+            snapshot.restore(&mut engine);
+            assert_eq!(engine.state_hash(), hash);
+            change(&mut engine);
+            assert_ne!(engine.state_hash(), hash, "{} isn't hashed", field);
+        }
+    }
+
+    fn fighter(engine: &mut Engine) -> &mut Entity {
+        engine.entities[0].as_mut().unwrap()
+    }
+
+    fn projectile(engine: &mut Engine) -> &mut Projectile {
+        engine.projectiles[0].as_mut().unwrap()
+    }
+}
@@ -13,9 +13,11 @@ pub struct PhysicsConfig {
     pub gravity: i32,
     /// Ground level Y coordinate
     pub ground_level: i32,
-    /// Momentum decay percentage (0-100)
-    pub momentum_decay_percent: i32,
-    /// Knockback threshold for launching into air
+    /// Momentum kept per grounded frame, in percent (0-100)
+    pub ground_friction_percent: i32,
+    /// Momentum kept per airborne frame, in percent (0-100)
+    pub air_drag_percent: i32,
+    /// Minimum upward velocity applied by launchers
     pub knockback_threshold: i32,
 }
 
@@ -24,7 +26,8 @@ impl Default for PhysicsConfig {
         Self {
             gravity: GRAVITY,
             ground_level: GROUND_LEVEL,
-            momentum_decay_percent: MOMENTUM_DECAY_PERCENT,
+            ground_friction_percent: GROUND_FRICTION_PERCENT,
+            air_drag_percent: AIR_DRAG_PERCENT,
             knockback_threshold: KNOCKBACK_THRESHOLD,
         }
     }
@@ -32,11 +35,14 @@ impl Default for PhysicsConfig {
 
 impl PhysicsConfig {
     /// Creates a new physics config with custom values
+    ///
+    /// `momentum_decay_percent` is used for both ground friction and air drag.
     pub fn new(gravity: i32, ground_level: i32, momentum_decay_percent: i32) -> Self {
         Self {
             gravity,
             ground_level,
-            momentum_decay_percent,
+            ground_friction_percent: momentum_decay_percent,
+            air_drag_percent: momentum_decay_percent,
             knockback_threshold: KNOCKBACK_THRESHOLD,
         }
     }
@@ -60,7 +66,16 @@ impl PhysicsConfig {
     /// Creates a config with fast momentum decay (less slidey)
     pub fn fast_decay() -> Self {
         Self {
-            momentum_decay_percent: 70,
+            ground_friction_percent: 70,
+            air_drag_percent: 70,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a config with floaty juggles but normal ground slides
+    pub fn floaty_juggles() -> Self {
+        Self {
+            air_drag_percent: 97,
             ..Default::default()
         }
     }
@@ -68,12 +83,91 @@ impl PhysicsConfig {
     /// Creates a config with slow momentum decay (more slidey)
     pub fn slow_decay() -> Self {
         Self {
-            momentum_decay_percent: 95,
+            ground_friction_percent: 95,
+            air_drag_percent: 95,
             ..Default::default()
         }
     }
 }
 
+/// Bundled stage layouts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StagePreset {
+    /// Walled arena of standard width
+    #[default]
+    Standard,
+    /// Small walled arena; corners come quickly
+    Narrow,
+    /// Large walled arena with plenty of room to retreat
+    Wide,
+    /// Standard width with a low ceiling that cuts jumps short
+    Corridor,
+    /// Open platform: fighters stop at the edges but there is no wall to
+    /// push against, so cornered pushback isn't passed to the attacker
+    Platform,
+}
+
+/// What stops fighters at the stage edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageEdge {
+    /// Solid walls; pushback against a wall moves the attacker instead
+    Wall,
+    /// Ledges; fighters stop at the edge and pushback is lost
+    Ledge,
+}
+
+/// Stage layout
+///
+/// Renderers and cameras can read the layout from `Engine::config.stage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageConfig {
+    /// Preset this layout was built from
+    pub preset: StagePreset,
+    /// Distance from the stage center to each edge
+    pub half_width: i32,
+    /// Whether the edges are walls or ledges
+    pub edge: StageEdge,
+    /// Highest point a fighter can reach above the ground (None = open sky)
+    pub ceiling: Option<i32>,
+}
+
+impl Default for StageConfig {
+    fn default() -> Self {
+        Self::preset(StagePreset::Standard)
+    }
+}
+
+impl StageConfig {
+    /// Layout of a bundled stage
+    pub fn preset(preset: StagePreset) -> Self {
+        let standard = Self {
+            preset,
+            half_width: STAGE_HALF_WIDTH,
+            edge: StageEdge::Wall,
+            ceiling: None,
+        };
+        match preset {
+            StagePreset::Standard => standard,
+            StagePreset::Narrow => Self {
+                half_width: STAGE_HALF_WIDTH * 2 / 3,
+                ..standard
+            },
+            StagePreset::Wide => Self {
+                half_width: STAGE_HALF_WIDTH * 5 / 3,
+                ..standard
+            },
+            StagePreset::Corridor => Self {
+                ceiling: Some(CORRIDOR_CEILING),
+                ..standard
+            },
+            StagePreset::Platform => Self {
+                edge: StageEdge::Ledge,
+                ..standard
+            },
+        }
+    }
+}
+
 /// Input configuration for motion detection and buffering
 #[derive(Debug, Clone, Copy)]
 pub struct InputConfig {
@@ -81,6 +175,12 @@ pub struct InputConfig {
     pub buffer_size: usize,
     /// Motion detection window in frames
     pub detection_window: usize,
+    /// Frames each player's inputs are held back, to even out known
+    /// device latency differences
+    pub latency_offset: [u32; MAX_PLAYERS],
+    /// Frames attack presses are held while the fighter can't act yet
+    /// (0 = presses only count on the frame they happen)
+    pub action_buffer_frames: u32,
 }
 
 impl Default for InputConfig {
@@ -88,6 +188,8 @@ impl Default for InputConfig {
         Self {
             buffer_size: INPUT_BUFFER_SIZE,
             detection_window: MOTION_DETECTION_WINDOW,
+            latency_offset: [0; MAX_PLAYERS],
+            action_buffer_frames: ACTION_BUFFER_FRAMES,
         }
     }
 }
@@ -98,6 +200,7 @@ impl InputConfig {
         Self {
             buffer_size,
             detection_window,
+            ..Default::default()
         }
     }
 
@@ -118,6 +221,141 @@ impl InputConfig {
     }
 }
 
+/// Guts threshold: at or below `health_percent` of maximum health, the
+/// defender takes `damage_percent` of incoming damage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GutsTier {
+    pub health_percent: i32,
+    pub damage_percent: i32,
+}
+
+impl GutsTier {
+    pub const fn new(health_percent: i32, damage_percent: i32) -> Self {
+        Self {
+            health_percent,
+            damage_percent,
+        }
+    }
+}
+
+/// Damage pipeline configuration
+///
+/// Defaults leave damage untouched; `standard()` enables typical scaling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DamageConfig {
+    /// Combo hits landed before scaling starts
+    pub combo_scaling_start: u32,
+    /// Damage removed per scaled combo hit, in percent
+    pub combo_scaling_step_percent: i32,
+    /// Lowest combo scaling, in percent
+    pub combo_scaling_floor_percent: i32,
+    /// Guts tiers: damage taken shrinks as health crosses each threshold
+    pub guts: [Option<GutsTier>; MAX_GUTS_TIERS],
+    /// Smallest damage a damaging hit can deal after scaling
+    pub minimum_damage: i32,
+    /// Recent hits remembered per player for stale-move negation (0 = off)
+    pub stale_queue_length: usize,
+    /// Damage removed per stale copy of a move in the queue, in percent
+    pub stale_step_percent: i32,
+}
+
+impl Default for DamageConfig {
+    fn default() -> Self {
+        Self {
+            combo_scaling_start: 0,
+            combo_scaling_step_percent: 0,
+            combo_scaling_floor_percent: 100,
+            guts: [None; MAX_GUTS_TIERS],
+            minimum_damage: 0,
+            stale_queue_length: 0,
+            stale_step_percent: 0,
+        }
+    }
+}
+
+impl DamageConfig {
+    /// Typical fighting game scaling: 10% per hit after the second, down to 30%
+    pub fn standard() -> Self {
+        Self {
+            combo_scaling_start: 2,
+            combo_scaling_step_percent: 10,
+            combo_scaling_floor_percent: 30,
+            guts: [
+                Some(GutsTier::new(50, 90)),
+                Some(GutsTier::new(30, 80)),
+                Some(GutsTier::new(10, 70)),
+                None,
+            ],
+            minimum_damage: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Platform-fighter style stale-move negation on top of default damage
+    pub fn stale_moves() -> Self {
+        Self {
+            stale_queue_length: MAX_STALE_QUEUE,
+            stale_step_percent: 5,
+            minimum_damage: 1,
+            ..Default::default()
+        }
+    }
+}
+
+/// Default feel of attacks dealing at least `min_damage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HitFeelTier {
+    pub min_damage: i32,
+    /// Hitstop frames on contact
+    pub hitstop: u32,
+    /// Hit sound/effect cue reported with the hit event
+    pub cue: u16,
+}
+
+impl HitFeelTier {
+    pub const fn new(min_damage: i32, hitstop: u32, cue: u16) -> Self {
+        Self {
+            min_damage,
+            hitstop,
+            cue,
+        }
+    }
+}
+
+/// Hitstop and hit cue defaults by damage tier
+///
+/// Attacks without an explicit hitstop or cue take them from the highest
+/// tier their base damage reaches. Defaults have no tiers (no hitstop, no
+/// cue); `standard()` gives light/medium/heavy/super tiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HitFeelConfig {
+    pub tiers: [Option<HitFeelTier>; MAX_HIT_FEEL_TIERS],
+}
+
+impl HitFeelConfig {
+    /// Typical tiers: 8/10/12/16 hitstop frames with cues 1-4
+    pub fn standard() -> Self {
+        Self {
+            tiers: [
+                Some(HitFeelTier::new(0, 8, 1)),
+                Some(HitFeelTier::new(70, 10, 2)),
+                Some(HitFeelTier::new(100, 12, 3)),
+                Some(HitFeelTier::new(200, 16, 4)),
+            ],
+        }
+    }
+
+    /// Tier an attack of `damage` falls into
+    pub fn tier_for(&self, damage: i32) -> Option<HitFeelTier> {
+        self.tiers
+            .iter()
+            .flatten()
+            .filter(|tier| damage >= tier.min_damage)
+            .max_by_key(|tier| tier.min_damage)
+            .copied()
+    }
+}
+
 /// Game rule configuration
 #[derive(Debug, Clone, Copy)]
 pub struct GameConfig {
@@ -127,6 +365,239 @@ pub struct GameConfig {
     pub time_limit_frames: u64,
     /// Number of rounds to win
     pub rounds_to_win: u32,
+    /// Extra frames a downed player stays down when holding a button
+    pub wakeup_delay_frames: u32,
+    /// Frames ticks keep running in win and lose poses after the match is
+    /// decided (0 = stop at once)
+    pub outro_frames: u32,
+    /// How a round ends when both players are KO'd on the same frame
+    pub double_ko: DoubleKoPolicy,
+    /// How hits from behind can be blocked
+    pub behind_block: BehindBlockPolicy,
+    /// What carries over from one round to the next
+    pub persistence: RoundPersistence,
+    /// Meter handed out at the start of each new round
+    pub round_bonus: RoundBonus,
+    /// Bonuses of hits landing during the defender's attack startup
+    pub counter_hit: CounterHitConfig,
+    /// Guard meter drained by blocking, and the guard crush it leads to
+    pub guard: GuardConfig,
+    /// Who wins when a jump-in and an anti-air hit each other
+    pub anti_air: AntiAirConfig,
+    /// Blocks started at the last moment
+    pub just_defend: JustDefendConfig,
+    /// Stun built up by taking hits, and the dizzy it leads to
+    pub dizzy: DizzyConfig,
+}
+
+/// Bonuses of a counter hit: a clean hit on a defender still in the
+/// startup frames of an attack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterHitConfig {
+    /// Damage dealt, in percent of the normal damage
+    pub damage_percent: i32,
+    /// Hitstun frames added to the attack's own
+    pub hitstun_bonus: u32,
+}
+
+impl Default for CounterHitConfig {
+    fn default() -> Self {
+        Self {
+            damage_percent: COUNTER_HIT_DAMAGE_PERCENT,
+            hitstun_bonus: COUNTER_HIT_HITSTUN_BONUS,
+        }
+    }
+}
+
+/// Guard meter: blocked hits drain it, and blocking with it empty is a
+/// guard crush that leaves the defender open
+///
+/// The crush stun scales with the crushing attack's hitstun, so crushing
+/// with a light only buys a short window while a heavy opens a real
+/// punish. The crushed player then takes reduced damage for a moment.
+/// Disabled by default (`max` 0); `standard()` enables it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GuardConfig {
+    /// Guard meter capacity (0 = no guard meter)
+    pub max: i32,
+    /// Guard drained by a blocked hit, in percent of the attack's damage
+    pub drain_percent: i32,
+    /// Guard recovered per frame outside blockstun
+    pub regen: i32,
+    /// Crush stun, in percent of the crushing attack's hitstun
+    pub crush_stun_percent: i32,
+    /// Frames after a crush during which the crushed player is protected
+    pub protection_frames: u32,
+    /// Damage taken while protected, in percent
+    pub protection_percent: i32,
+}
+
+impl GuardConfig {
+    /// Guard breaks after a long blockstring of heavies or a longer one of lights
+    pub fn standard() -> Self {
+        Self {
+            max: 1000,
+            drain_percent: 200,
+            regen: 2,
+            crush_stun_percent: 150,
+            protection_frames: 60,
+            protection_percent: 80,
+        }
+    }
+
+    /// Whether fighters have a guard meter at all
+    pub fn enabled(&self) -> bool {
+        self.max > 0
+    }
+}
+
+/// Just defend: a block within the first frames of holding back is a parry
+///
+/// A just defend drains no guard meter (blocked hits deal no damage here,
+/// so nothing else is chipped away), leaves the defender in shorter
+/// blockstun and raises `GameEvent::Parry`. Only blocks made by holding
+/// back qualify, not reversed blocks from behind. Disabled by default
+/// (`window_frames` 0); `standard()` enables it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct JustDefendConfig {
+    /// Frames after starting to hold back in which a block counts (0 = no
+    /// just defend)
+    pub window_frames: u32,
+    /// Blockstun, in percent of the attack's blockstun
+    pub blockstun_percent: i32,
+}
+
+impl JustDefendConfig {
+    pub fn standard() -> Self {
+        Self {
+            window_frames: JUST_DEFEND_WINDOW_FRAMES,
+            blockstun_percent: JUST_DEFEND_BLOCKSTUN_PERCENT,
+        }
+    }
+
+    /// Whether a block made `held` frames into holding back is a just
+    /// defend (1 = back pressed this frame)
+    pub fn catches(&self, held: u32) -> bool {
+        (1..=self.window_frames).contains(&held)
+    }
+}
+
+/// Dizzy meter: clean hits fill it with their stun, and filling it dizzies
+/// the defender
+///
+/// The dizzy starts once the hit reaction of the filling hit is over, and
+/// leaves the fighter unable to act, or block, until it runs out or a hit
+/// snaps them out of it. The meter drains while the fighter isn't reeling
+/// from a hit, so only sustained pressure dizzies. Disabled by default
+/// (`threshold` 0); `standard()` enables it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DizzyConfig {
+    /// Stun that dizzies (0 = no dizzy)
+    pub threshold: i32,
+    /// Stun drained per frame outside hit reactions
+    pub recovery: i32,
+    /// Frames a dizzy lasts
+    pub frames: u32,
+}
+
+impl DizzyConfig {
+    pub fn standard() -> Self {
+        Self {
+            threshold: DIZZY_THRESHOLD,
+            recovery: DIZZY_RECOVERY,
+            frames: DIZZY_FRAMES,
+        }
+    }
+
+    /// Whether fighters can be dizzied at all
+    pub fn enabled(&self) -> bool {
+        self.threshold > 0
+    }
+}
+
+/// Meter granted when a new round starts, by how the previous one ended
+///
+/// Bonuses are added on top of whatever meter persisted. A drawn round
+/// grants nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoundBonus {
+    /// Meter for the player who lost the previous round (comeback)
+    pub loser_meter: i32,
+    /// Meter for the player who won the previous round (momentum)
+    pub winner_meter: i32,
+}
+
+/// Fighter state kept between rounds; everything else is reset
+///
+/// Nothing persists by default, so each round starts fresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoundPersistence {
+    /// Super meter carries over
+    pub meter: bool,
+    /// The round winner keeps the damage they took; the loser is refilled
+    pub winner_damage: bool,
+    /// Stale-move queues carry over, so repeated moves stay weakened
+    pub stale_moves: bool,
+}
+
+/// Side favored when a jump-in and an anti-air connect on the same frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AntiAirPriority {
+    /// Both hits land and trade
+    #[default]
+    Trade,
+    /// The grounded fighter's anti-air wins; the jump-in whiffs
+    Defender,
+    /// The airborne fighter's jump-in wins; the anti-air whiffs
+    Attacker,
+}
+
+/// Resolution of the "jump-in vs anti-air" tie
+///
+/// Applies when a fighter in the air, at most `height` above the ground,
+/// and a grounded fighter hit each other on the same frame. Collisions
+/// are settled before any hit applies: the losing side's hits are dropped,
+/// so the winner lands a clean (non-trade) hit. Hits the other side is
+/// invulnerable to don't count, so an invincible reversal still wins on
+/// its own. Higher up, or with `Trade`, both hits land as a normal trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AntiAirConfig {
+    pub priority: AntiAirPriority,
+    /// Highest the jumping fighter can be for the priority to apply
+    pub height: i32,
+}
+
+impl Default for AntiAirConfig {
+    fn default() -> Self {
+        Self {
+            priority: AntiAirPriority::default(),
+            height: ANTI_AIR_HEIGHT,
+        }
+    }
+}
+
+/// Blocking of hits landing on a fighter's back (cross-ups)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BehindBlockPolicy {
+    /// Blocked by holding away from the attacker, i.e. towards the
+    /// direction the defender still faces
+    #[default]
+    Reversed,
+    /// Hits from behind can't be blocked
+    Impossible,
+}
+
+/// Resolution of a double KO (both players reaching zero on the same frame)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DoubleKoPolicy {
+    /// The round is drawn: nobody scores and the next round starts
+    Draw,
+    /// Both players are awarded the round; the match is a draw if both
+    /// reach the required round count
+    #[default]
+    BothScore,
+    /// Nobody scores and the next round is fought at minimal health
+    SuddenDeath,
 }
 
 impl Default for GameConfig {
@@ -135,6 +606,17 @@ impl Default for GameConfig {
             starting_health: 1000,
             time_limit_frames: 3600, // 60 seconds at 60 FPS
             rounds_to_win: 2,
+            wakeup_delay_frames: DELAYED_WAKEUP_FRAMES,
+            outro_frames: MATCH_OUTRO_FRAMES,
+            double_ko: DoubleKoPolicy::default(),
+            behind_block: BehindBlockPolicy::default(),
+            persistence: RoundPersistence::default(),
+            round_bonus: RoundBonus::default(),
+            counter_hit: CounterHitConfig::default(),
+            guard: GuardConfig::default(),
+            anti_air: AntiAirConfig::default(),
+            just_defend: JustDefendConfig::default(),
+            dizzy: DizzyConfig::default(),
         }
     }
 }
@@ -146,6 +628,7 @@ impl GameConfig {
             starting_health,
             time_limit_frames,
             rounds_to_win,
+            ..Default::default()
         }
     }
 
@@ -155,6 +638,7 @@ impl GameConfig {
             starting_health: 500,
             time_limit_frames: 1800, // 30 seconds
             rounds_to_win: 1,
+            ..Default::default()
         }
     }
 
@@ -164,6 +648,7 @@ impl GameConfig {
             starting_health: 2000,
             time_limit_frames: 7200, // 120 seconds
             rounds_to_win: 3,
+            ..Default::default()
         }
     }
 
@@ -185,6 +670,12 @@ pub struct EngineConfig {
     pub input: InputConfig,
     /// Game rules
     pub game: GameConfig,
+    /// Damage pipeline
+    pub damage: DamageConfig,
+    /// Default hitstop and hit cues
+    pub hit_feel: HitFeelConfig,
+    /// Stage walls
+    pub stage: StageConfig,
 }
 
 impl EngineConfig {
@@ -194,6 +685,9 @@ impl EngineConfig {
             physics,
             input,
             game,
+            damage: DamageConfig::default(),
+            hit_feel: HitFeelConfig::default(),
+            stage: StageConfig::default(),
         }
     }
 
@@ -222,6 +716,7 @@ impl EngineConfig {
                 starting_health: 10000,
                 time_limit_frames: 0,
                 rounds_to_win: 1,
+                ..Default::default()
             },
             ..Default::default()
         }
@@ -242,6 +737,7 @@ mod tests {
 
         let game = GameConfig::default();
         assert_eq!(game.starting_health, 1000);
+        assert_eq!(game.wakeup_delay_frames, DELAYED_WAKEUP_FRAMES);
     }
 
     #[test]
@@ -263,5 +759,18 @@ mod tests {
 
         let low_g = PhysicsConfig::low_gravity();
         assert_eq!(low_g.gravity, GRAVITY / 2);
+
+        let floaty = PhysicsConfig::floaty_juggles();
+        assert_eq!(floaty.ground_friction_percent, GROUND_FRICTION_PERCENT);
+        assert!(floaty.air_drag_percent > floaty.ground_friction_percent);
+    }
+
+    #[test]
+    fn test_hit_feel_tier_lookup() {
+        let feel = HitFeelConfig::standard();
+        assert_eq!(feel.tier_for(50).map(|t| t.hitstop), Some(8));
+        assert_eq!(feel.tier_for(100).map(|t| t.cue), Some(3));
+        assert_eq!(feel.tier_for(999).map(|t| t.cue), Some(4));
+        assert_eq!(HitFeelConfig::default().tier_for(999), None);
     }
 }
@@ -15,18 +15,180 @@ pub const GRAVITY: i32 = 80;
 /// Entities below this level are considered grounded
 pub const GROUND_LEVEL: i32 = 18000;
 
-/// Momentum decay factor (percentage)
-/// Each frame, momentum is multiplied by this percentage
+/// Ground friction (percentage)
+/// Each grounded frame, momentum is multiplied by this percentage
 /// Default: 90% (momentum decays by 10% per frame)
-pub const MOMENTUM_DECAY_PERCENT: i32 = 90;
+pub const GROUND_FRICTION_PERCENT: i32 = 90;
+
+/// Air drag (percentage)
+/// Each airborne frame, momentum is multiplied by this percentage
+/// Default: 90% (momentum decays by 10% per frame)
+pub const AIR_DRAG_PERCENT: i32 = 90;
 
 /// Momentum decay divisor
 pub const MOMENTUM_DECAY_DIVISOR: i32 = 100;
 
-/// Minimum knockback threshold (internal units)
-/// Knockback velocities below this value are considered zero
+/// Minimum launch velocity (internal units)
+/// Launchers always lift the victim at least this much upward
 pub const KNOCKBACK_THRESHOLD: i32 = -100;
 
+/// Largest speed any velocity or momentum component can reach
+/// Keeps stacked knockback far from integer overflow
+pub const MAX_SPEED: i32 = 100_000;
+
+/// Largest distance from the origin a position component can reach
+pub const MAX_COORDINATE: i32 = 10_000_000;
+
+/// Distance from the stage center to each wall
+/// Default: 150000 units, three times the starting distance from center
+pub const STAGE_HALF_WIDTH: i32 = 150_000;
+
+/// Ceiling height of the corridor stage, low enough to cut jumps short
+pub const CORRIDOR_CEILING: i32 = 6000;
+
+/// Default character health
+pub const DEFAULT_HEALTH: i32 = 1000;
+
+/// Default character weight used for pushbox separation
+/// Heavier characters are displaced less when pushboxes overlap
+pub const DEFAULT_WEIGHT: i32 = 100;
+
+/// Weight multiplier for a character walking into their opponent
+/// The advancing character yields less ground than the one being walked into
+pub const ADVANCING_WEIGHT_MULTIPLIER: i32 = 2;
+
+// =============================================================================
+// Combat Constants
+// =============================================================================
+
+/// Frames spent on the ground after a soft knockdown
+pub const SOFT_KNOCKDOWN_FRAMES: u32 = 30;
+
+/// Frames spent on the ground after a hard knockdown or sweep
+pub const HARD_KNOCKDOWN_FRAMES: u32 = 60;
+
+/// Extra frames a downed player can stay down by holding a button
+pub const DELAYED_WAKEUP_FRAMES: u32 = 20;
+
+/// Duration of a neutral (in place) ground tech
+pub const TECH_NEUTRAL_FRAMES: u32 = 8;
+
+/// Duration of a back or forward tech roll
+pub const TECH_ROLL_FRAMES: u32 = 18;
+
+/// Horizontal speed of a tech roll (internal units per frame)
+pub const TECH_ROLL_SPEED: i32 = 500;
+
+/// Invulnerable frames granted by a neutral tech
+pub const TECH_NEUTRAL_INVULN_FRAMES: u32 = 6;
+
+/// Invulnerable frames granted by a tech roll
+pub const TECH_ROLL_INVULN_FRAMES: u32 = 14;
+
+/// Longest gap (in frames) still treated as part of the same blockstring
+pub const BLOCKSTRING_GAP_WINDOW: u32 = 20;
+
+/// Health both players start a sudden death round with
+pub const SUDDEN_DEATH_HEALTH: i32 = 1;
+
+/// Frames the fighters hold their win and lose poses once the match is
+/// decided
+pub const MATCH_OUTRO_FRAMES: u32 = 120;
+
+/// Frames Heavy must be held for a fully charged heavy attack
+pub const HEAVY_FULL_CHARGE_FRAMES: u32 = 30;
+
+/// Frames a heavy charge can be held before it releases automatically
+pub const HEAVY_MAX_CHARGE_FRAMES: u32 = 90;
+
+/// Distance between fighters at the start of a round
+pub const START_DISTANCE: i32 = 100000;
+
+/// Default spread of seeded starting distances, either way
+pub const START_DISTANCE_VARIATION: i32 = 20000;
+
+/// Most simulation ticks a turbo match runs per rendered frame
+pub const MAX_TICKS_PER_FRAME: u8 = 4;
+
+/// Maximum number of guts (low-health damage reduction) tiers
+pub const MAX_GUTS_TIERS: usize = 4;
+
+/// Maximum length of a stale-move queue
+pub const MAX_STALE_QUEUE: usize = 9;
+
+/// Maximum number of hit feel (default hitstop and cue) tiers
+pub const MAX_HIT_FEEL_TIERS: usize = 4;
+
+/// Most cues a fighter can raise in one update; later ones are dropped
+pub const MAX_CUES_PER_UPDATE: usize = 4;
+
+/// Fullest a super meter can get
+pub const METER_MAX: i32 = 1000;
+
+/// Meter the attacker builds when an attack hits
+pub const METER_GAIN_ON_HIT: i32 = 20;
+
+/// Meter the attacker builds when an attack is blocked
+pub const METER_GAIN_ON_BLOCK: i32 = 10;
+
+/// Meter built by a special move that ends without making contact
+pub const METER_GAIN_ON_WHIFFED_SPECIAL: i32 = 10;
+
+/// Meter spent by an EX special
+pub const EX_METER_COST: i32 = 250;
+
+/// Meter spent by a super move
+pub const SUPER_METER_COST: i32 = 1000;
+
+/// Damage of a counter hit, in percent of the normal damage
+pub const COUNTER_HIT_DAMAGE_PERCENT: i32 = 120;
+
+/// Extra hitstun frames a counter hit inflicts
+pub const COUNTER_HIT_HITSTUN_BONUS: u32 = 6;
+
+/// Frames after starting to hold back in which a block is a just defend,
+/// with `JustDefendConfig::standard`
+pub const JUST_DEFEND_WINDOW_FRAMES: u32 = 4;
+
+/// Blockstun of a just defend, in percent of the normal blockstun
+pub const JUST_DEFEND_BLOCKSTUN_PERCENT: i32 = 50;
+
+/// Dizzy meter capacity with `DizzyConfig::standard`
+pub const DIZZY_THRESHOLD: i32 = 500;
+
+/// Dizzy meter drained per frame outside hit reactions
+pub const DIZZY_RECOVERY: i32 = 1;
+
+/// Frames a dizzied fighter stands helpless
+pub const DIZZY_FRAMES: u32 = 120;
+
+/// Recovery after landing from a jump that used an air attack
+pub const LANDING_RECOVERY_FRAMES: u32 = 4;
+
+/// Farthest distance between fighters at which a throw can be started
+pub const THROW_RANGE: i32 = 20000;
+
+/// Damage dealt by a standard throw
+pub const THROW_DAMAGE: i32 = 120;
+
+/// Recovery of a throw attempted out of range
+pub const THROW_WHIFF_FRAMES: u32 = 30;
+
+/// Frames after a techable throw connects during which the victim can
+/// input throw to escape
+pub const THROW_TECH_WINDOW: u32 = 7;
+
+/// Highest a jumping fighter can be for `AntiAirPriority` to settle a
+/// jump-in trading with an anti-air
+/// Default: 4000 units (under half a neutral jump)
+pub const ANTI_AIR_HEIGHT: i32 = 4000;
+
+/// Length of the push apart after a teched throw
+pub const THROW_TECH_FRAMES: u32 = 16;
+
+/// Backward speed of both fighters after a teched throw
+pub const THROW_TECH_SPEED: i32 = 600;
+
 // =============================================================================
 // Input System Constants
 // =============================================================================
@@ -39,18 +201,26 @@ pub const INPUT_BUFFER_SIZE: usize = 30;
 /// Default: 15 frames (0.25 seconds at 60 FPS)
 pub const MOTION_DETECTION_WINDOW: usize = 15;
 
+/// Largest per-player latency offset, in frames
+pub const MAX_INPUT_DELAY: usize = 8;
+
+/// Frames an attack press is remembered while the fighter can't act, so
+/// it comes out on the first actionable frame
+/// Default: 4 frames
+pub const ACTION_BUFFER_FRAMES: u32 = 4;
+
 // =============================================================================
 // State Machine Limits
 // =============================================================================
 
 /// Maximum number of states that can be registered in the state machine
-pub const MAX_STATES: usize = 32;
-
-/// Maximum number of frame data entries per state
-pub const MAX_FRAME_DATA_PER_STATE: usize = 32;
+/// Every character starts with the engine's reaction states and the
+/// default moveset, so this leaves room for character-specific states.
+/// At most 64: once-per-airtime and once-per-combo use is a `u64` mask.
+pub const MAX_STATES: usize = 64;
 
-/// Maximum number of actions that can execute in a single frame
-pub const MAX_ACTIONS_PER_FRAME: usize = 8;
+/// Maximum number of limited-use resources a character can declare
+pub const MAX_RESOURCES: usize = 4;
 
 // =============================================================================
 // Collision System Limits
@@ -70,12 +240,18 @@ pub const MAX_COLLISIONS_PER_FRAME: usize = 16;
 // =============================================================================
 
 /// Maximum number of entities in the game
-/// Default: 4 (2 fighters + 2 projectiles)
+/// Default: 4 (2 fighters plus spare slots; projectiles have their own)
 pub const MAX_ENTITIES: usize = 4;
 
 /// Number of players in the game
 pub const MAX_PLAYERS: usize = 2;
 
+/// Maximum number of live projectiles
+pub const MAX_PROJECTILES: usize = 4;
+
+/// Entity ID of the first projectile slot; slot `n` uses this plus `n`
+pub const PROJECTILE_ID_BASE: u32 = 100;
+
 // =============================================================================
 // Conversion Constants
 // =============================================================================
@@ -83,3 +259,70 @@ pub const MAX_PLAYERS: usize = 2;
 /// Internal units to display units conversion factor
 /// Divide internal units by this value to get display units
 pub const INTERNAL_TO_DISPLAY: i32 = 1000;
+
+/// Length of one simulation tick in microseconds (60 Hz)
+pub const MICROS_PER_TICK: u64 = 1_000_000 / 60;
+
+/// Most ticks a fixed-timestep driver runs for one elapsed interval
+pub const MAX_TICKS_PER_ADVANCE: u32 = 4;
+
+// =============================================================================
+// Replay Constants
+// =============================================================================
+
+/// Version of the simulation rules saved data was produced under
+///
+/// Bump whenever a change makes existing replays or snapshots play out
+/// differently; data carrying another version is refused on load.
+pub const ENGINE_DATA_VERSION: u16 = 1;
+
+/// Version of the replay byte format
+pub const REPLAY_FORMAT_VERSION: u16 = 5;
+
+/// Most frames the engine can keep for local rewinding (10 seconds)
+pub const MAX_REWIND_FRAMES: usize = 600;
+
+/// Frames between snapshots kept for replay seeking
+pub const REPLAY_KEYFRAME_INTERVAL: usize = 60;
+
+/// Replays an attract mode playlist keeps
+pub const MAX_ATTRACT_REPLAYS: usize = 8;
+
+/// Shortest recorded match kept for attract mode (5 seconds)
+pub const ATTRACT_MIN_FRAMES: usize = 300;
+
+/// Frames the last frame of a demo replay is held before the next one
+pub const ATTRACT_END_HOLD_FRAMES: u32 = 120;
+
+// =============================================================================
+// Rollback Constants
+// =============================================================================
+
+/// Frames of inputs and snapshots a rollback session keeps
+/// Inputs older than this can no longer be corrected
+pub const ROLLBACK_WINDOW: usize = 10;
+
+// =============================================================================
+// Presentation Constants
+// =============================================================================
+
+/// Horizontal hit shake of a fighter taking a hit
+pub const HIT_SHAKE_AMPLITUDE: i32 = 1500;
+
+/// Horizontal hit shake of a fighter blocking
+pub const BLOCK_SHAKE_AMPLITUDE: i32 = 750;
+
+/// Backwards jolt of an attacker whose hit connected
+pub const HIT_RECOIL_DISTANCE: i32 = 1000;
+
+/// Recoil kept each frame, in percent
+pub const RECOIL_DECAY_PERCENT: i32 = 70;
+
+/// Length of a full lifebar in normalized segment units
+pub const LIFEBAR_SCALE: i32 = 1000;
+
+/// Frames recently lost health stays shown after the combo ends
+pub const LIFEBAR_TRAIL_DELAY: u32 = 30;
+
+/// Health the recently lost portion drains per frame once released
+pub const LIFEBAR_TRAIL_DRAIN: i32 = 10;
@@ -0,0 +1,282 @@
+//! Damage calculation pipeline
+//!
+//! Every damaging hit runs through the same ordered stages:
+//!
+//! 1. **Base**: the attack's damage
+//! 2. **Staleness**: reduction for moves the attacker keeps landing
+//! 3. **Proration**: reduction carried from earlier hits of the combo
+//! 4. **Combo scaling**: reduction by combo length
+//! 5. **Defense**: the defender's damage multiplier
+//! 6. **Guts**: reduction at low health
+//! 7. **Minimum**: floor so damaging hits always deal something
+//!
+//! All math is integer percent arithmetic, so results are deterministic.
+
+use crate::checksum::StateHasher;
+use crate::config::DamageConfig;
+use crate::constants::MAX_STALE_QUEUE;
+use crate::state::StateId;
+use crate::types::Fixed;
+
+/// Everything the pipeline needs to know about one hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DamageContext {
+    /// Attack's base damage
+    pub base: i32,
+    /// Copies of the attacking move in the attacker's stale queue
+    pub stale_count: u32,
+    /// Hits already landed in the current combo
+    pub combo_hits: u32,
+    /// Proration accumulated by the combo so far, in percent
+    pub proration_percent: i32,
+    /// Defender's damage multiplier, in percent
+    pub defense_percent: i32,
+    /// Defender's health before the hit
+    pub health: i32,
+    /// Defender's maximum health
+    pub max_health: i32,
+}
+
+/// Run all stages in order
+pub fn compute(config: &DamageConfig, ctx: &DamageContext) -> i32 {
+    let damage = apply_staleness(config, ctx.base, ctx.stale_count);
+    let damage = prorate(damage, ctx.proration_percent);
+    let damage = combo_scale(config, damage, ctx.combo_hits);
+    let damage = apply_defense(damage, ctx.defense_percent);
+    let damage = apply_guts(config, damage, ctx.health, ctx.max_health);
+    apply_minimum(config, damage, ctx.base)
+}
+
+/// Stage 2: reduce moves that are still in the stale queue
+pub fn apply_staleness(config: &DamageConfig, damage: i32, stale_count: u32) -> i32 {
+    let reduction = Fixed::new(config.stale_step_percent).mul(stale_count as i32);
+    let percent = Fixed::new(100).sub(reduction).raw().max(0);
+    Fixed::new(damage).percent(percent).raw()
+}
+
+/// Stage 3: apply combo proration
+pub fn prorate(damage: i32, proration_percent: i32) -> i32 {
+    Fixed::new(damage).percent(proration_percent).raw()
+}
+
+/// Stage 4: scale by combo length
+pub fn combo_scale(config: &DamageConfig, damage: i32, combo_hits: u32) -> i32 {
+    let scaled_hits = combo_hits
+        .saturating_sub(config.combo_scaling_start)
+        .min(i32::MAX as u32) as i32;
+    let reduction = Fixed::new(config.combo_scaling_step_percent).mul(scaled_hits);
+    let percent = Fixed::new(100)
+        .sub(reduction)
+        .raw()
+        .max(config.combo_scaling_floor_percent)
+        .min(100);
+    Fixed::new(damage).percent(percent).raw()
+}
+
+/// Stage 5: apply the defender's damage multiplier
+pub fn apply_defense(damage: i32, defense_percent: i32) -> i32 {
+    Fixed::new(damage).percent(defense_percent).raw()
+}
+
+/// Stage 6: reduce damage taken at low health
+///
+/// The deepest tier the defender's health has crossed applies.
+pub fn apply_guts(config: &DamageConfig, damage: i32, health: i32, max_health: i32) -> i32 {
+    if max_health <= 0 {
+        return damage;
+    }
+    let tier = config
+        .guts
+        .iter()
+        .flatten()
+        .filter(|tier| health as i64 * 100 <= max_health as i64 * tier.health_percent as i64)
+        .min_by_key(|tier| tier.health_percent);
+    match tier {
+        Some(tier) => Fixed::new(damage).percent(tier.damage_percent).raw(),
+        None => damage,
+    }
+}
+
+/// Stage 7: keep damaging hits above the configured minimum
+pub fn apply_minimum(config: &DamageConfig, damage: i32, base: i32) -> i32 {
+    if base > 0 {
+        damage.max(config.minimum_damage.min(base))
+    } else {
+        damage
+    }
+}
+
+/// Moves a player landed recently, newest last
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StaleQueue {
+    moves: [Option<StateId>; MAX_STALE_QUEUE],
+    next: usize,
+}
+
+impl StaleQueue {
+    /// Record a landed move, forgetting the oldest beyond `length`
+    pub fn push(&mut self, state: StateId, length: usize) {
+        let length = length.min(MAX_STALE_QUEUE);
+        if length == 0 {
+            return;
+        }
+        self.next %= length;
+        self.moves[self.next] = Some(state);
+        self.next += 1;
+        for slot in self.moves[length..].iter_mut() {
+            *slot = None;
+        }
+    }
+
+    /// Copies of a move in the queue
+    pub fn count(&self, state: StateId) -> u32 {
+        self.moves.iter().filter(|m| **m == Some(state)).count() as u32
+    }
+
+    /// Feed the queue to a state hash
+    pub(crate) fn hash_state(&self, h: &mut StateHasher) {
+        for state in self.moves {
+            h.write_u32(state.map_or(u32::MAX, StateId::to_u32));
+        }
+        h.write_u32(self.next as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(base: i32, combo_hits: u32) -> DamageContext {
+        DamageContext {
+            base,
+            stale_count: 0,
+            combo_hits,
+            proration_percent: 100,
+            defense_percent: 100,
+            health: 1000,
+            max_health: 1000,
+        }
+    }
+
+    #[test]
+    fn test_default_config_leaves_damage_untouched() {
+        let config = DamageConfig::default();
+        assert_eq!(compute(&config, &context(100, 0)), 100);
+        assert_eq!(compute(&config, &context(100, 20)), 100);
+    }
+
+    #[test]
+    fn test_stages_apply_in_order() {
+        let config = DamageConfig::standard();
+
+        // 5th hit: 2 hits past the scaling start -> 80%, after 50% proration
+        // and 90% defense
+        let ctx = DamageContext {
+            proration_percent: 50,
+            defense_percent: 90,
+            ..context(200, 4)
+        };
+        assert_eq!(compute(&config, &ctx), 200 * 50 / 100 * 80 / 100 * 90 / 100);
+
+        // Long combos bottom out at the floor
+        assert_eq!(compute(&config, &context(100, 50)), 30);
+    }
+
+    #[test]
+    fn test_guts_tiers_follow_health() {
+        let config = DamageConfig::standard();
+        let at_health = |health| {
+            compute(
+                &config,
+                &DamageContext {
+                    health,
+                    ..context(100, 0)
+                },
+            )
+        };
+
+        assert_eq!(at_health(1000), 100);
+        assert_eq!(at_health(501), 100);
+        assert_eq!(at_health(500), 90);
+        assert_eq!(at_health(300), 80);
+        assert_eq!(at_health(100), 70);
+        assert_eq!(at_health(1), 70);
+
+        // Same inputs, same result, regardless of tier declaration order
+        let mut reversed = config;
+        reversed.guts.reverse();
+        for health in (0..=1000).step_by(7) {
+            let ctx = DamageContext {
+                health,
+                ..context(137, 3)
+            };
+            assert_eq!(compute(&config, &ctx), compute(&reversed, &ctx));
+        }
+    }
+
+    #[test]
+    fn test_guts_and_minimum() {
+        let config = DamageConfig::standard();
+        let low_health = DamageContext {
+            health: 300,
+            ..context(100, 0)
+        };
+        assert_eq!(compute(&config, &low_health), 80);
+
+        let tiny = DamageContext {
+            proration_percent: 1,
+            ..context(10, 0)
+        };
+        assert_eq!(compute(&config, &tiny), 1);
+        assert_eq!(compute(&config, &context(0, 0)), 0);
+    }
+
+    #[test]
+    fn test_stale_queue_reduces_repeated_moves() {
+        let config = DamageConfig::stale_moves();
+        let mut queue = StaleQueue::default();
+        let length = config.stale_queue_length;
+
+        for _ in 0..3 {
+            queue.push(StateId::LightAttack, length);
+        }
+        assert_eq!(queue.count(StateId::LightAttack), 3);
+        let stale = DamageContext {
+            stale_count: queue.count(StateId::LightAttack),
+            ..context(100, 0)
+        };
+        assert_eq!(compute(&config, &stale), 85);
+
+        // Using other moves freshens it again
+        for _ in 0..length {
+            queue.push(StateId::MediumAttack, length);
+        }
+        assert_eq!(queue.count(StateId::LightAttack), 0);
+        assert_eq!(queue.count(StateId::MediumAttack) as usize, length);
+    }
+
+    #[test]
+    fn test_extreme_values_saturate() {
+        let config = DamageConfig {
+            stale_queue_length: MAX_STALE_QUEUE,
+            stale_step_percent: i32::MAX,
+            ..DamageConfig::standard()
+        };
+        let ctx = DamageContext {
+            stale_count: 0,
+            combo_hits: u32::MAX,
+            defense_percent: 1000,
+            health: i32::MAX,
+            max_health: i32::MAX,
+            ..context(i32::MAX, 0)
+        };
+        // 30% floor scaling, then a 10x defense multiplier saturates
+        assert_eq!(compute(&config, &ctx), i32::MAX);
+
+        let stale = DamageContext {
+            stale_count: 3,
+            ..ctx
+        };
+        assert_eq!(compute(&config, &stale), config.minimum_damage);
+    }
+}
@@ -1,11 +1,30 @@
 //! Main game engine - ties together all systems
 //! Inspired by Castagne's phase-based execution model
 
+use std::sync::Arc;
+
+use crate::characters::CharacterDef;
+use crate::chardef::{parse_character, CharacterError};
+use crate::config::{AntiAirPriority, BehindBlockPolicy, DoubleKoPolicy, EngineConfig, StageEdge};
 use crate::constants::*;
-use crate::entity::Entity;
+use crate::damage::{self, DamageContext};
+use crate::entity::{Entity, Health, LifebarSegments};
+use crate::events::{
+    ClashEvent, CueEvent, FinishEvent, FinishKind, GameEvent, HitEvent, HitOutcome,
+};
 use crate::hitbox::{CollisionResult, CollisionSystem};
 use crate::input::{InputManager, InputState};
-use crate::types::{EntityId, Frame, PlayerId, Vec2};
+use crate::link::{PairLink, TechWindow};
+use crate::metrics::EngineMetrics;
+use crate::ordering::order_collisions;
+use crate::projectile::{Projectile, ProjectileDef};
+use crate::rewind::RewindHistory;
+use crate::setup::{MatchSetup, Mutators, StartingConditions};
+use crate::snapshot::EngineSnapshot;
+use crate::state::{GuardKind, LimitedResource, ResourceScope, StateAction, StateId, StateSet};
+use crate::training::TrainingInfo;
+use crate::tuning::TuningEntry;
+use crate::types::{EntityId, Facing, Fixed, Frame, PlayerId, Vec2};
 
 /// Game result
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,6 +35,18 @@ pub enum GameResult {
     Draw,
 }
 
+impl GameResult {
+    /// Stable numeric code, shared by the C ABI and state checksums
+    pub fn to_u32(self) -> u32 {
+        match self {
+            GameResult::InProgress => 0,
+            GameResult::Player1Wins => 1,
+            GameResult::Player2Wins => 2,
+            GameResult::Draw => 3,
+        }
+    }
+}
+
 /// Main game engine state
 pub struct Engine {
     pub frame: Frame,
@@ -24,6 +55,48 @@ pub struct Engine {
     pub collision_system: CollisionSystem,
     pub input_manager: InputManager,
     pub game_result: GameResult,
+    pub metrics: EngineMetrics,
+    pub config: EngineConfig,
+    /// Current round number (starting at 1)
+    pub round: u32,
+    /// Rounds won by each player
+    pub round_wins: [u32; MAX_PLAYERS],
+    /// Frame the current round started on
+    pub round_start_frame: Frame,
+    /// Meter each player was granted at the start of the current round
+    pub round_bonus: [i32; MAX_PLAYERS],
+    /// Frames left of win and lose poses after the match was decided
+    pub outro_remaining: u32,
+    /// Inputs fed to the buffers on the last tick, after latency
+    /// compensation; these are what a replay should record
+    pub inputs: [InputState; MAX_PLAYERS],
+    /// Hit events emitted during the last tick
+    pub events: Vec<HitEvent>,
+    /// Audiovisual cues raised during the last tick
+    pub cues: Vec<CueEvent>,
+    /// Projectile clashes during the last tick
+    pub clashes: Vec<ClashEvent>,
+    /// Special finishes of a round won during the last tick
+    pub finishes: Vec<FinishEvent>,
+    /// Typed events of the last tick, in order, until drained
+    pub game_events: Vec<GameEvent>,
+    /// Live projectiles, by slot
+    pub projectiles: [Option<Projectile>; MAX_PROJECTILES],
+    /// Attacker currently driving a victim (throws, cinematics)
+    pub link: Option<PairLink>,
+    /// Starting conditions resolved from the match setup
+    pub start: StartingConditions,
+    /// Rule changes of the current match
+    pub mutators: Mutators,
+    /// Recent frames kept for rewinding, when enabled
+    pub rewind: Option<RewindHistory>,
+    /// Whether the match is paused (menus); ticks are ignored while set
+    paused: bool,
+    /// Character of each player, kept for every match; both start as the
+    /// same default character
+    pub(crate) characters: [CharacterDef; MAX_PLAYERS],
+    /// Live tuning changes, kept across matches
+    pub(crate) tuning: Vec<TuningEntry>,
 }
 
 impl Default for Engine {
@@ -34,6 +107,11 @@ impl Default for Engine {
 
 impl Engine {
     pub fn new() -> Self {
+        Self::with_config(EngineConfig::default())
+    }
+
+    /// Create an engine using custom configuration
+    pub fn with_config(config: EngineConfig) -> Self {
         Self {
             frame: Frame::ZERO,
             entities: [None, None, None, None],
@@ -41,298 +119,4046 @@ impl Engine {
             collision_system: CollisionSystem::new(),
             input_manager: InputManager::new(),
             game_result: GameResult::InProgress,
+            metrics: EngineMetrics::new(),
+            config,
+            round: 1,
+            round_wins: [0; MAX_PLAYERS],
+            round_start_frame: Frame::ZERO,
+            round_bonus: [0; MAX_PLAYERS],
+            outro_remaining: 0,
+            inputs: [InputState::neutral(); MAX_PLAYERS],
+            events: Vec::new(),
+            cues: Vec::new(),
+            clashes: Vec::new(),
+            finishes: Vec::new(),
+            game_events: Vec::new(),
+            projectiles: [None; MAX_PROJECTILES],
+            link: None,
+            start: StartingConditions::default(),
+            mutators: Mutators::default(),
+            rewind: None,
+            paused: false,
+            characters: {
+                let default = CharacterDef::default();
+                [default.clone(), default]
+            },
+            tuning: Vec::new(),
         }
     }
 
     /// Initialize a standard 2-player match
     pub fn init_match(&mut self) {
-        // Player 1 on left
-        let p1 = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::new(-50000, 0));
+        self.init_match_with(MatchSetup::default());
+    }
+
+    /// Initialize a 2-player match with seeded starting conditions
+    pub fn init_match_with(&mut self, setup: MatchSetup) {
+        self.start = setup.resolve();
+        self.mutators = setup.mutators;
+        self.frame = Frame::ZERO;
+        self.input_manager = InputManager::new();
+        for (player, frames) in self.config.input.latency_offset.into_iter().enumerate() {
+            self.input_manager.set_latency_offset(player, frames);
+        }
+        self.input_manager
+            .set_action_buffer(self.config.input.action_buffer_frames);
+        self.inputs = [InputState::neutral(); MAX_PLAYERS];
+        self.game_result = GameResult::InProgress;
+        self.outro_remaining = 0;
+        self.metrics = EngineMetrics::new();
+        self.events.clear();
+        self.cues.clear();
+        self.clashes.clear();
+        self.finishes.clear();
+        self.game_events.clear();
+        self.clear_rewind();
+        self.round = 1;
+        self.round_wins = [0; MAX_PLAYERS];
+        self.round_bonus = [0; MAX_PLAYERS];
+
+        let definitions = self.characters.clone().map(|c| c.states);
+        self.start_round(definitions, None);
+    }
+
+    /// Initialize a standard 2-player match between two characters, which
+    /// later `init_match` calls keep
+    pub fn init_match_with_characters(&mut self, p1: CharacterDef, p2: CharacterDef) {
+        self.characters = [p1, p2];
+        self.init_match();
+    }
 
-        // Player 2 on right
-        let p2 = Entity::new(EntityId(1), PlayerId::PLAYER_2, Vec2::new(50000, 0));
+    /// Use a character definition (see `chardef`) for a player from the next
+    /// `init_match` on
+    ///
+    /// On error the player keeps their current character.
+    pub fn load_character(&mut self, player: PlayerId, text: &str) -> Result<(), CharacterError> {
+        let parsed = parse_character(text)?;
+        if let Some(character) = self.characters.get_mut(player.0 as usize) {
+            *character = parsed;
+        }
+        Ok(())
+    }
+
+    /// Place both fighters at their starting positions with fresh state
+    fn start_round(&mut self, definitions: [Arc<StateSet>; MAX_PLAYERS], health: Option<i32>) {
+        let [p1_states, p2_states] = definitions;
+        let mut p1 = Entity::with_states(
+            EntityId(0),
+            PlayerId::PLAYER_1,
+            Vec2::new(self.start.position_x(PlayerId::PLAYER_1), 0),
+            p1_states,
+        );
+
+        let mut p2 = Entity::with_states(
+            EntityId(1),
+            PlayerId::PLAYER_2,
+            Vec2::new(self.start.position_x(PlayerId::PLAYER_2), 0),
+            p2_states,
+        );
+
+        for entity in [&mut p1, &mut p2] {
+            entity.attack_delay = self.start.attack_delay(entity.player_id);
+            if entity.physics.position.x > 0 {
+                entity.facing = Facing::Left;
+            } else {
+                entity.facing = Facing::Right;
+            }
+            entity.wakeup_delay_frames = self.config.game.wakeup_delay_frames;
+            let character = &self.characters[entity.player_id.0 as usize];
+            entity.health = Health::new(character.health);
+            entity.weight = character.weight;
+            entity.physics.apply_config(&self.config.physics);
+            entity.physics.gravity = self.fighter_gravity(character);
+            entity.meter_gain = self.mutators.meter_gain_percent as i32;
+            entity.guard = self.config.game.guard.max;
+            entity.dizzy = 0;
+            entity.dizzy_remaining = 0;
+            if let Some(health) = health {
+                entity.health.set(health);
+            }
+        }
 
         self.entities[0] = Some(p1);
         self.entities[1] = Some(p2);
         self.entity_count = 2;
+        self.projectiles = [None; MAX_PROJECTILES];
 
-        self.frame = Frame::ZERO;
-        self.game_result = GameResult::InProgress;
+        self.round_start_frame = self.frame;
+        self.link = None;
+    }
+
+    /// Simulation ticks to run per rendered frame under the match's speed
+    /// mutator
+    pub fn ticks_per_frame(&self) -> u32 {
+        self.mutators.ticks_per_frame()
+    }
+
+    /// Pause the match
+    ///
+    /// Ticks received while paused are dropped before they reach the latency
+    /// queues or the input buffers, so pausing never changes simulation
+    /// state. The first tick after `resume` is the next frame the buffers
+    /// see: a button pressed in the pause menu and still held counts as a
+    /// fresh press on that frame instead of being swallowed.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resume the match; the next tick simulates the next frame
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
     }
 
     /// Main game tick - advances one frame
     /// This follows a phase-based execution model like Castagne
+    ///
+    /// Takes raw device inputs and applies each player's latency offset.
     pub fn tick(&mut self, p1_input: InputState, p2_input: InputState) {
-        if self.game_result != GameResult::InProgress {
-            return; // Game over
+        if self.is_over() || self.paused {
+            return; // Game over or paused
+        }
+
+        let p1_input = self.input_manager.compensate(0, p1_input);
+        let p2_input = self.input_manager.compensate(1, p2_input);
+        self.advance(p1_input, p2_input);
+    }
+
+    /// Advance one frame with inputs that are already latency-compensated,
+    /// such as those recorded in a replay
+    pub fn advance(&mut self, p1_input: InputState, p2_input: InputState) {
+        if self.is_over() || self.paused {
+            return; // Game over or paused
         }
+        self.record_rewind([p1_input, p2_input]);
+        self.inputs = [p1_input, p2_input];
+
+        self.metrics = EngineMetrics::new();
+        self.events.clear();
+        self.cues.clear();
+        self.clashes.clear();
+        self.finishes.clear();
+        self.game_events.clear();
+        self.metrics.frame = self.frame.0;
+
+        if self.frame == self.round_start_frame {
+            self.game_events.push(GameEvent::RoundStart {
+                frame: self.frame,
+                round: self.round,
+            });
+        }
+        let states_before = self.fighter_states();
 
         // PHASE 1: INPUT
-        self.input_manager.update_player_input(0, p1_input);
-        self.input_manager.update_player_input(1, p2_input);
+        for (player, input) in [p1_input, p2_input].into_iter().enumerate() {
+            if self
+                .input_manager
+                .update_player_input(player, self.frame, input)
+                .is_err()
+            {
+                self.metrics.rejected_inputs += 1;
+            }
+        }
+
+        // Presentation keeps moving through hitstop so shakes play out
+        self.update_presentation();
+
+        if self.game_result != GameResult::InProgress {
+            self.advance_outro(states_before);
+            return;
+        }
 
         // PHASE 2: UPDATE ENTITIES (Action phase)
-        self.update_entities();
+        let frozen = self.update_entities();
+        self.update_projectiles(&frozen);
 
-        // PHASE 3: COLLISION DETECTION (Physics phase)
+        // PHASE 3: PUSHBOX SEPARATION AND COLLISION DETECTION (Physics phase)
+        self.separate_pushboxes();
+        self.apply_stage_bounds();
         self.detect_collisions();
 
         // PHASE 4: RESOLVE HITS (Reaction phase)
         self.resolve_hits();
+        self.queue_hit_events();
 
         // PHASE 5: CHECK WIN CONDITIONS
         self.check_win_conditions();
+        self.check_timeout();
 
         // PHASE 6: UPDATE FACING
         self.update_facing();
+        self.queue_state_changes(states_before);
+
+        self.metrics.snapshot_struct_bytes = EngineSnapshot::size_bytes() as u32;
 
         // Advance frame counter
         self.frame = self.frame.next();
     }
 
-    /// Update all entities
-    fn update_entities(&mut self) {
-        for i in 0..self.entity_count {
-            if let Some(entity) = &mut self.entities[i] {
-                let player_id = entity.player_id.0 as usize;
-                let input = self.input_manager.get_player_input(player_id);
-                entity.update(input);
+    /// Whether ticks have nothing left to do: the match is decided and its
+    /// outro has played out
+    pub fn is_over(&self) -> bool {
+        self.game_result != GameResult::InProgress && self.outro_remaining == 0
+    }
+
+    /// Play a frame of the outro: fighters hold their poses with inputs
+    /// locked out, and physics still runs so airborne bodies land. The
+    /// finishing hit's hitstop plays out first
+    fn advance_outro(&mut self, states_before: [Option<StateId>; MAX_PLAYERS]) {
+        self.outro_remaining -= 1;
+        for entity in self.entities[..2].iter_mut().flatten() {
+            if entity.is_frozen() {
+                entity.frozen_frames -= 1;
+            } else {
+                entity.update(None);
             }
         }
+        self.separate_pushboxes();
+        self.apply_stage_bounds();
+        self.queue_state_changes(states_before);
+        self.frame = self.frame.next();
     }
 
-    /// Detect all collisions this frame
-    fn detect_collisions(&mut self) {
-        self.collision_system.clear();
-
-        // Gather all hitboxes and hurtboxes
-        for i in 0..self.entity_count {
-            if let Some(entity) = &self.entities[i] {
-                // Add hitboxes
-                let hitboxes = entity.get_hitboxes();
-                for hitbox in hitboxes.iter().flatten() {
-                    self.collision_system.add_hitbox(*hitbox);
-                }
-
-                // Add hurtboxes
-                let hurtboxes = entity.get_hurtboxes();
-                for hurtbox in hurtboxes.iter().flatten() {
-                    self.collision_system.add_hurtbox(*hurtbox);
+    /// Put the fighters in their outro states once the match is decided
+    ///
+    /// The winner's pose is picked by the button they hold, falling back to
+    /// `WinPose(0)` when their character lacks it; a draw has two losers.
+    fn start_outro(&mut self) {
+        self.outro_remaining = self.config.game.outro_frames;
+        if self.outro_remaining == 0 {
+            return;
+        }
+        self.link = None;
+        let winner = match self.game_result {
+            GameResult::Player1Wins => Some(0),
+            GameResult::Player2Wins => Some(1),
+            _ => None,
+        };
+        for player in 0..MAX_PLAYERS {
+            let held = self
+                .input_manager
+                .get_player_input(player)
+                .map_or(InputState::neutral(), |input| input.current());
+            let Some(fighter) = &mut self.entities[player] else {
+                continue;
+            };
+            let pose = if winner == Some(player) {
+                let buttons = [held.light, held.medium, held.heavy, held.special];
+                let picked = buttons.iter().position(|&b| b).map_or(0, |i| i as u8 + 1);
+                match fighter.state_machine.has_state(StateId::WinPose(picked)) {
+                    true => StateId::WinPose(picked),
+                    false => StateId::WinPose(0),
                 }
+            } else {
+                StateId::LosePose
+            };
+            if fighter.state_machine.has_state(pose) {
+                fighter.enter_outro(pose);
             }
         }
     }
 
-    /// Resolve all hit events
-    fn resolve_hits(&mut self) {
-        let collisions = self.collision_system.check_collisions();
+    /// Typed events of the last tick, in the order they happened
+    pub fn game_events(&self) -> &[GameEvent] {
+        &self.game_events
+    }
 
-        for collision in collisions.iter().flatten() {
-            self.apply_hit(collision);
-        }
+    /// Take the last tick's typed events, leaving the queue empty
+    pub fn drain_game_events(&mut self) -> std::vec::Drain<'_, GameEvent> {
+        self.game_events.drain(..)
     }
 
-    /// Apply a single hit to defender
-    fn apply_hit(&mut self, collision: &CollisionResult) {
-        // Find defender
-        let defender_idx = self.find_entity_index(collision.defender);
-        let Some(defender_idx) = defender_idx else {
-            return;
-        };
+    /// Current state of each player's fighter
+    fn fighter_states(&self) -> [Option<StateId>; MAX_PLAYERS] {
+        [0, 1].map(|i| {
+            self.entities[i]
+                .as_ref()
+                .map(|e| e.state_machine.current_state())
+        })
+    }
 
-        // Check if defender is blocking
-        let is_blocking = {
-            if let Some(defender) = &self.entities[defender_idx] {
-                let player_id = defender.player_id.0 as usize;
-                if let Some(input) = self.input_manager.get_player_input(player_id) {
-                    let current = input.current();
-                    // Blocking if holding back
-                    current.direction.is_back()
-                } else {
-                    false
+    /// Queue the tick's hit events as typed events
+    fn queue_hit_events(&mut self) {
+        for &event in &self.events {
+            match event.outcome {
+                HitOutcome::Hit | HitOutcome::Trade => {
+                    self.game_events.push(GameEvent::HitLanded(event));
+                    if event.counter {
+                        self.game_events.push(GameEvent::CounterHit(event));
+                    }
                 }
-            } else {
-                false
+                HitOutcome::Blocked | HitOutcome::GuardCrushed => {
+                    self.game_events.push(GameEvent::Blocked(event));
+                }
+                HitOutcome::Parried => self.game_events.push(GameEvent::Parry(event)),
+                HitOutcome::Armored => self.game_events.push(GameEvent::ArmorAbsorb(event)),
+                _ => {}
             }
-        };
-
-        // Apply hit
-        if let Some(defender) = &mut self.entities[defender_idx] {
-            defender.take_hit(collision, is_blocking);
         }
     }
 
-    /// Update all entities to face their opponents
-    fn update_facing(&mut self) {
-        if self.entity_count >= 2 {
-            // Get positions first (avoid borrow checker issues)
-            let p1_pos = self.entities[0].as_ref().map(|e| e.physics.position);
-            let p2_pos = self.entities[1].as_ref().map(|e| e.physics.position);
-
-            // Update p1 facing
-            if let (Some(p1), Some(pos)) = (&mut self.entities[0], p2_pos) {
-                p1.update_facing(pos);
-            }
-
-            // Update p2 facing
-            if let (Some(p2), Some(pos)) = (&mut self.entities[1], p1_pos) {
-                p2.update_facing(pos);
+    /// Queue a state change for each fighter whose state differs from `before`
+    fn queue_state_changes(&mut self, before: [Option<StateId>; MAX_PLAYERS]) {
+        for (i, from) in before.into_iter().enumerate() {
+            let Some(entity) = &self.entities[i] else {
+                continue;
+            };
+            let to = entity.state_machine.current_state();
+            if let Some(from) = from.filter(|&from| from != to) {
+                self.game_events.push(GameEvent::StateChanged {
+                    frame: self.frame,
+                    entity: entity.id,
+                    from,
+                    to,
+                });
             }
         }
     }
 
-    /// Check win conditions
-    fn check_win_conditions(&mut self) {
-        if self.entity_count < 2 {
-            return;
+    /// Diagnostics collected during the most recent tick
+    pub fn metrics(&self) -> &EngineMetrics {
+        &self.metrics
+    }
+
+    /// Advance every entity's display offsets
+    fn update_presentation(&mut self) {
+        for entity in self.entities[..self.entity_count].iter_mut().flatten() {
+            entity.presentation.tick();
         }
+    }
 
-        let p1_alive = self.entities[0]
-            .as_ref()
-            .map(|e| e.health.is_alive())
-            .unwrap_or(false);
-        let p2_alive = self.entities[1]
-            .as_ref()
-            .map(|e| e.health.is_alive())
-            .unwrap_or(false);
+    /// Update all entities
+    ///
+    /// Returns the fighters that sat this frame out frozen.
+    fn update_entities(&mut self) -> Vec<EntityId> {
+        self.drive_link();
+        let victim = self.link.map(|link| link.victim);
 
-        self.game_result = match (p1_alive, p2_alive) {
-            (true, true) => GameResult::InProgress,
-            (true, false) => GameResult::Player1Wins,
-            (false, true) => GameResult::Player2Wins,
-            (false, false) => GameResult::Draw,
-        };
-    }
+        if self.entity_count >= 2 {
+            let x = |i: usize| self.entities[i].as_ref().map(|e| e.physics.position.x);
+            let distance = x(0)
+                .zip(x(1))
+                .map(|(a, b)| a.saturating_sub(b).saturating_abs());
+            for entity in self.entities[..2].iter_mut().flatten() {
+                entity.opponent_distance = distance;
+            }
+            let in_combo = |i: usize| self.entities[i].as_ref().is_some_and(|e| e.in_combo());
+            let in_combo = [in_combo(1), in_combo(0)];
+            for (entity, in_combo) in self.entities[..2].iter_mut().zip(in_combo) {
+                if let Some(entity) = entity {
+                    entity.opponent_in_combo = in_combo;
+                }
+            }
+        }
 
-    /// Get entity by ID
-    pub fn get_entity(&self, id: EntityId) -> Option<&Entity> {
+        let mut spawns = Vec::new();
+        let mut flashes = Vec::new();
+        let mut frozen = Vec::new();
         for i in 0..self.entity_count {
-            if let Some(entity) = &self.entities[i] {
-                if entity.id == id {
-                    return Some(entity);
+            if let Some(entity) = &mut self.entities[i] {
+                // A linked victim is driven by the attacker instead
+                if Some(entity.id) == victim {
+                    continue;
+                }
+                // Frozen fighters skip the whole action phase
+                if entity.is_frozen() {
+                    entity.frozen_frames -= 1;
+                    frozen.push(entity.id);
+                    continue;
+                }
+
+                let player_id = entity.player_id.0 as usize;
+                let input = self.input_manager.get_player_input(player_id);
+                entity.update(input);
+                entity.recover_guard(&self.config.game.guard);
+                entity.recover_dizzy(&self.config.game.dizzy);
+                self.metrics.entities_updated += 1;
+                for cue in entity.cues.take() {
+                    self.cues.push(CueEvent {
+                        frame: self.frame,
+                        entity: entity.id,
+                        cue,
+                    });
+                }
+                if let Some(spawn) = entity.take_spawn() {
+                    spawns.push((entity.id, spawn));
+                }
+                if let Some(frames) = entity.flash.take() {
+                    flashes.push((entity.id, frames));
                 }
             }
         }
-        None
-    }
 
-    /// Get entity by player ID
-    pub fn get_player_entity(&self, player: PlayerId) -> Option<&Entity> {
-        for i in 0..self.entity_count {
-            if let Some(entity) = &self.entities[i] {
-                if entity.player_id == player {
-                    return Some(entity);
+        // A super flash stops everyone else from the next frame on
+        for (flasher, frames) in flashes {
+            for entity in self.entities[..self.entity_count].iter_mut().flatten() {
+                if entity.id != flasher {
+                    entity.freeze(frames);
                 }
             }
         }
-        None
+
+        // Projectiles launched by state actions; dropped when all slots are taken
+        for (owner, (offset, def)) in spawns {
+            self.spawn_projectile(owner, def, offset);
+        }
+        frozen
     }
 
-    fn find_entity_index(&self, id: EntityId) -> Option<usize> {
-        for i in 0..self.entity_count {
-            if let Some(entity) = &self.entities[i] {
-                if entity.id == id {
-                    return Some(i);
-                }
+    /// Move projectiles and despawn the expired ones
+    ///
+    /// Homing projectiles steer toward the first fighter that isn't their owner.
+    fn update_projectiles(&mut self, frozen: &[EntityId]) {
+        for slot in 0..MAX_PROJECTILES {
+            let Some(owner) = self.projectiles[slot].map(|p| p.owner) else {
+                continue;
+            };
+            // Projectiles wait with their frozen owner
+            if frozen.contains(&owner) {
+                continue;
+            }
+            let target = self.entities[..self.entity_count]
+                .iter()
+                .flatten()
+                .find(|e| e.id != owner)
+                .map(|e| e.physics.position);
+            if self.projectiles[slot]
+                .as_mut()
+                .is_some_and(|p| !p.update(target))
+            {
+                self.projectiles[slot] = None;
             }
         }
-        None
     }
 
-    /// Get game state summary for rendering/display
-    pub fn get_state(&self) -> GameState<'_> {
-        let p1 = self.get_player_entity(PlayerId::PLAYER_1);
-        let p2 = self.get_player_entity(PlayerId::PLAYER_2);
+    /// Spawn a projectile for `owner`, offset from it (authored facing right)
+    ///
+    /// Returns the projectile's ID, or `None` if the owner doesn't exist or
+    /// every projectile slot is in use.
+    pub fn spawn_projectile(
+        &mut self,
+        owner: EntityId,
+        def: ProjectileDef,
+        offset: Vec2,
+    ) -> Option<EntityId> {
+        let (facing, origin, team) = self
+            .get_entity(owner)
+            .map(|e| (e.facing, e.physics.position, e.team))?;
+        let slot = self.projectiles.iter().position(Option::is_none)?;
+        let id = EntityId(PROJECTILE_ID_BASE + slot as u32);
+        let position = origin.add(Vec2::new(offset.x * facing.sign(), offset.y));
+        let mut projectile = Projectile::new(id, owner, facing, position, def);
+        projectile.team = team;
+        self.projectiles[slot] = Some(projectile);
+        Some(id)
+    }
 
-        GameState {
-            frame: self.frame.0,
-            p1_pos: p1.map(|e| e.physics.position).unwrap_or(Vec2::ZERO),
-            p1_health: p1.map(|e| e.health.current).unwrap_or(0),
-            p1_state: p1
-                .map(|e| state_to_string(e.state_machine.current_state()))
-                .unwrap_or("Unknown"),
-            p1_facing: p1.map(|e| e.facing).unwrap_or(crate::types::Facing::Right),
-            p2_pos: p2.map(|e| e.physics.position).unwrap_or(Vec2::ZERO),
-            p2_health: p2.map(|e| e.health.current).unwrap_or(0),
-            p2_state: p2
-                .map(|e| state_to_string(e.state_machine.current_state()))
-                .unwrap_or("Unknown"),
-            p2_facing: p2.map(|e| e.facing).unwrap_or(crate::types::Facing::Left),
-            result: self.game_result,
+    /// Remove a projectile; returns false if it wasn't live
+    pub fn despawn_projectile(&mut self, id: EntityId) -> bool {
+        match self.projectile_slot(id) {
+            Some(slot) => {
+                self.projectiles[slot] = None;
+                true
+            }
+            None => false,
         }
     }
-}
 
-/// Game state snapshot for display/serialization
-#[derive(Debug, Clone)]
-pub struct GameState<'a> {
-    pub frame: u64,
-    pub p1_pos: Vec2,
-    pub p1_health: i32,
-    pub p1_state: &'a str,
-    pub p1_facing: crate::types::Facing,
-    pub p2_pos: Vec2,
-    pub p2_health: i32,
-    pub p2_state: &'a str,
-    pub p2_facing: crate::types::Facing,
-    pub result: GameResult,
-}
+    /// Get a live projectile by ID
+    pub fn get_projectile(&self, id: EntityId) -> Option<&Projectile> {
+        self.projectiles.iter().flatten().find(|p| p.id == id)
+    }
 
-fn state_to_string(state: crate::state::StateId) -> &'static str {
-    use crate::state::StateId;
-    match state {
-        StateId::Idle => "Idle",
-        StateId::Walk => "Walk",
-        StateId::WalkBack => "WalkBack",
-        StateId::Crouch => "Crouch",
-        StateId::Jump => "Jump",
-        StateId::LightAttack => "Light",
-        StateId::MediumAttack => "Medium",
-        StateId::HeavyAttack => "Heavy",
-        StateId::SpecialMove => "Special",
-        StateId::Hitstun => "Hit",
-        StateId::Blockstun => "Block",
-        StateId::Knockdown => "Down",
-        StateId::Custom(_) => "Custom",
+    fn projectile_slot(&self, id: EntityId) -> Option<usize> {
+        self.projectiles
+            .iter()
+            .position(|p| p.is_some_and(|p| p.id == id))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Reflect or absorb a projectile caught by the defender's guard
+    ///
+    /// Projectiles lacking the matching flag go through as normal hits.
+    fn guard_projectile(&mut self, collision: &CollisionResult) -> Option<HitEvent> {
+        let defender = self.get_entity(collision.defender)?;
+        let kind = defender.state_machine.active_projectile_guard()?;
+        let team = defender.team;
+        let slot = self.projectile_slot(collision.attacker)?;
+        let projectile = self.projectiles[slot].as_mut()?;
+        if projectile.is_residual() {
+            return None;
+        }
 
-    #[test]
-    fn test_engine_initialization() {
-        let mut engine = Engine::new();
-        engine.init_match();
+        let outcome = match kind {
+            GuardKind::Reflect if projectile.def.reflectable => {
+                projectile.reflect(collision.defender, team);
+                HitOutcome::Reflected
+            }
+            GuardKind::Absorb if projectile.def.absorbable => {
+                self.projectiles[slot] = None;
+                HitOutcome::Absorbed
+            }
+            _ => return None,
+        };
+        Some(HitEvent {
+            frame: self.frame,
+            attacker: collision.attacker,
+            defender: collision.defender,
+            outcome,
+            cue: None,
+            damage: 0,
+            contact_point: collision.contact_point,
+            is_super: false,
+            counter: false,
+        })
+    }
 
-        assert_eq!(engine.entity_count, 2);
-        assert_eq!(engine.frame.0, 0);
-        assert_eq!(engine.game_result, GameResult::InProgress);
+    /// Projectile clash events from the last tick
+    pub fn clashes(&self) -> &[ClashEvent] {
+        &self.clashes
     }
 
-    #[test]
-    fn test_engine_tick() {
-        let mut engine = Engine::new();
-        engine.init_match();
+    /// Special finishes of a round won during the last tick
+    pub fn finishes(&self) -> &[FinishEvent] {
+        &self.finishes
+    }
 
-        let neutral = InputState::neutral();
-        engine.tick(neutral, neutral);
+    /// Destroy opposing projectiles whose hitboxes overlap
+    ///
+    /// Residual explosions don't clash; a destroyed explosive projectile
+    /// turns into its residual hitbox instead of disappearing.
+    fn resolve_clashes(&mut self) {
+        for a in 0..MAX_PROJECTILES {
+            for b in a + 1..MAX_PROJECTILES {
+                let (Some(pa), Some(pb)) = (self.projectiles[a], self.projectiles[b]) else {
+                    continue;
+                };
+                let opposed = pa.owner != pb.owner && pa.team.hostile_to(pb.team);
+                if !opposed || pa.is_residual() || pb.is_residual() {
+                    continue;
+                }
+                let Some(overlap) = pa.hitbox().bounds.intersection(&pb.hitbox().bounds) else {
+                    continue;
+                };
 
-        assert_eq!(engine.frame.0, 1);
+                self.clashes.push(ClashEvent {
+                    frame: self.frame,
+                    projectiles: [pa.id, pb.id],
+                    position: overlap.center(),
+                });
+                for slot in [a, b] {
+                    if self.projectiles[slot]
+                        .as_mut()
+                        .is_some_and(|p| !p.destroy())
+                    {
+                        self.projectiles[slot] = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Start a link where `attacker`'s current state drives `victim`
+    pub fn link_pair(&mut self, attacker: EntityId, victim: EntityId) -> bool {
+        if attacker == victim {
+            return false;
+        }
+        let (Some(a), Some(v)) = (
+            self.find_entity_index(attacker),
+            self.find_entity_index(victim),
+        ) else {
+            return false;
+        };
+        let Some(attacker_state) = self.entities[a]
+            .as_ref()
+            .map(|e| e.state_machine.current_state())
+        else {
+            return false;
+        };
+
+        if let Some(victim) = &mut self.entities[v] {
+            victim.hitstun_remaining = 0;
+            victim.blockstun_remaining = 0;
+            victim.knockdown_remaining = 0;
+            victim.physics.velocity = Vec2::ZERO;
+            victim.physics.momentum = Vec2::ZERO;
+            victim.state_machine.transition(StateId::Held);
+        }
+        self.link = Some(PairLink {
+            attacker,
+            victim,
+            attacker_state,
+            victim_state: StateId::Held,
+            tech: None,
+            is_super: false,
+        });
+        true
+    }
+
+    /// Let the linked victim go
+    pub fn release_link(&mut self) {
+        let Some(link) = self.link.take() else {
+            return;
+        };
+        if let Some(v) = self.find_entity_index(link.victim) {
+            if let Some(victim) = &mut self.entities[v] {
+                if victim.state_machine.current_state() == link.victim_state {
+                    victim.state_machine.transition(StateId::Idle);
+                }
+            }
+        }
+    }
+
+    /// Drop a link that no longer matches entity state
+    ///
+    /// Called after loading a snapshot, so a rollback that lands outside the
+    /// throw never leaves the victim stuck.
+    pub fn validate_link(&mut self) {
+        let Some(link) = self.link else {
+            return;
+        };
+        let attacker_ok = self
+            .get_entity(link.attacker)
+            .map(|e| e.state_machine.current_state() == link.attacker_state)
+            .unwrap_or(false);
+        let victim_ok = self
+            .get_entity(link.victim)
+            .map(|e| e.state_machine.current_state() == link.victim_state)
+            .unwrap_or(false);
+        if !attacker_ok || !victim_ok {
+            self.release_link();
+        }
+    }
+
+    /// Resolve a pending throw tech: escape if the victim inputs throw,
+    /// deal the held damage once the window closes
+    ///
+    /// Returns false if the throw was teched and the link is gone.
+    fn update_tech_window(&mut self) -> bool {
+        let Some(link) = self.link else {
+            return true;
+        };
+        let Some(window) = link.tech else {
+            return true;
+        };
+        let victim = self.get_entity(link.victim);
+        let teched = victim
+            .and_then(|v| self.input_manager.get_player_input(v.player_id.0 as usize))
+            .is_some_and(|input| input.detect_throw());
+
+        let mut event = HitEvent {
+            frame: self.frame,
+            attacker: link.attacker,
+            defender: link.victim,
+            outcome: HitOutcome::Teched,
+            cue: None,
+            damage: 0,
+            contact_point: window.contact_point,
+            is_super: false,
+            counter: false,
+        };
+        if teched {
+            self.release_link();
+            for id in [link.attacker, link.victim] {
+                if let Some(i) = self.find_entity_index(id) {
+                    if let Some(entity) = &mut self.entities[i] {
+                        entity.state_machine.transition(StateId::ThrowTech);
+                    }
+                }
+            }
+            self.events.push(event);
+            return false;
+        }
+
+        if let Some(pending) = self.link.as_mut().and_then(|l| l.tech.as_mut()) {
+            pending.frames = pending.frames.saturating_sub(1);
+            if pending.frames > 0 {
+                return true;
+            }
+        }
+        if let Some(link) = &mut self.link {
+            link.tech = None;
+        }
+        if let Some(i) = self.find_entity_index(link.victim) {
+            if let Some(victim) = &mut self.entities[i] {
+                let health_before = victim.health.current;
+                victim.health.take_damage(window.damage);
+                event.outcome = HitOutcome::Hit;
+                event.cue = window.cue;
+                event.damage = health_before - victim.health.current;
+                self.events.push(event);
+            }
+        }
+        true
+    }
+
+    /// Apply the attacker's throw or cinematic actions for this frame to
+    /// the victim
+    fn drive_link(&mut self) {
+        self.validate_link();
+        if !self.update_tech_window() {
+            return;
+        }
+        let Some(link) = self.link else {
+            return;
+        };
+        let Some(attacker) = self.get_entity(link.attacker) else {
+            return;
+        };
+
+        let origin = attacker.physics.position;
+        let sign = attacker.facing.sign();
+        let facing = attacker.facing.opposite();
+        let mut placement = None;
+        let mut victim_state = None;
+        let mut damage = 0;
+        let mut release = false;
+        for action in attacker.state_machine.current_actions() {
+            match action {
+                StateAction::MoveVictim { x, y } => {
+                    let offset = Vec2::new(Fixed(*x).mul(sign).raw(), *y);
+                    placement = Some(origin.add(offset))
+                }
+                StateAction::VictimState { state } => victim_state = Some(*state),
+                StateAction::DamageVictim { damage: d } => {
+                    damage = Fixed(damage).add(Fixed(*d)).raw()
+                }
+                StateAction::ReleaseVictim => release = true,
+                _ => {}
+            }
+        }
+
+        let Some(v) = self.find_entity_index(link.victim) else {
+            return;
+        };
+        if let Some(victim) = &mut self.entities[v] {
+            if let Some(position) = placement {
+                victim.physics.position = position;
+                victim.physics.on_ground = position.y >= 0;
+                victim.facing = facing;
+            }
+            if let Some(state) = victim_state {
+                victim.state_machine.transition(state);
+                if let Some(link) = &mut self.link {
+                    link.victim_state = state;
+                }
+            }
+            if damage > 0 {
+                let damage = self.mutators.scale_damage(damage);
+                let health_before = victim.health.current;
+                victim.health.take_damage(damage);
+                self.events.push(HitEvent {
+                    frame: self.frame,
+                    attacker: link.attacker,
+                    defender: link.victim,
+                    outcome: HitOutcome::Hit,
+                    cue: None,
+                    damage: health_before - victim.health.current,
+                    contact_point: victim.physics.position,
+                    is_super: link.is_super,
+                    counter: false,
+                });
+            }
+        }
+        if release {
+            self.release_link();
+        }
+    }
+
+    /// Push overlapping fighters apart
+    ///
+    /// Displacement is split in inverse proportion to push weight. A fighter
+    /// in the air against a grounded one takes the whole displacement, so
+    /// jumping onto the opponent can't be used to shove them.
+    fn separate_pushboxes(&mut self) {
+        // Throw positioning is authored; don't push the pair apart
+        if self.entity_count < 2 || self.link.is_some() {
+            return;
+        }
+        let (Some(p1), Some(p2)) = (&self.entities[0], &self.entities[1]) else {
+            return;
+        };
+
+        let box1 = p1.get_pushbox().bounds;
+        let box2 = p2.get_pushbox().bounds;
+        let Some(overlap) = box1.intersection(&box2) else {
+            return;
+        };
+
+        // Fighter whose box is further left is pushed left (P1 on exact ties)
+        let p1_is_left = box1.center().x <= box2.center().x;
+
+        let (w1, w2) = match (p1.physics.on_ground, p2.physics.on_ground) {
+            (false, true) => (0, 1),
+            (true, false) => (1, 0),
+            _ => (p1.push_weight(), p2.push_weight()),
+        };
+        // Weights come from character data, so the split is taken in 64 bits
+        let total = (w1 as i64 + w2 as i64).max(1);
+
+        // Each fighter moves by the share of the other's weight
+        let p1_share = Fixed::from_wide(overlap.width as i64 * w2 as i64 / total);
+        let p2_share = Fixed(overlap.width).sub(p1_share);
+        let dir = if p1_is_left { -1 } else { 1 };
+
+        if let Some(p1) = &mut self.entities[0] {
+            let x = Fixed(p1.physics.position.x).add(p1_share.mul(dir));
+            p1.physics.position.x = x.raw();
+        }
+        if let Some(p2) = &mut self.entities[1] {
+            let x = Fixed(p2.physics.position.x).sub(p2_share.mul(dir));
+            p2.physics.position.x = x.raw();
+        }
+    }
+
+    /// Keep fighters between the stage edges and under the ceiling
+    ///
+    /// A fighter pushed past an edge is put back against it. If the push
+    /// came from the opponent, either through pushboxes or hit pushback
+    /// against a wall, the opponent is moved back by the same distance
+    /// instead: pressure in the corner pushes the attacker out.
+    fn apply_stage_bounds(&mut self) {
+        let stage = self.config.stage;
+        let half_width = stage.half_width;
+        for i in 0..self.entity_count.min(MAX_PLAYERS) {
+            let Some(fighter) = &mut self.entities[i] else {
+                continue;
+            };
+            if fighter.hitstun_remaining == 0 && fighter.blockstun_remaining == 0 {
+                fighter.pusher = None;
+            }
+            if let Some(ceiling) = stage.ceiling {
+                let physics = &mut fighter.physics;
+                if physics.position.y < -ceiling {
+                    physics.position.y = -ceiling;
+                    physics.velocity.y = physics.velocity.y.max(0);
+                    physics.momentum.y = physics.momentum.y.max(0);
+                }
+            }
+            let overshoot = wall_overshoot(fighter.get_pushbox().bounds, half_width);
+            if overshoot == 0 {
+                continue;
+            }
+            let x = &mut fighter.physics.position.x;
+            *x = Fixed(*x).sub(Fixed(overshoot)).raw();
+            // Only walls pass hit pushback on; ledges just stop the fighter
+            let pusher = fighter.pusher.filter(|_| stage.edge == StageEdge::Wall);
+            let body = fighter.get_pushbox().bounds;
+
+            let transferred = match &mut self.entities[1 - i] {
+                Some(opponent)
+                    if pusher == Some(opponent.id)
+                        || opponent.get_pushbox().bounds.intersection(&body).is_some() =>
+                {
+                    let x = &mut opponent.physics.position.x;
+                    *x = Fixed(*x).sub(Fixed(overshoot)).raw();
+                    let back = wall_overshoot(opponent.get_pushbox().bounds, half_width);
+                    let x = &mut opponent.physics.position.x;
+                    *x = Fixed(*x).sub(Fixed(back)).raw();
+                    true
+                }
+                _ => false,
+            };
+
+            // Remaining pushback keeps feeding the opponent; otherwise it's lost
+            if let Some(fighter) = &mut self.entities[i] {
+                if !transferred && fighter.physics.momentum.x.signum() == overshoot.signum() {
+                    fighter.physics.momentum.x = 0;
+                }
+            }
+        }
+    }
+
+    /// Detect all collisions this frame
+    fn detect_collisions(&mut self) {
+        self.collision_system.clear();
+        self.resolve_clashes();
+
+        // Gather all hitboxes and hurtboxes; linked fighters are out of play
+        let linked = |id| {
+            self.link
+                .is_some_and(|l| l.attacker == id || l.victim == id)
+        };
+        for i in 0..self.entity_count {
+            if let Some(entity) = &self.entities[i] {
+                if linked(entity.id) {
+                    continue;
+                }
+                // Add hitboxes
+                let hitboxes = entity.get_hitboxes();
+                for hitbox in hitboxes.iter().flatten() {
+                    self.collision_system.add_hitbox(*hitbox);
+                }
+
+                // Add hurtboxes
+                let hurtboxes = entity.get_hurtboxes();
+                for hurtbox in hurtboxes.iter().flatten() {
+                    self.collision_system.add_hurtbox(*hurtbox);
+                }
+            }
+        }
+        for projectile in self.projectiles.iter().flatten() {
+            self.collision_system.add_hitbox(projectile.hitbox());
+        }
+
+        self.metrics.active_hitboxes = self.collision_system.active_hitbox_count() as u32;
+        self.metrics.active_hurtboxes = self.collision_system.active_hurtbox_count() as u32;
+        self.metrics.collision_pair_tests = self.collision_system.pair_test_count() as u32;
+    }
+
+    /// Resolve all hit events
+    fn resolve_hits(&mut self) {
+        // Same-frame collisions resolve in the order defined by `ordering`
+        let mut collisions = self.collision_system.check_collisions();
+        order_collisions(&mut collisions);
+        self.settle_anti_air(&mut collisions);
+
+        for collision in collisions.iter().flatten() {
+            // Projectiles connect only once; team checks already kept them
+            // off their own side
+            let from_projectile = is_projectile(collision.attacker);
+            // Partially invulnerable fighters keep hurtboxes; the kinds of
+            // attack they ignore pass through here
+            let ignored = self.get_entity(collision.defender).is_some_and(|d| {
+                d.invulnerability()
+                    .ignores(&collision.attack_data, from_projectile)
+            });
+            if ignored {
+                continue;
+            }
+            if from_projectile {
+                if self.get_projectile(collision.attacker).is_none() {
+                    continue;
+                }
+                if let Some(event) = self.guard_projectile(collision) {
+                    self.events.push(event);
+                    continue;
+                }
+            }
+            if let Some(event) = self.apply_hit(collision) {
+                self.events.push(event);
+                self.build_meter(&event);
+                if from_projectile {
+                    self.despawn_projectile(collision.attacker);
+                }
+            }
+        }
+
+        // Fighters hitting each other on the same frame trade
+        for i in 0..self.events.len() {
+            let event = self.events[i];
+            if event.outcome != HitOutcome::Hit {
+                continue;
+            }
+            let traded = self.events.iter().any(|other| {
+                other.outcome != HitOutcome::Blocked
+                    && other.attacker == event.defender
+                    && other.defender == event.attacker
+            });
+            if traded {
+                self.events[i].outcome = HitOutcome::Trade;
+            }
+        }
+
+        self.metrics.events_emitted = self.events.len() as u32;
+    }
+
+    /// Drop the losing side of a jump-in and anti-air hitting each other
+    /// near the ground, per `AntiAirConfig`
+    fn settle_anti_air(&self, collisions: &mut [Option<CollisionResult>]) {
+        let config = self.config.game.anti_air;
+        let (Some(p1), Some(p2)) = (&self.entities[0], &self.entities[1]) else {
+            return;
+        };
+        // Hits the defender would ignore don't make it a tie
+        let hits = |attacker: &Entity, defender: &Entity| {
+            collisions.iter().flatten().any(|c| {
+                c.attacker == attacker.id
+                    && c.defender == defender.id
+                    && !defender.invulnerability().ignores(&c.attack_data, false)
+            })
+        };
+        if !hits(p1, p2) || !hits(p2, p1) {
+            return;
+        }
+
+        let near_ground =
+            |e: &Entity| !e.physics.on_ground && -e.physics.position.y <= config.height;
+        let (jumper, anti_air) = match (p1.physics.on_ground, p2.physics.on_ground) {
+            (false, true) if near_ground(p1) => (p1.id, p2.id),
+            (true, false) if near_ground(p2) => (p2.id, p1.id),
+            _ => return,
+        };
+        let loser = match config.priority {
+            AntiAirPriority::Trade => return,
+            AntiAirPriority::Defender => jumper,
+            AntiAirPriority::Attacker => anti_air,
+        };
+        for slot in collisions.iter_mut() {
+            if slot.is_some_and(|c| c.attacker == loser) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Audiovisual cues raised during the last tick
+    pub fn cues(&self) -> &[CueEvent] {
+        &self.cues
+    }
+
+    /// Hit events emitted during the last tick
+    pub fn events(&self) -> &[HitEvent] {
+        &self.events
+    }
+
+    /// Apply a single hit to defender, returning the resulting event
+    fn apply_hit(&mut self, collision: &CollisionResult) -> Option<HitEvent> {
+        let resolved = self.resolve_hit_feel(collision);
+        let collision = &resolved;
+
+        // Find defender
+        let defender_idx = self.find_entity_index(collision.defender);
+        let defender_idx = defender_idx?;
+
+        // Hits landing on the defender's back use the behind-block policy
+        let from_behind = self.hit_from_behind(collision.attacker, defender_idx);
+        let back_turned = self.entities[defender_idx]
+            .as_ref()
+            .map(|e| e.state_machine.is_back_turned())
+            .unwrap_or(false);
+
+        // Blocks made within the first frames of holding back are just defends
+        let mut just_defend = false;
+
+        // Check if defender is blocking
+        let is_blocking = {
+            if let Some(defender) = &self.entities[defender_idx] {
+                let player_id = defender.player_id.0 as usize;
+                if let Some(input) = self.input_manager.get_player_input(player_id) {
+                    let direction = input.current().direction;
+                    // Blocking if holding away from the attacker
+                    let guarding = match (from_behind, self.config.game.behind_block) {
+                        _ if back_turned || defender.is_dizzy() => false,
+                        (false, _) => direction.is_back(),
+                        (true, BehindBlockPolicy::Reversed) => direction.is_forward(),
+                        (true, BehindBlockPolicy::Impossible) => false,
+                    };
+                    // Lows must be blocked crouching, overheads standing
+                    let crouching = direction.is_down();
+                    let attack = &collision.attack_data;
+                    let blocking = guarding
+                        && !(attack.is_low && !crouching || attack.is_overhead && crouching);
+                    just_defend = blocking
+                        && !from_behind
+                        && self
+                            .config
+                            .game
+                            .just_defend
+                            .catches(input.back_held_frames());
+                    blocking
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        };
+
+        // Downed characters can only be hit by OTG attacks
+        let is_downed = self.entities[defender_idx]
+            .as_ref()
+            .map(|e| e.is_knocked_down())
+            .unwrap_or(false);
+        if is_downed && !collision.attack_data.can_otg {
+            return None;
+        }
+
+        // Each hit group of an attack connects once per activation
+        let group = collision.attack_data.hit_group;
+        if let Some(attacker_idx) = self.find_entity_index(collision.attacker) {
+            if let Some(attacker) = &mut self.entities[attacker_idx] {
+                if attacker.state_machine.has_hit_group(group) {
+                    return None;
+                }
+                attacker.state_machine.mark_hit_group(group);
+            }
+        }
+
+        // Hitstop freezes both fighters, and their projectiles wait with them
+        let hitstop = collision.attack_data.hitstop.unwrap_or(0);
+        for id in [collision.attacker, collision.defender] {
+            self.freeze_entity(id, hitstop);
+        }
+
+        let attack = &collision.attack_data;
+
+        // Counter stances negate qualifying attacks and punish
+        let defender = self.entities[defender_idx].as_mut()?;
+        if let Some(punish) = defender.state_machine.counter_catches(attack) {
+            defender.state_machine.transition(punish);
+            return Some(HitEvent {
+                frame: self.frame,
+                attacker: collision.attacker,
+                defender: collision.defender,
+                outcome: HitOutcome::Parried,
+                cue: attack.hit_cue,
+                damage: 0,
+                contact_point: collision.contact_point,
+                is_super: attack.is_super,
+                counter: false,
+            });
+        }
+
+        // Any hit snaps a dizzied fighter out of it
+        if defender.is_dizzy() {
+            defender.dizzy_remaining = 0;
+        }
+
+        let blocked = is_blocking && attack.can_block;
+        if let (Some(grab_state), false) = (attack.grab_state, blocked) {
+            return self.start_hit_grab(collision, grab_state);
+        }
+
+        // Apply hit, with damage run through the pipeline
+        let mut scaled = *collision;
+        scaled.attack_data.damage = self.scaled_damage(collision, defender_idx);
+        let defender = self.entities[defender_idx].as_mut()?;
+        let armored = !blocked && defender.armor_absorbs(attack);
+        if armored {
+            let percent = defender.armor_damage_percent;
+            scaled.attack_data.damage = Fixed(scaled.attack_data.damage).percent(percent).raw();
+        }
+        let counter = !blocked && !armored && defender.state_machine.in_startup();
+        if counter {
+            let bonus = self.config.game.counter_hit;
+            let damage = Fixed(scaled.attack_data.damage).percent(bonus.damage_percent);
+            scaled.attack_data.damage = damage.raw();
+            let hitstun = scaled
+                .attack_data
+                .hitstun
+                .saturating_add(bonus.hitstun_bonus);
+            scaled.attack_data.hitstun = hitstun;
+        }
+        let just_defend = blocked && just_defend;
+        if just_defend {
+            let percent = self.config.game.just_defend.blockstun_percent;
+            let blockstun = Fixed(scaled.attack_data.blockstun as i32).percent(percent);
+            scaled.attack_data.blockstun = blockstun.raw().max(0) as u32;
+        }
+        let health_before = defender.health.current;
+        let guard = &self.config.game.guard;
+        let outcome = if just_defend {
+            defender.take_hit(&scaled, is_blocking, from_behind);
+            HitOutcome::Parried
+        } else if armored {
+            defender.absorb_hit(&scaled.attack_data)
+        } else if blocked && defender.drain_guard(attack.damage, guard) {
+            defender.guard_crush(&scaled.attack_data, guard)
+        } else {
+            defender.take_hit(&scaled, is_blocking, from_behind)
+        };
+        let damage = health_before - defender.health.current;
+        defender.pusher = (!is_projectile(collision.attacker)).then_some(collision.attacker);
+        if outcome == HitOutcome::Hit {
+            defender.add_dizzy(attack.dizzy_stun(), &self.config.game.dizzy);
+            self.record_stale_move(collision.attacker);
+        }
+        if let Some(attacker) = self
+            .find_entity_index(collision.attacker)
+            .and_then(|i| self.entities[i].as_mut())
+        {
+            let recoil = -attacker.facing.sign() * HIT_RECOIL_DISTANCE;
+            attacker.presentation.recoil(recoil);
+        }
+
+        Some(HitEvent {
+            frame: self.frame,
+            attacker: collision.attacker,
+            defender: collision.defender,
+            outcome,
+            cue: collision.attack_data.hit_cue,
+            damage,
+            contact_point: collision.contact_point,
+            is_super: collision.attack_data.is_super,
+            counter,
+        })
+    }
+
+    /// Build the attacker's meter for an attack that made contact
+    fn build_meter(&mut self, event: &HitEvent) {
+        let gain = match event.outcome {
+            HitOutcome::Hit | HitOutcome::Trade | HitOutcome::GuardCrushed => METER_GAIN_ON_HIT,
+            HitOutcome::Blocked | HitOutcome::Armored => METER_GAIN_ON_BLOCK,
+            _ => return,
+        };
+        // Projectiles build meter for whoever threw them
+        let attacker = self
+            .get_projectile(event.attacker)
+            .map_or(event.attacker, |p| p.owner);
+        if let Some(idx) = self.find_entity_index(attacker) {
+            if let Some(fighter) = &mut self.entities[idx] {
+                fighter.gain_meter(gain);
+            }
+        }
+    }
+
+    /// Fill in hitstop and cue the attack leaves to the hit feel tiers
+    fn resolve_hit_feel(&self, collision: &CollisionResult) -> CollisionResult {
+        let mut resolved = *collision;
+        let attack = &mut resolved.attack_data;
+        if let Some(tier) = self.config.hit_feel.tier_for(attack.damage) {
+            attack.hitstop.get_or_insert(tier.hitstop);
+            attack.hit_cue.get_or_insert(tier.cue);
+        }
+        resolved
+    }
+
+    /// Whether an attack lands on the defender's back
+    ///
+    /// Decided by the attacker's position at impact: an attacker on the side
+    /// the defender faces away from hits their back, which a back-turned
+    /// state reverses.
+    fn hit_from_behind(&self, attacker: EntityId, defender_idx: usize) -> bool {
+        let (Some(attacker), Some(defender)) =
+            (self.get_entity(attacker), &self.entities[defender_idx])
+        else {
+            return false;
+        };
+        let offset = attacker.physics.position.x - defender.physics.position.x;
+        let behind = offset * defender.facing.sign() < 0;
+        behind != defender.state_machine.is_back_turned()
+    }
+
+    /// Damage a collision would deal to the defender after scaling
+    fn scaled_damage(&self, collision: &CollisionResult, defender_idx: usize) -> i32 {
+        let attack = &collision.attack_data;
+        let Some(defender) = &self.entities[defender_idx] else {
+            return attack.damage;
+        };
+        let stale_count = self
+            .get_entity(collision.attacker)
+            .map(|a| a.stale_moves.count(a.state_machine.current_state()))
+            .unwrap_or(0);
+        let in_combo = defender.in_combo();
+        // Freshly guard-crushed fighters take reduced damage for a moment
+        let defense_percent = if defender.crush_protection_remaining > 0 {
+            Fixed(defender.defense)
+                .percent(self.config.game.guard.protection_percent)
+                .raw()
+        } else {
+            defender.defense
+        };
+        let ctx = DamageContext {
+            base: attack.damage,
+            stale_count,
+            combo_hits: if in_combo { defender.combo_hits } else { 0 },
+            proration_percent: if in_combo {
+                defender.combo_proration
+            } else {
+                100
+            },
+            defense_percent,
+            health: defender.health.current,
+            max_health: defender.health.maximum,
+        };
+        self.mutators
+            .scale_damage(damage::compute(&self.config.damage, &ctx))
+    }
+
+    /// Add the attacker's current move to its stale queue
+    fn record_stale_move(&mut self, attacker: EntityId) {
+        let length = self.config.damage.stale_queue_length;
+        if let Some(idx) = self.find_entity_index(attacker) {
+            if let Some(entity) = &mut self.entities[idx] {
+                let state = entity.state_machine.current_state();
+                entity.stale_moves.push(state, length);
+            }
+        }
+    }
+
+    /// Convert a connecting hit-grab into a linked grab sequence
+    fn start_hit_grab(
+        &mut self,
+        collision: &CollisionResult,
+        grab_state: StateId,
+    ) -> Option<HitEvent> {
+        let attacker_idx = self.find_entity_index(collision.attacker)?;
+        let defender_idx = self.find_entity_index(collision.defender)?;
+
+        self.entities[attacker_idx]
+            .as_mut()?
+            .state_machine
+            .transition(grab_state);
+        if !self.link_pair(collision.attacker, collision.defender) {
+            return None;
+        }
+        if let Some(link) = &mut self.link {
+            link.is_super = collision.attack_data.is_super;
+        }
+
+        let damage = self.scaled_damage(collision, defender_idx);
+        if collision.attack_data.techable {
+            // Damage and the hit event wait until the tech window closes
+            if let Some(link) = &mut self.link {
+                link.tech = Some(TechWindow {
+                    frames: THROW_TECH_WINDOW,
+                    damage,
+                    cue: collision.attack_data.hit_cue,
+                    contact_point: collision.contact_point,
+                });
+            }
+            return None;
+        }
+        let defender = self.entities[defender_idx].as_mut()?;
+        let health_before = defender.health.current;
+        defender.health.take_damage(damage);
+
+        Some(HitEvent {
+            frame: self.frame,
+            attacker: collision.attacker,
+            defender: collision.defender,
+            outcome: HitOutcome::Hit,
+            cue: collision.attack_data.hit_cue,
+            damage: health_before - defender.health.current,
+            contact_point: collision.contact_point,
+            is_super: collision.attack_data.is_super,
+            counter: false,
+        })
+    }
+
+    /// Update all entities to face their opponents
+    fn update_facing(&mut self) {
+        if self.entity_count >= 2 {
+            // Get positions first (avoid borrow checker issues)
+            let p1_pos = self.entities[0].as_ref().map(|e| e.physics.position);
+            let p2_pos = self.entities[1].as_ref().map(|e| e.physics.position);
+
+            // Update p1 facing
+            if let (Some(p1), Some(pos)) = (&mut self.entities[0], p2_pos) {
+                p1.update_facing(pos);
+            }
+
+            // Update p2 facing
+            if let (Some(p2), Some(pos)) = (&mut self.entities[1], p1_pos) {
+                p2.update_facing(pos);
+            }
+        }
+    }
+
+    /// Check win conditions
+    fn check_win_conditions(&mut self) {
+        if self.entity_count < 2 {
+            return;
+        }
+
+        let p1_alive = self.entities[0]
+            .as_ref()
+            .map(|e| e.health.is_alive())
+            .unwrap_or(false);
+        let p2_alive = self.entities[1]
+            .as_ref()
+            .map(|e| e.health.is_alive())
+            .unwrap_or(false);
+
+        for (i, alive) in [p1_alive, p2_alive].into_iter().enumerate() {
+            if let (false, Some(entity)) = (alive, &self.entities[i]) {
+                self.game_events.push(GameEvent::Ko {
+                    frame: self.frame,
+                    entity: entity.id,
+                });
+            }
+        }
+
+        match (p1_alive, p2_alive) {
+            (true, true) => {}
+            (true, false) => {
+                self.detect_finishes(0, 1);
+                self.end_round(&[0], None)
+            }
+            (false, true) => {
+                self.detect_finishes(1, 0);
+                self.end_round(&[1], None)
+            }
+            (false, false) => match self.config.game.double_ko {
+                DoubleKoPolicy::Draw => self.end_round(&[], None),
+                DoubleKoPolicy::BothScore => self.end_round(&[0, 1], None),
+                DoubleKoPolicy::SuddenDeath => self.end_round(&[], Some(SUDDEN_DEATH_HEALTH)),
+            },
+        }
+    }
+
+    /// Record the special finishes of a KO before the round resets
+    fn detect_finishes(&mut self, winner: usize, loser: usize) {
+        let (Some(w), Some(l)) = (&self.entities[winner], &self.entities[loser]) else {
+            return;
+        };
+        let stage = self.config.stage;
+        let bounds = l.get_pushbox().bounds;
+        let cornered = bounds.x <= -stage.half_width || bounds.x + bounds.width >= stage.half_width;
+        let super_ko = self
+            .events
+            .iter()
+            .rev()
+            .find(|e| e.defender == l.id && e.damage > 0)
+            .is_some_and(|e| e.is_super);
+
+        let kinds = [
+            (FinishKind::Super, super_ko),
+            (
+                FinishKind::WallBreak,
+                cornered && stage.edge == StageEdge::Wall,
+            ),
+            (FinishKind::Perfect, w.health.current == w.health.maximum),
+        ];
+        let (winner, loser) = (w.id, l.id);
+        for (kind, _) in kinds.into_iter().filter(|(_, met)| *met) {
+            self.finishes.push(FinishEvent {
+                frame: self.frame,
+                winner,
+                loser,
+                kind,
+            });
+        }
+    }
+
+    /// End the round on time: more health remaining wins, equal draws the
+    /// match
+    fn check_timeout(&mut self) {
+        let limit = self.config.game.time_limit_frames;
+        // The tick in progress counts, so the round lasts exactly `limit` frames
+        let elapsed = self.frame.0 + 1 - self.round_start_frame.0;
+        if limit == 0 || elapsed < limit || self.game_result != GameResult::InProgress {
+            return;
+        }
+
+        let health = |i: usize| self.entities[i].as_ref().map_or(0, |e| e.health.current);
+        match health(0).cmp(&health(1)) {
+            core::cmp::Ordering::Greater => self.end_round(&[0], None),
+            core::cmp::Ordering::Less => self.end_round(&[1], None),
+            core::cmp::Ordering::Equal => {
+                self.game_result = GameResult::Draw;
+                self.start_outro();
+            }
+        }
+    }
+
+    /// Award the round, then either decide the match or start the next round
+    fn end_round(&mut self, winners: &[usize], next_round_health: Option<i32>) {
+        for &player in winners {
+            self.round_wins[player] += 1;
+        }
+
+        let target = self.config.game.rounds_to_win.max(1);
+        self.game_result = match (self.round_wins[0] >= target, self.round_wins[1] >= target) {
+            (true, true) => GameResult::Draw,
+            (true, false) => GameResult::Player1Wins,
+            (false, true) => GameResult::Player2Wins,
+            (false, false) => GameResult::InProgress,
+        };
+        if self.game_result != GameResult::InProgress {
+            self.start_outro();
+        }
+
+        if self.game_result == GameResult::InProgress {
+            let definitions = [0, 1].map(|player| match &self.entities[player] {
+                Some(entity) => entity.state_machine.definitions().clone(),
+                None => self.characters[player].states.clone(),
+            });
+            let previous = [self.entities[0].take(), self.entities[1].take()];
+            self.round += 1;
+            self.start_round(definitions, next_round_health);
+            self.carry_over(previous, winners);
+            // Rounds end mid-tick; the next one starts on the following frame
+            self.round_start_frame = self.frame.next();
+        }
+    }
+
+    /// Copy what the round persistence rules keep into the new round's fighters
+    fn carry_over(&mut self, previous: [Option<Entity>; MAX_PLAYERS], winners: &[usize]) {
+        let persistence = self.config.game.persistence;
+        for (player, old) in previous.into_iter().enumerate() {
+            let (Some(old), Some(fighter)) = (old, &mut self.entities[player]) else {
+                continue;
+            };
+            if persistence.meter {
+                fighter.meter = old.meter;
+            }
+            if persistence.stale_moves {
+                fighter.stale_moves = old.stale_moves;
+            }
+            // Match-long resources stay spent
+            for (resource, old) in fighter.resources.iter_mut().zip(old.resources) {
+                if let (Some(resource), Some(old)) = (resource, old) {
+                    if old.scope == ResourceScope::Match {
+                        resource.remaining = old.remaining;
+                    }
+                }
+            }
+            // A sole winner keeps their damage; shared rounds refill everyone
+            if persistence.winner_damage && winners == [player] {
+                fighter
+                    .health
+                    .set(fighter.health.current.min(old.health.current));
+            }
+        }
+        self.grant_round_bonus(winners);
+    }
+
+    /// Hand out the round-start meter bonuses for the previous round's result
+    fn grant_round_bonus(&mut self, winners: &[usize]) {
+        let bonus = self.config.game.round_bonus;
+        for player in 0..MAX_PLAYERS {
+            self.round_bonus[player] = match winners {
+                [winner] if *winner == player => bonus.winner_meter,
+                [_] => bonus.loser_meter,
+                _ => 0,
+            };
+            if let Some(fighter) = &mut self.entities[player] {
+                fighter.gain_meter(self.round_bonus[player]);
+            }
+        }
+    }
+
+    /// Get entity by ID
+    pub fn get_entity(&self, id: EntityId) -> Option<&Entity> {
+        for i in 0..self.entity_count {
+            if let Some(entity) = &self.entities[i] {
+                if entity.id == id {
+                    return Some(entity);
+                }
+            }
+        }
+        None
+    }
+
+    /// Frames the fighters stay frozen, by hitstop or a super flash
+    pub fn hitstop_remaining(&self) -> u32 {
+        self.entities[..self.entity_count]
+            .iter()
+            .flatten()
+            .map(|e| e.frozen_frames)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Freeze an entity for at least `frames` frames (see `Entity::freeze`)
+    ///
+    /// Returns false if no entity has that ID.
+    pub fn freeze_entity(&mut self, id: EntityId, frames: u32) -> bool {
+        let entities = &mut self.entities[..self.entity_count];
+        match entities.iter_mut().flatten().find(|e| e.id == id) {
+            Some(entity) => {
+                entity.freeze(frames);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get entity by player ID
+    pub fn get_player_entity(&self, player: PlayerId) -> Option<&Entity> {
+        for i in 0..self.entity_count {
+            if let Some(entity) = &self.entities[i] {
+                if entity.player_id == player {
+                    return Some(entity);
+                }
+            }
+        }
+        None
+    }
+
+    fn find_entity_index(&self, id: EntityId) -> Option<usize> {
+        for i in 0..self.entity_count {
+            if let Some(entity) = &self.entities[i] {
+                if entity.id == id {
+                    return Some(i);
+                }
+            }
+        }
+        None
+    }
+
+    /// Get training overlay information for a player
+    pub fn training_info(&self, player: PlayerId) -> Option<TrainingInfo> {
+        self.get_player_entity(player).map(|e| TrainingInfo {
+            knockdown_remaining: e.knockdown_remaining,
+            wakeup_delay: e.wakeup_delay_applied,
+            blockstring: e.blockstring,
+        })
+    }
+
+    /// Frames left on the round timer, or None without a time limit
+    pub fn time_remaining(&self) -> Option<u64> {
+        match self.config.game.time_limit_frames {
+            0 => None,
+            limit => Some(limit.saturating_sub(self.frame.0 - self.round_start_frame.0)),
+        }
+    }
+
+    /// Get game state summary for rendering/display
+    pub fn get_state(&self) -> GameState<'_> {
+        let p1 = self.get_player_entity(PlayerId::PLAYER_1);
+        let p2 = self.get_player_entity(PlayerId::PLAYER_2);
+
+        GameState {
+            frame: self.frame.0,
+            p1_pos: p1.map(|e| e.physics.position).unwrap_or(Vec2::ZERO),
+            p1_health: p1.map(|e| e.health.current).unwrap_or(0),
+            p1_state: p1
+                .map(|e| state_to_string(e.state_machine.current_state()))
+                .unwrap_or("Unknown"),
+            p1_facing: p1.map(|e| e.facing).unwrap_or(crate::types::Facing::Right),
+            p2_pos: p2.map(|e| e.physics.position).unwrap_or(Vec2::ZERO),
+            p2_health: p2.map(|e| e.health.current).unwrap_or(0),
+            p2_state: p2
+                .map(|e| state_to_string(e.state_machine.current_state()))
+                .unwrap_or("Unknown"),
+            p2_facing: p2.map(|e| e.facing).unwrap_or(crate::types::Facing::Left),
+            result: self.game_result,
+            time_remaining: self.time_remaining(),
+            round: self.round,
+            p1_rounds: self.round_wins[0],
+            p2_rounds: self.round_wins[1],
+            p1_meter: p1.map(|e| e.meter).unwrap_or(0),
+            p2_meter: p2.map(|e| e.meter).unwrap_or(0),
+            p1_round_bonus: self.round_bonus[0],
+            p2_round_bonus: self.round_bonus[1],
+            // A player's combo is what their opponent is taking
+            p1_combo_hits: p2.map(|e| e.combo_hits).unwrap_or(0),
+            p1_combo_damage: p2.map(|e| e.combo_damage).unwrap_or(0),
+            p2_combo_hits: p1.map(|e| e.combo_hits).unwrap_or(0),
+            p2_combo_damage: p1.map(|e| e.combo_damage).unwrap_or(0),
+            hitstop: self.hitstop_remaining(),
+            p1_lifebar: p1.map(|e| e.health.segments()).unwrap_or_default(),
+            p2_lifebar: p2.map(|e| e.health.segments()).unwrap_or_default(),
+            p1_resources: p1.map(|e| e.resources).unwrap_or_default(),
+            p2_resources: p2.map(|e| e.resources).unwrap_or_default(),
+            p1_guard: p1.map(|e| e.guard).unwrap_or(0),
+            p2_guard: p2.map(|e| e.guard).unwrap_or(0),
+            p1_dizzy: p1.map(|e| e.dizzy).unwrap_or(0),
+            p2_dizzy: p2.map(|e| e.dizzy).unwrap_or(0),
+        }
+    }
+}
+
+/// Game state snapshot for display/serialization
+#[derive(Debug, Clone)]
+pub struct GameState<'a> {
+    pub frame: u64,
+    pub p1_pos: Vec2,
+    pub p1_health: i32,
+    pub p1_state: &'a str,
+    pub p1_facing: crate::types::Facing,
+    pub p2_pos: Vec2,
+    pub p2_health: i32,
+    pub p2_state: &'a str,
+    pub p2_facing: crate::types::Facing,
+    pub result: GameResult,
+    pub time_remaining: Option<u64>, // Frames left, None without a time limit
+    pub round: u32,
+    pub p1_rounds: u32,
+    pub p2_rounds: u32,
+    pub p1_meter: i32,
+    pub p2_meter: i32,
+    pub p1_round_bonus: i32, // Meter granted at the start of this round
+    pub p2_round_bonus: i32,
+    pub p1_combo_hits: u32, // Combo P1 is currently landing
+    pub p1_combo_damage: i32,
+    pub p2_combo_hits: u32, // Combo P2 is currently landing
+    pub p2_combo_damage: i32,
+    pub hitstop: u32, // Frames of hit freeze remaining
+    pub p1_lifebar: LifebarSegments,
+    pub p2_lifebar: LifebarSegments,
+    pub p1_resources: [Option<LimitedResource>; MAX_RESOURCES], // Limited-use resources by slot
+    pub p2_resources: [Option<LimitedResource>; MAX_RESOURCES],
+    pub p1_guard: i32, // Guard meter (0 when disabled)
+    pub p2_guard: i32,
+    pub p1_dizzy: i32, // Dizzy meter (0 when disabled)
+    pub p2_dizzy: i32,
+}
+
+/// Distance a box sticks out past the stage walls (negative on the left)
+fn wall_overshoot(bounds: crate::types::Rect, half_width: i32) -> i32 {
+    let half_width = Fixed(half_width);
+    let left = Fixed(bounds.x);
+    let right = left.add(Fixed(bounds.width));
+    if left < Fixed::ZERO.sub(half_width) {
+        left.add(half_width).raw()
+    } else if right > half_width {
+        right.sub(half_width).raw()
+    } else {
+        0
+    }
+}
+
+/// Whether an entity ID belongs to a projectile slot
+fn is_projectile(id: EntityId) -> bool {
+    (PROJECTILE_ID_BASE..PROJECTILE_ID_BASE + MAX_PROJECTILES as u32).contains(&id.0)
+}
+
+fn state_to_string(state: crate::state::StateId) -> &'static str {
+    use crate::state::StateId;
+    match state {
+        StateId::Idle => "Idle",
+        StateId::Walk => "Walk",
+        StateId::WalkBack => "WalkBack",
+        StateId::Crouch => "Crouch",
+        StateId::Jump => "Jump",
+        StateId::LightAttack => "Light",
+        StateId::MediumAttack => "Medium",
+        StateId::HeavyAttack => "Heavy",
+        StateId::Sweep => "Sweep",
+        StateId::SpecialMove => "Special",
+        StateId::Hitstun => "Hit",
+        StateId::Blockstun => "Block",
+        StateId::Knockdown => "Down",
+        StateId::TechNeutral => "Tech",
+        StateId::TechBack => "TechBack",
+        StateId::TechForward => "TechForward",
+        StateId::HeavyCharge => "Charge",
+        StateId::ChargedHeavy => "ChargedHeavy",
+        StateId::Held => "Held",
+        StateId::Throw => "Throw",
+        StateId::Throwing => "Throwing",
+        StateId::ThrowWhiff => "ThrowWhiff",
+        StateId::BackHitstun => "BackHit",
+        StateId::ThrowTech => "ThrowTech",
+        StateId::ExSpecial => "ExSpecial",
+        StateId::SuperMove => "Super",
+        StateId::JumpLight => "JumpLight",
+        StateId::JumpMedium => "JumpMedium",
+        StateId::JumpHeavy => "JumpHeavy",
+        StateId::Landing => "Landing",
+        StateId::Dizzy => "Dizzy",
+        StateId::WinPose(_) => "Win",
+        StateId::LosePose => "Lose",
+        StateId::Custom(_) => "Custom",
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::config::{GameConfig, StageConfig, StagePreset};
+    use crate::projectile::ProjectileDef;
+    use crate::state::{states, StateId};
+
+    #[test]
+    fn test_engine_initialization() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        assert_eq!(engine.entity_count, 2);
+        assert_eq!(engine.frame.0, 0);
+        assert_eq!(engine.game_result, GameResult::InProgress);
+    }
+
+    #[test]
+    fn test_engine_tick() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        let neutral = InputState::neutral();
+        engine.tick(neutral, neutral);
+
+        assert_eq!(engine.frame.0, 1);
+    }
+
+    #[test]
+    fn test_win_condition() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        // Kill player 2: round 1 goes to player 1 and round 2 starts
+        if let Some(p2) = &mut engine.entities[1] {
+            p2.health.current = 0;
+        }
+        engine.check_win_conditions();
+        assert_eq!(engine.game_result, GameResult::InProgress);
+        assert_eq!(engine.round, 2);
+        assert_eq!(engine.round_wins, [1, 0]);
+        assert!(engine.entities[1].as_ref().unwrap().health.is_alive());
+
+        // Kill player 2 again to take the match
+        if let Some(p2) = &mut engine.entities[1] {
+            p2.health.current = 0;
+        }
+        engine.check_win_conditions();
+        assert_eq!(engine.game_result, GameResult::Player1Wins);
+    }
+
+    fn double_ko(policy: DoubleKoPolicy, rounds_to_win: u32) -> Engine {
+        let mut config = EngineConfig::default();
+        config.game.double_ko = policy;
+        config.game.rounds_to_win = rounds_to_win;
+
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        for entity in engine.entities.iter_mut().flatten() {
+            entity.health.current = 0;
+        }
+        engine.check_win_conditions();
+        engine
+    }
+
+    #[test]
+    fn test_double_ko_policies() {
+        let engine = double_ko(DoubleKoPolicy::Draw, 2);
+        assert_eq!(engine.game_result, GameResult::InProgress);
+        assert_eq!((engine.round, engine.round_wins), (2, [0, 0]));
+
+        let engine = double_ko(DoubleKoPolicy::BothScore, 2);
+        assert_eq!(engine.game_result, GameResult::InProgress);
+        assert_eq!((engine.round, engine.round_wins), (2, [1, 1]));
+
+        let engine = double_ko(DoubleKoPolicy::BothScore, 1);
+        assert_eq!(engine.game_result, GameResult::Draw);
+
+        let engine = double_ko(DoubleKoPolicy::SuddenDeath, 1);
+        assert_eq!(engine.game_result, GameResult::InProgress);
+        assert_eq!(engine.round_wins, [0, 0]);
+        for entity in engine.entities.iter().flatten() {
+            assert_eq!(entity.health.current, SUDDEN_DEATH_HEALTH);
+        }
+    }
+
+    #[test]
+    fn test_hit_groups_connect_once() {
+        use crate::hitbox::AttackData;
+        use crate::state::{FrameData, State, StateAction, StateType};
+
+        let hitbox = |frame, group| {
+            FrameData::new(
+                frame,
+                StateAction::Hitbox {
+                    x: 0,
+                    y: 0,
+                    width: 30000,
+                    height: 30000,
+                    attack: AttackData::new(10)
+                        .with_knockback(0, 0)
+                        .with_hit_group(group),
+                },
+            )
+        };
+        // Group 0 is active for two frames, group 1 re-arms on frame 3
+        let rapid = State::new(StateId::SpecialMove, StateType::Attack, 10)
+            .add_frame_data(hitbox(1, 0))
+            .add_frame_data(hitbox(2, 0))
+            .add_frame_data(hitbox(3, 1));
+
+        let mut engine = Engine::new();
+        engine.init_match();
+        start_poke(&mut engine, rapid);
+
+        for _ in 0..5 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+
+        let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+        assert_eq!(p2.health.current, 980);
+    }
+
+    pub(crate) fn poke_state() -> crate::state::State {
+        poke_with(crate::hitbox::AttackData::new(50).with_knockback(0, 0))
+    }
+
+    /// Ten frame special move whose hitbox deals `attack` from frame 1
+    pub(crate) fn poke_with(attack: crate::hitbox::AttackData) -> crate::state::State {
+        use crate::state::{FrameData, State, StateAction, StateType};
+
+        State::new(StateId::SpecialMove, StateType::Attack, 10).add_frame_data(FrameData::new(
+            1,
+            StateAction::Hitbox {
+                x: 0,
+                y: 0,
+                width: 30000,
+                height: 30000,
+                attack,
+            },
+        ))
+    }
+
+    /// Put P2 in range of P1 and start `poke` on P1
+    fn start_poke(engine: &mut Engine, poke: crate::state::State) {
+        engine.entities[1].as_mut().unwrap().physics.position.x = 10000;
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.physics.position.x = 0;
+        let id = poke.id;
+        p1.state_machine.register_state(poke);
+        p1.state_machine.transition(id);
+    }
+
+    /// Fresh match with P1 starting a 50 damage poke (`poke_state`) on P2
+    pub(crate) fn engine_with_poke(config: EngineConfig) -> Engine {
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        start_poke(&mut engine, poke_state());
+        engine
+    }
+
+    #[test]
+    fn test_walls_stop_fighters() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let back = InputState {
+            direction: crate::input::Direction::Back,
+            ..InputState::neutral()
+        };
+        for _ in 0..600 {
+            engine.tick(back, back);
+        }
+        let p1 = engine.entities[0].as_ref().unwrap();
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert_eq!(p1.get_pushbox().bounds.x, -STAGE_HALF_WIDTH);
+        let right = p2.get_pushbox().bounds;
+        assert_eq!(right.x + right.width, STAGE_HALF_WIDTH);
+    }
+
+    /// Shove P2 into the right edge and return both final x positions
+    fn corner_shove(stage: StageConfig) -> (i32, i32) {
+        use crate::hitbox::AttackData;
+        use crate::state::{FrameData, State, StateAction, StateType};
+
+        let shove =
+            State::new(StateId::SpecialMove, StateType::Attack, 20).add_frame_data(FrameData::new(
+                1,
+                StateAction::Hitbox {
+                    x: 0,
+                    y: 0,
+                    width: 30000,
+                    height: 30000,
+                    attack: AttackData::new(50).with_knockback(3000, 0),
+                },
+            ));
+        let config = EngineConfig {
+            stage,
+            ..Default::default()
+        };
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        let wall = stage.half_width - 10000;
+        engine.entities[1].as_mut().unwrap().physics.position.x = wall;
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.physics.position.x = wall - 15000;
+        p1.state_machine.register_state(shove);
+        p1.state_machine.transition(StateId::SpecialMove);
+
+        for _ in 0..20 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        let p1 = engine.entities[0].as_ref().unwrap();
+        let p2 = engine.entities[1].as_ref().unwrap();
+        (p1.physics.position.x, p2.physics.position.x)
+    }
+
+    #[test]
+    fn test_cornered_pushback_moves_attacker() {
+        let wall = STAGE_HALF_WIDTH - 10000;
+        let (p1, p2) = corner_shove(StageConfig::default());
+        assert_eq!(p2, wall);
+        assert!(p1 < wall - 25000);
+    }
+
+    #[test]
+    fn test_ledge_does_not_push_attacker() {
+        let stage = StageConfig::preset(StagePreset::Platform);
+        let wall = stage.half_width - 10000;
+        let (p1, p2) = corner_shove(stage);
+        assert_eq!(p2, wall);
+        assert_eq!(p1, wall - 15000);
+    }
+
+    #[test]
+    fn test_stage_presets_place_walls() {
+        for preset in [StagePreset::Narrow, StagePreset::Wide] {
+            let config = EngineConfig {
+                stage: StageConfig::preset(preset),
+                ..Default::default()
+            };
+            let mut engine = Engine::with_config(config);
+            engine.init_match();
+            engine.entities[0].as_mut().unwrap().physics.position.x =
+                20000 - config.stage.half_width;
+            let back = InputState {
+                direction: crate::input::Direction::Back,
+                ..InputState::neutral()
+            };
+            for _ in 0..120 {
+                engine.tick(back, back);
+            }
+            let p1 = engine.entities[0].as_ref().unwrap();
+            assert_eq!(p1.get_pushbox().bounds.x, -config.stage.half_width);
+        }
+        assert!(
+            StageConfig::preset(StagePreset::Narrow).half_width
+                < StageConfig::preset(StagePreset::Wide).half_width
+        );
+    }
+
+    #[test]
+    fn test_corridor_ceiling_caps_jump() {
+        let config = EngineConfig {
+            stage: StageConfig::preset(StagePreset::Corridor),
+            ..Default::default()
+        };
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        let up = InputState {
+            direction: crate::input::Direction::Up,
+            ..InputState::neutral()
+        };
+        let mut peak = 0;
+        for _ in 0..60 {
+            engine.tick(up, InputState::neutral());
+            peak = peak.min(engine.entities[0].as_ref().unwrap().physics.position.y);
+        }
+        assert_eq!(peak, -CORRIDOR_CEILING);
+    }
+
+    #[test]
+    fn test_hit_events_classify_outcome() {
+        let mut engine = engine_with_poke(EngineConfig::default());
+
+        let blocking = InputState {
+            direction: crate::input::Direction::Back,
+            ..InputState::neutral()
+        };
+        engine.tick(InputState::neutral(), blocking);
+
+        let events = engine.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].outcome, HitOutcome::Blocked);
+        assert_eq!(events[0].damage, 0);
+        assert_eq!(events[0].attacker, engine.entities[0].as_ref().unwrap().id);
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert!(engine.events().is_empty());
+    }
+
+    /// Outcome of a poke with `attack` against P2 holding `direction`
+    fn high_low(
+        attack: crate::hitbox::AttackData,
+        direction: crate::input::Direction,
+    ) -> HitOutcome {
+        let mut engine = Engine::new();
+        engine.init_match();
+        start_poke(&mut engine, poke_with(attack));
+
+        let guard = InputState {
+            direction,
+            ..InputState::neutral()
+        };
+        engine.tick(InputState::neutral(), guard);
+        engine.events()[0].outcome
+    }
+
+    #[test]
+    fn test_lows_and_overheads_beat_the_wrong_guard() {
+        use crate::hitbox::AttackData;
+        use crate::input::Direction;
+
+        let low = AttackData::new(50).low();
+        let overhead = AttackData::new(50).overhead();
+        assert_eq!(high_low(low, Direction::Back), HitOutcome::Hit);
+        assert_eq!(high_low(low, Direction::DownBack), HitOutcome::Blocked);
+        assert_eq!(high_low(overhead, Direction::DownBack), HitOutcome::Hit);
+        assert_eq!(high_low(overhead, Direction::Back), HitOutcome::Blocked);
+        // Mids are blocked either way
+        assert_eq!(
+            high_low(AttackData::new(50), Direction::DownBack),
+            HitOutcome::Blocked
+        );
+    }
+
+    /// Finishes raised when P1 KOs a low-health P2 standing at `p2_x`
+    fn ko_finishes(
+        attack: crate::hitbox::AttackData,
+        p2_x: i32,
+        p1_damage: i32,
+    ) -> Vec<FinishKind> {
+        let mut engine = Engine::new();
+        engine.init_match();
+        start_poke(&mut engine, poke_with(attack));
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.physics.position.x = p2_x;
+        p2.health.current = 10;
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.physics.position.x = p2_x - 10000;
+        p1.health.take_damage(p1_damage);
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_eq!(engine.round_wins, [1, 0]);
+        engine.finishes().iter().map(|f| f.kind).collect()
+    }
+
+    #[test]
+    fn test_ko_finish_detection() {
+        use crate::hitbox::AttackData;
+
+        let midscreen = ko_finishes(AttackData::new(50).super_move(), 10000, 0);
+        assert_eq!(midscreen, vec![FinishKind::Super, FinishKind::Perfect]);
+
+        // Stage bounds put P2 back against the wall before the hit lands
+        assert_eq!(
+            ko_finishes(AttackData::new(50), STAGE_HALF_WIDTH, 100),
+            vec![FinishKind::WallBreak]
+        );
+        assert!(ko_finishes(AttackData::new(50), 10000, 100).is_empty());
+    }
+
+    #[test]
+    fn test_crouch_while_down_is_held() {
+        use crate::input::Direction;
+
+        let mut engine = Engine::new();
+        engine.init_match();
+        let down = InputState {
+            direction: Direction::DownForward,
+            ..InputState::neutral()
+        };
+        let x = engine.entities[0].as_ref().unwrap().physics.position.x;
+        for _ in 0..10 {
+            engine.tick(down, InputState::neutral());
+        }
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::Crouch);
+        assert_eq!(p1.physics.position.x, x);
+
+        // Attacks come out of a crouch; releasing down stands up
+        let mut light = down;
+        light.light = true;
+        engine.tick(light, InputState::neutral());
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::LightAttack);
+        for _ in 0..30 {
+            engine.tick(down, InputState::neutral());
+        }
+        engine.tick(InputState::neutral(), InputState::neutral());
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::Idle);
+    }
+
+    #[test]
+    fn test_air_attacks_and_landing_recovery() {
+        use crate::input::Direction;
+
+        let mut engine = Engine::new();
+        engine.init_match();
+        let up = InputState {
+            direction: Direction::Up,
+            ..InputState::neutral()
+        };
+        engine.tick(up, InputState::neutral());
+        for _ in 0..3 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert!(!p1.physics.on_ground);
+
+        // Throw and sweep are ground-only; light becomes a jump attack
+        let mut buttons = InputState::neutral();
+        buttons.light = true;
+        buttons.heavy = true;
+        buttons.direction = Direction::DownForward;
+        engine.tick(buttons, InputState::neutral());
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::JumpHeavy);
+
+        let mut landed = false;
+        for _ in 0..60 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            let p1 = engine.entities[0].as_ref().unwrap();
+            if p1.physics.on_ground {
+                assert_eq!(p1.state_machine.current_state(), StateId::Landing);
+                landed = true;
+                break;
+            }
+        }
+        assert!(landed);
+        for _ in 0..LANDING_RECOVERY_FRAMES {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::Idle);
+    }
+
+    #[test]
+    fn test_mutators_scale_damage_gravity_and_meter() {
+        use crate::setup::Mutators;
+
+        // (damage taken by p2, p1 meter, p1 gravity)
+        let poke = |mutators: Mutators| {
+            let mut engine = Engine::new();
+            engine.init_match_with(MatchSetup {
+                mutators,
+                ..Default::default()
+            });
+            start_poke(&mut engine, poke_state());
+            engine.tick(InputState::neutral(), InputState::neutral());
+            let p1 = engine.entities[0].as_ref().unwrap();
+            let p2 = engine.entities[1].as_ref().unwrap();
+            (
+                p2.health.maximum - p2.health.current,
+                p1.meter,
+                p1.physics.gravity,
+            )
+        };
+        let (damage, meter, gravity) = poke(Mutators::default());
+        let party = Mutators {
+            gravity_percent: 50,
+            damage_percent: 200,
+            meter_gain_percent: 300,
+            ..Default::default()
+        };
+        assert_eq!(poke(party), (damage * 2, meter * 3, gravity / 2));
+
+        // Throws and cinematic damage scale too
+        let throw = |mutators: Mutators| {
+            let mut engine = Engine::new();
+            engine.init_match_with(MatchSetup {
+                distance: Some(THROW_RANGE),
+                mutators,
+                ..Default::default()
+            });
+            engine.tick(throw_input(), InputState::neutral());
+            for _ in 0..THROW_TECH_WINDOW {
+                engine.tick(InputState::neutral(), InputState::neutral());
+            }
+            let p2 = engine.entities[1].as_ref().unwrap();
+            p2.health.maximum - p2.health.current
+        };
+        assert_eq!(throw(Mutators::default()), THROW_DAMAGE);
+        assert_eq!(throw(party), THROW_DAMAGE * 2);
+
+        let mut engine = engine_with_cinematic();
+        engine.mutators = party;
+        for _ in 0..40 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert_eq!(p2.health.maximum - p2.health.current, 600);
+    }
+
+    fn poke_into(defender_state: StateId) -> (HitEvent, u32) {
+        let mut engine = engine_with_poke(EngineConfig::default());
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.state_machine.transition(defender_state);
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+        let p2 = engine.entities[1].as_ref().unwrap();
+        (engine.events()[0], p2.hitstun_remaining)
+    }
+
+    #[test]
+    fn test_meter_builds_on_contact_and_gates_super() {
+        use crate::input::Direction;
+        use crate::state::{FrameData, State, StateType};
+
+        let mut engine = engine_with_poke(EngineConfig::default());
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_eq!(
+            engine.entities[0].as_ref().unwrap().meter,
+            METER_GAIN_ON_HIT
+        );
+
+        let super_move = State::new(StateId::SuperMove, StateType::Attack, 60)
+            .requires_meter(SUPER_METER_COST)
+            .add_frame_data(FrameData::new(
+                0,
+                StateAction::ConsumeMeter {
+                    amount: SUPER_METER_COST,
+                },
+            ));
+        let qcb = |meter: i32| {
+            let mut engine = Engine::new();
+            engine.init_match();
+            let p1 = engine.entities[0].as_mut().unwrap();
+            p1.state_machine.register_state(super_move.clone());
+            p1.meter = meter;
+            for direction in [Direction::Down, Direction::DownBack] {
+                let input = InputState {
+                    direction,
+                    ..InputState::neutral()
+                };
+                engine.tick(input, InputState::neutral());
+            }
+            let input = InputState {
+                direction: Direction::Back,
+                special: true,
+                ..InputState::neutral()
+            };
+            engine.tick(input, InputState::neutral());
+            let p1 = engine.entities[0].as_ref().unwrap();
+            (
+                p1.state_machine.current_state() == StateId::SuperMove,
+                p1.meter,
+            )
+        };
+        assert_eq!(qcb(SUPER_METER_COST - 1), (false, SUPER_METER_COST - 1));
+        assert_eq!(qcb(METER_MAX), (true, METER_MAX - SUPER_METER_COST));
+    }
+
+    #[test]
+    fn test_invincibility_by_state_and_category() {
+        use crate::hitbox::Invulnerability;
+        use crate::state::{FrameData, State, StateType};
+
+        let reversal = |invulnerability| {
+            State::new(StateId::Custom(30), StateType::Attack, 20).add_frame_data(FrameData::new(
+                0,
+                StateAction::SetInvincible {
+                    frames: 10,
+                    invulnerability,
+                },
+            ))
+        };
+        let attacked_in = |state: State, attack: StateId| {
+            let mut engine = engine_with_poke(EngineConfig::default());
+            let p2 = engine.entities[1].as_mut().unwrap();
+            let id = state.id;
+            p2.state_machine.register_state(state);
+            p2.state_machine.transition(id);
+            let p1 = engine.entities[0].as_mut().unwrap();
+            p1.state_machine.transition(attack);
+            let mut connected = false;
+            for _ in 0..2 {
+                engine.tick(InputState::neutral(), InputState::neutral());
+                // Throws link before their damage lands
+                connected |= !engine.events().is_empty() || engine.link.is_some();
+            }
+            connected
+        };
+
+        let invincible = State::new(StateId::Custom(30), StateType::Invincible, 20);
+        assert!(!attacked_in(invincible, StateId::SpecialMove));
+        let strike_invuln = reversal(Invulnerability::STRIKE);
+        assert!(!attacked_in(strike_invuln.clone(), StateId::SpecialMove));
+        // Strike invulnerability doesn't stop throws
+        assert!(attacked_in(strike_invuln, StateId::Throw));
+        assert!(attacked_in(
+            reversal(Invulnerability::THROW),
+            StateId::SpecialMove
+        ));
+    }
+
+    #[test]
+    fn test_normals_chain_only_on_contact() {
+        let chain = |p2_x: i32| {
+            let mut engine = Engine::new();
+            engine.init_match();
+            engine.entities[0].as_mut().unwrap().physics.position.x = 0;
+            engine.entities[1].as_mut().unwrap().physics.position.x = p2_x;
+            let light = InputState {
+                light: true,
+                ..InputState::neutral()
+            };
+            let medium = InputState {
+                medium: true,
+                ..InputState::neutral()
+            };
+            engine.tick(light, InputState::neutral());
+            for _ in 0..7 {
+                engine.tick(InputState::neutral(), InputState::neutral());
+            }
+            engine.tick(medium, InputState::neutral());
+            let p1 = engine.entities[0].as_ref().unwrap();
+            p1.state_machine.current_state()
+        };
+
+        assert_eq!(chain(20000), StateId::MediumAttack);
+        // A whiffed light is stuck in its recovery
+        assert_eq!(chain(100000), StateId::LightAttack);
+    }
+
+    #[test]
+    fn test_counter_hit_during_startup() {
+        let (clean, clean_stun) = poke_into(StateId::Idle);
+        assert!(!clean.counter);
+
+        // Heavy attack startup lasts until its hitbox comes out
+        let (counter, counter_stun) = poke_into(StateId::HeavyAttack);
+        let bonus = GameConfig::default().counter_hit;
+        assert!(counter.counter);
+        assert_eq!(counter.damage, clean.damage * bonus.damage_percent / 100);
+        assert_eq!(counter_stun, clean_stun + bonus.hitstun_bonus);
+    }
+
+    #[test]
+    fn test_tick_queues_typed_events() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert!(matches!(
+            engine.game_events(),
+            [GameEvent::RoundStart { round: 1, .. }]
+        ));
+
+        start_poke(&mut engine, poke_state());
+        engine.entities[1].as_mut().unwrap().health.set(1);
+        engine.tick(InputState::neutral(), InputState::neutral());
+
+        let events: Vec<_> = engine.drain_game_events().collect();
+        assert!(engine.game_events().is_empty());
+        let hit = events
+            .iter()
+            .position(|e| matches!(e, GameEvent::HitLanded(hit) if hit.defender == EntityId(1)));
+        let ko = events
+            .iter()
+            .position(|e| matches!(e, GameEvent::Ko { entity, .. } if *entity == EntityId(1)));
+        assert!(hit.unwrap() < ko.unwrap());
+        // The KO started round 2, putting P1 back to idle from its attack
+        assert!(events.contains(&GameEvent::StateChanged {
+            frame: Frame(1),
+            entity: EntityId(0),
+            from: StateId::SpecialMove,
+            to: StateId::Idle,
+        }));
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert!(matches!(
+            engine.game_events(),
+            [GameEvent::RoundStart { round: 2, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_press_during_blockstun_is_buffered() {
+        let attack_after_blockstun = |buffer_frames| {
+            let mut config = EngineConfig::default();
+            config.input.action_buffer_frames = buffer_frames;
+            let mut engine = Engine::with_config(config);
+            engine.init_match();
+            let p2 = engine.entities[1].as_mut().unwrap();
+            p2.blockstun_remaining = 3;
+            p2.state_machine.transition(StateId::Blockstun);
+
+            let light = InputState {
+                light: true,
+                ..InputState::neutral()
+            };
+            engine.tick(InputState::neutral(), light);
+            engine.tick(InputState::neutral(), InputState::neutral());
+            engine.tick(InputState::neutral(), InputState::neutral());
+            engine.entities[1]
+                .as_ref()
+                .unwrap()
+                .state_machine
+                .current_state()
+        };
+
+        assert_eq!(
+            attack_after_blockstun(ACTION_BUFFER_FRAMES),
+            StateId::LightAttack
+        );
+        assert_eq!(attack_after_blockstun(0), StateId::Idle);
+    }
+
+    #[test]
+    fn test_guard_crush_protection_reduces_damage() {
+        let mut config = EngineConfig::default();
+        config.game.guard = crate::config::GuardConfig::standard();
+        let poke = |protected: bool| {
+            let mut engine = engine_with_poke(config);
+            assert_eq!(engine.get_state().p2_guard, config.game.guard.max);
+            if protected {
+                engine.entities[1]
+                    .as_mut()
+                    .unwrap()
+                    .crush_protection_remaining = 10;
+            }
+            engine.tick(InputState::neutral(), InputState::neutral());
+            engine.events()[0].damage
+        };
+
+        let protection = config.game.guard.protection_percent;
+        assert_eq!(poke(true), poke(false) * protection / 100);
+    }
+
+    #[test]
+    fn test_just_defend_parries_early_blocks() {
+        let mut config = EngineConfig::default();
+        config.game.guard = crate::config::GuardConfig::standard();
+        config.game.just_defend = crate::config::JustDefendConfig::standard();
+        let back = InputState {
+            direction: crate::input::Direction::Back,
+            ..InputState::neutral()
+        };
+        // Block a poke after holding back for `held` frames
+        let block = |held: u32| {
+            let mut engine = Engine::with_config(config);
+            engine.init_match();
+            for _ in 0..held {
+                engine.tick(InputState::neutral(), back);
+            }
+            start_poke(&mut engine, poke_state());
+            engine.tick(InputState::neutral(), back);
+            let p2 = engine.entities[1].as_ref().unwrap();
+            let parried = engine
+                .game_events()
+                .iter()
+                .any(|e| matches!(e, GameEvent::Parry(_)));
+            (
+                engine.events()[0].outcome,
+                p2.blockstun_remaining,
+                p2.guard,
+                parried,
+            )
+        };
+
+        let window = config.game.just_defend.window_frames;
+        let (outcome, parry_stun, guard, parried) = block(window - 1);
+        assert_eq!(outcome, HitOutcome::Parried);
+        assert_eq!(guard, config.game.guard.max);
+        assert!(parried);
+
+        let (outcome, block_stun, guard, parried) = block(window);
+        assert_eq!(outcome, HitOutcome::Blocked);
+        assert!(guard < config.game.guard.max);
+        assert!(!parried);
+        assert!(parry_stun < block_stun);
+    }
+
+    /// Engine with P1 starting a 50 damage poke on P2, whose dizzy meter
+    /// holds `dizzy`
+    fn dizzy_poke(dizzy: i32) -> Engine {
+        let mut config = EngineConfig::default();
+        config.game.dizzy = crate::config::DizzyConfig::standard();
+        let mut engine = engine_with_poke(config);
+        engine.entities[1].as_mut().unwrap().dizzy = dizzy;
+        engine
+    }
+
+    #[test]
+    fn test_dizzy_meter_fills_and_drains() {
+        let mut engine = dizzy_poke(0);
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_eq!(engine.get_state().p2_dizzy, 50);
+
+        // Draining waits for the hit reaction to end
+        for _ in 0..60 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert!(p2.dizzy < 50);
+        assert_eq!(p2.dizzy_remaining, 0);
+    }
+
+    #[test]
+    fn test_filled_dizzy_meter_leaves_fighter_helpless() {
+        let threshold = crate::config::DizzyConfig::standard().threshold;
+        let mut engine = dizzy_poke(threshold - 1);
+        let back_light = InputState {
+            direction: crate::input::Direction::Back,
+            light: true,
+            ..InputState::neutral()
+        };
+        engine.tick(InputState::neutral(), InputState::neutral());
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert_eq!(p2.state_machine.current_state(), StateId::Hitstun);
+        assert_eq!(p2.dizzy, 0);
+
+        // Dizzied after the hitstun, unable to attack
+        for _ in 0..60 {
+            engine.tick(InputState::neutral(), back_light);
+        }
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert_eq!(p2.state_machine.current_state(), StateId::Dizzy);
+
+        // Nor block: the next hit lands and snaps them out of it
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.state_machine.transition(StateId::SpecialMove);
+        engine.tick(InputState::neutral(), back_light);
+        assert_eq!(engine.events()[0].outcome, HitOutcome::Hit);
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert_eq!(p2.state_machine.current_state(), StateId::Hitstun);
+        assert_eq!(p2.dizzy_remaining, 0);
+    }
+
+    #[test]
+    fn test_armor_absorbs_hits_without_reaction() {
+        use crate::state::{FrameData, State, StateAction, StateType};
+
+        let armored =
+            State::new(StateId::Custom(7), StateType::Attack, 60).add_frame_data(FrameData::new(
+                0,
+                StateAction::SetArmor {
+                    hits: 1,
+                    frames: 60,
+                    damage_percent: 50,
+                },
+            ));
+        let poke = |engine: &mut Engine| {
+            let p1 = engine.entities[0].as_mut().unwrap();
+            p1.state_machine.transition(StateId::SpecialMove);
+            engine.tick(InputState::neutral(), InputState::neutral());
+            let p2 = engine.entities[1].as_ref().unwrap();
+            let absorbed = engine
+                .game_events()
+                .iter()
+                .any(|e| matches!(e, GameEvent::ArmorAbsorb(_)));
+            let event = engine.events()[0];
+            (
+                event.outcome,
+                event.damage,
+                p2.state_machine.current_state(),
+                absorbed,
+            )
+        };
+
+        let mut engine = engine_with_poke(EngineConfig::default());
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.state_machine.register_state(armored);
+        p2.state_machine.transition(StateId::Custom(7));
+
+        // The first hit is absorbed at half damage, the second one lands
+        let (outcome, armored_damage, state, absorbed) = poke(&mut engine);
+        assert_eq!(outcome, HitOutcome::Armored);
+        assert_eq!(state, StateId::Custom(7));
+        assert!(absorbed);
+        for _ in 0..20 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        let (outcome, damage, state, absorbed) = poke(&mut engine);
+        assert_eq!(outcome, HitOutcome::Hit);
+        assert_eq!(state, StateId::Hitstun);
+        assert!(!absorbed);
+        assert_eq!(armored_damage, damage / 2);
+    }
+
+    #[test]
+    fn test_match_ends_in_win_and_lose_poses() {
+        let mut config = EngineConfig::default();
+        config.game.rounds_to_win = 1;
+        let mut engine = engine_with_poke(config);
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.physics.position.y = -5000;
+        p2.physics.on_ground = false;
+        p2.health.set(1);
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.state_machine.register_state(states::win_pose(2));
+
+        // Holding medium through the KO picks the second win pose
+        let medium = InputState {
+            medium: true,
+            ..InputState::neutral()
+        };
+        engine.tick(medium, InputState::neutral());
+        assert_eq!(engine.game_result, GameResult::Player1Wins);
+        let state = |engine: &Engine, i: usize| {
+            let fighter = engine.entities[i].as_ref().unwrap();
+            (
+                fighter.state_machine.current_state(),
+                fighter.physics.on_ground,
+            )
+        };
+        assert_eq!(state(&engine, 0).0, StateId::WinPose(2));
+        assert_eq!(state(&engine, 1), (StateId::LosePose, false));
+
+        // Inputs are locked out while the loser falls into place
+        let light = InputState {
+            light: true,
+            ..InputState::neutral()
+        };
+        while !engine.is_over() {
+            engine.tick(light, light);
+        }
+        assert_eq!(state(&engine, 0).0, StateId::WinPose(2));
+        assert_eq!(state(&engine, 1), (StateId::LosePose, true));
+        let frame = engine.frame;
+        engine.tick(light, light);
+        assert_eq!(engine.frame, frame);
+    }
+
+    fn crossup(policy: BehindBlockPolicy, p2_input: InputState) -> (HitOutcome, StateId) {
+        let mut config = EngineConfig::default();
+        config.game.behind_block = policy;
+        let mut engine = engine_with_poke(config);
+        // Still facing away from the attacker, as after a cross-up
+        engine.entities[1].as_mut().unwrap().facing = Facing::Right;
+
+        engine.tick(InputState::neutral(), p2_input);
+        let p2 = engine.entities[1].as_ref().unwrap();
+        (engine.events()[0].outcome, p2.state_machine.current_state())
+    }
+
+    #[test]
+    fn test_hits_from_behind() {
+        let holding = |direction| InputState {
+            direction,
+            ..InputState::neutral()
+        };
+        let back = holding(crate::input::Direction::Back);
+        let forward = holding(crate::input::Direction::Forward);
+
+        // Holding back means holding towards the attacker: a back hit
+        assert_eq!(
+            crossup(BehindBlockPolicy::Reversed, back),
+            (HitOutcome::Hit, StateId::BackHitstun)
+        );
+        assert_eq!(
+            crossup(BehindBlockPolicy::Reversed, forward).0,
+            HitOutcome::Blocked
+        );
+        assert_eq!(
+            crossup(BehindBlockPolicy::Impossible, forward).0,
+            HitOutcome::Hit
+        );
+    }
+
+    #[test]
+    fn test_back_turned_state_cannot_block() {
+        use crate::state::{State, StateType};
+
+        let mut engine = engine_with_poke(EngineConfig::default());
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.state_machine.register_state(
+            State::new(StateId::Custom(5), StateType::Normal, 60).with_back_turned(),
+        );
+        p2.state_machine.transition(StateId::Custom(5));
+
+        let back = InputState {
+            direction: crate::input::Direction::Back,
+            ..InputState::neutral()
+        };
+        engine.tick(InputState::neutral(), back);
+        assert_eq!(engine.events()[0].outcome, HitOutcome::Hit);
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert_eq!(p2.state_machine.current_state(), StateId::BackHitstun);
+    }
+
+    #[test]
+    fn test_hit_feel_tiers_fill_defaults() {
+        let run = |poke: crate::state::State| {
+            let mut config = EngineConfig::default();
+            config.hit_feel = crate::config::HitFeelConfig::standard();
+            let mut engine = Engine::with_config(config);
+            engine.init_match();
+            start_poke(&mut engine, poke);
+            engine.tick(InputState::neutral(), InputState::neutral());
+            (engine.hitstop_remaining(), engine.events()[0].cue)
+        };
+
+        // A 50 damage poke falls in the lightest tier
+        assert_eq!(run(poke_state()), (8, Some(1)));
+
+        // Explicit values win over the tier
+        let mut tuned = poke_state();
+        if let StateAction::Hitbox { attack, .. } = &mut tuned.frame_data[0].action {
+            *attack = attack.with_hitstop(3).with_hit_cue(9);
+        }
+        assert_eq!(run(tuned), (3, Some(9)));
+    }
+
+    #[test]
+    fn test_hits_use_damage_pipeline() {
+        let mut config = EngineConfig::default();
+        config.damage = crate::config::DamageConfig::standard();
+        let mut engine = engine_with_poke(config);
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.hitstun_remaining = 10;
+        p2.combo_hits = 4;
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+
+        // Fifth hit of a combo: 2 hits past the scaling start -> 80%
+        assert_eq!(engine.events()[0].damage, 40);
+    }
+
+    fn fireball() -> ProjectileDef {
+        use crate::hitbox::AttackData;
+        use crate::types::Rect;
+
+        ProjectileDef::new(
+            Rect::new(0, 5000, 8000, 8000),
+            AttackData::new(40),
+            Vec2::new(2000, 0),
+            100,
+        )
+    }
+
+    #[test]
+    fn test_projectile_hits_opponent_once() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let id = engine
+            .spawn_projectile(EntityId(0), fireball(), Vec2::new(10000, 0))
+            .unwrap();
+
+        let mut hits = Vec::new();
+        for _ in 0..60 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            hits.extend_from_slice(engine.events());
+        }
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].attacker, id);
+        assert_eq!(hits[0].defender, EntityId(1));
+        assert!(engine.get_projectile(id).is_none());
+    }
+
+    #[test]
+    fn test_neutral_projectile_hits_its_thrower() {
+        use crate::hitbox::{AttackData, Team};
+        use crate::types::Rect;
+
+        let hazard = ProjectileDef::new(
+            Rect::new(-5000, 0, 10000, 10000),
+            AttackData::new(40).with_team(Team::Neutral),
+            Vec2::ZERO,
+            10,
+        );
+        let mut engine = Engine::new();
+        engine.init_match();
+        let id = engine.spawn_projectile(EntityId(0), hazard, Vec2::new(0, 0));
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_eq!(engine.events().len(), 1);
+        assert_eq!(engine.events()[0].attacker, id.unwrap());
+        assert_eq!(engine.events()[0].defender, EntityId(0));
+    }
+
+    #[test]
+    fn test_projectiles_clash() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let a = engine.spawn_projectile(EntityId(0), fireball(), Vec2::new(10000, 0));
+        let b = engine.spawn_projectile(EntityId(1), fireball(), Vec2::new(10000, 0));
+
+        let mut clashes = Vec::new();
+        for _ in 0..60 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            assert!(engine.events().is_empty());
+            clashes.extend_from_slice(engine.clashes());
+        }
+        assert_eq!(clashes.len(), 1);
+        assert_eq!(clashes[0].projectiles, [a.unwrap(), b.unwrap()]);
+        assert_eq!(clashes[0].position, Vec2::new(0, 9000));
+        assert!(engine.projectiles.iter().all(Option::is_none));
+    }
+
+    fn guard_p2(engine: &mut Engine, kind: crate::state::GuardKind) {
+        use crate::state::{ProjectileGuard, State, StateType};
+
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.state_machine.register_state(
+            State::new(StateId::Custom(6), StateType::Normal, 200)
+                .with_projectile_guard(ProjectileGuard::new(0, 200, kind)),
+        );
+        p2.state_machine.transition(StateId::Custom(6));
+    }
+
+    #[test]
+    fn test_reflected_projectile_hits_its_thrower() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        guard_p2(&mut engine, crate::state::GuardKind::Reflect);
+        let id = engine
+            .spawn_projectile(EntityId(0), fireball(), Vec2::new(10000, 0))
+            .unwrap();
+
+        let mut hits = Vec::new();
+        for _ in 0..120 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            hits.extend_from_slice(engine.events());
+        }
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].outcome, HitOutcome::Reflected);
+        assert_eq!(hits[0].defender, EntityId(1));
+        assert_eq!(hits[0].damage, 0);
+        assert_eq!(hits[1].attacker, id);
+        assert_eq!(hits[1].defender, EntityId(0));
+        assert_eq!(hits[1].outcome, HitOutcome::Hit);
+        let p2 = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(p2.health.current, p2.health.maximum);
+    }
+
+    #[test]
+    fn test_absorbed_projectile_disappears() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        guard_p2(&mut engine, crate::state::GuardKind::Absorb);
+        let id = engine
+            .spawn_projectile(EntityId(0), fireball(), Vec2::new(10000, 0))
+            .unwrap();
+
+        let mut hits = Vec::new();
+        for _ in 0..60 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            hits.extend_from_slice(engine.events());
+        }
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].outcome, HitOutcome::Absorbed);
+        assert!(engine.get_projectile(id).is_none());
+        let p2 = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(p2.health.current, p2.health.maximum);
+    }
+
+    #[test]
+    fn test_unreflectable_projectile_goes_through_guard() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        guard_p2(&mut engine, crate::state::GuardKind::Reflect);
+        engine.spawn_projectile(EntityId(0), fireball().unreflectable(), Vec2::new(10000, 0));
+
+        let mut hits = Vec::new();
+        for _ in 0..60 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            hits.extend_from_slice(engine.events());
+        }
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].outcome, HitOutcome::Hit);
+        assert_eq!(hits[0].defender, EntityId(1));
+    }
+
+    #[test]
+    fn test_explosive_clash_leaves_residual_hitbox() {
+        use crate::hitbox::AttackData;
+        use crate::projectile::Residual;
+        use crate::types::Rect;
+
+        let explosive = fireball().with_residual(Residual {
+            hitbox: Rect::new(-5000, 0, 70000, 20000),
+            attack: AttackData::new(25),
+            frames: 5,
+        });
+        let mut engine = Engine::new();
+        engine.init_match();
+        let a = engine
+            .spawn_projectile(EntityId(0), explosive, Vec2::new(10000, 0))
+            .unwrap();
+        engine.spawn_projectile(EntityId(1), fireball(), Vec2::new(10000, 0));
+
+        while engine.clashes().is_empty() {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        // The explosion comes out on the clash frame, catching the opponent
+        // but never its owner
+        assert_eq!(engine.events().len(), 1);
+        assert_eq!(engine.events()[0].attacker, a);
+        assert_eq!(engine.events()[0].damage, 25);
+        assert_eq!(engine.events()[0].defender, EntityId(1));
+        assert!(engine.get_projectile(a).is_none());
+    }
+
+    #[test]
+    fn test_stale_moves_deal_less_damage() {
+        let mut config = EngineConfig::default();
+        config.damage = crate::config::DamageConfig::stale_moves();
+        let mut engine = engine_with_poke(config);
+        let p1 = engine.entities[0].as_mut().unwrap();
+        for _ in 0..2 {
+            p1.stale_moves.push(StateId::SpecialMove, MAX_STALE_QUEUE);
+        }
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+
+        // Two stale copies at 5% each -> 90%
+        assert_eq!(engine.events()[0].damage, 45);
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.stale_moves.count(StateId::SpecialMove), 3);
+    }
+
+    #[test]
+    fn test_hit_shake_does_not_move_fighters() {
+        use crate::hitbox::AttackData;
+
+        let mut engine = Engine::new();
+        engine.init_match();
+        start_poke(
+            &mut engine,
+            poke_with(AttackData::new(50).with_knockback(0, 0).with_hitstop(6)),
+        );
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert!(engine.hitstop_remaining() > 0);
+        let p2 = engine.entities[1].as_ref().unwrap();
+        let position = p2.physics.position;
+        assert!(p2.presentation.shake_remaining > 0);
+        assert!(engine.entities[0].as_ref().unwrap().presentation.recoil < 0);
+
+        // The defender shakes through hitstop while the simulation stands still
+        let mut offsets = Vec::new();
+        while engine.hitstop_remaining() > 0 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            let p2 = engine.entities[1].as_ref().unwrap();
+            assert_eq!(p2.physics.position, position);
+            offsets.push(p2.display_position().x - position.x);
+        }
+        assert!(offsets.iter().any(|&x| x > 0) && offsets.iter().any(|&x| x < 0));
+        assert!(!engine.entities[1]
+            .as_ref()
+            .unwrap()
+            .presentation
+            .is_active());
+    }
+
+    #[test]
+    fn test_animation_cues_become_events() {
+        use crate::events::Cue;
+        use crate::state::Animation;
+
+        let mut engine = Engine::new();
+        engine.init_match();
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.state_machine
+            .register_state(poke_state().with_animation(Animation::new(1).with_cue(2, 9)));
+        p1.state_machine.transition(StateId::SpecialMove);
+        let id = p1.id;
+
+        let mut cues = Vec::new();
+        for _ in 0..5 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            cues.extend_from_slice(engine.cues());
+        }
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].entity, id);
+        assert_eq!(cues[0].cue, Cue::Animation(9));
+        assert_eq!(cues[0].frame.0, 2);
+    }
+
+    #[test]
+    fn test_cues_sharing_a_frame_all_become_events() {
+        use crate::events::Cue;
+        use crate::state::Animation;
+
+        let mut engine = Engine::new();
+        engine.init_match();
+        let p1 = engine.entities[0].as_mut().unwrap();
+        let animation = Animation::new(1).with_cue(2, 9).with_cue(2, 10);
+        p1.state_machine
+            .register_state(poke_state().with_animation(animation));
+        p1.state_machine.transition(StateId::SpecialMove);
+
+        let mut cues = Vec::new();
+        for _ in 0..5 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+            cues.extend(engine.cues().iter().map(|c| (c.frame.0, c.cue)));
+        }
+        assert_eq!(cues, vec![(2, Cue::Animation(9)), (2, Cue::Animation(10))]);
+    }
+
+    #[test]
+    fn test_seeded_match_start() {
+        let setup = MatchSetup {
+            swap_sides: Some(true),
+            first_attacker: Some(PlayerId::PLAYER_2),
+            handicap_frames: 10,
+            ..MatchSetup::seeded(42)
+        };
+        let mut engine = Engine::new();
+        engine.init_match_with(setup);
+        let mut other = Engine::new();
+        other.init_match_with(setup);
+        crate::snapshot::assert_snapshots_match(&engine.save_snapshot(), &other.save_snapshot());
+
+        let p1 = engine.entities[0].as_ref().unwrap();
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert!(p1.physics.position.x > p2.physics.position.x);
+        assert_eq!(p1.facing, Facing::Left);
+
+        // Player 1 can't attack until the handicap runs out
+        let mut light = InputState::neutral();
+        light.light = true;
+        engine.tick(light, InputState::neutral());
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::Idle);
+        for _ in 0..10 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        engine.tick(light, InputState::neutral());
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::LightAttack);
+    }
+
+    #[test]
+    fn test_pause_leaves_simulation_untouched() {
+        let forward = InputState {
+            direction: crate::input::Direction::Forward,
+            ..InputState::neutral()
+        };
+        let mut light = InputState::neutral();
+        light.light = true;
+
+        let mut paused = Engine::new();
+        paused.init_match();
+        paused.input_manager.set_latency_offset(0, 2);
+        let mut reference = Engine::new();
+        reference.init_match();
+        reference.input_manager.set_latency_offset(0, 2);
+        for _ in 0..10 {
+            paused.tick(forward, InputState::neutral());
+            reference.tick(forward, InputState::neutral());
+        }
+
+        paused.pause();
+        for _ in 0..30 {
+            paused.tick(light, forward);
+        }
+        assert!(paused.is_paused());
+        crate::snapshot::assert_snapshots_match(
+            &reference.save_snapshot(),
+            &paused.save_snapshot(),
+        );
+
+        // Light is still held on resume; it registers as a press instead of
+        // having been eaten by the pause menu
+        paused.resume();
+        for _ in 0..3 {
+            paused.tick(light, InputState::neutral());
+            reference.tick(light, InputState::neutral());
+        }
+        crate::snapshot::assert_snapshots_match(
+            &reference.save_snapshot(),
+            &paused.save_snapshot(),
+        );
+        let p1 = paused.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::LightAttack);
+    }
+
+    #[test]
+    fn test_simultaneous_hits_trade() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        for (i, x) in [(0, 0), (1, 10000)] {
+            let fighter = engine.entities[i].as_mut().unwrap();
+            fighter.physics.position.x = x;
+            fighter.state_machine.register_state(poke_state());
+            fighter.state_machine.transition(StateId::SpecialMove);
+        }
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+
+        let events = engine.events();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.outcome == HitOutcome::Trade));
+        assert!(events.iter().all(|e| e.damage == 50));
+        // Player 1's hit is always resolved and emitted first
+        assert_eq!(events[0].attacker, EntityId(0));
+        assert_eq!(events[1].attacker, EntityId(1));
+    }
+
+    /// Outcomes of P1 jumping in at `height` while P2 anti-airs, by attacker
+    fn jump_in_vs_anti_air(
+        priority: crate::config::AntiAirPriority,
+        height: i32,
+    ) -> Vec<(EntityId, HitOutcome)> {
+        let mut config = EngineConfig::default();
+        config.game.anti_air.priority = priority;
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        for (i, x) in [(0, 0), (1, 10000)] {
+            let fighter = engine.entities[i].as_mut().unwrap();
+            fighter.physics.position.x = x;
+            fighter.state_machine.register_state(poke_state());
+            fighter.state_machine.transition(StateId::SpecialMove);
+        }
+        let jumper = engine.entities[0].as_mut().unwrap();
+        jumper.physics.position.y = -height;
+        jumper.physics.on_ground = false;
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+        engine
+            .events()
+            .iter()
+            .map(|e| (e.attacker, e.outcome))
+            .collect()
     }
 
     #[test]
-    fn test_win_condition() {
+    fn test_anti_air_priority_near_the_ground() {
+        use crate::config::AntiAirPriority;
+
+        let low = ANTI_AIR_HEIGHT / 2;
+        let trade = vec![
+            (EntityId(0), HitOutcome::Trade),
+            (EntityId(1), HitOutcome::Trade),
+        ];
+        assert_eq!(jump_in_vs_anti_air(AntiAirPriority::Trade, low), trade);
+        assert_eq!(
+            jump_in_vs_anti_air(AntiAirPriority::Defender, low),
+            vec![(EntityId(1), HitOutcome::Hit)]
+        );
+        assert_eq!(
+            jump_in_vs_anti_air(AntiAirPriority::Attacker, low),
+            vec![(EntityId(0), HitOutcome::Hit)]
+        );
+        // Well above the ground, it's an ordinary trade whatever the bias
+        assert_eq!(
+            jump_in_vs_anti_air(AntiAirPriority::Defender, ANTI_AIR_HEIGHT * 2),
+            trade
+        );
+    }
+
+    fn throw_state() -> crate::state::State {
+        use crate::state::{FrameData, State, StateType};
+
+        State::new(StateId::Custom(10), StateType::Attack, 20)
+            .add_frame_data(FrameData::new(0, StateAction::MoveVictim { x: 8000, y: 0 }))
+            .add_frame_data(FrameData::new(
+                4,
+                StateAction::MoveVictim { x: 4000, y: -6000 },
+            ))
+            .add_frame_data(FrameData::new(8, StateAction::ReleaseVictim))
+    }
+
+    fn engine_with_throw() -> Engine {
         let mut engine = Engine::new();
         engine.init_match();
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.state_machine.register_state(throw_state());
+        p1.state_machine.transition(StateId::Custom(10));
+        assert!(engine.link_pair(EntityId(0), EntityId(1)));
+        engine
+    }
 
-        // Kill player 2
-        if let Some(p2) = &mut engine.entities[1] {
-            p2.health.current = 0;
+    #[test]
+    fn test_throw_drives_victim_until_release() {
+        let mut engine = engine_with_throw();
+        let neutral = InputState::neutral();
+
+        engine.tick(neutral, neutral);
+        let victim = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(victim.state_machine.current_state(), StateId::Held);
+        assert_eq!(victim.physics.position, Vec2::new(-50000 + 8000, 0));
+
+        for _ in 0..4 {
+            engine.tick(neutral, neutral);
         }
+        let victim = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(victim.physics.position.y, -6000);
 
-        engine.check_win_conditions();
-        assert_eq!(engine.game_result, GameResult::Player1Wins);
+        for _ in 0..4 {
+            engine.tick(neutral, neutral);
+        }
+        assert!(engine.link.is_none());
+        let victim = engine.get_entity(EntityId(1)).unwrap();
+        assert_ne!(victim.state_machine.current_state(), StateId::Held);
+    }
+
+    fn throw_input() -> InputState {
+        InputState {
+            light: true,
+            medium: true,
+            ..InputState::neutral()
+        }
+    }
+
+    #[test]
+    fn test_throw_in_range_grabs() {
+        let mut engine = Engine::new();
+        engine.init_match_with(MatchSetup {
+            distance: Some(THROW_RANGE),
+            ..Default::default()
+        });
+        let neutral = InputState::neutral();
+        engine.tick(throw_input(), neutral);
+
+        // The grab hitbox comes out on the activation tick; damage waits
+        // for the tech window
+        assert!(engine.events().is_empty());
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::Throwing);
+        assert!(engine.link.is_some());
+
+        for _ in 1..THROW_TECH_WINDOW {
+            engine.tick(neutral, neutral);
+            assert!(engine.events().is_empty());
+        }
+        engine.tick(neutral, neutral);
+        assert_eq!(engine.events()[0].outcome, HitOutcome::Hit);
+        assert_eq!(engine.events()[0].damage, THROW_DAMAGE);
+
+        // A tech after the window closes does nothing
+        engine.tick(neutral, throw_input());
+        assert!(engine.events().is_empty());
+
+        for _ in 0..20 {
+            engine.tick(neutral, neutral);
+        }
+        assert!(engine.link.is_none());
+    }
+
+    #[test]
+    fn test_throw_tech_escapes() {
+        let mut engine = Engine::new();
+        engine.init_match_with(MatchSetup {
+            distance: Some(THROW_RANGE),
+            ..Default::default()
+        });
+        let neutral = InputState::neutral();
+        engine.tick(throw_input(), neutral);
+        engine.tick(neutral, neutral);
+        engine.tick(neutral, throw_input());
+
+        assert_eq!(engine.events()[0].outcome, HitOutcome::Teched);
+        assert_eq!(engine.events()[0].damage, 0);
+        assert!(engine.link.is_none());
+        let distance = |engine: &Engine| {
+            engine.entities[0].as_ref().unwrap().physics.position.x
+                - engine.entities[1].as_ref().unwrap().physics.position.x
+        };
+        let before = distance(&engine).abs();
+        for entity in engine.entities[..2].iter().flatten() {
+            assert_eq!(entity.state_machine.current_state(), StateId::ThrowTech);
+            assert_eq!(entity.health.current, entity.health.maximum);
+        }
+
+        for _ in 0..THROW_TECH_FRAMES {
+            engine.tick(neutral, neutral);
+        }
+        assert!(distance(&engine).abs() > before);
+    }
+
+    #[test]
+    fn test_throw_out_of_range_whiffs() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let neutral = InputState::neutral();
+        engine.tick(throw_input(), neutral);
+
+        // The activation frame is the first frame of recovery
+        let mut recovery = 1;
+        while engine.entities[0]
+            .as_ref()
+            .unwrap()
+            .state_machine
+            .current_state()
+            == StateId::ThrowWhiff
+        {
+            recovery += 1;
+            engine.tick(neutral, neutral);
+        }
+        assert_eq!(recovery, THROW_WHIFF_FRAMES);
+        assert!(engine.link.is_none());
+    }
+
+    #[test]
+    fn test_link_released_when_attacker_leaves_state() {
+        let mut engine = engine_with_throw();
+        let neutral = InputState::neutral();
+        engine.tick(neutral, neutral);
+
+        // Attacker gets interrupted before the release frame
+        engine.entities[0]
+            .as_mut()
+            .unwrap()
+            .state_machine
+            .transition(StateId::Hitstun);
+        engine.tick(neutral, neutral);
+
+        assert!(engine.link.is_none());
+        let victim = engine.get_entity(EntityId(1)).unwrap();
+        assert_ne!(victim.state_machine.current_state(), StateId::Held);
+    }
+
+    #[test]
+    fn test_rollback_outside_throw_releases_victim() {
+        let mut engine = engine_with_throw();
+        let neutral = InputState::neutral();
+        engine.tick(neutral, neutral);
+
+        // A snapshot whose attacker is no longer throwing can't keep the link
+        let mut snapshot = engine.save_snapshot();
+        assert!(snapshot.link.is_some());
+        snapshot.entities[0]
+            .as_mut()
+            .unwrap()
+            .state_machine
+            .transition(StateId::Idle);
+        engine.load_snapshot(&snapshot).unwrap();
+
+        assert!(engine.link.is_none());
+        let victim = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(victim.state_machine.current_state(), StateId::Idle);
+    }
+
+    #[test]
+    fn test_hit_grab_starts_throw_on_hit_only() {
+        use crate::hitbox::AttackData;
+        use crate::state::{FrameData, State, StateType};
+
+        let grab_attack =
+            State::new(StateId::Custom(11), StateType::Attack, 20).add_frame_data(FrameData::new(
+                1,
+                StateAction::Hitbox {
+                    x: 0,
+                    y: 0,
+                    width: 30000,
+                    height: 30000,
+                    attack: AttackData::new(20).hit_grab(StateId::Custom(10)),
+                },
+            ));
+        let setup = || {
+            let mut engine = Engine::new();
+            engine.init_match();
+            let p1 = engine.entities[0].as_mut().unwrap();
+            p1.state_machine.register_state(throw_state());
+            start_poke(&mut engine, grab_attack.clone());
+            engine
+        };
+
+        let mut engine = setup();
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_eq!(engine.events()[0].damage, 20);
+        assert_eq!(
+            engine.link.map(|l| l.attacker_state),
+            Some(StateId::Custom(10))
+        );
+        let victim = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(victim.state_machine.current_state(), StateId::Held);
+
+        // Blocking the hit-grab is a normal block
+        let mut engine = setup();
+        let back = InputState {
+            direction: crate::input::Direction::Back,
+            ..InputState::neutral()
+        };
+        engine.tick(InputState::neutral(), back);
+        assert_eq!(engine.events()[0].outcome, HitOutcome::Blocked);
+        assert!(engine.link.is_none());
+    }
+
+    /// P1 mid-way into a cinematic super on P2, one tick after it connects
+    fn engine_with_cinematic() -> Engine {
+        engine_with_cinematic_from(crate::hitbox::AttackData::new(0).unblockable())
+    }
+
+    /// Like `engine_with_cinematic`, with `attack` as the starter's hitbox
+    fn engine_with_cinematic_from(attack: crate::hitbox::AttackData) -> Engine {
+        use crate::state::{FrameData, State, StateType};
+
+        let starter =
+            State::new(StateId::Custom(12), StateType::Attack, 20).add_frame_data(FrameData::new(
+                1,
+                StateAction::Hitbox {
+                    x: 0,
+                    y: 0,
+                    width: 30000,
+                    height: 30000,
+                    attack: attack.hit_grab(StateId::Custom(13)),
+                },
+            ));
+        let cinematic = State::new(StateId::Custom(13), StateType::Attack, 40)
+            .add_frame_data(FrameData::new(
+                0,
+                StateAction::VictimState {
+                    state: StateId::Hitstun,
+                },
+            ))
+            .add_frame_data(FrameData::new(
+                0,
+                StateAction::MoveVictim { x: 10000, y: 0 },
+            ))
+            .add_frame_data(FrameData::new(
+                10,
+                StateAction::DamageVictim { damage: 100 },
+            ))
+            .add_frame_data(FrameData::new(
+                20,
+                StateAction::MoveVictim {
+                    x: 10000,
+                    y: -20000,
+                },
+            ))
+            .add_frame_data(FrameData::new(
+                20,
+                StateAction::DamageVictim { damage: 200 },
+            ))
+            .add_frame_data(FrameData::new(30, StateAction::ReleaseVictim));
+
+        let mut engine = Engine::new();
+        engine.init_match();
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.state_machine.register_state(cinematic);
+        start_poke(&mut engine, starter);
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert!(engine.link.is_some());
+        engine
+    }
+
+    #[test]
+    fn test_cinematic_deals_scripted_damage_untouchable() {
+        let mut engine = engine_with_cinematic();
+        let mut damage = Vec::new();
+        for _ in 0..40 {
+            // Collision is suspended while the cinematic runs
+            if engine.link.is_some() {
+                engine.spawn_projectile(EntityId(1), fireball(), Vec2::ZERO);
+            }
+            engine.tick(InputState::neutral(), InputState::neutral());
+            if engine.link.is_some() {
+                assert_eq!(engine.metrics().active_hurtboxes, 0);
+                let victim = engine.get_entity(EntityId(1)).unwrap();
+                assert_eq!(victim.state_machine.current_state(), StateId::Hitstun);
+            }
+            damage.extend(engine.events().iter().map(|e| (e.defender, e.damage)));
+        }
+        assert_eq!(damage, vec![(EntityId(1), 100), (EntityId(1), 200)]);
+        assert!(engine.link.is_none());
+        let victim = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(victim.health.current, victim.health.maximum - 300);
+    }
+
+    #[test]
+    fn test_cinematic_damage_is_super_only_from_a_super_starter() {
+        use crate::hitbox::AttackData;
+
+        for (attack, expected) in [
+            (AttackData::new(0).unblockable(), false),
+            (AttackData::new(0).unblockable().super_move(), true),
+        ] {
+            let mut engine = engine_with_cinematic_from(attack);
+            let mut flags = Vec::new();
+            for _ in 0..40 {
+                engine.tick(InputState::neutral(), InputState::neutral());
+                flags.extend(engine.events().iter().map(|e| e.is_super));
+            }
+            assert_eq!(flags, vec![expected; 2]);
+        }
+    }
+
+    #[test]
+    fn test_cinematic_survives_rollback() {
+        let mut engine = engine_with_cinematic();
+        for _ in 0..5 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        let saved = engine.save_snapshot();
+        for _ in 0..40 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        let first_run = engine.save_snapshot();
+
+        engine.load_snapshot(&saved).unwrap();
+        for _ in 0..40 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        crate::snapshot::assert_snapshots_match(&first_run, &engine.save_snapshot());
+
+        // Landing where the attacker has left the cinematic lets the victim go
+        let mut interrupted = saved.clone();
+        interrupted.entities[0]
+            .as_mut()
+            .unwrap()
+            .state_machine
+            .transition(StateId::Idle);
+        engine.load_snapshot(&interrupted).unwrap();
+        assert!(engine.link.is_none());
+        let victim = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(victim.state_machine.current_state(), StateId::Idle);
+    }
+
+    #[test]
+    fn test_counter_stance_negates_and_punishes() {
+        use crate::state::{CounterData, State, StateType};
+
+        let stance = State::new(StateId::Custom(20), StateType::Normal, 30)
+            .with_counter(CounterData::new(0, 20, StateId::Custom(21)));
+        let punish = State::new(StateId::Custom(21), StateType::Attack, 20);
+
+        let mut engine = engine_with_poke(EngineConfig::default());
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.state_machine.register_state(stance);
+        p2.state_machine.register_state(punish);
+        p2.state_machine.transition(StateId::Custom(20));
+
+        engine.tick(InputState::neutral(), InputState::neutral());
+
+        assert_eq!(engine.events()[0].outcome, HitOutcome::Parried);
+        let p2 = engine.get_entity(EntityId(1)).unwrap();
+        assert_eq!(p2.health.current, 1000);
+        assert_eq!(p2.state_machine.current_state(), StateId::Custom(21));
+    }
+
+    #[test]
+    fn test_wakeup_delay_from_config() {
+        let mut config = EngineConfig::default();
+        config.game.wakeup_delay_frames = 7;
+
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+
+        let p2 = engine.entities[1].as_mut().unwrap();
+        assert_eq!(p2.wakeup_delay_frames, 7);
+        p2.knockdown_remaining = 1;
+
+        let mut held = InputState::neutral();
+        held.light = true;
+        engine.tick(InputState::neutral(), held);
+
+        let info = engine.training_info(PlayerId::PLAYER_2).unwrap();
+        assert_eq!(info.wakeup_delay, 7);
+        assert_eq!(info.knockdown_remaining, 7);
+    }
+
+    #[test]
+    fn test_players_share_state_definitions() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        let p1 = engine.get_player_entity(PlayerId::PLAYER_1).unwrap();
+        let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+        assert!(Arc::ptr_eq(
+            p1.state_machine.definitions(),
+            p2.state_machine.definitions()
+        ));
+
+        // Per-entity runtime state stays small regardless of definition size
+        assert!(core::mem::size_of::<Entity>() < 512);
+    }
+
+    fn overlap_players(engine: &mut Engine, p1_x: i32, p2_x: i32) {
+        engine.entities[0].as_mut().unwrap().physics.position.x = p1_x;
+        engine.entities[1].as_mut().unwrap().physics.position.x = p2_x;
+    }
+
+    #[test]
+    fn test_pushbox_separation_splits_by_weight() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        // Equal weights, 4000 overlap: each moves 2000
+        overlap_players(&mut engine, 0, 6000);
+        engine.separate_pushboxes();
+        let p1 = engine.get_player_entity(PlayerId::PLAYER_1).unwrap();
+        let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+        assert_eq!(p1.physics.position.x, -2000);
+        assert_eq!(p2.physics.position.x, 8000);
+
+        // P2 three times heavier: P1 moves 3000, P2 moves 1000
+        overlap_players(&mut engine, 0, 6000);
+        engine.entities[1].as_mut().unwrap().weight = 300;
+        engine.separate_pushboxes();
+        let p1 = engine.get_player_entity(PlayerId::PLAYER_1).unwrap();
+        let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+        assert_eq!(p1.physics.position.x, -3000);
+        assert_eq!(p2.physics.position.x, 7000);
+
+        // Extreme weights saturate: both at the maximum still split evenly
+        overlap_players(&mut engine, 0, 6000);
+        for fighter in engine.entities[..2].iter_mut().flatten() {
+            fighter.weight = i32::MAX;
+        }
+        engine.separate_pushboxes();
+        let p1 = engine.get_player_entity(PlayerId::PLAYER_1).unwrap();
+        let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+        assert_eq!(p1.physics.position.x, -2000);
+        assert_eq!(p2.physics.position.x, 8000);
+    }
+
+    #[test]
+    fn test_walking_fighter_yields_less() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        overlap_players(&mut engine, 0, 6000);
+        engine.entities[0]
+            .as_mut()
+            .unwrap()
+            .state_machine
+            .transition(StateId::Walk);
+        engine.separate_pushboxes();
+
+        let p1 = engine.get_player_entity(PlayerId::PLAYER_1).unwrap();
+        let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+        assert!(p1.physics.position.x > -2000);
+        assert!(p2.physics.position.x > 8000);
+        assert_eq!(p2.physics.position.x - p1.physics.position.x, 10000);
+    }
+
+    #[test]
+    fn test_airborne_fighter_is_pushed_out() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        overlap_players(&mut engine, 0, 6000);
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.physics.position.y = -5000;
+        p1.physics.on_ground = false;
+        engine.separate_pushboxes();
+
+        let p1 = engine.get_player_entity(PlayerId::PLAYER_1).unwrap();
+        let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+        assert_eq!(p1.physics.position.x, -4000);
+        assert_eq!(p2.physics.position.x, 6000);
+    }
+
+    #[test]
+    fn test_limited_resources_per_round_and_match() {
+        use crate::state::{LimitedResource, ResourceScope};
+
+        let mut set = states::default_set();
+        set.register(states::light_attack().spends_resource(0));
+        set.register(states::medium_attack().spends_resource(1));
+        set.set_resource(0, LimitedResource::new(1, ResourceScope::Match));
+        set.set_resource(1, LimitedResource::new(1, ResourceScope::Round));
+        let mut engine = Engine::new();
+        engine.init_match();
+        let p1 = engine.entities[0].as_ref().unwrap();
+        let (id, player, position) = (p1.id, p1.player_id, p1.physics.position);
+        engine.entities[0] = Some(Entity::with_states(id, player, position, Arc::new(set)));
+
+        let press = |engine: &mut Engine, input: InputState| {
+            engine.tick(input, InputState::neutral());
+            let state = engine.entities[0]
+                .as_ref()
+                .unwrap()
+                .state_machine
+                .current_state();
+            for _ in 0..40 {
+                engine.tick(InputState::neutral(), InputState::neutral());
+            }
+            state
+        };
+        let light = InputState {
+            light: true,
+            ..InputState::neutral()
+        };
+        let medium = InputState {
+            medium: true,
+            ..InputState::neutral()
+        };
+        assert_eq!(press(&mut engine, light), StateId::LightAttack);
+        assert_eq!(press(&mut engine, medium), StateId::MediumAttack);
+        assert_ne!(press(&mut engine, light), StateId::LightAttack);
+        assert_ne!(press(&mut engine, medium), StateId::MediumAttack);
+
+        // A new round refills the per-round resource only
+        engine.entities[1].as_mut().unwrap().health.current = 0;
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_eq!(engine.round, 2);
+        let remaining = engine
+            .get_state()
+            .p1_resources
+            .map(|r| r.map(|r| r.remaining));
+        assert_eq!(remaining, [Some(0), Some(1), None, None]);
+    }
+
+    #[test]
+    fn test_round_persistence_carries_meter_and_winner_damage() {
+        let run = |persistence: crate::config::RoundPersistence| {
+            let mut config = EngineConfig::default();
+            config.game.persistence = persistence;
+            let mut engine = Engine::with_config(config);
+            engine.init_match();
+            let p1 = engine.entities[0].as_mut().unwrap();
+            p1.meter = 50;
+            p1.health.current -= 100;
+            engine.entities[1].as_mut().unwrap().health.current = 0;
+            engine.tick(InputState::neutral(), InputState::neutral());
+            assert_eq!(engine.round, 2);
+            let p1 = engine.entities[0].as_ref().unwrap();
+            let p2 = engine.entities[1].as_ref().unwrap();
+            (
+                p1.meter,
+                p1.health.maximum - p1.health.current,
+                p2.health.current == p2.health.maximum,
+            )
+        };
+
+        assert_eq!(run(Default::default()), (0, 0, true));
+        let keep = crate::config::RoundPersistence {
+            meter: true,
+            winner_damage: true,
+            ..Default::default()
+        };
+        assert_eq!(run(keep), (50, 100, true));
+    }
+
+    #[test]
+    fn test_round_bonus_meter_for_loser() {
+        let mut config = EngineConfig::default();
+        config.game.round_bonus = crate::config::RoundBonus {
+            loser_meter: 30,
+            winner_meter: 5,
+        };
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        assert_eq!(engine.get_state().p2_round_bonus, 0);
+
+        engine.entities[1].as_mut().unwrap().health.current = 0;
+        engine.tick(InputState::neutral(), InputState::neutral());
+        let state = engine.get_state();
+        assert_eq!(state.round, 2);
+        assert_eq!((state.p1_meter, state.p2_meter), (5, 30));
+        assert_eq!((state.p1_round_bonus, state.p2_round_bonus), (5, 30));
+    }
+
+    #[test]
+    fn test_timeout_decides_by_health() {
+        let mut config = EngineConfig::default();
+        config.game.time_limit_frames = 10;
+        config.game.rounds_to_win = 1;
+        let neutral = InputState::neutral();
+
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        engine.entities[0].as_mut().unwrap().health.current -= 50;
+        for _ in 0..9 {
+            engine.tick(neutral, neutral);
+        }
+        assert_eq!(engine.get_state().time_remaining, Some(1));
+        assert_eq!(engine.game_result, GameResult::InProgress);
+        engine.tick(neutral, neutral);
+        assert_eq!(engine.game_result, GameResult::Player2Wins);
+
+        // Equal health draws the match, even with rounds left to play
+        config.game.rounds_to_win = 2;
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        for _ in 0..10 {
+            engine.tick(neutral, neutral);
+        }
+        assert_eq!(engine.game_result, GameResult::Draw);
+        assert_eq!(engine.round, 1);
+        assert_eq!(engine.round_wins, [0, 0]);
+    }
+
+    #[test]
+    fn test_game_state_hud_fields() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        let neutral = InputState::neutral();
+        for _ in 0..10 {
+            engine.tick(neutral, neutral);
+        }
+
+        let state = engine.get_state();
+        assert_eq!(state.time_remaining, Some(3590));
+        assert_eq!(state.round, 1);
+        assert_eq!((state.p1_rounds, state.p2_rounds), (0, 0));
+        assert_eq!(state.p1_combo_hits, 0);
+        assert_eq!(state.hitstop, 0);
+
+        let mut config = EngineConfig::default();
+        config.game.time_limit_frames = 0;
+        let engine = Engine::with_config(config);
+        assert_eq!(engine.get_state().time_remaining, None);
+    }
+
+    #[test]
+    fn test_hitstop_freezes_entities() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        for id in [EntityId(0), EntityId(1)] {
+            assert!(engine.freeze_entity(id, 3));
+        }
+
+        let mut forward = InputState::neutral();
+        forward.direction = crate::input::Direction::Forward;
+        let start_x = engine.entities[0].as_ref().unwrap().physics.position.x;
+        for _ in 0..3 {
+            engine.tick(forward, InputState::neutral());
+            assert_eq!(
+                engine.entities[0].as_ref().unwrap().physics.position.x,
+                start_x
+            );
+        }
+
+        engine.tick(forward, InputState::neutral());
+        assert!(engine.entities[0].as_ref().unwrap().physics.position.x > start_x);
+        assert_eq!(engine.frame.0, 4);
+    }
+
+    #[test]
+    fn test_characters_bring_their_stats() {
+        let mut engine = Engine::new();
+        engine.init_match_with_characters(CharacterDef::grappler(), CharacterDef::rushdown());
+        let stats = |engine: &Engine| {
+            [0, 1].map(|i| {
+                let fighter = engine.entities[i].as_ref().unwrap();
+                (
+                    fighter.health.maximum,
+                    fighter.weight,
+                    fighter.physics.gravity,
+                )
+            })
+        };
+        assert_eq!(
+            stats(&engine),
+            [(1150, 130, GRAVITY * 6 / 5), (900, 90, GRAVITY)]
+        );
+
+        // Walking forward for a second, the rushdown covers more ground
+        let position =
+            |engine: &Engine, i: usize| engine.entities[i].as_ref().unwrap().physics.position.x;
+        let start = [0, 1].map(|i| position(&engine, i));
+        let forward = InputState {
+            direction: crate::input::Direction::Forward,
+            ..InputState::neutral()
+        };
+        for _ in 0..10 {
+            engine.tick(forward, forward);
+        }
+        let walked = [0, 1].map(|i| (position(&engine, i) - start[i]).abs());
+        assert!(walked[1] > walked[0]);
+
+        // The characters stay for the next round and match
+        engine.entities[0].as_mut().unwrap().health.set(0);
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_eq!(engine.round, 2);
+        assert_eq!(stats(&engine)[1].0, 900);
+        engine.init_match();
+        assert_eq!(stats(&engine)[0].0, 1150);
+    }
+
+    #[test]
+    fn test_characters_load_per_player() {
+        let mut engine = Engine::new();
+        let rushdown = include_str!("../data/rushdown.chr");
+        assert!(engine.load_character(PlayerId::PLAYER_2, rushdown).is_ok());
+        let error = engine
+            .load_character(
+                PlayerId::PLAYER_1,
+                "version 1\nstate Walk normal 1\n  frame 0 dash\nend",
+            )
+            .unwrap_err();
+        assert_eq!(error.line, 3);
+
+        engine.init_match();
+        let light_duration = |engine: &Engine| {
+            [0, 1].map(|i| {
+                let definitions = engine.entities[i]
+                    .as_ref()
+                    .unwrap()
+                    .state_machine
+                    .definitions();
+                definitions.find(StateId::LightAttack).unwrap().duration
+            })
+        };
+        assert_eq!(light_duration(&engine), [18, 14]);
+
+        // Each player keeps their character into the next round
+        engine.entities[0].as_mut().unwrap().health.set(0);
+        engine.tick(InputState::neutral(), InputState::neutral());
+        assert_eq!(engine.round, 2);
+        assert_eq!(light_duration(&engine), [18, 14]);
+    }
+
+    #[test]
+    fn test_super_flash_freezes_everyone_else() {
+        use crate::state::{FrameData, State, StateAction, StateType};
+
+        let flash = State::new(StateId::Custom(9), StateType::Attack, 30)
+            .add_frame_data(FrameData::new(0, StateAction::SuperFlash { frames: 4 }));
+        let mut engine = Engine::new();
+        engine.init_match();
+        let p1 = engine.entities[0].as_mut().unwrap();
+        p1.state_machine.register_state(flash);
+        p1.state_machine.transition(StateId::Custom(9));
+        engine.entities[1].as_mut().unwrap().hitstun_remaining = 20;
+        let fireball = engine
+            .spawn_projectile(EntityId(1), fireball(), Vec2::new(-1000, 0))
+            .unwrap();
+
+        let neutral = InputState::neutral();
+        engine.tick(neutral, neutral);
+        let p2 = engine.entities[1].as_ref().unwrap();
+        assert_eq!((p2.frozen_frames, p2.hitstun_remaining), (4, 19));
+        let held = engine.get_projectile(fireball).unwrap().position;
+
+        // The opponent's stun and projectile wait out the flash
+        for _ in 0..4 {
+            engine.tick(neutral, neutral);
+            assert_eq!(engine.entities[1].as_ref().unwrap().hitstun_remaining, 19);
+            assert_eq!(engine.get_projectile(fireball).unwrap().position, held);
+        }
+        assert!(
+            engine.entities[0]
+                .as_ref()
+                .unwrap()
+                .state_machine
+                .state_frame()
+                > 4
+        );
+
+        engine.tick(neutral, neutral);
+        assert_eq!(engine.entities[1].as_ref().unwrap().hitstun_remaining, 18);
+        assert_ne!(engine.get_projectile(fireball).unwrap().position, held);
+        assert!(!engine.freeze_entity(EntityId(7), 3));
+    }
+
+    #[test]
+    fn test_metrics_collected_each_tick() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        let neutral = InputState::neutral();
+        engine.tick(neutral, neutral);
+
+        let metrics = engine.metrics();
+        assert_eq!(metrics.frame, 0);
+        assert_eq!(metrics.entities_updated, 2);
+        assert_eq!(metrics.active_hitboxes, 0);
+        assert_eq!(metrics.active_hurtboxes, 2);
+        assert_eq!(metrics.collision_pair_tests, 0);
+        assert_eq!(metrics.events_emitted, 0);
+        assert_eq!(
+            metrics.snapshot_struct_bytes as usize,
+            core::mem::size_of::<EngineSnapshot>()
+        );
     }
 }
@@ -1,17 +1,44 @@
 //! Entity system for fighters and other game objects
 //! Combines state machine, physics, and collision
 
+use std::sync::Arc;
+
+use crate::config::{DizzyConfig, GuardConfig, PhysicsConfig};
 use crate::constants::*;
-use crate::hitbox::{CollisionBox, CollisionResult};
-use crate::input::InputBuffer;
-use crate::state::{states, StateAction, StateId, StateMachine};
-use crate::types::{EntityId, Facing, PlayerId, Vec2};
+use crate::damage::StaleQueue;
+use crate::events::{Cue, CueQueue, HitOutcome};
+use crate::hitbox::{
+    AttackData, CollisionBox, CollisionResult, Invulnerability, KnockdownType, Team,
+};
+use crate::input::{Direction, InputBuffer};
+use crate::presentation::Presentation;
+use crate::projectile::ProjectileDef;
+use crate::range::throw_reaches;
+use crate::state::{
+    states, Install, LimitedResource, StateAction, StateId, StateMachine, StateSet, StateType,
+    UseLimit,
+};
+use crate::training::Blockstring;
+use crate::types::{EntityId, Facing, Fixed, PlayerId, Vec2};
 
 /// Health and damage tracking
 #[derive(Debug, Clone, Copy)]
 pub struct Health {
     pub current: i32,
     pub maximum: i32,
+    /// Health the lifebar still shows as recently lost ("white damage")
+    pub trail: i32,
+    /// Frames before the trail starts draining
+    pub trail_delay: u32,
+}
+
+/// Lifebar split into normalized segments, each out of `LIFEBAR_SCALE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LifebarSegments {
+    /// Remaining (red) life
+    pub life: i32,
+    /// Recently lost (white) damage, drawn right after the remaining life
+    pub recent: i32,
 }
 
 impl Health {
@@ -19,11 +46,52 @@ impl Health {
         Self {
             current: max,
             maximum: max,
+            trail: max,
+            trail_delay: 0,
         }
     }
 
     pub fn take_damage(&mut self, damage: i32) {
-        self.current = (self.current - damage).max(0);
+        let before = self.current;
+        self.current = Fixed(self.current).sub(Fixed(damage)).raw().max(0);
+        if self.current < before {
+            self.trail_delay = LIFEBAR_TRAIL_DELAY;
+        }
+    }
+
+    /// Set health outright, with nothing shown as recently lost
+    pub fn set(&mut self, value: i32) {
+        self.current = value;
+        self.trail = value;
+        self.trail_delay = 0;
+    }
+
+    /// Drain the recently lost portion; it holds while `holding` (a combo
+    /// is still running) and for `LIFEBAR_TRAIL_DELAY` frames after
+    pub fn update_trail(&mut self, holding: bool) {
+        if holding {
+            self.trail_delay = LIFEBAR_TRAIL_DELAY;
+        } else if self.trail_delay > 0 {
+            self.trail_delay -= 1;
+        } else {
+            self.trail -= LIFEBAR_TRAIL_DRAIN;
+        }
+        self.trail = self
+            .trail
+            .clamp(self.current, self.maximum.max(self.current));
+    }
+
+    /// Normalized lifebar segments
+    pub fn segments(&self) -> LifebarSegments {
+        if self.maximum <= 0 {
+            return LifebarSegments::default();
+        }
+        let scale = |value: i32| (value as i64 * LIFEBAR_SCALE as i64 / self.maximum as i64) as i32;
+        let life = scale(self.current);
+        LifebarSegments {
+            life,
+            recent: scale(self.trail) - life,
+        }
     }
 
     pub fn is_alive(&self) -> bool {
@@ -43,6 +111,12 @@ pub struct Physics {
     pub momentum: Vec2, // Knockback/hitstun momentum
     pub gravity: i32,   // Applied each frame when airborne
     pub on_ground: bool,
+    pub ground_friction: i32, // Momentum kept per grounded frame (percent)
+    pub air_drag: i32,        // Momentum kept per airborne frame (percent)
+    pub gravity_scale: i32,   // Percent of gravity applied (set by attack states)
+    pub max_fall_speed: i32,  // Falling speed cap (0 = uncapped)
+    pub pinned_frames: u32,   // Frames left held in place
+    pub vertical_freeze: u32, // Frames left without vertical movement
 }
 
 impl Physics {
@@ -53,26 +127,80 @@ impl Physics {
             momentum: Vec2::ZERO,
             gravity: GRAVITY,
             on_ground: true,
+            ground_friction: GROUND_FRICTION_PERCENT,
+            air_drag: AIR_DRAG_PERCENT,
+            gravity_scale: 100,
+            max_fall_speed: 0,
+            pinned_frames: 0,
+            vertical_freeze: 0,
         }
     }
 
+    /// Restore physics overridden by a state
+    pub fn reset_trajectory(&mut self) {
+        self.gravity_scale = 100;
+        self.max_fall_speed = 0;
+        self.pinned_frames = 0;
+        self.vertical_freeze = 0;
+    }
+
+    /// Apply engine-wide physics tuning
+    pub fn apply_config(&mut self, config: &PhysicsConfig) {
+        self.gravity = config.gravity;
+        self.ground_friction = config.ground_friction_percent;
+        self.air_drag = config.air_drag_percent;
+    }
+
     /// Apply physics for one frame
     pub fn update(&mut self) {
+        // Pinned characters (flash kick startup, throw animations) don't move
+        if self.pinned_frames > 0 {
+            self.pinned_frames -= 1;
+            self.velocity = Vec2::ZERO;
+            self.momentum = Vec2::ZERO;
+            return;
+        }
+
+        if self.vertical_freeze > 0 {
+            self.vertical_freeze -= 1;
+            self.velocity.y = 0;
+            self.momentum.y = 0;
+        }
+        let frozen = self.vertical_freeze > 0;
+
+        // Keep speeds bounded so accumulated knockback can't overflow
+        self.velocity = clamp_components(self.velocity, MAX_SPEED);
+        self.momentum = clamp_components(self.momentum, MAX_SPEED);
+
         // Apply momentum (from hits)
         self.position = self.position.add(self.momentum);
 
-        // Decay momentum
-        self.momentum.x = self.momentum.x * MOMENTUM_DECAY_PERCENT / MOMENTUM_DECAY_DIVISOR;
-        self.momentum.y = self.momentum.y * MOMENTUM_DECAY_PERCENT / MOMENTUM_DECAY_DIVISOR;
+        // Decay momentum (friction on the ground, drag in the air)
+        let decay = if self.on_ground {
+            self.ground_friction
+        } else {
+            self.air_drag
+        };
+        // Decay is unbounded config, so the product is taken in 64 bits
+        let decayed = |v: i32| {
+            Fixed::from_wide(v as i64 * decay as i64 / MOMENTUM_DECAY_DIVISOR as i64).raw()
+        };
+        self.momentum = Vec2::new(decayed(self.momentum.x), decayed(self.momentum.y));
 
         // Apply velocity (from movement)
         self.position = self.position.add(self.velocity);
 
         // Apply gravity if airborne
-        if !self.on_ground {
-            self.velocity.y += self.gravity;
+        if !self.on_ground && !frozen {
+            let gravity = Fixed(self.gravity).percent(self.gravity_scale);
+            self.velocity.y = Fixed(self.velocity.y).add(gravity).raw();
+            if self.max_fall_speed > 0 {
+                self.velocity.y = self.velocity.y.min(self.max_fall_speed);
+            }
         }
 
+        self.position = clamp_components(self.position, MAX_COORDINATE);
+
         // Ground collision (simplified)
         if self.position.y >= 0 {
             self.position.y = 0;
@@ -83,76 +211,227 @@ impl Physics {
             self.on_ground = false;
         }
 
-        // Reset velocity each frame (must be reapplied)
-        self.velocity = Vec2::ZERO;
+        // Horizontal velocity must be reapplied each frame, vertical velocity
+        // carries through the air so jumps and launches follow an arc
+        self.velocity.x = 0;
     }
 
     pub fn apply_knockback(&mut self, x: i32, y: i32) {
-        self.momentum.x += x;
-        self.momentum.y += y;
+        self.momentum = self.momentum.add(Vec2::new(x, y));
+    }
 
-        // Launch into air if significant upward momentum
-        if y < KNOCKBACK_THRESHOLD {
-            self.on_ground = false;
-        }
+    /// Knock into the air, lifting by at least the launch threshold
+    pub fn launch(&mut self, x: i32, y: i32) {
+        self.apply_knockback(x, y.min(KNOCKBACK_THRESHOLD));
+        self.on_ground = false;
     }
 }
 
+/// Limit each component of a vector to `limit` either way
+fn clamp_components(v: Vec2, limit: i32) -> Vec2 {
+    Vec2::new(
+        Fixed(v.x).clamp_abs(limit).raw(),
+        Fixed(v.y).clamp_abs(limit).raw(),
+    )
+}
+
 /// Fighter entity
+#[derive(Clone)]
 pub struct Entity {
     pub id: EntityId,
     pub player_id: PlayerId,
+    /// Side this entity fights for; boxes of the same team never hit
+    pub team: Team,
     pub facing: Facing,
     pub health: Health,
     pub physics: Physics,
     pub state_machine: StateMachine,
     pub hitstun_remaining: u32,
     pub blockstun_remaining: u32,
+    pub knockdown_remaining: u32,
+    /// Whether the current knockdown allows a ground tech
+    pub can_tech: bool,
+    /// Direction held during the knockdown, chooses the tech option
+    pub tech_direction: Direction,
+    pub invuln_remaining: u32,
+    /// Kinds of attack ignored while `invuln_remaining` lasts
+    pub invuln: Invulnerability,
+    /// Frames left of armor
+    pub armor_remaining: u32,
+    /// Strikes armor can still absorb while `armor_remaining` lasts
+    pub armor_hits: u32,
+    /// Damage taken through armor, in percent
+    pub armor_damage_percent: i32,
+    /// Frames before this fighter may attack (match handicap)
+    pub attack_delay: u32,
+    /// Frames added to a knockdown when the downed player holds a button
+    pub wakeup_delay_frames: u32,
+    /// Delay applied to the current or most recent knockdown
+    pub wakeup_delay_applied: u32,
+    /// Resistance to being displaced by the opponent's pushbox
+    pub weight: i32,
+    /// Super meter
+    pub meter: i32,
+    /// Meter gain multiplier, in percent
+    pub meter_gain: i32,
+    /// Guard meter, drained by blocking (0 unless `GuardConfig` enables it)
+    pub guard: i32,
+    /// Frames left of reduced damage after being guard crushed
+    pub crush_protection_remaining: u32,
+    /// Dizzy meter, filled by clean hits (stays 0 unless `DizzyConfig`
+    /// enables it)
+    pub dizzy: i32,
+    /// Frames of dizzy left, counted down once the hit reaction is over
+    pub dizzy_remaining: u32,
+    /// Limited-use resources declared by the character, by slot
+    pub resources: [Option<LimitedResource>; MAX_RESOURCES],
+    /// Hits taken in the combo currently being received
+    pub combo_hits: u32,
+    /// Damage taken in the combo currently being received
+    pub combo_damage: i32,
+    /// Proration accumulated by the combo being received, in percent
+    pub combo_proration: i32,
+    /// Damage taken multiplier, in percent
+    pub defense: i32,
+    /// Moves this fighter landed recently, for stale-move negation
+    pub stale_moves: StaleQueue,
+    /// Gap analysis of blocked pressure
+    pub blockstring: Blockstring,
+    /// Cues raised during the last update, collected by the engine
+    pub cues: CueQueue,
+    /// State whose trajectory modifiers are applied to physics
+    pub trajectory_state: StateId,
+    /// Hit shake and recoil, drawn but never simulated
+    pub presentation: Presentation,
+    /// Horizontal distance to the opponent, refreshed by the engine each frame
+    pub opponent_distance: Option<i32>,
+    /// Whether the opponent is taking a combo, refreshed by the engine each frame
+    pub opponent_in_combo: bool,
+    /// Projectile launched during the last update, spawned by the engine:
+    /// the state and frame data entry of its `SpawnProjectile` action, so
+    /// the definition stays in the shared state set (see `take_spawn`)
+    pub spawn: Option<(StateId, usize)>,
+    /// Fighter whose hit is pushing this one back; takes over the pushback
+    /// when this fighter is against a wall
+    pub pusher: Option<EntityId>,
+    /// Whether an air attack was used since leaving the ground; landing
+    /// then costs `LANDING_RECOVERY_FRAMES`
+    pub air_attacked: bool,
+    /// Updates since a move last started; presses older than that were
+    /// already spent and aren't replayed from the action buffer
+    pub frames_since_action: u32,
+    /// Frames this fighter sits out entirely: the engine skips its update,
+    /// so states, physics and stun timers all wait
+    pub frozen_frames: u32,
+    /// Super flash raised during the last update, collected by the engine
+    pub flash: Option<u32>,
+    /// Power-up changing the timing of moves started while it lasts
+    pub install: Option<Install>,
 }
 
 impl Entity {
     pub fn new(id: EntityId, player_id: PlayerId, position: Vec2) -> Self {
+        Self::with_states(id, player_id, position, Arc::new(states::default_set()))
+    }
+
+    /// Create an entity driven by shared (per-character) state definitions
+    pub fn with_states(
+        id: EntityId,
+        player_id: PlayerId,
+        position: Vec2,
+        definitions: Arc<StateSet>,
+    ) -> Self {
         let facing = match player_id {
             PlayerId::PLAYER_1 => Facing::Right,
             _ => Facing::Left,
         };
+        let resources = definitions.resources();
 
-        let mut entity = Self {
+        Self {
             id,
             player_id,
+            team: Team::Side(player_id.0),
             facing,
-            health: Health::new(1000),
+            health: Health::new(DEFAULT_HEALTH),
             physics: Physics::new(position),
-            state_machine: StateMachine::new(),
+            state_machine: StateMachine::with_states(definitions),
             hitstun_remaining: 0,
             blockstun_remaining: 0,
-        };
+            knockdown_remaining: 0,
+            can_tech: false,
+            tech_direction: Direction::Neutral,
+            invuln_remaining: 0,
+            armor_remaining: 0,
+            armor_hits: 0,
+            armor_damage_percent: 100,
+            invuln: Invulnerability::FULL,
+            attack_delay: 0,
+            wakeup_delay_frames: DELAYED_WAKEUP_FRAMES,
+            wakeup_delay_applied: 0,
+            weight: DEFAULT_WEIGHT,
+            meter: 0,
+            meter_gain: 100,
+            guard: 0,
+            crush_protection_remaining: 0,
+            dizzy: 0,
+            dizzy_remaining: 0,
+            resources,
+            combo_hits: 0,
+            combo_damage: 0,
+            combo_proration: 100,
+            defense: 100,
+            stale_moves: StaleQueue::default(),
+            blockstring: Blockstring::default(),
+            cues: CueQueue::default(),
+            trajectory_state: StateId::Idle,
+            presentation: Presentation::default(),
+            opponent_distance: None,
+            opponent_in_combo: false,
+            spawn: None,
+            pusher: None,
+            air_attacked: false,
+            frames_since_action: u32::MAX,
+            frozen_frames: 0,
+            flash: None,
+            install: None,
+        }
+    }
 
-        // Register default states
-        entity.register_default_states();
+    /// Freeze for at least `frames` more frames; overlapping freezes don't stack
+    pub fn freeze(&mut self, frames: u32) {
+        self.frozen_frames = self.frozen_frames.max(frames);
+    }
 
-        entity
+    /// Whether the engine skips this fighter's update this frame
+    pub fn is_frozen(&self) -> bool {
+        self.frozen_frames > 0
     }
 
-    fn register_default_states(&mut self) {
-        self.state_machine.register_state(states::idle());
-        self.state_machine.register_state(states::walk());
-        self.state_machine.register_state(states::walk_back());
-        self.state_machine.register_state(states::jump());
-        self.state_machine.register_state(states::light_attack());
-        self.state_machine.register_state(states::medium_attack());
-        self.state_machine.register_state(states::heavy_attack());
-        self.state_machine.register_state(states::hitstun(20));
-        self.state_machine.register_state(states::blockstun(15));
+    /// Hand the install's modifier to moves started this update, then count
+    /// the install down
+    fn tick_install(&mut self) {
+        let modifier = self.install.map(|i| i.modifier).unwrap_or_default();
+        self.state_machine.set_modifier(modifier);
+        if let Some(install) = &mut self.install {
+            install.remaining -= 1;
+            if install.remaining == 0 {
+                self.install = None;
+            }
+        }
     }
 
     /// Update entity for one frame
     pub fn update(&mut self, input: Option<&InputBuffer>) {
+        self.frames_since_action = self.frames_since_action.saturating_add(1);
+        self.tick_install();
+        self.health.update_trail(self.in_combo());
+
         // Reduce stun timers
         if self.hitstun_remaining > 0 {
             self.hitstun_remaining -= 1;
             if self.hitstun_remaining == 0 {
                 self.state_machine.transition(StateId::Idle);
+                self.end_combo();
             }
         }
 
@@ -162,20 +441,150 @@ impl Entity {
                 self.state_machine.transition(StateId::Idle);
             }
         }
+        if self.blockstun_remaining == 0 {
+            self.blockstring.tick_actionable();
+        }
+
+        if self.invuln_remaining > 0 {
+            self.invuln_remaining -= 1;
+        }
+
+        if self.armor_remaining > 0 {
+            self.armor_remaining -= 1;
+        }
+
+        if self.crush_protection_remaining > 0 {
+            self.crush_protection_remaining -= 1;
+        }
+
+        if self.attack_delay > 0 {
+            self.attack_delay -= 1;
+        }
+
+        if self.knockdown_remaining > 0 {
+            if self.can_tech {
+                if let Some(input) = input {
+                    self.tech_direction = input.current().direction;
+                }
+            }
+
+            self.knockdown_remaining -= 1;
+
+            // Holding a button delays the wakeup once per knockdown
+            let holding_button = input.map(|i| i.current().any_button()).unwrap_or(false);
+            if self.knockdown_remaining == 0
+                && holding_button
+                && self.wakeup_delay_applied == 0
+                && self.wakeup_delay_frames > 0
+            {
+                self.knockdown_remaining = self.wakeup_delay_frames;
+                self.wakeup_delay_applied = self.wakeup_delay_frames;
+            }
+
+            if self.knockdown_remaining == 0 {
+                self.wake_up();
+            }
+        }
+
+        // A filled dizzy meter takes over once the hit reaction is over
+        let held = self.state_machine.current_state() == StateId::Held;
+        if self.dizzy_remaining > 0 && !self.in_combo() && !held {
+            if self.state_machine.current_state() != StateId::Dizzy {
+                self.state_machine.transition(StateId::Dizzy);
+            }
+            self.dizzy_remaining -= 1;
+            if self.dizzy_remaining == 0 {
+                self.state_machine.transition(StateId::Idle);
+            }
+        }
+
+        // Button presses shorten mashable holds and staggers
+        if let Some(input) = input {
+            self.state_machine
+                .register_mash(input.buttons_just_pressed());
+        }
+
+        // Back in neutral with the combo over, once-per-combo moves return
+        if !self.opponent_in_combo && self.state_machine.current_state() == StateId::Idle {
+            self.state_machine.reset_uses(UseLimit::OncePerCombo);
+        }
 
         // Process input if not in stun
-        if self.hitstun_remaining == 0 && self.blockstun_remaining == 0 {
+        if self.hitstun_remaining == 0
+            && self.blockstun_remaining == 0
+            && self.knockdown_remaining == 0
+            && self.dizzy_remaining == 0
+        {
+            self.update_charge(input);
+            self.update_hold(input);
             self.process_input(input);
         }
 
         // Execute state actions
+        self.sync_trajectory();
         self.execute_state_actions();
 
         // Advance state
+        let state = self.state_machine.current_state();
+        let whiffing = matches!(state, StateId::SpecialMove | StateId::ExSpecial)
+            && !self.state_machine.hit_confirmed();
         self.state_machine.advance_frame();
+        self.sync_trajectory();
+        if whiffing && self.state_machine.current_state() != state {
+            self.gain_meter(METER_GAIN_ON_WHIFFED_SPECIAL);
+        }
 
         // Update physics
+        let was_airborne = !self.physics.on_ground;
         self.physics.update();
+
+        if was_airborne && self.physics.on_ground {
+            self.state_machine.reset_uses(UseLimit::OncePerAirtime);
+
+            // Touching down during or after an air attack cuts it short
+            if self.air_attacked && self.hitstun_remaining == 0 && self.knockdown_remaining == 0 {
+                self.state_machine.transition(StateId::Landing);
+            }
+            self.air_attacked = false;
+        }
+    }
+
+    /// Release or keep charging a hold-to-charge state
+    fn update_charge(&mut self, input: Option<&InputBuffer>) {
+        let Some(charge) = self.state_machine.current_charge() else {
+            return;
+        };
+        let held = input
+            .map(|i| i.current().button_pressed(charge.button))
+            .unwrap_or(false);
+        let frame = self.state_machine.state_frame();
+
+        if !held {
+            let release = if frame >= charge.full_charge_frames {
+                charge.charged
+            } else {
+                charge.tap
+            };
+            self.state_machine.transition(release);
+        } else if frame == charge.full_charge_frames {
+            self.cues.push(Cue::FullyCharged);
+        }
+    }
+
+    /// Leave a held state on button release
+    fn update_hold(&mut self, input: Option<&InputBuffer>) {
+        let Some(hold) = self.state_machine.current_hold() else {
+            return;
+        };
+        let held = input
+            .map(|i| i.current().button_pressed(hold.button))
+            .unwrap_or(false);
+
+        if held {
+            self.state_machine.set_held(true);
+        } else {
+            self.state_machine.transition(hold.release);
+        }
     }
 
     /// Process player input
@@ -184,27 +593,74 @@ impl Entity {
         let current = input.current();
 
         // Attack inputs
-        if self.can_act() {
+        if self.can_act() && self.attack_delay == 0 {
             use crate::input::Button;
 
-            if input.button_just_pressed(Button::Light) {
-                self.state_machine.transition(StateId::LightAttack);
+            // Throws check range on activation and whiff when nobody is close
+            if input.detect_throw() && self.state_machine.has_state(StateId::Throw) {
+                let in_range = self.opponent_distance.is_some_and(throw_reaches);
+                let throw = if in_range {
+                    StateId::Throw
+                } else {
+                    StateId::ThrowWhiff
+                };
+                if self.start_move(throw) {
+                    return;
+                }
+            }
+
+            // Motions take priority over normals sharing their buttons
+            // Super: QCB + Special, gated by meter
+            if input.detect_qcb()
+                && self.buffered_press(input, Button::Special)
+                && self.start_move(StateId::SuperMove)
+            {
                 return;
             }
 
-            if input.button_just_pressed(Button::Medium) {
-                self.state_machine.transition(StateId::MediumAttack);
+            // Special move example: QCF + button, EX with Heavy held
+            if input.detect_qcf()
+                && self.buffered_press(input, Button::Special)
+                && (current.heavy && self.start_move(StateId::ExSpecial)
+                    || self.start_move(StateId::SpecialMove))
+            {
                 return;
             }
 
-            if input.button_just_pressed(Button::Heavy) {
-                self.state_machine.transition(StateId::HeavyAttack);
+            // Airborne, the strongest button pressed picks the air attack;
+            // footing rules keep these out on the ground
+            let air_attacks = [
+                (Button::Heavy, StateId::JumpHeavy),
+                (Button::Medium, StateId::JumpMedium),
+                (Button::Light, StateId::JumpLight),
+            ];
+            if air_attacks
+                .into_iter()
+                .any(|(button, state)| self.buffered_press(input, button) && self.start_move(state))
+            {
                 return;
             }
 
-            // Special move example: QCF + button
-            if input.detect_qcf() && input.button_just_pressed(Button::Special) {
-                self.state_machine.transition(StateId::SpecialMove);
+            if self.buffered_press(input, Button::Light) && self.start_move(StateId::LightAttack) {
+                return;
+            }
+
+            if self.buffered_press(input, Button::Medium) && self.start_move(StateId::MediumAttack)
+            {
+                return;
+            }
+
+            if self.buffered_press(input, Button::Heavy)
+                && current.direction.is_down()
+                && self.start_move(StateId::Sweep)
+            {
+                return;
+            }
+
+            // Characters with a charge state release heavies on button up
+            if self.buffered_press(input, Button::Heavy)
+                && (self.start_move(StateId::HeavyCharge) || self.start_move(StateId::HeavyAttack))
+            {
                 return;
             }
         }
@@ -212,6 +668,18 @@ impl Entity {
         // Movement (can always move when not in stun)
         use crate::input::Direction;
 
+        // Crouch while down is held on the ground, stand up on release
+        let state = self.state_machine.current_state();
+        if current.direction.is_down() && self.physics.on_ground {
+            if matches!(state, StateId::Idle | StateId::Walk | StateId::WalkBack) {
+                self.state_machine.transition(StateId::Crouch);
+            }
+            return;
+        }
+        if state == StateId::Crouch {
+            self.state_machine.transition(StateId::Idle);
+        }
+
         // Jump if pressing up while on ground
         if current.direction.is_up() && self.physics.on_ground {
             let current_state = self.state_machine.current_state();
@@ -222,12 +690,12 @@ impl Entity {
         }
 
         match current.direction {
-            Direction::Forward | Direction::DownForward | Direction::UpForward => {
+            Direction::Forward | Direction::UpForward => {
                 if self.state_machine.current_state() == StateId::Idle {
                     self.state_machine.transition(StateId::Walk);
                 }
             }
-            Direction::Back | Direction::DownBack | Direction::UpBack => {
+            Direction::Back | Direction::UpBack => {
                 // Transition to backward walk if idle
                 if self.state_machine.current_state() == StateId::Idle {
                     self.state_machine.transition(StateId::WalkBack);
@@ -243,23 +711,107 @@ impl Entity {
         }
     }
 
+    /// Drop trajectory modifiers once the state that set them has ended
+    fn sync_trajectory(&mut self) {
+        let state = self.state_machine.current_state();
+        if state != self.trajectory_state {
+            self.physics.reset_trajectory();
+            self.trajectory_state = state;
+        }
+    }
+
+    /// Take the projectile launched during the last update (offset,
+    /// definition)
+    pub fn take_spawn(&mut self) -> Option<(Vec2, ProjectileDef)> {
+        let (state, index) = self.spawn.take()?;
+        let definitions = self.state_machine.definitions();
+        match definitions.find(state)?.frame_data.get(index)?.action {
+            StateAction::SpawnProjectile { x, y, projectile } => {
+                Some((Vec2::new(x, y), projectile))
+            }
+            _ => None,
+        }
+    }
+
     /// Execute actions from current state
     fn execute_state_actions(&mut self) {
-        let actions = self.state_machine.get_current_actions();
+        // Hold the shared definitions so actions can be read without copying
+        let definitions = Arc::clone(self.state_machine.definitions());
+        let Some(state) = definitions.find(self.state_machine.current_state()) else {
+            return;
+        };
+        let plays = self.state_machine.current_frame_filter();
+        if let Some(animation) = &state.animation {
+            for &(_, cue) in animation.cues.iter().filter(|&&(frame, _)| plays(frame)) {
+                self.cues.push(Cue::Animation(cue));
+            }
+        }
 
-        for action in actions.iter().flatten() {
-            match action {
+        let actions = state
+            .frame_data
+            .iter()
+            .enumerate()
+            .filter(|(_, data)| plays(data.frame));
+        for (index, data) in actions {
+            match &data.action {
                 StateAction::SetVelocity { x, y } => {
                     self.physics.velocity.x = x * self.facing.sign();
                     self.physics.velocity.y = *y;
                 }
                 StateAction::AddMomentum { x, y } => {
-                    self.physics.momentum.x += x * self.facing.sign();
-                    self.physics.momentum.y += y;
+                    let push = Vec2::new(Fixed(*x).mul(self.facing.sign()).raw(), *y);
+                    self.physics.momentum = self.physics.momentum.add(push);
+                }
+                StateAction::SetGravityScale { percent } => {
+                    self.physics.gravity_scale = *percent;
+                }
+                StateAction::ClampFallSpeed { max } => {
+                    self.physics.max_fall_speed = *max;
+                }
+                StateAction::PinPosition { frames } => {
+                    self.physics.pinned_frames = *frames;
+                }
+                StateAction::FreezeVertical { frames } => {
+                    self.physics.vertical_freeze = *frames;
+                }
+                StateAction::HaltMomentum => {
+                    self.physics.velocity.y = 0;
+                    self.physics.momentum = Vec2::ZERO;
                 }
                 StateAction::Transition { target } => {
                     self.state_machine.transition(*target);
                 }
+                StateAction::SpawnProjectile { .. } => {
+                    self.spawn = Some((state.id, index));
+                }
+                StateAction::Install { frames, modifier } => {
+                    self.install = (*frames > 0).then_some(Install {
+                        modifier: *modifier,
+                        remaining: *frames,
+                    });
+                }
+                StateAction::SuperFlash { frames } => {
+                    self.flash = Some(*frames);
+                }
+                StateAction::ConsumeMeter { amount } => {
+                    self.meter = self.meter.saturating_sub(*amount).max(0);
+                }
+                StateAction::SetInvincible {
+                    frames,
+                    invulnerability,
+                } => {
+                    self.invuln_remaining = *frames;
+                    self.invuln = *invulnerability;
+                }
+                StateAction::SetArmor {
+                    hits,
+                    frames,
+                    damage_percent,
+                } => {
+                    self.armor_remaining = *frames;
+                    self.armor_hits = *hits;
+                    self.armor_damage_percent = *damage_percent;
+                }
                 _ => {}
             }
         }
@@ -270,15 +822,14 @@ impl Entity {
         let mut hitboxes = [None; 4];
         let mut count = 0;
 
-        let actions = self.state_machine.get_current_actions();
-        for action_opt in &actions {
-            if let Some(StateAction::Hitbox {
+        for action in self.state_machine.current_actions() {
+            if let StateAction::Hitbox {
                 x,
                 y,
                 width,
                 height,
                 attack,
-            }) = action_opt
+            } = action
             {
                 if count < 4 {
                     let mut bounds = crate::types::Rect::new(*x, *y, *width, *height);
@@ -290,6 +841,7 @@ impl Entity {
 
                     hitboxes[count] = Some(
                         CollisionBox::hitbox(self.id, bounds, *attack)
+                            .with_team(attack.team.unwrap_or(self.team))
                             .translate(self.physics.position),
                     );
                     count += 1;
@@ -300,49 +852,350 @@ impl Entity {
         hitboxes
     }
 
-    /// Get hurtboxes (always present unless invincible)
+    /// Kinds of attack this fighter ignores right now
+    pub fn invulnerability(&self) -> Invulnerability {
+        let invincible_state = self
+            .state_machine
+            .definitions()
+            .find(self.state_machine.current_state())
+            .is_some_and(|s| s.state_type == StateType::Invincible);
+        if invincible_state {
+            Invulnerability::FULL
+        } else if self.invuln_remaining > 0 {
+            self.invuln
+        } else {
+            Invulnerability::NONE
+        }
+    }
+
+    /// Get hurtboxes (present unless invulnerable to everything)
     pub fn get_hurtboxes(&self) -> [Option<CollisionBox>; 2] {
-        // Default body hurtbox
-        let body_box = crate::types::Rect::new(0, 0, 10000, 25000);
-        let hurtbox = CollisionBox::hurtbox(self.id, body_box).translate(self.physics.position);
+        if self.invulnerability().is_full() {
+            return [None, None];
+        }
+
+        let body = self.state_machine.definitions().body;
+        let posture = if self.state_machine.current_state() == StateId::Crouch {
+            body.crouch
+        } else {
+            body.stand
+        };
+        let hurtbox = CollisionBox::hurtbox(self.id, posture)
+            .with_team(self.team)
+            .translate(self.physics.position);
 
         [Some(hurtbox), None]
     }
 
+    /// Get pushbox (body space the opponent can't walk through)
+    pub fn get_pushbox(&self) -> CollisionBox {
+        let body = self.state_machine.definitions().body.stand;
+        CollisionBox::pushbox(self.id, body).translate(self.physics.position)
+    }
+
+    /// Weight used when separating overlapping pushboxes
+    ///
+    /// Walking into the opponent counts extra, so the advancing character
+    /// yields less ground than the one being walked into.
+    pub fn push_weight(&self) -> i32 {
+        if self.state_machine.current_state() == StateId::Walk {
+            Fixed(self.weight).mul(ADVANCING_WEIGHT_MULTIPLIER).raw()
+        } else {
+            self.weight
+        }
+    }
+
     /// Handle being hit
-    pub fn take_hit(&mut self, collision: &CollisionResult, is_blocking: bool) {
+    pub fn take_hit(
+        &mut self,
+        collision: &CollisionResult,
+        is_blocking: bool,
+        from_behind: bool,
+    ) -> HitOutcome {
         let attack = &collision.attack_data;
 
+        let in_blockstun = self.blockstun_remaining > 0;
         if is_blocking && attack.can_block {
             // Blocked
+            self.blockstring.record_block(in_blockstun);
             self.blockstun_remaining = attack.blockstun;
             self.state_machine.transition(StateId::Blockstun);
+            self.presentation
+                .shake(attack.hitstop.unwrap_or(0), BLOCK_SHAKE_AMPLITUDE);
 
-            // Reduced pushback when blocking
-            self.physics
-                .apply_knockback(attack.pushback_x / 2 * -self.facing.sign(), 0);
+            let pushback = Fixed(attack.block_pushback).mul(-self.facing.sign());
+            self.physics.apply_knockback(pushback.raw(), 0);
+            HitOutcome::Blocked
         } else {
             // Hit
+            self.blockstring.record_hit(in_blockstun);
             self.health.take_damage(attack.damage);
-            self.hitstun_remaining = attack.hitstun;
-            self.state_machine.transition(StateId::Hitstun);
+            self.presentation
+                .shake(attack.hitstop.unwrap_or(0), HIT_SHAKE_AMPLITUDE);
+
+            // Hits landing before the victim recovers extend the combo
+            if !self.in_combo() {
+                self.end_combo();
+            }
+            self.combo_hits += 1;
+            self.combo_damage = self.combo_damage.saturating_add(attack.damage);
+            self.combo_proration = Fixed(self.combo_proration).percent(attack.proration).raw();
+
+            let pushback_x = Fixed(attack.pushback_x).mul(-self.facing.sign()).raw();
+            match attack.knockdown {
+                KnockdownType::None => {
+                    self.enter_hitstun(attack.hitstun, from_behind);
+                    self.physics.apply_knockback(pushback_x, attack.pushback_y);
+                }
+                KnockdownType::Launcher => {
+                    self.enter_hitstun(attack.hitstun, from_behind);
+                    self.physics.launch(pushback_x, attack.pushback_y);
+                }
+                KnockdownType::Soft => {
+                    self.enter_knockdown(SOFT_KNOCKDOWN_FRAMES, true);
+                    self.physics.apply_knockback(pushback_x, attack.pushback_y);
+                }
+                KnockdownType::Hard => {
+                    self.enter_knockdown(HARD_KNOCKDOWN_FRAMES, false);
+                    self.physics.apply_knockback(pushback_x, attack.pushback_y);
+                }
+                KnockdownType::Sweep => {
+                    // Trip: no vertical lift
+                    self.enter_knockdown(HARD_KNOCKDOWN_FRAMES, false);
+                    self.physics.apply_knockback(pushback_x, 0);
+                }
+            }
+            HitOutcome::Hit
+        }
+    }
+
+    /// Whether armor would absorb `attack` instead of taking a hit reaction
+    pub fn armor_absorbs(&self, attack: &AttackData) -> bool {
+        self.armor_remaining > 0 && self.armor_hits > 0 && attack.grab_state.is_none()
+    }
+
+    /// Take a hit on armor: the damage, already reduced by the armor, and a
+    /// shake, but no hitstun, knockback or combo
+    pub fn absorb_hit(&mut self, attack: &AttackData) -> HitOutcome {
+        self.health.take_damage(attack.damage);
+        if self.armor_hits != u32::MAX {
+            self.armor_hits -= 1;
+        }
+        self.presentation
+            .shake(attack.hitstop.unwrap_or(0), HIT_SHAKE_AMPLITUDE);
+        HitOutcome::Armored
+    }
+
+    /// Drain guard for blocking `damage`; returns true if the guard breaks
+    pub fn drain_guard(&mut self, damage: i32, config: &GuardConfig) -> bool {
+        if !config.enabled() {
+            return false;
+        }
+        let drain = Fixed(damage).percent(config.drain_percent);
+        self.guard = Fixed(self.guard).sub(drain).raw();
+        self.guard <= 0
+    }
+
+    /// Recover guard while out of blockstun
+    pub fn recover_guard(&mut self, config: &GuardConfig) {
+        if self.blockstun_remaining == 0 {
+            self.guard = Fixed(self.guard)
+                .add(Fixed(config.regen))
+                .raw()
+                .min(config.max);
+        }
+    }
+
+    /// Break the guard on a blocked hit: stun scaled by the crushing
+    /// attack, then a refilled meter and a moment of damage protection
+    pub fn guard_crush(&mut self, attack: &AttackData, config: &GuardConfig) -> HitOutcome {
+        let in_blockstun = self.blockstun_remaining > 0;
+        self.blockstring.record_block(in_blockstun);
+        self.guard = config.max;
+        self.crush_protection_remaining = config.protection_frames;
+        self.blockstun_remaining = 0;
+        self.end_combo();
+        let stun = Fixed(attack.hitstun as i32)
+            .percent(config.crush_stun_percent)
+            .raw();
+        self.enter_hitstun(stun.max(1) as u32, false);
+        self.presentation
+            .shake(attack.hitstop.unwrap_or(0), HIT_SHAKE_AMPLITUDE);
+        let pushback = Fixed(attack.block_pushback).mul(-self.facing.sign());
+        self.physics.apply_knockback(pushback.raw(), 0);
+        HitOutcome::GuardCrushed
+    }
+
+    /// Add a clean hit's stun to the dizzy meter; once it fills, the
+    /// fighter is dizzied when the hit reaction ends
+    pub fn add_dizzy(&mut self, stun: i32, config: &DizzyConfig) {
+        if !config.enabled() || self.dizzy_remaining > 0 {
+            return;
+        }
+        self.dizzy = self.dizzy.saturating_add(stun.max(0));
+        if self.dizzy >= config.threshold {
+            self.dizzy = 0;
+            self.dizzy_remaining = config.frames.max(1);
+        }
+    }
+
+    /// Drain the dizzy meter while not reeling from a hit
+    pub fn recover_dizzy(&mut self, config: &DizzyConfig) {
+        if !self.in_combo() {
+            self.dizzy = Fixed(self.dizzy).sub(Fixed(config.recovery)).raw().max(0);
+        }
+    }
+
+    /// Whether the fighter is standing dizzied
+    pub fn is_dizzy(&self) -> bool {
+        self.state_machine.current_state() == StateId::Dizzy
+    }
+
+    /// Strike a win or lose pose once the match is decided; a body still
+    /// in the air keeps falling into it
+    pub fn enter_outro(&mut self, pose: StateId) {
+        self.hitstun_remaining = 0;
+        self.blockstun_remaining = 0;
+        self.knockdown_remaining = 0;
+        self.dizzy_remaining = 0;
+        self.air_attacked = false;
+        self.state_machine.transition(pose);
+    }
+
+    /// Where to draw the entity: its position plus presentation offsets
+    pub fn display_position(&self) -> Vec2 {
+        self.physics.position.add(self.presentation.offset())
+    }
+
+    /// Whether a hit landing now would extend the combo being received
+    pub fn in_combo(&self) -> bool {
+        self.hitstun_remaining > 0 || self.knockdown_remaining > 0
+    }
+
+    fn end_combo(&mut self) {
+        self.combo_hits = 0;
+        self.combo_damage = 0;
+        self.combo_proration = 100;
+    }
+
+    fn enter_hitstun(&mut self, frames: u32, from_behind: bool) {
+        self.knockdown_remaining = 0;
+        self.hitstun_remaining = frames;
+        let reaction = if from_behind && self.state_machine.has_state(StateId::BackHitstun) {
+            StateId::BackHitstun
+        } else {
+            StateId::Hitstun
+        };
+        self.state_machine.transition(reaction);
+    }
 
-            // Full knockback
-            self.physics
-                .apply_knockback(attack.pushback_x * -self.facing.sign(), attack.pushback_y);
+    fn enter_knockdown(&mut self, frames: u32, can_tech: bool) {
+        self.hitstun_remaining = 0;
+        self.knockdown_remaining = frames;
+        self.can_tech = can_tech;
+        self.tech_direction = Direction::Neutral;
+        self.wakeup_delay_applied = 0;
+        self.state_machine.transition(StateId::Knockdown);
+    }
+
+    /// Get up from a knockdown, teching if it was a soft knockdown
+    fn wake_up(&mut self) {
+        self.end_combo();
+        if !self.can_tech {
+            self.state_machine.transition(StateId::Idle);
+            return;
         }
+
+        self.can_tech = false;
+        if self.tech_direction.is_back() {
+            self.state_machine.transition(StateId::TechBack);
+            self.invuln_remaining = TECH_ROLL_INVULN_FRAMES;
+            self.invuln = Invulnerability::FULL;
+        } else if self.tech_direction.is_forward() {
+            self.state_machine.transition(StateId::TechForward);
+            self.invuln_remaining = TECH_ROLL_INVULN_FRAMES;
+            self.invuln = Invulnerability::FULL;
+        } else {
+            self.state_machine.transition(StateId::TechNeutral);
+            self.invuln_remaining = TECH_NEUTRAL_INVULN_FRAMES;
+            self.invuln = Invulnerability::FULL;
+        }
+    }
+
+    /// Check if entity is lying on the ground after a knockdown
+    pub fn is_knocked_down(&self) -> bool {
+        self.knockdown_remaining > 0
+    }
+
+    /// Start a move if its footing and use limit allow it right now
+    fn start_move(&mut self, id: StateId) -> bool {
+        let on_ground = self.physics.on_ground;
+        if !self.state_machine.can_cancel_into(id) || !self.state_machine.can_start(id, on_ground) {
+            return false;
+        }
+        let definitions = Arc::clone(self.state_machine.definitions());
+        let Some(state) = definitions.find(id) else {
+            return false;
+        };
+        if self.meter < state.meter_required {
+            return false;
+        }
+        if let Some(slot) = state.resource_cost {
+            let resource = self.resources.get_mut(slot).and_then(|r| r.as_mut());
+            if !resource.is_some_and(|r| r.spend()) {
+                return false;
+            }
+        }
+        self.state_machine.start(id);
+        self.frames_since_action = 0;
+        if !on_ground && state.state_type == StateType::Attack {
+            self.air_attacked = true;
+        }
+        true
+    }
+
+    /// Whether a button press should trigger a move this frame: pressed
+    /// now, or inside the action buffer and not already spent on a move
+    fn buffered_press(&self, input: &InputBuffer, button: crate::input::Button) -> bool {
+        let frames = (input.action_buffer() + 1).min(self.frames_since_action);
+        input.button_pressed_within(button, frames)
+    }
+
+    /// Build super meter, up to `METER_MAX`
+    pub fn gain_meter(&mut self, amount: i32) {
+        let amount = if amount > 0 {
+            Fixed(amount).percent(self.meter_gain).raw()
+        } else {
+            amount
+        };
+        self.meter = self.meter.saturating_add(amount).clamp(0, METER_MAX);
     }
 
     /// Check if entity can act (not in recovery/stun)
     fn can_act(&self) -> bool {
         self.hitstun_remaining == 0
             && self.blockstun_remaining == 0
+            && self.knockdown_remaining == 0
+            && self.dizzy_remaining == 0
             && (self.state_machine.current_state() == StateId::Idle
-                || self.state_machine.can_cancel())
+                || self.state_machine.can_cancel()
+                || self.state_machine.has_cancel_routes())
+    }
+
+    /// Check if facing is locked this frame
+    ///
+    /// Airborne characters keep their takeoff facing until they land, which
+    /// is what makes cross-up jumps possible.
+    pub fn facing_locked(&self) -> bool {
+        !self.physics.on_ground
     }
 
     /// Update facing to look at opponent
     pub fn update_facing(&mut self, opponent_pos: Vec2) {
+        if self.facing_locked() {
+            return;
+        }
+
         if opponent_pos.x > self.physics.position.x {
             self.facing = Facing::Right;
         } else if opponent_pos.x < self.physics.position.x {
@@ -376,6 +1229,36 @@ mod tests {
         assert!(!health.is_alive());
     }
 
+    #[test]
+    fn test_lifebar_trail_drains_after_combo() {
+        let mut health = Health::new(1000);
+        health.take_damage(300);
+        assert_eq!(
+            health.segments(),
+            LifebarSegments {
+                life: 700,
+                recent: 300
+            }
+        );
+
+        // Held for the whole combo, then for the delay
+        for _ in 0..100 {
+            health.update_trail(true);
+        }
+        for _ in 0..LIFEBAR_TRAIL_DELAY {
+            health.update_trail(false);
+        }
+        assert_eq!(health.segments().recent, 300);
+
+        health.update_trail(false);
+        assert_eq!(health.trail, 1000 - LIFEBAR_TRAIL_DRAIN);
+        for _ in 0..1000 {
+            health.update_trail(false);
+        }
+        assert_eq!(health.segments().recent, 0);
+        assert_eq!(health.segments().life, 700);
+    }
+
     #[test]
     fn test_physics_update() {
         let mut physics = Physics::new(Vec2::new(0, -1000));
@@ -390,6 +1273,93 @@ mod tests {
         assert!(physics.position.y >= -1000);
     }
 
+    #[test]
+    fn test_friction_and_drag_are_separate() {
+        let mut ground = Physics::new(Vec2::ZERO);
+        ground.ground_friction = 50;
+        ground.air_drag = 100;
+        ground.apply_knockback(1000, 0);
+        ground.update();
+        assert_eq!(ground.momentum.x, 500);
+
+        let mut air = Physics::new(Vec2::new(0, -50000));
+        air.on_ground = false;
+        air.ground_friction = 50;
+        air.air_drag = 100;
+        air.apply_knockback(1000, 0);
+        air.update();
+        assert_eq!(air.momentum.x, 1000);
+
+        // Extreme friction saturates instead of overflowing
+        ground.ground_friction = i32::MAX;
+        ground.apply_knockback(MAX_SPEED, 0);
+        ground.update();
+        assert_eq!(ground.momentum.x, i32::MAX);
+    }
+
+    #[test]
+    fn test_stacked_knockback_stays_bounded() {
+        let mut physics = Physics::new(Vec2::ZERO);
+        for _ in 0..100 {
+            physics.apply_knockback(i32::MAX / 2, 0);
+        }
+        assert_eq!(physics.momentum.x, i32::MAX);
+
+        physics.update();
+        assert!(physics.momentum.x <= MAX_SPEED);
+        assert_eq!(physics.position.x, MAX_SPEED);
+
+        for _ in 0..1000 {
+            physics.apply_knockback(MAX_SPEED, 0);
+            physics.update();
+        }
+        assert_eq!(physics.position.x, MAX_COORDINATE);
+    }
+
+    #[test]
+    fn test_extreme_attack_values_saturate() {
+        use crate::hitbox::AttackData;
+
+        // Pushed away from a right-facing fighter: the negation saturates
+        let mut entity = Entity::new(EntityId(1), PlayerId::PLAYER_2, Vec2::ZERO);
+        entity.facing = Facing::Right;
+        let collision = CollisionResult {
+            attacker: EntityId(0),
+            defender: EntityId(1),
+            attack_data: AttackData::new(10).with_knockback(i32::MIN, 0),
+            contact_point: Vec2::ZERO,
+        };
+        entity.take_hit(&collision, false, false);
+        assert_eq!(entity.physics.momentum.x, i32::MAX);
+
+        let config = GuardConfig {
+            regen: i32::MAX,
+            ..GuardConfig::standard()
+        };
+        let mut entity = Entity::new(EntityId(1), PlayerId::PLAYER_2, Vec2::ZERO);
+        entity.guard = i32::MIN + 1;
+        assert!(entity.drain_guard(i32::MAX, &config));
+        assert_eq!(entity.guard, i32::MIN);
+        entity.recover_guard(&config);
+        entity.recover_guard(&config);
+        assert_eq!(entity.guard, config.max);
+    }
+
+    #[test]
+    fn test_dizzy_stun_saturates_and_never_drains() {
+        let config = DizzyConfig {
+            threshold: i32::MAX,
+            ..DizzyConfig::standard()
+        };
+        let mut entity = Entity::new(EntityId(1), PlayerId::PLAYER_2, Vec2::ZERO);
+        entity.add_dizzy(100, &config);
+        entity.add_dizzy(-50, &config);
+        assert_eq!(entity.dizzy, 100);
+
+        entity.add_dizzy(i32::MAX, &config);
+        assert_eq!(entity.dizzy_remaining, config.frames);
+    }
+
     #[test]
     fn test_facing_update() {
         let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::new(0, 0));
@@ -400,4 +1370,448 @@ mod tests {
         entity.update_facing(Vec2::new(-1000, 0));
         assert_eq!(entity.facing, Facing::Left);
     }
+
+    #[test]
+    fn test_facing_kept_while_airborne() {
+        let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::new(0, -5000));
+        entity.physics.on_ground = false;
+
+        // Opponent is now behind, but the jump keeps its takeoff facing
+        entity.update_facing(Vec2::new(-1000, 0));
+        assert_eq!(entity.facing, Facing::Right);
+
+        // Re-face on landing
+        entity.physics.position.y = 0;
+        entity.physics.on_ground = true;
+        entity.update_facing(Vec2::new(-1000, 0));
+        assert_eq!(entity.facing, Facing::Left);
+    }
+
+    #[test]
+    fn test_jump_lands() {
+        let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::new(0, 0));
+        entity.state_machine.transition(StateId::Jump);
+
+        let mut peak = 0;
+        for _ in 0..40 {
+            entity.update(None);
+            peak = peak.min(entity.physics.position.y);
+        }
+
+        assert!(peak < -5000);
+        assert!(entity.physics.on_ground);
+        assert_eq!(entity.physics.position.y, 0);
+    }
+
+    fn charging_entity() -> Entity {
+        let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::new(0, 0));
+        entity.state_machine.register_state(states::heavy_charge());
+        entity.state_machine.register_state(states::charged_heavy());
+        entity
+    }
+
+    #[test]
+    fn test_heavy_tap_and_charge() {
+        use crate::input::InputState;
+
+        let mut held = InputState::neutral();
+        held.heavy = true;
+
+        // Tap: press then release right away
+        let mut entity = charging_entity();
+        let mut input = InputBuffer::new(Facing::Right);
+        input.push(held);
+        entity.update(Some(&input));
+        assert_eq!(entity.state_machine.current_state(), StateId::HeavyCharge);
+        input.push(InputState::neutral());
+        entity.update(Some(&input));
+        assert_eq!(entity.state_machine.current_state(), StateId::HeavyAttack);
+
+        // Charge: hold until the cue, then release
+        let mut entity = charging_entity();
+        let mut input = InputBuffer::new(Facing::Right);
+        let mut cue_frames = 0;
+        for _ in 0..=HEAVY_FULL_CHARGE_FRAMES {
+            input.push(held);
+            entity.update(Some(&input));
+            if entity.cues.take().eq([Cue::FullyCharged]) {
+                cue_frames += 1;
+            }
+        }
+        assert_eq!(cue_frames, 1);
+        input.push(InputState::neutral());
+        entity.update(Some(&input));
+        assert_eq!(entity.state_machine.current_state(), StateId::ChargedHeavy);
+    }
+
+    #[test]
+    fn test_hold_state_loops_until_release() {
+        use crate::input::{Button, InputState};
+        use crate::state::{HoldData, State, StateType};
+
+        let flight = State::new(StateId::Custom(4), StateType::Normal, 10).with_hold(HoldData {
+            button: Button::Special,
+            release: StateId::Custom(5),
+            loop_from: 4,
+        });
+        let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::new(0, 0));
+        entity.state_machine.register_state(flight);
+        entity
+            .state_machine
+            .register_state(State::new(StateId::Custom(5), StateType::Normal, 5));
+        entity.state_machine.transition(StateId::Custom(4));
+
+        let mut held = InputState::neutral();
+        held.special = true;
+        let mut input = InputBuffer::new(Facing::Right);
+        for _ in 0..25 {
+            input.push(held);
+            entity.update(Some(&input));
+            assert_eq!(entity.state_machine.current_state(), StateId::Custom(4));
+            assert!((0..10).contains(&entity.state_machine.state_frame()));
+        }
+        // 25 frames: 0..10, then 4..10 twice, then 4..7
+        assert_eq!(entity.state_machine.state_frame(), 7);
+
+        input.push(InputState::neutral());
+        entity.update(Some(&input));
+        assert_eq!(entity.state_machine.current_state(), StateId::Custom(5));
+    }
+
+    #[test]
+    fn test_trajectory_modifiers_last_for_the_state() {
+        use crate::state::{FrameData, State, StateType};
+
+        let float = State::new(StateId::Custom(3), StateType::Attack, 10)
+            .add_frame_data(FrameData::new(0, StateAction::HaltMomentum))
+            .add_frame_data(FrameData::new(
+                0,
+                StateAction::SetGravityScale { percent: 0 },
+            ))
+            .add_frame_data(FrameData::new(5, StateAction::ClampFallSpeed { max: 20 }))
+            .add_frame_data(FrameData::new(
+                5,
+                StateAction::SetGravityScale { percent: 100 },
+            ));
+
+        let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::new(0, -50000));
+        entity.state_machine.register_state(float);
+        entity.physics.on_ground = false;
+        entity.physics.velocity.y = 500;
+        entity.state_machine.transition(StateId::Custom(3));
+
+        // Hangs in place while gravity is off
+        entity.update(None);
+        assert_eq!(entity.physics.velocity.y, 0);
+        for _ in 0..4 {
+            entity.update(None);
+        }
+        assert_eq!(entity.physics.position.y, -50000);
+
+        // Falls, but no faster than the clamp
+        for _ in 0..4 {
+            entity.update(None);
+            assert!(entity.physics.velocity.y <= 20);
+        }
+        assert!(entity.physics.position.y > -50000);
+
+        // Modifiers are gone once the state ends
+        entity.update(None);
+        assert_eq!(entity.state_machine.current_state(), StateId::Idle);
+        assert_eq!(entity.physics.gravity_scale, 100);
+        assert_eq!(entity.physics.max_fall_speed, 0);
+    }
+
+    #[test]
+    fn test_install_speeds_up_moves_started_during_it() {
+        use crate::state::{FrameData, FrameDataModifier, State, StateType};
+
+        let install =
+            State::new(StateId::Custom(6), StateType::Normal, 1).add_frame_data(FrameData::new(
+                0,
+                StateAction::Install {
+                    frames: 4,
+                    modifier: FrameDataModifier::new(-2, 0),
+                },
+            ));
+        let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::ZERO);
+        entity.state_machine.register_state(install);
+        entity.state_machine.transition(StateId::Custom(6));
+        entity.update(None);
+        assert_eq!(entity.install.map(|i| i.remaining), Some(4));
+
+        entity.update(None);
+        entity.state_machine.transition(StateId::LightAttack);
+        for _ in 0..3 {
+            entity.update(None);
+        }
+        // Startup of 5 frames shortened to 3
+        assert!(!entity.state_machine.in_startup());
+        assert_eq!(entity.install, None);
+
+        // Moves started after the install ended keep their frame data
+        entity.state_machine.transition(StateId::Idle);
+        entity.update(None);
+        entity.state_machine.transition(StateId::LightAttack);
+        for _ in 0..3 {
+            entity.update(None);
+        }
+        assert!(entity.state_machine.in_startup());
+    }
+
+    #[test]
+    fn test_pin_and_vertical_freeze() {
+        use crate::state::{FrameData, State, StateType};
+
+        let flash = State::new(StateId::Custom(4), StateType::Attack, 20)
+            .add_frame_data(FrameData::new(0, StateAction::PinPosition { frames: 3 }))
+            .add_frame_data(FrameData::new(3, StateAction::FreezeVertical { frames: 4 }));
+
+        let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::new(0, -30000));
+        entity.state_machine.register_state(flash);
+        entity.physics.on_ground = false;
+        entity.physics.momentum = Vec2::new(400, 0);
+        entity.state_machine.transition(StateId::Custom(4));
+
+        for _ in 0..3 {
+            entity.update(None);
+        }
+        assert_eq!(entity.physics.position, Vec2::new(0, -30000));
+
+        // Frozen vertically: no gravity for the frame range
+        for _ in 0..3 {
+            entity.update(None);
+        }
+        assert_eq!(entity.physics.position.y, -30000);
+        entity.update(None);
+        entity.update(None);
+        assert!(entity.physics.position.y > -30000);
+
+        // Leaving the state early restores normal physics
+        entity.physics.pinned_frames = 10;
+        entity.state_machine.transition(StateId::Idle);
+        entity.update(None);
+        assert_eq!(entity.physics.pinned_frames, 0);
+    }
+
+    fn hit_with(attack: crate::hitbox::AttackData) -> Entity {
+        let mut entity = Entity::new(EntityId(1), PlayerId::PLAYER_2, Vec2::new(0, 0));
+        let collision = CollisionResult {
+            attacker: EntityId(0),
+            defender: EntityId(1),
+            attack_data: attack,
+            contact_point: Vec2::ZERO,
+        };
+        entity.take_hit(&collision, false, false);
+        entity
+    }
+
+    #[test]
+    fn test_block_pushback_is_independent_of_hit_knockback() {
+        use crate::hitbox::AttackData;
+
+        let mut entity = Entity::new(EntityId(1), PlayerId::PLAYER_2, Vec2::new(0, 0));
+        let collision = CollisionResult {
+            attacker: EntityId(0),
+            defender: EntityId(1),
+            attack_data: AttackData::new(10)
+                .with_knockback(1000, 0)
+                .with_block_pushback(100),
+            contact_point: Vec2::ZERO,
+        };
+        let outcome = entity.take_hit(&collision, true, false);
+
+        assert_eq!(outcome, HitOutcome::Blocked);
+        assert_eq!(entity.physics.momentum.x.abs(), 100);
+    }
+
+    #[test]
+    fn test_guard_crush_scales_with_crushing_attack() {
+        use crate::hitbox::AttackData;
+
+        let config = GuardConfig {
+            max: 100,
+            drain_percent: 100,
+            crush_stun_percent: 200,
+            protection_frames: 30,
+            ..GuardConfig::standard()
+        };
+        let crush_with = |hitstun| {
+            let mut entity = Entity::new(EntityId(1), PlayerId::PLAYER_2, Vec2::new(0, 0));
+            entity.guard = config.max;
+            let attack = AttackData::new(60).with_stun(hitstun, 10);
+            let collision = CollisionResult {
+                attacker: EntityId(0),
+                defender: EntityId(1),
+                attack_data: attack,
+                contact_point: Vec2::ZERO,
+            };
+            let mut outcomes = Vec::new();
+            for _ in 0..2 {
+                outcomes.push(if entity.drain_guard(attack.damage, &config) {
+                    entity.guard_crush(&attack, &config)
+                } else {
+                    entity.take_hit(&collision, true, false)
+                });
+            }
+            (outcomes, entity)
+        };
+
+        let (outcomes, light) = crush_with(10);
+        assert_eq!(outcomes, [HitOutcome::Blocked, HitOutcome::GuardCrushed]);
+        assert_eq!(light.blockstring.hits, 2);
+        assert_eq!(light.state_machine.current_state(), StateId::Hitstun);
+        assert_eq!(light.hitstun_remaining, 20);
+        assert_eq!(light.health.current, 1000);
+        assert_eq!(light.guard, 100);
+        assert_eq!(light.crush_protection_remaining, 30);
+
+        let (_, heavy) = crush_with(25);
+        assert_eq!(heavy.hitstun_remaining, 50);
+    }
+
+    #[test]
+    fn test_knockdown_types_map_to_victim_states() {
+        use crate::hitbox::AttackData;
+
+        let plain = hit_with(AttackData::new(10));
+        assert_eq!(plain.state_machine.current_state(), StateId::Hitstun);
+        assert!(!plain.is_knocked_down());
+
+        let launched = hit_with(AttackData::new(10).with_knockback(0, 0).launcher());
+        assert_eq!(launched.state_machine.current_state(), StateId::Hitstun);
+        assert!(!launched.physics.on_ground);
+        assert!(launched.physics.momentum.y <= KNOCKBACK_THRESHOLD);
+
+        let soft = hit_with(AttackData::new(10).soft_knockdown());
+        assert_eq!(soft.state_machine.current_state(), StateId::Knockdown);
+        assert_eq!(soft.knockdown_remaining, SOFT_KNOCKDOWN_FRAMES);
+
+        let swept = hit_with(AttackData::new(10).with_knockback(500, -800).sweep());
+        assert_eq!(swept.state_machine.current_state(), StateId::Knockdown);
+        assert_eq!(swept.knockdown_remaining, HARD_KNOCKDOWN_FRAMES);
+        assert_eq!(swept.physics.momentum.y, 0);
+    }
+
+    #[test]
+    fn test_wakeup_after_knockdown() {
+        use crate::hitbox::AttackData;
+
+        let mut entity = hit_with(AttackData::new(10).soft_knockdown());
+        for _ in 0..SOFT_KNOCKDOWN_FRAMES {
+            assert!(entity.is_knocked_down());
+            entity.update(None);
+        }
+        assert!(!entity.is_knocked_down());
+        assert_eq!(entity.state_machine.current_state(), StateId::TechNeutral);
+        assert!(entity.get_hurtboxes()[0].is_none());
+    }
+
+    #[test]
+    fn test_combo_counts_hits_during_hitstun() {
+        use crate::hitbox::AttackData;
+
+        let collision = CollisionResult {
+            attacker: EntityId(0),
+            defender: EntityId(1),
+            attack_data: AttackData::new(30).with_stun(10, 5),
+            contact_point: Vec2::ZERO,
+        };
+
+        let mut entity = hit_with(collision.attack_data);
+        assert_eq!(entity.combo_hits, 1);
+
+        entity.update(None);
+        entity.take_hit(&collision, false, false);
+        assert_eq!(entity.combo_hits, 2);
+        assert_eq!(entity.combo_damage, 60);
+
+        // Combo ends once the victim recovers
+        for _ in 0..10 {
+            entity.update(None);
+        }
+        assert_eq!(entity.combo_hits, 0);
+        assert_eq!(entity.combo_damage, 0);
+    }
+
+    #[test]
+    fn test_crouching_lowers_the_hurtbox() {
+        let mut entity = Entity::new(EntityId(0), PlayerId::PLAYER_1, Vec2::ZERO);
+        let hurtbox = |entity: &Entity| entity.get_hurtboxes()[0].unwrap().bounds;
+        let standing = hurtbox(&entity);
+
+        entity.state_machine.transition(StateId::Crouch);
+        let crouching = hurtbox(&entity);
+        assert!(crouching.y > standing.y);
+        assert_eq!(crouching.y + crouching.height, standing.y + standing.height);
+        // The pushbox keeps the standing body
+        assert_eq!(entity.get_pushbox().bounds, standing);
+    }
+
+    #[test]
+    fn test_hard_knockdown_cannot_tech() {
+        use crate::hitbox::AttackData;
+
+        let mut entity = hit_with(AttackData::new(10).hard_knockdown());
+        for _ in 0..HARD_KNOCKDOWN_FRAMES {
+            entity.update(None);
+        }
+        assert_eq!(entity.state_machine.current_state(), StateId::Idle);
+        assert!(entity.get_hurtboxes()[0].is_some());
+    }
+
+    #[test]
+    fn test_delayed_wakeup_while_holding_button() {
+        use crate::hitbox::AttackData;
+        use crate::input::InputState;
+
+        let mut entity = hit_with(AttackData::new(10).hard_knockdown());
+        let mut input = InputBuffer::new(entity.facing);
+        let held = InputState {
+            heavy: true,
+            ..InputState::neutral()
+        };
+        // Held since well before the knockdown ends, so it isn't a
+        // buffered press
+        for _ in 0..=ACTION_BUFFER_FRAMES + 1 {
+            input.push(held);
+        }
+
+        for _ in 0..HARD_KNOCKDOWN_FRAMES {
+            entity.update(Some(&input));
+        }
+        assert!(entity.is_knocked_down());
+        assert_eq!(entity.wakeup_delay_applied, DELAYED_WAKEUP_FRAMES);
+
+        // The delay is only granted once, holding longer does not extend it
+        for _ in 0..DELAYED_WAKEUP_FRAMES {
+            entity.update(Some(&input));
+        }
+        assert!(!entity.is_knocked_down());
+        assert_eq!(entity.state_machine.current_state(), StateId::Idle);
+    }
+
+    #[test]
+    fn test_back_tech_roll_from_held_direction() {
+        use crate::hitbox::AttackData;
+        use crate::input::InputState;
+
+        let mut entity = hit_with(AttackData::new(10).with_knockback(0, 0).soft_knockdown());
+        let mut input = InputBuffer::new(entity.facing);
+        input.push(InputState {
+            direction: Direction::Back,
+            ..InputState::neutral()
+        });
+
+        for _ in 0..SOFT_KNOCKDOWN_FRAMES {
+            entity.update(Some(&input));
+        }
+        assert_eq!(entity.state_machine.current_state(), StateId::TechBack);
+        assert_eq!(entity.invuln_remaining, TECH_ROLL_INVULN_FRAMES);
+
+        // P2 faces left, so rolling back moves towards positive X
+        let start_x = entity.physics.position.x;
+        entity.update(Some(&input));
+        assert!(entity.physics.position.x > start_x);
+    }
 }
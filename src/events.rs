@@ -0,0 +1,153 @@
+//! Gameplay events emitted during a tick
+//!
+//! Events are the engine's report of what happened this frame. Audiovisual
+//! layers and stats code read them instead of re-deriving outcomes from
+//! entity state.
+//!
+//! Besides the detailed per-kind lists (`Engine::events`, `finishes`, ...),
+//! each tick fills a queue of `GameEvent`s in the order things happened,
+//! for callers that just want to drain one stream and react.
+
+use crate::constants::MAX_CUES_PER_UPDATE;
+use crate::state::StateId;
+use crate::types::{EntityId, Frame, Vec2};
+
+/// How an attack connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitOutcome {
+    /// Clean hit
+    Hit,
+    /// Defender blocked
+    Blocked,
+    /// Defender blocked with an empty guard meter and was left open
+    GuardCrushed,
+    /// Defender absorbed the hit with armor
+    Armored,
+    /// Defender parried or countered the attack
+    Parried,
+    /// Defender sent the projectile back, taking ownership of it
+    Reflected,
+    /// Defender absorbed the projectile, destroying it
+    Absorbed,
+    /// Both fighters hit each other on the same frame
+    Trade,
+    /// Defender escaped a throw by teching it
+    Teched,
+}
+
+/// An attack connecting with a defender
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitEvent {
+    pub frame: Frame,
+    pub attacker: EntityId,
+    pub defender: EntityId,
+    pub outcome: HitOutcome,
+    /// Hit sound/effect cue, explicit or from the hit feel tiers
+    pub cue: Option<u16>,
+    /// Damage actually dealt (0 unless the hit landed)
+    pub damage: i32,
+    /// Center of the hitbox/hurtbox overlap, for spark placement
+    pub contact_point: Vec2,
+    /// Dealt by a super (a super attack or a cinematic's scripted damage)
+    pub is_super: bool,
+    /// Landed during the startup of the defender's attack
+    pub counter: bool,
+}
+
+/// Two opposing projectiles destroying each other
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClashEvent {
+    pub frame: Frame,
+    pub projectiles: [EntityId; 2],
+    /// Center of the hitbox overlap, for the cancel spark
+    pub position: Vec2,
+}
+
+/// Special way a round was won by KO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinishKind {
+    /// The KO was dealt by a super
+    Super,
+    /// The loser was KO'd against a stage wall
+    WallBreak,
+    /// The winner finished the round at full health
+    Perfect,
+}
+
+/// A round won with a special finish; a KO meeting several conditions
+/// raises one event per kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FinishEvent {
+    pub frame: Frame,
+    pub winner: EntityId,
+    pub loser: EntityId,
+    pub kind: FinishKind,
+}
+
+/// Typed event queued by `Engine::tick`, drained with
+/// `Engine::drain_game_events`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    /// A new round began (round 1 on the first tick of the match)
+    RoundStart { frame: Frame, round: u32 },
+    /// An attack hit cleanly, trades included
+    HitLanded(HitEvent),
+    /// An attack was blocked (guard crushes included)
+    Blocked(HitEvent),
+    /// A hit landed during the defender's attack startup; follows the
+    /// `HitLanded` for the same hit
+    CounterHit(HitEvent),
+    /// An attack was parried, by a just defend or a counter stance
+    Parry(HitEvent),
+    /// An attack was absorbed by armor; the damage went through but not
+    /// the hit reaction
+    ArmorAbsorb(HitEvent),
+    /// A fighter's health ran out
+    Ko { frame: Frame, entity: EntityId },
+    /// A fighter ended the tick in a different state than it started in
+    StateChanged {
+        frame: Frame,
+        entity: EntityId,
+        from: StateId,
+        to: StateId,
+    },
+}
+
+/// Audiovisual cue raised by a fighter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    /// A charge attack reached full charge
+    FullyCharged,
+    /// An animation cue declared in the state's timeline
+    Animation(u16),
+}
+
+/// Cues a fighter raised during one update, oldest first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CueQueue {
+    cues: [Option<Cue>; MAX_CUES_PER_UPDATE],
+    len: usize,
+}
+
+impl CueQueue {
+    /// Queue a cue; past `MAX_CUES_PER_UPDATE` it is dropped
+    pub fn push(&mut self, cue: Cue) {
+        if let Some(slot) = self.cues.get_mut(self.len) {
+            *slot = Some(cue);
+            self.len += 1;
+        }
+    }
+
+    /// Take every queued cue, oldest first, leaving the queue empty
+    pub fn take(&mut self) -> impl Iterator<Item = Cue> {
+        core::mem::take(self).cues.into_iter().flatten()
+    }
+}
+
+/// A cue raised during a tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CueEvent {
+    pub frame: Frame,
+    pub entity: EntityId,
+    pub cue: Cue,
+}
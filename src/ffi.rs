@@ -0,0 +1,441 @@
+//! Platform-neutral C ABI
+//!
+//! A stable `extern "C"` function set for embedding the engine from C, C#,
+//! GDExtension, and other non-Rust frontends. Engines and snapshots are
+//! opaque heap handles; per-frame state is copied out into `#[repr(C)]`
+//! structs so callers never touch Rust layouts.
+//!
+//! The C header `include/bagarre.h` is generated from the tables in this
+//! module by `c_header()`. Run the tests with `BAGARRE_WRITE_HEADER=1` to
+//! regenerate it after changing the ABI.
+//!
+//! Inputs use the same byte layout as `InputState::to_bits`.
+
+use crate::engine::{Engine, GameResult};
+use crate::input::InputState;
+use crate::limits::Limits;
+use crate::setup::MatchSetup;
+use crate::snapshot::EngineSnapshot;
+use crate::state::StateId;
+use crate::types::PlayerId;
+
+/// Version of the C ABI; bumped on any breaking change
+pub const BAGARRE_API_VERSION: u32 = 1;
+
+/// Per-fighter state copied out for rendering
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BagarreFighter {
+    pub x: i32,
+    pub y: i32,
+    pub health: i32,
+    pub max_health: i32,
+    pub meter: i32,
+    /// 1 = facing right, -1 = facing left
+    pub facing: i32,
+    /// State code, see `encode_state`
+    pub state: u32,
+    pub state_frame: u32,
+    pub combo_hits: u32,
+}
+
+/// Match state copied out once per frame
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BagarreMatch {
+    pub frame: u64,
+    /// 0 = in progress, 1 = P1 wins, 2 = P2 wins, 3 = draw
+    pub result: u32,
+    pub round: u32,
+    pub p1_rounds: u32,
+    pub p2_rounds: u32,
+    pub fighters: [BagarreFighter; 2],
+}
+
+/// Build-time limits, see `Limits`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BagarreLimits {
+    pub max_players: u32,
+    pub max_entities: u32,
+    pub max_projectiles: u32,
+    pub max_hitboxes: u32,
+    pub max_hurtboxes: u32,
+    pub max_collisions_per_frame: u32,
+    pub max_states: u32,
+    pub max_resources: u32,
+    pub input_buffer_size: u32,
+    pub motion_detection_window: u32,
+    pub max_input_delay: u32,
+    pub rollback_window: u32,
+    pub max_rewind_frames: u32,
+    pub units_per_pixel: i32,
+    pub max_coordinate: i32,
+    pub ticks_per_second: u32,
+    pub engine_data_version: u32,
+    pub replay_format_version: u32,
+}
+
+impl From<Limits> for BagarreLimits {
+    fn from(limits: Limits) -> Self {
+        Self {
+            max_players: limits.max_players as u32,
+            max_entities: limits.max_entities as u32,
+            max_projectiles: limits.max_projectiles as u32,
+            max_hitboxes: limits.max_hitboxes as u32,
+            max_hurtboxes: limits.max_hurtboxes as u32,
+            max_collisions_per_frame: limits.max_collisions_per_frame as u32,
+            max_states: limits.max_states as u32,
+            max_resources: limits.max_resources as u32,
+            input_buffer_size: limits.input_buffer_size as u32,
+            motion_detection_window: limits.motion_detection_window as u32,
+            max_input_delay: limits.max_input_delay as u32,
+            rollback_window: limits.rollback_window as u32,
+            max_rewind_frames: limits.max_rewind_frames as u32,
+            units_per_pixel: limits.units_per_pixel,
+            max_coordinate: limits.max_coordinate,
+            ticks_per_second: limits.ticks_per_second,
+            engine_data_version: limits.engine_data_version as u32,
+            replay_format_version: limits.replay_format_version as u32,
+        }
+    }
+}
+
+/// ABI version the library was built with
+#[no_mangle]
+pub extern "C" fn bagarre_api_version() -> u32 {
+    BAGARRE_API_VERSION
+}
+
+/// Copy the build-time limits into `out`; returns 0 on success
+///
+/// # Safety
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_get_limits(out: *mut BagarreLimits) -> i32 {
+    match out.as_mut() {
+        Some(out) => {
+            *out = Limits::current().into();
+            0
+        }
+        None => -1,
+    }
+}
+
+/// Create an engine with a standard match ready to tick
+#[no_mangle]
+pub extern "C" fn bagarre_engine_new() -> *mut Engine {
+    let mut engine = Engine::new();
+    engine.init_match();
+    Box::into_raw(Box::new(engine))
+}
+
+/// Destroy an engine created by `bagarre_engine_new`
+///
+/// # Safety
+/// `engine` must come from `bagarre_engine_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_engine_free(engine: *mut Engine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// Restart the match with seeded starting conditions
+///
+/// # Safety
+/// `engine` must be a live engine handle.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_init_match(engine: *mut Engine, seed: u64) {
+    if let Some(engine) = engine.as_mut() {
+        engine.init_match_with(MatchSetup::seeded(seed));
+    }
+}
+
+/// Advance one frame
+///
+/// # Safety
+/// `engine` must be a live engine handle.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_tick(engine: *mut Engine, p1_input: u8, p2_input: u8) {
+    if let Some(engine) = engine.as_mut() {
+        engine.tick(
+            InputState::from_bits(p1_input),
+            InputState::from_bits(p2_input),
+        );
+    }
+}
+
+/// Copy the current match state into `out`; returns 0 on success
+///
+/// # Safety
+/// `engine` must be a live engine handle and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_get_match(engine: *const Engine, out: *mut BagarreMatch) -> i32 {
+    match (engine.as_ref(), out.as_mut()) {
+        (Some(engine), Some(out)) => {
+            *out = match_state(engine);
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// Capture a snapshot; free it with `bagarre_snapshot_free`
+///
+/// # Safety
+/// `engine` must be a live engine handle.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_snapshot_save(engine: *const Engine) -> *mut EngineSnapshot {
+    match engine.as_ref() {
+        Some(engine) => Box::into_raw(Box::new(engine.save_snapshot())),
+        None => core::ptr::null_mut(),
+    }
+}
+
+/// Restore a snapshot into an engine; returns 0 on success, -1 for a null
+/// handle, -2 if the snapshot was taken by another engine version
+///
+/// # Safety
+/// Both handles must be live.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_snapshot_load(
+    engine: *mut Engine,
+    snapshot: *const EngineSnapshot,
+) -> i32 {
+    match (engine.as_mut(), snapshot.as_ref()) {
+        (Some(engine), Some(snapshot)) => match engine.load_snapshot(snapshot) {
+            Ok(()) => 0,
+            Err(_) => -2,
+        },
+        _ => -1,
+    }
+}
+
+/// Destroy a snapshot created by `bagarre_snapshot_save`
+///
+/// # Safety
+/// `snapshot` must come from `bagarre_snapshot_save` and not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_snapshot_free(snapshot: *mut EngineSnapshot) {
+    if !snapshot.is_null() {
+        drop(Box::from_raw(snapshot));
+    }
+}
+
+/// Checksum of the engine's gameplay state (see `Engine::state_hash`);
+/// 0 for a null handle
+///
+/// # Safety
+/// `engine` must be a live engine handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn bagarre_state_hash(engine: *const Engine) -> u64 {
+    engine.as_ref().map_or(0, Engine::state_hash)
+}
+
+/// Copy the engine's state into the C-facing struct
+pub fn match_state(engine: &Engine) -> BagarreMatch {
+    let fighter = |player: PlayerId| {
+        engine
+            .get_player_entity(player)
+            .map(|e| BagarreFighter {
+                x: e.physics.position.x,
+                y: e.physics.position.y,
+                health: e.health.current,
+                max_health: e.health.maximum,
+                meter: e.meter,
+                facing: e.facing.sign(),
+                state: encode_state(e.state_machine.current_state()),
+                state_frame: e.state_machine.state_frame(),
+                combo_hits: e.combo_hits,
+            })
+            .unwrap_or_default()
+    };
+
+    BagarreMatch {
+        frame: engine.frame.0,
+        result: encode_result(engine.game_result),
+        round: engine.round,
+        p1_rounds: engine.round_wins[0],
+        p2_rounds: engine.round_wins[1],
+        fighters: [fighter(PlayerId::PLAYER_1), fighter(PlayerId::PLAYER_2)],
+    }
+}
+
+/// Encode a game result for foreign callers
+pub fn encode_result(result: GameResult) -> u32 {
+    result.to_u32()
+}
+
+/// Encode a state ID for foreign callers (win poses are 31-35, custom
+/// states start at 100)
+pub fn encode_state(state: StateId) -> u32 {
+    state.to_u32()
+}
+
+/// C declarations of the `#[repr(C)]` structs: (name, fields)
+const STRUCTS: &[(&str, &[&str])] = &[
+    (
+        "BagarreFighter",
+        &[
+            "int32_t x",
+            "int32_t y",
+            "int32_t health",
+            "int32_t max_health",
+            "int32_t meter",
+            "int32_t facing",
+            "uint32_t state",
+            "uint32_t state_frame",
+            "uint32_t combo_hits",
+        ],
+    ),
+    (
+        "BagarreMatch",
+        &[
+            "uint64_t frame",
+            "uint32_t result",
+            "uint32_t round",
+            "uint32_t p1_rounds",
+            "uint32_t p2_rounds",
+            "BagarreFighter fighters[2]",
+        ],
+    ),
+    (
+        "BagarreLimits",
+        &[
+            "uint32_t max_players",
+            "uint32_t max_entities",
+            "uint32_t max_projectiles",
+            "uint32_t max_hitboxes",
+            "uint32_t max_hurtboxes",
+            "uint32_t max_collisions_per_frame",
+            "uint32_t max_states",
+            "uint32_t max_resources",
+            "uint32_t input_buffer_size",
+            "uint32_t motion_detection_window",
+            "uint32_t max_input_delay",
+            "uint32_t rollback_window",
+            "uint32_t max_rewind_frames",
+            "int32_t units_per_pixel",
+            "int32_t max_coordinate",
+            "uint32_t ticks_per_second",
+            "uint32_t engine_data_version",
+            "uint32_t replay_format_version",
+        ],
+    ),
+];
+
+/// C prototypes of the exported functions
+const FUNCTIONS: &[&str] = &[
+    "uint32_t bagarre_api_version(void)",
+    "int32_t bagarre_get_limits(BagarreLimits *out)",
+    "BagarreEngine *bagarre_engine_new(void)",
+    "void bagarre_engine_free(BagarreEngine *engine)",
+    "void bagarre_init_match(BagarreEngine *engine, uint64_t seed)",
+    "void bagarre_tick(BagarreEngine *engine, uint8_t p1_input, uint8_t p2_input)",
+    "int32_t bagarre_get_match(const BagarreEngine *engine, BagarreMatch *out)",
+    "BagarreSnapshot *bagarre_snapshot_save(const BagarreEngine *engine)",
+    "int32_t bagarre_snapshot_load(BagarreEngine *engine, const BagarreSnapshot *snapshot)",
+    "void bagarre_snapshot_free(BagarreSnapshot *snapshot)",
+    "uint64_t bagarre_state_hash(const BagarreEngine *engine)",
+];
+
+/// Generate the C header for this ABI
+pub fn c_header() -> String {
+    let mut out = String::new();
+    out.push_str("/* Generated by bagarre::ffi::c_header(); do not edit. */\n");
+    out.push_str("#ifndef BAGARRE_H\n#define BAGARRE_H\n\n");
+    out.push_str("#include <stdint.h>\n\n");
+    out.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+    out.push_str(&format!(
+        "#define BAGARRE_API_VERSION {}\n\n",
+        BAGARRE_API_VERSION
+    ));
+    out.push_str("typedef struct BagarreEngine BagarreEngine;\n");
+    out.push_str("typedef struct BagarreSnapshot BagarreSnapshot;\n\n");
+    for (name, fields) in STRUCTS {
+        out.push_str(&format!("typedef struct {} {{\n", name));
+        for field in *fields {
+            out.push_str(&format!("    {};\n", field));
+        }
+        out.push_str(&format!("}} {};\n\n", name));
+    }
+    for function in FUNCTIONS {
+        out.push_str(&format!("{};\n", function));
+    }
+    out.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif /* BAGARRE_H */\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_struct_layout_is_stable() {
+        assert_eq!(core::mem::size_of::<BagarreFighter>(), 36);
+        assert_eq!(core::mem::size_of::<BagarreMatch>(), 96);
+        assert_eq!(core::mem::align_of::<BagarreMatch>(), 8);
+        assert_eq!(core::mem::size_of::<BagarreLimits>(), 72);
+    }
+
+    #[test]
+    fn test_limits_match_the_build() {
+        let mut limits = BagarreLimits::default();
+        unsafe {
+            assert_eq!(bagarre_get_limits(&mut limits), 0);
+            assert_eq!(bagarre_get_limits(core::ptr::null_mut()), -1);
+        }
+        assert_eq!(limits.max_entities, crate::constants::MAX_ENTITIES as u32);
+        assert_eq!(limits.max_hitboxes, crate::constants::MAX_HITBOXES as u32);
+        assert_eq!(
+            limits.units_per_pixel,
+            crate::constants::INTERNAL_TO_DISPLAY
+        );
+        assert_eq!(limits.ticks_per_second, 60);
+    }
+
+    #[test]
+    fn test_engine_round_trip_through_handles() {
+        unsafe {
+            let engine = bagarre_engine_new();
+            let snapshot = bagarre_snapshot_save(engine);
+            let light = InputState {
+                light: true,
+                ..InputState::neutral()
+            };
+            bagarre_tick(engine, light.to_bits(), 0);
+
+            let mut state = BagarreMatch::default();
+            assert_eq!(bagarre_get_match(engine, &mut state), 0);
+            assert_eq!(state.frame, 1);
+            assert_eq!(state.fighters[0].state, encode_state(StateId::LightAttack));
+            assert_eq!(state.fighters[1].facing, -1);
+
+            let ticked = bagarre_state_hash(engine);
+            assert_eq!(bagarre_snapshot_load(engine, snapshot), 0);
+            assert_eq!(bagarre_get_match(engine, &mut state), 0);
+            assert_eq!(state.frame, 0);
+            assert_ne!(bagarre_state_hash(engine), ticked);
+            assert_eq!(bagarre_state_hash(core::ptr::null()), 0);
+
+            assert_eq!(bagarre_get_match(engine, core::ptr::null_mut()), -1);
+            bagarre_snapshot_free(snapshot);
+            bagarre_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn test_header_is_up_to_date() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/include/bagarre.h");
+        if std::env::var_os("BAGARRE_WRITE_HEADER").is_some() {
+            std::fs::write(path, c_header()).unwrap();
+        }
+        let on_disk = std::fs::read_to_string(path).unwrap_or_default();
+        assert!(
+            on_disk == c_header(),
+            "include/bagarre.h is stale; rerun with BAGARRE_WRITE_HEADER=1"
+        );
+    }
+}
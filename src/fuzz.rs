@@ -0,0 +1,326 @@
+//! Seeded scenario fuzzer (`fuzz` feature)
+//!
+//! `fuzz` plays matches on a seeded stage, rule set and pair of characters,
+//! driven by random input streams drawn from the same seed, and checks the
+//! engine's invariants after every frame:
+//!
+//! - health stays between 0 and the fighter's maximum
+//! - fighters stay between the stage edges and under the ceiling
+//! - no fighter stays in one state longer than `FuzzConfig::stuck_frames`
+//! - a snapshot restores to the state it was taken from, and resimulating
+//!   from it with the same inputs reaches the same state again
+//!
+//! A seed always plays the same scenario, so a failing seed is a ready-made
+//! regression test.
+
+use crate::characters::CharacterDef;
+use crate::config::{
+    DizzyConfig, EngineConfig, GuardConfig, JustDefendConfig, StageConfig, StagePreset,
+};
+use crate::engine::Engine;
+use crate::input::{Direction, InputState};
+use crate::setup::{MatchSetup, SplitMix64};
+use crate::snapshot::{EngineSnapshot, FieldDiff};
+use crate::state::StateId;
+use crate::types::{Frame, PlayerId, Vec2};
+
+/// Longest a random input is held, in frames
+const MAX_HOLD_FRAMES: u64 = 12;
+
+/// How long and how closely to fuzz
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzConfig {
+    /// Frames to simulate; finished matches restart with a new setup
+    pub frames: u32,
+    /// Longest a fighter may stay in one state
+    pub stuck_frames: u32,
+    /// Frames between snapshot checks (0 = no snapshot checks)
+    pub snapshot_interval: u32,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            frames: 3600,
+            stuck_frames: 600,
+            snapshot_interval: 60,
+        }
+    }
+}
+
+/// A broken invariant
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// Health below 0 or above the maximum
+    Health { player: PlayerId, health: i32 },
+    /// Body past a stage edge or above the ceiling
+    OutsideStage { player: PlayerId, position: Vec2 },
+    /// In the same state for longer than `FuzzConfig::stuck_frames`
+    Stuck { player: PlayerId, state: StateId },
+    /// Restoring a snapshot didn't reproduce the captured state
+    SnapshotRoundTrip(Vec<FieldDiff>),
+    /// Resimulating from a snapshot diverged from the original run
+    Resimulation(Vec<FieldDiff>),
+}
+
+/// Where and how a fuzzed scenario failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzFailure {
+    pub seed: u64,
+    /// Frame the engine was on when the violation was found
+    pub frame: Frame,
+    pub violation: Violation,
+}
+
+/// Play the scenario of `seed`, stopping at the first broken invariant
+pub fn fuzz(seed: u64, config: &FuzzConfig) -> Result<(), FuzzFailure> {
+    let mut scenario = Scenario::new(seed);
+    let mut engine = scenario.engine();
+    // Snapshot taken at the last check and the inputs played since
+    let mut checkpoint: Option<(EngineSnapshot, Vec<[InputState; 2]>)> = None;
+
+    for tick in 1..=config.frames {
+        if engine.is_over() {
+            engine.init_match_with(MatchSetup::seeded(scenario.rng.next()));
+            checkpoint = None;
+        }
+        let inputs = scenario.inputs();
+        engine.tick(inputs[0], inputs[1]);
+        if let Some((_, played)) = &mut checkpoint {
+            played.push(inputs);
+        }
+
+        let frame = engine.frame;
+        let fail = |violation| FuzzFailure {
+            seed,
+            frame,
+            violation,
+        };
+        check_fighters(&engine, config).map_err(fail)?;
+        if config.snapshot_interval > 0 && tick % config.snapshot_interval == 0 {
+            if let Some((snapshot, played)) = &checkpoint {
+                check_resimulation(&engine, snapshot, played).map_err(fail)?;
+            }
+            let snapshot = engine.save_snapshot();
+            check_round_trip(&engine, &snapshot).map_err(fail)?;
+            checkpoint = Some((snapshot, Vec::new()));
+        }
+    }
+    Ok(())
+}
+
+/// Random choices of one scenario
+struct Scenario {
+    rng: SplitMix64,
+    /// Input each player is holding, and for how many more frames
+    held: [(InputState, u64); 2],
+}
+
+impl Scenario {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: SplitMix64::new(seed),
+            held: [(InputState::neutral(), 0); 2],
+        }
+    }
+
+    /// Engine with a seeded stage, rules and characters, in a seeded match
+    fn engine(&mut self) -> Engine {
+        let presets = [
+            StagePreset::Standard,
+            StagePreset::Narrow,
+            StagePreset::Wide,
+            StagePreset::Corridor,
+            StagePreset::Platform,
+        ];
+        let mut config = EngineConfig {
+            stage: StageConfig::preset(presets[self.rng.below(presets.len() as u64) as usize]),
+            ..Default::default()
+        };
+        if self.rng.below(2) == 0 {
+            config.game.guard = GuardConfig::standard();
+        }
+        if self.rng.below(2) == 0 {
+            config.game.just_defend = JustDefendConfig::standard();
+        }
+        if self.rng.below(2) == 0 {
+            config.game.dizzy = DizzyConfig::standard();
+        }
+        if self.rng.below(2) == 0 {
+            // Short single-round matches, so runs reach the match outro
+            config.game.time_limit_frames = 600;
+            config.game.rounds_to_win = 1;
+        }
+
+        let characters: [fn() -> CharacterDef; 5] = [
+            CharacterDef::default,
+            CharacterDef::rushdown,
+            CharacterDef::grappler,
+            CharacterDef::zoner,
+            CharacterDef::charge,
+        ];
+        let mut pick = || characters[self.rng.below(characters.len() as u64) as usize]();
+        let (p1, p2) = (pick(), pick());
+
+        let mut engine = Engine::with_config(config);
+        engine.init_match_with_characters(p1, p2);
+        engine.init_match_with(MatchSetup::seeded(self.rng.next()));
+        engine
+    }
+
+    /// Inputs of the next frame: each player holds a random input for a
+    /// few frames before picking another
+    fn inputs(&mut self) -> [InputState; 2] {
+        [0, 1].map(|player| {
+            let (input, remaining) = self.held[player];
+            if remaining > 0 {
+                self.held[player].1 -= 1;
+                return input;
+            }
+            let input = self.random_input();
+            self.held[player] = (input, self.rng.below(MAX_HOLD_FRAMES));
+            input
+        })
+    }
+
+    fn random_input(&mut self) -> InputState {
+        let directions = [
+            Direction::Neutral,
+            Direction::Forward,
+            Direction::Back,
+            Direction::Up,
+            Direction::Down,
+            Direction::UpForward,
+            Direction::UpBack,
+            Direction::DownForward,
+            Direction::DownBack,
+        ];
+        let direction = directions[self.rng.below(directions.len() as u64) as usize];
+        let mut press = || self.rng.below(4) == 0;
+        InputState {
+            direction,
+            light: press(),
+            medium: press(),
+            heavy: press(),
+            special: press(),
+        }
+    }
+}
+
+fn check_fighters(engine: &Engine, config: &FuzzConfig) -> Result<(), Violation> {
+    let stage = engine.config.stage;
+    for fighter in engine.entities.iter().take(2).flatten() {
+        let player = fighter.player_id;
+        let health = fighter.health.current;
+        if !(0..=fighter.health.maximum).contains(&health) {
+            return Err(Violation::Health { player, health });
+        }
+
+        let body = fighter.get_pushbox().bounds;
+        let position = fighter.physics.position;
+        let inside = body.x >= -stage.half_width
+            && body.x + body.width <= stage.half_width
+            && stage.ceiling.is_none_or(|ceiling| position.y >= -ceiling);
+        if !inside {
+            return Err(Violation::OutsideStage { player, position });
+        }
+
+        if fighter.state_machine.state_frame() > config.stuck_frames {
+            let state = fighter.state_machine.current_state();
+            return Err(Violation::Stuck { player, state });
+        }
+    }
+    Ok(())
+}
+
+/// Restoring a snapshot of `engine` on a separate engine must reproduce it,
+/// down to the state checksum
+fn check_round_trip(engine: &Engine, snapshot: &EngineSnapshot) -> Result<(), Violation> {
+    let mut replica = replica_of(engine);
+    snapshot.restore(&mut replica);
+    let diffs = engine.save_snapshot().diff(&replica.save_snapshot());
+    match diffs.is_empty() && engine.state_hash() == replica.state_hash() {
+        true => Ok(()),
+        false => Err(Violation::SnapshotRoundTrip(diffs)),
+    }
+}
+
+/// Replaying `inputs` from `snapshot` on a separate engine must reach the
+/// state `engine` is in now
+fn check_resimulation(
+    engine: &Engine,
+    snapshot: &EngineSnapshot,
+    inputs: &[[InputState; 2]],
+) -> Result<(), Violation> {
+    let mut replica = replica_of(engine);
+    snapshot.restore(&mut replica);
+    for [p1, p2] in inputs {
+        replica.tick(*p1, *p2);
+    }
+    let diffs = engine.save_snapshot().diff(&replica.save_snapshot());
+    match diffs.is_empty() {
+        true => Ok(()),
+        false => Err(Violation::Resimulation(diffs)),
+    }
+}
+
+/// Blank engine with the match settings snapshots don't carry
+fn replica_of(engine: &Engine) -> Engine {
+    let mut replica = Engine::with_config(engine.config);
+    replica.characters = engine.characters.clone();
+    replica.mutators = engine.mutators;
+    replica.start = engine.start;
+    replica
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzed_scenarios_keep_invariants() {
+        let config = FuzzConfig {
+            frames: 1200,
+            ..Default::default()
+        };
+        for seed in 0..8 {
+            assert_eq!(fuzz(seed, &config), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_scenarios_are_seeded() {
+        let play = |seed| {
+            let mut scenario = Scenario::new(seed);
+            let mut engine = scenario.engine();
+            for _ in 0..300 {
+                let [p1, p2] = scenario.inputs();
+                engine.tick(p1, p2);
+            }
+            engine.state_hash()
+        };
+        assert_eq!(play(7), play(7));
+        assert_ne!(play(7), play(8));
+    }
+
+    #[test]
+    fn test_violations_are_reported() {
+        let mut scenario = Scenario::new(1);
+        let mut engine = scenario.engine();
+        engine.entities[1].as_mut().unwrap().physics.position.x = 10_000_000;
+        assert!(matches!(
+            check_fighters(&engine, &FuzzConfig::default()),
+            Err(Violation::OutsideStage { player, .. }) if player == PlayerId::PLAYER_2
+        ));
+
+        // Round trips are checked against the live engine, not the snapshot
+        let snapshot = engine.save_snapshot();
+        assert_eq!(check_round_trip(&engine, &snapshot), Ok(()));
+        let [p1, p2] = scenario.inputs();
+        engine.tick(p1, p2);
+        assert!(matches!(
+            check_round_trip(&engine, &snapshot),
+            Err(Violation::SnapshotRoundTrip(_))
+        ));
+    }
+}
@@ -2,6 +2,7 @@
 //! Inspired by Castagne's attack/defense collision model
 
 use crate::constants::*;
+use crate::state::StateId;
 use crate::types::{EntityId, Rect, Vec2};
 
 /// Type of collision box
@@ -15,17 +16,117 @@ pub enum BoxType {
     Pushbox,
 }
 
+/// How an attack knocks the victim down on a clean hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnockdownType {
+    /// Standard hitstun, victim stays on their feet
+    None,
+    /// Victim is knocked down and may recover quickly
+    Soft,
+    /// Victim is knocked down for the full hard knockdown duration
+    Hard,
+    /// Trips a grounded victim into a hard knockdown without lifting them
+    Sweep,
+    /// Launches the victim into the air in hitstun
+    Launcher,
+}
+
+/// Side a collision box fights for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Team {
+    /// Hits every hurtbox, including its own side's (stage hazards); a box
+    /// still never hits the entity it belongs to
+    #[default]
+    Neutral,
+    /// A player's side: the fighter, its assists and its projectiles never
+    /// hit each other
+    Side(u8),
+}
+
+impl Team {
+    /// Whether boxes of this team can hit boxes of `other`
+    pub fn hostile_to(self, other: Team) -> bool {
+        match (self, other) {
+            (Team::Side(a), Team::Side(b)) => a != b,
+            _ => true,
+        }
+    }
+}
+
+/// Kinds of attack a fighter is invulnerable to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Invulnerability {
+    /// Strikes: any attack that isn't a throw, thrown by a fighter
+    pub strike: bool,
+    /// Throws and command grabs (unblockable hit-grabs)
+    pub throw: bool,
+    pub projectile: bool,
+}
+
+impl Invulnerability {
+    pub const NONE: Self = Self {
+        strike: false,
+        throw: false,
+        projectile: false,
+    };
+    pub const FULL: Self = Self {
+        strike: true,
+        throw: true,
+        projectile: true,
+    };
+    pub const STRIKE: Self = Self {
+        strike: true,
+        ..Self::NONE
+    };
+    pub const THROW: Self = Self {
+        throw: true,
+        ..Self::NONE
+    };
+    pub const PROJECTILE: Self = Self {
+        projectile: true,
+        ..Self::NONE
+    };
+
+    /// Invulnerable to everything; such fighters have no hurtboxes
+    pub fn is_full(&self) -> bool {
+        *self == Self::FULL
+    }
+
+    /// Whether an attack passes through
+    pub fn ignores(&self, attack: &AttackData, from_projectile: bool) -> bool {
+        if from_projectile {
+            self.projectile
+        } else if attack.is_throw() {
+            self.throw
+        } else {
+            self.strike
+        }
+    }
+}
+
 /// Attack properties for hitboxes
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct AttackData {
     pub damage: i32,
-    pub hitstun: u32,      // Frames of hitstun on hit
-    pub blockstun: u32,    // Frames of blockstun if blocked
-    pub pushback_x: i32,   // Horizontal knockback
-    pub pushback_y: i32,   // Vertical knockback (for launchers)
-    pub can_block: bool,   // Is this blockable?
-    pub is_overhead: bool, // Must block standing
-    pub is_low: bool,      // Must block crouching
+    pub hitstun: u32,                // Frames of hitstun on hit
+    pub blockstun: u32,              // Frames of blockstun if blocked
+    pub pushback_x: i32,             // Horizontal knockback
+    pub pushback_y: i32,             // Vertical knockback (for launchers)
+    pub block_pushback: i32,         // Horizontal push if blocked
+    pub can_block: bool,             // Is this blockable?
+    pub is_overhead: bool,           // Must block standing
+    pub is_low: bool,                // Must block crouching
+    pub knockdown: KnockdownType,    // Victim reaction on a clean hit
+    pub can_otg: bool,               // Can hit a knocked-down victim
+    pub hit_group: u8,               // Hitboxes in the same group connect once per activation
+    pub hitstop: Option<u32>, // Frames the fight freezes on contact (None = from hit feel tiers)
+    pub hit_cue: Option<u16>, // Hit sound/effect cue (None = from hit feel tiers)
+    pub grab_state: Option<StateId>, // On a clean hit, attacker enters this state holding the victim
+    pub proration: i32,              // Percent of damage later hits in the combo keep
+    pub team: Option<Team>,          // Overrides the owner's team (None = owner's)
+    pub techable: bool,              // Grab the victim can escape by inputting throw in time
+    pub is_super: bool,              // Super attack, for finish detection
+    pub dizzy: Option<i32>,          // Stun added to the dizzy meter on a clean hit (None = damage)
 }
 
 impl AttackData {
@@ -36,9 +137,21 @@ impl AttackData {
             blockstun: 8,
             pushback_x: 500,
             pushback_y: 0,
+            block_pushback: 250,
             can_block: true,
             is_overhead: false,
             is_low: false,
+            knockdown: KnockdownType::None,
+            can_otg: false,
+            hit_group: 0,
+            hitstop: None,
+            hit_cue: None,
+            grab_state: None,
+            proration: 100,
+            team: None,
+            techable: false,
+            is_super: false,
+            dizzy: None,
         }
     }
 
@@ -48,6 +161,12 @@ impl AttackData {
         self
     }
 
+    /// Set how far a blocking victim is pushed, independent of hit knockback
+    pub fn with_block_pushback(mut self, x: i32) -> Self {
+        self.block_pushback = x;
+        self
+    }
+
     pub fn with_stun(mut self, hitstun: u32, blockstun: u32) -> Self {
         self.hitstun = hitstun;
         self.blockstun = blockstun;
@@ -68,6 +187,97 @@ impl AttackData {
         self.is_low = true;
         self
     }
+
+    pub fn soft_knockdown(mut self) -> Self {
+        self.knockdown = KnockdownType::Soft;
+        self
+    }
+
+    pub fn hard_knockdown(mut self) -> Self {
+        self.knockdown = KnockdownType::Hard;
+        self
+    }
+
+    pub fn sweep(mut self) -> Self {
+        self.knockdown = KnockdownType::Sweep;
+        self
+    }
+
+    pub fn launcher(mut self) -> Self {
+        self.knockdown = KnockdownType::Launcher;
+        self
+    }
+
+    pub fn otg(mut self) -> Self {
+        self.can_otg = true;
+        self
+    }
+
+    /// Override the hitstop picked by the hit feel tiers
+    pub fn with_hitstop(mut self, frames: u32) -> Self {
+        self.hitstop = Some(frames);
+        self
+    }
+
+    /// Override the hit cue picked by the hit feel tiers
+    pub fn with_hit_cue(mut self, cue: u16) -> Self {
+        self.hit_cue = Some(cue);
+        self
+    }
+
+    /// Turn a clean hit into a grab: the attacker enters `state`, which then
+    /// drives the victim like a throw
+    pub fn hit_grab(mut self, state: StateId) -> Self {
+        self.grab_state = Some(state);
+        self
+    }
+
+    /// Let the grabbed victim tech: damage waits out `THROW_TECH_WINDOW`
+    /// and inputting throw before then escapes
+    pub fn techable(mut self) -> Self {
+        self.techable = true;
+        self
+    }
+
+    /// Whether this is a throw: an unblockable grab
+    pub fn is_throw(&self) -> bool {
+        self.grab_state.is_some() && !self.can_block
+    }
+
+    /// Mark as a super; a KO with it is a super finish
+    pub fn super_move(mut self) -> Self {
+        self.is_super = true;
+        self
+    }
+
+    /// Override the stun a clean hit adds to the dizzy meter
+    pub fn with_dizzy(mut self, stun: i32) -> Self {
+        self.dizzy = Some(stun);
+        self
+    }
+
+    /// Stun a clean hit adds to the dizzy meter
+    pub fn dizzy_stun(&self) -> i32 {
+        self.dizzy.unwrap_or(self.damage)
+    }
+
+    /// Reduce the damage of later hits in the combo (percent kept)
+    pub fn with_proration(mut self, percent: i32) -> Self {
+        self.proration = percent;
+        self
+    }
+
+    /// Hit as `team` instead of the owner's team
+    pub fn with_team(mut self, team: Team) -> Self {
+        self.team = Some(team);
+        self
+    }
+
+    /// Assign to a hit group (0-31); each group of a move can connect once
+    pub fn with_hit_group(mut self, group: u8) -> Self {
+        self.hit_group = group.min(31);
+        self
+    }
 }
 
 /// A collision box with properties
@@ -76,6 +286,7 @@ pub struct CollisionBox {
     pub box_type: BoxType,
     pub bounds: Rect,
     pub owner: EntityId,
+    pub team: Team,
     pub active: bool,
     pub attack_data: Option<AttackData>,
 }
@@ -86,6 +297,7 @@ impl CollisionBox {
             box_type: BoxType::Hitbox,
             bounds,
             owner,
+            team: Team::Neutral,
             active: true,
             attack_data: Some(attack_data),
         }
@@ -96,6 +308,7 @@ impl CollisionBox {
             box_type: BoxType::Hurtbox,
             bounds,
             owner,
+            team: Team::Neutral,
             active: true,
             attack_data: None,
         }
@@ -106,11 +319,24 @@ impl CollisionBox {
             box_type: BoxType::Pushbox,
             bounds,
             owner,
+            team: Team::Neutral,
             active: true,
             attack_data: None,
         }
     }
 
+    /// Fight for `team`
+    pub fn with_team(mut self, team: Team) -> Self {
+        self.team = team;
+        self
+    }
+
+    /// Whether this hitbox may hit `hurtbox`: never its owner or its own
+    /// team
+    pub fn can_hit(&self, hurtbox: &CollisionBox) -> bool {
+        self.owner != hurtbox.owner && self.team.hostile_to(hurtbox.team)
+    }
+
     /// Translate box by offset (for entity positioning)
     pub fn translate(&self, offset: Vec2) -> CollisionBox {
         let mut new_box = *self;
@@ -126,6 +352,8 @@ pub struct CollisionResult {
     pub attacker: EntityId,
     pub defender: EntityId,
     pub attack_data: AttackData,
+    /// Center of the hitbox/hurtbox overlap, for hitsparks and camera effects
+    pub contact_point: Vec2,
 }
 
 /// Collision detection system
@@ -152,15 +380,12 @@ impl CollisionSystem {
         }
     }
 
+    /// Forget this frame's boxes; only the slots in use are reset
     pub fn clear(&mut self) {
+        self.hitboxes[..self.hit_count].fill(None);
+        self.hurtboxes[..self.hurt_count].fill(None);
         self.hit_count = 0;
         self.hurt_count = 0;
-        for i in 0..MAX_HITBOXES {
-            self.hitboxes[i] = None;
-        }
-        for i in 0..MAX_HURTBOXES {
-            self.hurtboxes[i] = None;
-        }
     }
 
     pub fn add_hitbox(&mut self, hitbox: CollisionBox) {
@@ -177,6 +402,40 @@ impl CollisionSystem {
         }
     }
 
+    /// Number of active hitboxes registered this frame
+    pub fn active_hitbox_count(&self) -> usize {
+        self.hitboxes[..self.hit_count]
+            .iter()
+            .flatten()
+            .filter(|b| b.active)
+            .count()
+    }
+
+    /// Number of active hurtboxes registered this frame
+    pub fn active_hurtbox_count(&self) -> usize {
+        self.hurtboxes[..self.hurt_count]
+            .iter()
+            .flatten()
+            .filter(|b| b.active)
+            .count()
+    }
+
+    /// Number of hitbox/hurtbox overlap tests `check_collisions` performs
+    pub fn pair_test_count(&self) -> usize {
+        let mut tests = 0;
+        for hitbox in self.hitboxes[..self.hit_count].iter().flatten() {
+            if !hitbox.active {
+                continue;
+            }
+            for hurtbox in self.hurtboxes[..self.hurt_count].iter().flatten() {
+                if hurtbox.active && hitbox.can_hit(hurtbox) {
+                    tests += 1;
+                }
+            }
+        }
+        tests
+    }
+
     /// Check all hitbox vs hurtbox collisions
     /// Returns list of collision results
     pub fn check_collisions(&self) -> [Option<CollisionResult>; MAX_COLLISIONS_PER_FRAME] {
@@ -195,19 +454,20 @@ impl CollisionSystem {
                             continue;
                         }
 
-                        // Don't hit yourself
-                        if hitbox.owner == hurtbox.owner {
+                        // Don't hit yourself or your team
+                        if !hitbox.can_hit(hurtbox) {
                             continue;
                         }
 
                         // Check collision
-                        if hitbox.bounds.intersects(&hurtbox.bounds) {
+                        if let Some(overlap) = hitbox.bounds.intersection(&hurtbox.bounds) {
                             if let Some(attack_data) = hitbox.attack_data {
                                 if result_count < MAX_COLLISIONS_PER_FRAME {
                                     results[result_count] = Some(CollisionResult {
                                         attacker: hitbox.owner,
                                         defender: hurtbox.owner,
                                         attack_data,
+                                        contact_point: overlap.center(),
                                     });
                                     result_count += 1;
                                 }
@@ -234,6 +494,11 @@ mod tests {
         assert_eq!(attack.pushback_x, 1000);
         assert_eq!(attack.pushback_y, 500);
         assert!(!attack.can_block);
+        assert_eq!(attack.knockdown, KnockdownType::None);
+
+        let sweep = AttackData::new(80).low().sweep();
+        assert!(sweep.is_low);
+        assert_eq!(sweep.knockdown, KnockdownType::Sweep);
     }
 
     #[test]
@@ -259,6 +524,8 @@ mod tests {
         assert_eq!(collision.attacker, attacker_id);
         assert_eq!(collision.defender, defender_id);
         assert_eq!(collision.attack_data.damage, 100);
+        // Overlap is (15, 15)-(30, 30)
+        assert_eq!(collision.contact_point, Vec2::new(22, 22));
     }
 
     #[test]
@@ -277,4 +544,51 @@ mod tests {
         let results = system.check_collisions();
         assert!(results[0].is_none()); // No self-collision
     }
+
+    #[test]
+    fn test_team_filters_collisions() {
+        // An assist (entity 2) on player 1's side, over both fighters
+        let hits = |attack: AttackData| {
+            let mut system = CollisionSystem::new();
+            system.add_hitbox(
+                CollisionBox::hitbox(EntityId(2), Rect::new(0, 0, 50, 10), attack)
+                    .with_team(attack.team.unwrap_or(Team::Side(0))),
+            );
+            for (id, side) in [(0, 0), (1, 1)] {
+                let hurtbox =
+                    CollisionBox::hurtbox(EntityId(id), Rect::new(id as i32 * 30, 0, 10, 10));
+                system.add_hurtbox(hurtbox.with_team(Team::Side(side)));
+            }
+            system
+                .check_collisions()
+                .iter()
+                .flatten()
+                .map(|c| c.defender.0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(hits(AttackData::new(10)), vec![1]);
+        // A hazard hits both sides
+        assert_eq!(
+            hits(AttackData::new(10).with_team(Team::Neutral)),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_pair_test_count() {
+        let mut system = CollisionSystem::new();
+
+        system.add_hitbox(CollisionBox::hitbox(
+            EntityId(0),
+            Rect::new(0, 0, 10, 10),
+            AttackData::new(100),
+        ));
+        system.add_hurtbox(CollisionBox::hurtbox(EntityId(0), Rect::new(0, 0, 10, 10)));
+        system.add_hurtbox(CollisionBox::hurtbox(EntityId(1), Rect::new(50, 0, 10, 10)));
+
+        assert_eq!(system.active_hitbox_count(), 1);
+        assert_eq!(system.active_hurtbox_count(), 2);
+        assert_eq!(system.pair_test_count(), 1); // Self pair is skipped
+    }
 }
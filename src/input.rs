@@ -1,8 +1,9 @@
 //! Input system with motion detection for fighting games
 //! Supports directional inputs, buttons, and special move motions
 
+use crate::checksum::StateHasher;
 use crate::constants::*;
-use crate::types::Facing;
+use crate::types::{Facing, Frame};
 
 /// Button inputs
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -102,7 +103,7 @@ pub enum MotionInput {
 }
 
 /// Input state for a single frame
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct InputState {
     pub direction: Direction,
     pub light: bool,
@@ -130,14 +131,53 @@ impl InputState {
             Button::Special => self.special,
         }
     }
+
+    /// Check if any button is held this frame
+    pub fn any_button(&self) -> bool {
+        self.light || self.medium || self.heavy || self.special
+    }
+
+    /// Pack into one byte: numpad direction in bits 0-3, buttons in bits 4-7
+    pub fn to_bits(&self) -> u8 {
+        self.direction as u8
+            | (self.light as u8) << 4
+            | (self.medium as u8) << 5
+            | (self.heavy as u8) << 6
+            | (self.special as u8) << 7
+    }
+
+    /// Unpack from `to_bits` layout (unknown directions read as neutral)
+    pub fn from_bits(bits: u8) -> Self {
+        let direction = match bits & 0xF {
+            1 => Direction::DownBack,
+            2 => Direction::Down,
+            3 => Direction::DownForward,
+            4 => Direction::Back,
+            6 => Direction::Forward,
+            7 => Direction::UpBack,
+            8 => Direction::Up,
+            9 => Direction::UpForward,
+            _ => Direction::Neutral,
+        };
+        Self {
+            direction,
+            light: bits & 0x10 != 0,
+            medium: bits & 0x20 != 0,
+            heavy: bits & 0x40 != 0,
+            special: bits & 0x80 != 0,
+        }
+    }
 }
 
 /// Input buffer for motion detection
 /// Keeps last INPUT_BUFFER_SIZE frames (0.5 seconds at 60fps)
+#[derive(Clone, PartialEq, Eq)]
 pub struct InputBuffer {
     buffer: [InputState; INPUT_BUFFER_SIZE],
     write_index: usize,
     facing: Facing,
+    action_buffer: u32,
+    back_held: u32, // Consecutive frames back has been held, up to now
 }
 
 impl InputBuffer {
@@ -146,13 +186,34 @@ impl InputBuffer {
             buffer: [InputState::neutral(); INPUT_BUFFER_SIZE],
             write_index: 0,
             facing,
+            action_buffer: ACTION_BUFFER_FRAMES,
+            back_held: 0,
         }
     }
 
+    /// Frames attack presses stay buffered while the fighter can't act
+    pub fn action_buffer(&self) -> u32 {
+        self.action_buffer
+    }
+
+    pub fn set_action_buffer(&mut self, frames: u32) {
+        self.action_buffer = frames;
+    }
+
     /// Push new input state to buffer
     pub fn push(&mut self, input: InputState) {
         self.buffer[self.write_index] = input;
         self.write_index = (self.write_index + 1) % INPUT_BUFFER_SIZE;
+        self.back_held = match input.direction.is_back() {
+            true => self.back_held.saturating_add(1),
+            false => 0,
+        };
+    }
+
+    /// Frames back (including down-back and up-back) has been held without
+    /// letting go, counting the current one; 0 if not held
+    pub fn back_held_frames(&self) -> u32 {
+        self.back_held
     }
 
     /// Get most recent input
@@ -178,6 +239,56 @@ impl InputBuffer {
         current.button_pressed(button) && !previous.button_pressed(button)
     }
 
+    /// Check if button was pressed on any of the last `frames` frames
+    /// (1 = just pressed)
+    pub fn button_pressed_within(&self, button: Button, frames: u32) -> bool {
+        let frames = (frames as usize).min(INPUT_BUFFER_SIZE - 1);
+        (0..frames).any(|age| {
+            self.at_age(age).button_pressed(button) && !self.at_age(age + 1).button_pressed(button)
+        })
+    }
+
+    /// Every buffered input, newest first
+    pub fn history(&self) -> impl Iterator<Item = InputState> + '_ {
+        (0..INPUT_BUFFER_SIZE).map(|age| self.at_age(age))
+    }
+
+    /// Input from `age` frames ago (0 = current)
+    fn at_age(&self, age: usize) -> InputState {
+        self.buffer[(self.write_index + INPUT_BUFFER_SIZE * 2 - 1 - age) % INPUT_BUFFER_SIZE]
+    }
+
+    /// Check if button was just released
+    pub fn button_just_released(&self, button: Button) -> bool {
+        let current = self.current();
+        let prev_index = if self.write_index < 2 {
+            INPUT_BUFFER_SIZE - 2 + self.write_index
+        } else {
+            self.write_index - 2
+        };
+        let previous = self.buffer[prev_index];
+
+        !current.button_pressed(button) && previous.button_pressed(button)
+    }
+
+    /// Count buttons newly pressed this frame (for mash mechanics)
+    pub fn buttons_just_pressed(&self) -> u32 {
+        [
+            Button::Light,
+            Button::Medium,
+            Button::Heavy,
+            Button::Special,
+        ]
+        .iter()
+        .filter(|b| self.button_just_pressed(**b))
+        .count() as u32
+    }
+
+    /// Detect a throw input (Light + Medium pressed together)
+    pub fn detect_throw(&self) -> bool {
+        self.button_just_pressed(Button::Light) && self.button_just_pressed(Button::Medium)
+    }
+
     /// Detect quarter circle forward motion (236)
     pub fn detect_qcf(&self) -> bool {
         self.detect_sequence(&[Direction::Down, Direction::DownForward, Direction::Forward])
@@ -233,9 +344,75 @@ impl InputBuffer {
     }
 }
 
+/// Fixed delay line holding a player's inputs back a few frames
+///
+/// Used to even out known device latency: the player on the faster device
+/// gets the difference added. Empty slots read as neutral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputDelay {
+    pending: [InputState; MAX_INPUT_DELAY],
+    frames: usize,
+    index: usize,
+}
+
+impl Default for InputDelay {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl InputDelay {
+    /// Delay of `frames` frames, clamped to `MAX_INPUT_DELAY`
+    pub fn new(frames: u32) -> Self {
+        Self {
+            pending: [InputState::neutral(); MAX_INPUT_DELAY],
+            frames: (frames as usize).min(MAX_INPUT_DELAY),
+            index: 0,
+        }
+    }
+
+    /// Frames of delay applied
+    pub fn frames(&self) -> u32 {
+        self.frames as u32
+    }
+
+    /// Queue an input and take the one from `frames` frames ago
+    pub fn apply(&mut self, input: InputState) -> InputState {
+        if self.frames == 0 {
+            return input;
+        }
+        let delayed = core::mem::replace(&mut self.pending[self.index], input);
+        self.index = (self.index + 1) % self.frames;
+        delayed
+    }
+}
+
+/// Outcome of feeding a frame-tagged input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFeed {
+    /// Input was for the expected next frame
+    Accepted,
+    /// Frames were skipped; the last known input was repeated to fill them
+    Gap { missing: u64 },
+}
+
+/// Reasons a frame-tagged input is rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputError {
+    /// Player index out of range
+    InvalidPlayer(usize),
+    /// An input for this frame was already received
+    Duplicate { frame: Frame },
+    /// Input is older than the last received frame
+    Late { frame: Frame, last: Frame },
+}
+
 /// Input manager for multiple players
+#[derive(Clone, PartialEq, Eq)]
 pub struct InputManager {
     pub player_inputs: [InputBuffer; MAX_PLAYERS],
+    last_frames: [Option<Frame>; MAX_PLAYERS],
+    delays: [InputDelay; MAX_PLAYERS],
 }
 
 impl Default for InputManager {
@@ -251,15 +428,86 @@ impl InputManager {
                 InputBuffer::new(Facing::Right),
                 InputBuffer::new(Facing::Left),
             ],
+            last_frames: [None; MAX_PLAYERS],
+            delays: [InputDelay::default(); MAX_PLAYERS],
         }
     }
 
-    pub fn update_player_input(&mut self, player: usize, input: InputState) {
-        if player < MAX_PLAYERS {
-            self.player_inputs[player].push(input);
+    /// Buffer attack presses for `frames` frames for every player
+    pub fn set_action_buffer(&mut self, frames: u32) {
+        for buffer in &mut self.player_inputs {
+            buffer.set_action_buffer(frames);
+        }
+    }
+
+    /// Hold a player's inputs back by `frames` frames from now on
+    pub fn set_latency_offset(&mut self, player: usize, frames: u32) {
+        if let Some(delay) = self.delays.get_mut(player) {
+            *delay = InputDelay::new(frames);
+        }
+    }
+
+    /// Latency offset applied to a player's inputs
+    pub fn latency_offset(&self, player: usize) -> u32 {
+        self.delays.get(player).map_or(0, InputDelay::frames)
+    }
+
+    /// Apply a player's latency offset to a raw device input
+    ///
+    /// Runs before `update_player_input`, so everything downstream (the
+    /// buffer, replays, rollback) only ever sees compensated inputs.
+    pub fn compensate(&mut self, player: usize, input: InputState) -> InputState {
+        match self.delays.get_mut(player) {
+            Some(delay) => delay.apply(input),
+            None => input,
         }
     }
 
+    /// Push an input produced for `frame`
+    ///
+    /// Frames must be strictly increasing per player. Duplicate and late
+    /// inputs are rejected without touching the buffer; skipped frames are
+    /// filled by repeating the last input so motion timing stays intact.
+    pub fn update_player_input(
+        &mut self,
+        player: usize,
+        frame: Frame,
+        input: InputState,
+    ) -> Result<InputFeed, InputError> {
+        if player >= MAX_PLAYERS {
+            return Err(InputError::InvalidPlayer(player));
+        }
+
+        let mut feed = InputFeed::Accepted;
+        if let Some(last) = self.last_frames[player] {
+            if frame == last {
+                return Err(InputError::Duplicate { frame });
+            }
+            if frame < last {
+                return Err(InputError::Late { frame, last });
+            }
+
+            let missing = frame.0 - last.0 - 1;
+            if missing > 0 {
+                let buffer = &mut self.player_inputs[player];
+                let held = buffer.current();
+                for _ in 0..missing.min(INPUT_BUFFER_SIZE as u64) {
+                    buffer.push(held);
+                }
+                feed = InputFeed::Gap { missing };
+            }
+        }
+
+        self.player_inputs[player].push(input);
+        self.last_frames[player] = Some(frame);
+        Ok(feed)
+    }
+
+    /// Frame of the last accepted input for a player
+    pub fn last_frame(&self, player: usize) -> Option<Frame> {
+        self.last_frames.get(player).copied().flatten()
+    }
+
     pub fn get_player_input(&self, player: usize) -> Option<&InputBuffer> {
         if player < MAX_PLAYERS {
             Some(&self.player_inputs[player])
@@ -267,6 +515,34 @@ impl InputManager {
             None
         }
     }
+
+    /// Feed buffered, delayed and last received inputs to a state hash
+    pub(crate) fn hash_state(&self, h: &mut StateHasher) {
+        for buffer in &self.player_inputs {
+            for input in buffer.history() {
+                h.write_u8(input.to_bits());
+            }
+            h.write_i32(buffer.facing.sign());
+            h.write_u32(buffer.action_buffer);
+            h.write_u32(buffer.back_held);
+        }
+        for delay in &self.delays {
+            for input in delay.pending {
+                h.write_u8(input.to_bits());
+            }
+            h.write_u32(delay.frames as u32);
+            h.write_u32(delay.index as u32);
+        }
+        for last in self.last_frames {
+            match last {
+                Some(frame) => {
+                    h.write_bool(true);
+                    h.write_u64(frame.0);
+                }
+                None => h.write_bool(false),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -301,6 +577,68 @@ mod tests {
         assert!(!buffer.button_just_pressed(Button::Light)); // Not "just" pressed
     }
 
+    #[test]
+    fn test_back_held_frames() {
+        let mut buffer = InputBuffer::new(Facing::Right);
+        let holding = |direction| InputState {
+            direction,
+            ..InputState::neutral()
+        };
+        assert_eq!(buffer.back_held_frames(), 0);
+        buffer.push(holding(Direction::Back));
+        buffer.push(holding(Direction::DownBack));
+        assert_eq!(buffer.back_held_frames(), 2);
+        buffer.push(holding(Direction::Down));
+        assert_eq!(buffer.back_held_frames(), 0);
+    }
+
+    #[test]
+    fn test_button_just_released() {
+        let mut buffer = InputBuffer::new(Facing::Right);
+        let mut held = InputState::neutral();
+        held.heavy = true;
+
+        buffer.push(held);
+        buffer.push(held);
+        assert!(!buffer.button_just_released(Button::Heavy));
+
+        buffer.push(InputState::neutral());
+        assert!(buffer.button_just_released(Button::Heavy));
+
+        buffer.push(InputState::neutral());
+        assert!(!buffer.button_just_released(Button::Heavy));
+    }
+
+    #[test]
+    fn test_button_pressed_within() {
+        let mut buffer = InputBuffer::new(Facing::Right);
+        let mut input = InputState::neutral();
+        input.medium = true;
+        buffer.push(input);
+        assert!(buffer.button_pressed_within(Button::Medium, 1));
+
+        buffer.push(input);
+        buffer.push(InputState::neutral());
+        assert!(!buffer.button_pressed_within(Button::Medium, 2));
+        assert!(buffer.button_pressed_within(Button::Medium, 3));
+        assert!(!buffer.button_pressed_within(Button::Light, 3));
+    }
+
+    #[test]
+    fn test_buttons_just_pressed_count() {
+        let mut buffer = InputBuffer::new(Facing::Right);
+        buffer.push(InputState::neutral());
+
+        let mut input = InputState::neutral();
+        input.light = true;
+        input.heavy = true;
+        buffer.push(input);
+        assert_eq!(buffer.buttons_just_pressed(), 2);
+
+        buffer.push(input);
+        assert_eq!(buffer.buttons_just_pressed(), 0);
+    }
+
     #[test]
     fn test_qcf_detection() {
         let mut buffer = InputBuffer::new(Facing::Right);
@@ -343,4 +681,88 @@ mod tests {
 
         assert!(buffer.detect_dp());
     }
+
+    #[test]
+    fn test_input_frames_must_increase() {
+        let mut manager = InputManager::new();
+        assert_eq!(
+            manager.update_player_input(0, Frame(0), InputState::neutral()),
+            Ok(InputFeed::Accepted)
+        );
+        assert_eq!(
+            manager.update_player_input(0, Frame(1), InputState::neutral()),
+            Ok(InputFeed::Accepted)
+        );
+        assert_eq!(
+            manager.update_player_input(0, Frame(1), InputState::neutral()),
+            Err(InputError::Duplicate { frame: Frame(1) })
+        );
+        assert_eq!(
+            manager.update_player_input(0, Frame(0), InputState::neutral()),
+            Err(InputError::Late {
+                frame: Frame(0),
+                last: Frame(1)
+            })
+        );
+        assert_eq!(
+            manager.update_player_input(2, Frame(0), InputState::neutral()),
+            Err(InputError::InvalidPlayer(2))
+        );
+        assert_eq!(manager.last_frame(0), Some(Frame(1)));
+        assert_eq!(manager.last_frame(1), None);
+    }
+
+    #[test]
+    fn test_input_gap_repeats_last_input() {
+        let mut manager = InputManager::new();
+        let down = InputState {
+            direction: Direction::Down,
+            ..InputState::neutral()
+        };
+        let forward = InputState {
+            direction: Direction::Forward,
+            ..InputState::neutral()
+        };
+        manager.update_player_input(0, Frame(0), down).unwrap();
+        let feed = manager.update_player_input(0, Frame(3), forward);
+        assert_eq!(feed, Ok(InputFeed::Gap { missing: 2 }));
+
+        let buffer = manager.get_player_input(0).unwrap();
+        assert_eq!(buffer.current(), forward);
+        // Filled frames hold the last known direction
+        assert!(buffer.detect_sequence(&[Direction::Down, Direction::Down, Direction::Down]));
+    }
+
+    #[test]
+    fn test_latency_offset_delays_inputs() {
+        let mut manager = InputManager::new();
+        manager.set_latency_offset(1, 2);
+        let light = InputState {
+            light: true,
+            ..InputState::neutral()
+        };
+        assert_eq!(manager.compensate(0, light), light);
+        assert_eq!(manager.compensate(1, light), InputState::neutral());
+        assert_eq!(
+            manager.compensate(1, InputState::neutral()),
+            InputState::neutral()
+        );
+        assert_eq!(manager.compensate(1, InputState::neutral()), light);
+
+        manager.set_latency_offset(0, 99);
+        assert_eq!(manager.latency_offset(0), MAX_INPUT_DELAY as u32);
+    }
+
+    #[test]
+    fn test_input_bits_round_trip() {
+        let input = InputState {
+            direction: Direction::DownForward,
+            heavy: true,
+            special: true,
+            ..InputState::neutral()
+        };
+        assert_eq!(input.to_bits(), 0xC3);
+        assert_eq!(InputState::from_bits(input.to_bits()), input);
+        assert_eq!(InputState::from_bits(0), InputState::neutral());
+    }
 }
@@ -0,0 +1,243 @@
+//! Game framework integration adapter (`integration` feature)
+//!
+//! Engines like Bevy or Godot want the simulation as a single resource that
+//! a system advances from the frame clock, with render data mirrored into
+//! per-fighter components. `MatchRunner` is that resource: it turns variable
+//! frame times into fixed 60 Hz ticks and exposes `FighterView` and
+//! `MatchView` values that map one-to-one onto components, plus drained
+//! hit and cue events for effects.
+//!
+//! A Bevy plugin is then a thin wrapper: insert a `MatchRunner` resource,
+//! set inputs from the input system, call `advance` with the frame delta,
+//! copy `fighter_views()` into components, and forward `drain_events()`.
+//! Keeping the adapter framework-agnostic keeps the core dependency-free.
+
+use crate::constants::*;
+use crate::engine::{Engine, GameResult};
+use crate::events::{ClashEvent, CueEvent, HitEvent};
+use crate::input::InputState;
+use crate::setup::MatchSetup;
+use crate::state::{AnimationFrame, StateId};
+use crate::types::{Facing, PlayerId};
+
+/// Render data for one fighter, in display units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FighterView {
+    pub player: PlayerId,
+    pub x: f32,
+    pub y: f32,
+    /// Hit shake and recoil to add when drawing; not part of `x`/`y`
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub facing: Facing,
+    pub state: StateId,
+    pub state_frame: u32,
+    /// Clip and sprite from the state's timeline, if it declares one
+    pub animation: Option<AnimationFrame>,
+    pub health: i32,
+    pub max_health: i32,
+    pub meter: i32,
+}
+
+/// Match-wide render data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchView {
+    pub frame: u64,
+    pub result: GameResult,
+    pub round: u32,
+    pub round_wins: [u32; MAX_PLAYERS],
+    pub time_remaining: Option<u64>,
+    pub hitstop: u32,
+}
+
+/// Events produced since the last drain
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchEvents {
+    pub hits: Vec<HitEvent>,
+    pub cues: Vec<CueEvent>,
+    pub clashes: Vec<ClashEvent>,
+}
+
+/// Engine wrapped as a fixed-timestep resource
+pub struct MatchRunner {
+    engine: Engine,
+    inputs: [InputState; MAX_PLAYERS],
+    accumulator_micros: u64,
+    pending: MatchEvents,
+}
+
+impl MatchRunner {
+    /// Runner for a standard match
+    pub fn new() -> Self {
+        Self::with_engine(Engine::new(), MatchSetup::default())
+    }
+
+    /// Runner for a configured engine and match setup
+    pub fn with_engine(mut engine: Engine, setup: MatchSetup) -> Self {
+        engine.init_match_with(setup);
+        Self {
+            engine,
+            inputs: [InputState::neutral(); MAX_PLAYERS],
+            accumulator_micros: 0,
+            pending: MatchEvents::default(),
+        }
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Set the input a player holds for the following ticks
+    pub fn set_input(&mut self, player: PlayerId, input: InputState) {
+        if let Some(slot) = self.inputs.get_mut(player.0 as usize) {
+            *slot = input;
+        }
+    }
+
+    /// Advance by elapsed real time; returns the number of ticks run
+    ///
+    /// Leftover time carries over, so ticks stay at a steady 60 Hz whatever
+    /// the render rate; a turbo match runs `Engine::ticks_per_frame` ticks
+    /// each period. At most `MAX_TICKS_PER_ADVANCE` periods run per call so
+    /// a long stall doesn't freeze the frame.
+    pub fn advance(&mut self, elapsed_micros: u64) -> u32 {
+        self.accumulator_micros += elapsed_micros;
+        let speed = self.engine.ticks_per_frame();
+        let mut periods = 0;
+        while self.accumulator_micros >= MICROS_PER_TICK && periods < MAX_TICKS_PER_ADVANCE {
+            self.accumulator_micros -= MICROS_PER_TICK;
+            for _ in 0..speed {
+                self.step();
+            }
+            periods += 1;
+        }
+        if periods == MAX_TICKS_PER_ADVANCE {
+            self.accumulator_micros = self.accumulator_micros.min(MICROS_PER_TICK);
+        }
+        periods * speed
+    }
+
+    /// Run exactly one tick with the current inputs
+    pub fn step(&mut self) {
+        let [p1, p2] = self.inputs;
+        self.engine.tick(p1, p2);
+        self.pending.hits.extend_from_slice(self.engine.events());
+        self.pending.cues.extend_from_slice(self.engine.cues());
+        self.pending
+            .clashes
+            .extend_from_slice(self.engine.clashes());
+    }
+
+    /// Take every hit and cue since the last drain
+    pub fn drain_events(&mut self) -> MatchEvents {
+        core::mem::take(&mut self.pending)
+    }
+
+    /// Render data for both fighters
+    pub fn fighter_views(&self) -> Vec<FighterView> {
+        [PlayerId::PLAYER_1, PlayerId::PLAYER_2]
+            .into_iter()
+            .filter_map(|player| {
+                let e = self.engine.get_player_entity(player)?;
+                let offset = e.presentation.offset();
+                Some(FighterView {
+                    player,
+                    x: e.physics.position.x as f32 / INTERNAL_TO_DISPLAY as f32,
+                    y: e.physics.position.y as f32 / INTERNAL_TO_DISPLAY as f32,
+                    offset_x: offset.x as f32 / INTERNAL_TO_DISPLAY as f32,
+                    offset_y: offset.y as f32 / INTERNAL_TO_DISPLAY as f32,
+                    facing: e.facing,
+                    state: e.state_machine.current_state(),
+                    state_frame: e.state_machine.state_frame(),
+                    animation: e.state_machine.animation_frame(),
+                    health: e.health.current,
+                    max_health: e.health.maximum,
+                    meter: e.meter,
+                })
+            })
+            .collect()
+    }
+
+    /// Match-wide render data
+    pub fn match_view(&self) -> MatchView {
+        MatchView {
+            frame: self.engine.frame.0,
+            result: self.engine.game_result,
+            round: self.engine.round,
+            round_wins: self.engine.round_wins,
+            time_remaining: self.engine.time_remaining(),
+            hitstop: self.engine.hitstop_remaining(),
+        }
+    }
+}
+
+impl Default for MatchRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::HitOutcome;
+    use crate::setup::Mutators;
+
+    #[test]
+    fn test_advance_runs_fixed_ticks() {
+        let mut runner = MatchRunner::new();
+        // Two 144 Hz render frames aren't enough for a tick, the third is
+        assert_eq!(runner.advance(6944), 0);
+        assert_eq!(runner.advance(6944), 0);
+        assert_eq!(runner.advance(6944), 1);
+        assert_eq!(runner.match_view().frame, 1);
+
+        // A long stall is capped
+        assert_eq!(runner.advance(1_000_000), MAX_TICKS_PER_ADVANCE);
+    }
+
+    #[test]
+    fn test_turbo_runs_extra_ticks_per_period() {
+        let mut runner = MatchRunner::with_engine(
+            Engine::new(),
+            MatchSetup {
+                mutators: Mutators {
+                    speed: 2,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        assert_eq!(runner.advance(MICROS_PER_TICK), 2);
+        assert_eq!(runner.match_view().frame, 2);
+    }
+
+    #[test]
+    fn test_views_and_events_mirror_engine() {
+        let mut runner = MatchRunner::with_engine(
+            Engine::new(),
+            MatchSetup {
+                distance: Some(20000),
+                ..Default::default()
+            },
+        );
+        let views = runner.fighter_views();
+        assert_eq!(views.len(), 2);
+        assert_eq!(views[0].x, -10.0);
+        assert_eq!(views[1].facing, Facing::Left);
+
+        let light = InputState {
+            light: true,
+            ..InputState::neutral()
+        };
+        runner.set_input(PlayerId::PLAYER_1, light);
+        for _ in 0..10 {
+            runner.step();
+        }
+
+        let events = runner.drain_events();
+        assert_eq!(events.hits.len(), 1);
+        assert_eq!(events.hits[0].outcome, HitOutcome::Hit);
+        assert!(runner.drain_events().hits.is_empty());
+    }
+}
@@ -47,24 +47,77 @@
 //! }
 //! ```
 
+pub mod attract;
+pub mod characters;
+pub mod chardef;
+pub mod checksum;
 pub mod config;
 pub mod constants;
+pub mod damage;
 pub mod engine;
 pub mod entity;
+pub mod events;
+pub mod ffi;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
 pub mod hitbox;
 pub mod input;
+#[cfg(feature = "integration")]
+pub mod integration;
+pub mod limits;
+pub mod link;
+pub mod metrics;
+pub mod ordering;
+pub mod presentation;
+pub mod projectile;
+pub mod range;
+pub mod replay;
+pub mod rewind;
+pub mod rollback;
+pub mod setup;
+pub mod snapshot;
 pub mod state;
+pub mod training;
+pub mod tuning;
 pub mod types;
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
 
 // Re-export main types for convenience
-pub use config::{EngineConfig, GameConfig, InputConfig, PhysicsConfig};
+pub use attract::AttractMode;
+pub use characters::CharacterDef;
+pub use chardef::{parse_character, write_state, CharacterError, CharacterErrorKind};
+pub use checksum::StateHasher;
+pub use config::{
+    AntiAirConfig, AntiAirPriority, BehindBlockPolicy, CounterHitConfig, DamageConfig, DizzyConfig,
+    DoubleKoPolicy, EngineConfig, GameConfig, GuardConfig, GutsTier, HitFeelConfig, HitFeelTier,
+    InputConfig, JustDefendConfig, PhysicsConfig, RoundBonus, RoundPersistence, StageConfig,
+    StageEdge, StagePreset,
+};
 pub use engine::{Engine, GameResult, GameState};
-pub use input::{Button, Direction, InputState};
-pub use state::StateId;
-pub use types::{EntityId, Facing, PlayerId, Vec2};
+pub use entity::LifebarSegments;
+pub use events::{
+    ClashEvent, Cue, CueEvent, FinishEvent, FinishKind, GameEvent, HitEvent, HitOutcome,
+};
+pub use hitbox::Invulnerability;
+pub use input::{Button, Direction, InputError, InputFeed, InputState};
+pub use limits::Limits;
+pub use metrics::EngineMetrics;
+pub use presentation::Presentation;
+pub use projectile::{Motion, Projectile, ProjectileDef, Residual};
+pub use replay::{Replay, ReplayError, ReplayPlayer};
+pub use rewind::RewindHistory;
+pub use rollback::{InputRecord, PredictionPolicy, RollbackError, RollbackSession, RollbackStats};
+pub use setup::{MatchSetup, Mutators, StartingConditions};
+pub use snapshot::{EngineSnapshot, SnapshotError};
+pub use state::{
+    Animation, AnimationFrame, Body, CancelRoute, Footing, FrameDataModifier, GuardKind, Install,
+    LimitedResource, ProjectileGuard, ResourceScope, StateId, UseLimit,
+};
+pub use training::{Blockstring, TrainingInfo};
+pub use tuning::{TuningEntry, Tweak};
+pub use types::{EntityId, Facing, Fixed, PlayerId, Vec2};
 
 #[cfg(test)]
 mod integration_tests {
@@ -0,0 +1,78 @@
+//! Build-time limits, readable at runtime
+//!
+//! Tools and bindings size their buffers and convert units from `Limits`
+//! instead of copying constants, so they keep working with an engine built
+//! with different limits. The C ABI exposes the same values through
+//! `bagarre_get_limits`, and the wasm build through `get_limit`.
+
+use crate::constants::*;
+
+/// Fixed capacities, buffer sizes and unit scales of this build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_players: usize,
+    /// Entity slots, fighters included
+    pub max_entities: usize,
+    pub max_projectiles: usize,
+    /// Hitboxes per entity
+    pub max_hitboxes: usize,
+    /// Hurtboxes per entity
+    pub max_hurtboxes: usize,
+    pub max_collisions_per_frame: usize,
+    /// States per character
+    pub max_states: usize,
+    /// Limited resources per character
+    pub max_resources: usize,
+    /// Frames of input history kept per player
+    pub input_buffer_size: usize,
+    /// Frames a motion input may take
+    pub motion_detection_window: usize,
+    /// Largest per-player latency offset, in frames
+    pub max_input_delay: usize,
+    /// Frames a rollback session can correct
+    pub rollback_window: usize,
+    /// Frames the engine can keep for rewinding
+    pub max_rewind_frames: usize,
+    /// Internal units per display unit (pixel)
+    pub units_per_pixel: i32,
+    /// Largest coordinate magnitude, in internal units
+    pub max_coordinate: i32,
+    /// Simulation ticks per second
+    pub ticks_per_second: u32,
+    /// See `ENGINE_DATA_VERSION`
+    pub engine_data_version: u16,
+    /// See `REPLAY_FORMAT_VERSION`
+    pub replay_format_version: u16,
+}
+
+impl Limits {
+    /// Limits of this build
+    pub const fn current() -> Self {
+        Self {
+            max_players: MAX_PLAYERS,
+            max_entities: MAX_ENTITIES,
+            max_projectiles: MAX_PROJECTILES,
+            max_hitboxes: MAX_HITBOXES,
+            max_hurtboxes: MAX_HURTBOXES,
+            max_collisions_per_frame: MAX_COLLISIONS_PER_FRAME,
+            max_states: MAX_STATES,
+            max_resources: MAX_RESOURCES,
+            input_buffer_size: INPUT_BUFFER_SIZE,
+            motion_detection_window: MOTION_DETECTION_WINDOW,
+            max_input_delay: MAX_INPUT_DELAY,
+            rollback_window: ROLLBACK_WINDOW,
+            max_rewind_frames: MAX_REWIND_FRAMES,
+            units_per_pixel: INTERNAL_TO_DISPLAY,
+            max_coordinate: MAX_COORDINATE,
+            ticks_per_second: (1_000_000 / MICROS_PER_TICK) as u32,
+            engine_data_version: ENGINE_DATA_VERSION,
+            replay_format_version: REPLAY_FORMAT_VERSION,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::current()
+    }
+}
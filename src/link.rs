@@ -0,0 +1,44 @@
+//! Paired-entity links for throws and cinematics
+//!
+//! While linked, the attacker's current state drives the victim: the victim
+//! skips its own update and is placed by `MoveVictim` actions until the
+//! attacker releases it or leaves the state that started the link.
+//!
+//! Cinematic supers use the same timeline: `VictimState` picks what the
+//! victim shows and `DamageVictim` deals guaranteed damage at authored
+//! frames. Neither fighter collides with anything while linked. Everything
+//! a link needs lives in the link and the two entities' states, so a
+//! rollback that lands mid-cinematic resumes it, and one that lands outside
+//! it drops the link and lets the victim go.
+//!
+//! Techable throws hold their damage for a short window first; the victim
+//! inputting throw inside it breaks the link and pushes both fighters apart.
+
+use crate::state::StateId;
+use crate::types::{EntityId, Vec2};
+
+/// An attacker holding a victim
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairLink {
+    pub attacker: EntityId,
+    pub victim: EntityId,
+    /// Attacker state that owns the link; leaving it releases the victim
+    pub attacker_state: StateId,
+    /// State the timeline holds the victim in (`Held` unless changed)
+    pub victim_state: StateId,
+    /// Pending throw the victim can still tech
+    pub tech: Option<TechWindow>,
+    /// Whether the attack that started the link was a super, so damage the
+    /// timeline deals counts as super damage
+    pub is_super: bool,
+}
+
+/// Throw damage held back while the victim can still tech
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TechWindow {
+    /// Frames left to input the tech
+    pub frames: u32,
+    pub damage: i32,
+    pub cue: Option<u16>,
+    pub contact_point: Vec2,
+}
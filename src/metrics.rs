@@ -0,0 +1,53 @@
+//! Per-tick diagnostics for profiling the engine
+//!
+//! Counters are reset at the start of every tick and filled in by each phase,
+//! so integrators can see where the frame budget goes without a profiler.
+
+/// Diagnostics collected during the most recent tick
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EngineMetrics {
+    /// Frame the metrics were collected on
+    pub frame: u64,
+    /// Number of entities updated in the action phase
+    pub entities_updated: u32,
+    /// Active hitboxes submitted to the collision system
+    pub active_hitboxes: u32,
+    /// Active hurtboxes submitted to the collision system
+    pub active_hurtboxes: u32,
+    /// Hitbox/hurtbox pairs tested for overlap
+    pub collision_pair_tests: u32,
+    /// Inputs rejected as duplicate or late
+    pub rejected_inputs: u32,
+    /// Collisions resolved in the reaction phase
+    pub events_emitted: u32,
+    /// Size in bytes of an `EngineSnapshot`. Snapshots are fixed-size (state
+    /// definitions are shared, not copied), so this only changes between
+    /// builds
+    pub snapshot_struct_bytes: u32,
+}
+
+impl EngineMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total active boxes (hitboxes + hurtboxes)
+    pub fn active_boxes(&self) -> u32 {
+        self.active_hitboxes + self.active_hurtboxes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_active_boxes() {
+        let metrics = EngineMetrics {
+            active_hitboxes: 2,
+            active_hurtboxes: 3,
+            ..EngineMetrics::new()
+        };
+        assert_eq!(metrics.active_boxes(), 5);
+    }
+}
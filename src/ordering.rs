@@ -0,0 +1,99 @@
+//! Deterministic ordering of same-frame interactions
+//!
+//! Anything that happens "simultaneously" within a tick is processed in a
+//! fixed order so that refactors of gathering code (entity storage, box
+//! registration) cannot change match outcomes:
+//!
+//! 1. Entities update in slot order, so Player 1 acts before Player 2.
+//! 2. Collisions resolve by attacker entity id, then hit group, then
+//!    defender entity id. Hitboxes sharing that key keep the order they are
+//!    declared in the attacker's frame data.
+//! 3. Before anything resolves, a jump-in and an anti-air hitting each
+//!    other near the ground are settled by `AntiAirConfig`, which may drop
+//!    the losing side's collisions.
+//! 4. Hit events are emitted in resolution order; trade classification runs
+//!    afterwards over the complete list and never reorders it.
+
+use crate::hitbox::CollisionResult;
+
+/// Sort key for a collision; lower keys resolve first
+pub fn collision_order_key(collision: &CollisionResult) -> (u32, u8, u32) {
+    (
+        collision.attacker.0,
+        collision.attack_data.hit_group,
+        collision.defender.0,
+    )
+}
+
+/// Put collisions into resolution order, moving empty slots to the end
+///
+/// The sort is stable, so declaration order breaks ties.
+pub fn order_collisions(collisions: &mut [Option<CollisionResult>]) {
+    collisions.sort_by_key(|c| match c {
+        Some(collision) => (false, collision_order_key(collision)),
+        None => (true, (u32::MAX, u8::MAX, u32::MAX)),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hitbox::{AttackData, CollisionBox, CollisionSystem};
+    use crate::types::{EntityId, Rect, Vec2};
+
+    fn collision(attacker: u32, defender: u32, damage: i32, group: u8) -> CollisionResult {
+        CollisionResult {
+            attacker: EntityId(attacker),
+            defender: EntityId(defender),
+            attack_data: AttackData::new(damage).with_hit_group(group),
+            contact_point: Vec2::ZERO,
+        }
+    }
+
+    fn resolved(collisions: &mut [Option<CollisionResult>]) -> Vec<(u32, i32)> {
+        order_collisions(collisions);
+        collisions
+            .iter()
+            .flatten()
+            .map(|c| (c.attacker.0, c.attack_data.damage))
+            .collect()
+    }
+
+    #[test]
+    fn test_lower_attacker_resolves_first() {
+        let mut collisions = [
+            None,
+            Some(collision(1, 0, 20, 0)),
+            Some(collision(0, 1, 10, 0)),
+        ];
+        assert_eq!(resolved(&mut collisions), vec![(0, 10), (1, 20)]);
+        assert!(collisions[2].is_none());
+    }
+
+    #[test]
+    fn test_hit_group_then_declaration_order() {
+        let mut collisions = [
+            Some(collision(0, 1, 30, 1)),
+            Some(collision(0, 1, 10, 0)),
+            Some(collision(0, 1, 20, 0)),
+        ];
+        assert_eq!(resolved(&mut collisions), vec![(0, 10), (0, 20), (0, 30)]);
+    }
+
+    #[test]
+    fn test_registration_order_does_not_matter() {
+        let boxes = |first: u32, second: u32| {
+            let mut system = CollisionSystem::new();
+            for owner in [first, second] {
+                let bounds = Rect::new(0, 0, 10000, 10000);
+                let attack = AttackData::new(10 * (owner as i32 + 1));
+                system.add_hitbox(CollisionBox::hitbox(EntityId(owner), bounds, attack));
+                system.add_hurtbox(CollisionBox::hurtbox(EntityId(owner), bounds));
+            }
+            let mut collisions = system.check_collisions();
+            resolved(&mut collisions)
+        };
+
+        assert_eq!(boxes(0, 1), boxes(1, 0));
+    }
+}
@@ -0,0 +1,93 @@
+//! Presentation offsets (hit shake and recoil)
+//!
+//! These offsets are visual only. They advance with the simulation, so
+//! replays and rollbacks show the same shake, but they are never added to
+//! the physics position: collision, pushboxes and facing ignore them.
+//! Renderers draw a fighter at `Entity::display_position()`.
+
+use crate::constants::*;
+use crate::types::Vec2;
+
+/// Per-entity display offset state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Presentation {
+    /// Frames of shake left
+    pub shake_remaining: u32,
+    /// Horizontal shake distance either side of the fighter
+    pub shake_amplitude: i32,
+    /// Horizontal recoil offset, eased back to zero each frame
+    pub recoil: i32,
+}
+
+impl Presentation {
+    /// Shake for `frames` frames, replacing any shake in progress
+    pub fn shake(&mut self, frames: u32, amplitude: i32) {
+        if frames > 0 {
+            self.shake_remaining = frames;
+            self.shake_amplitude = amplitude;
+        }
+    }
+
+    /// Jolt the fighter by `offset`
+    pub fn recoil(&mut self, offset: i32) {
+        self.recoil = offset;
+    }
+
+    /// Advance one frame; runs during hitstop as well
+    pub fn tick(&mut self) {
+        if self.shake_remaining > 0 {
+            self.shake_remaining -= 1;
+            if self.shake_remaining == 0 {
+                self.shake_amplitude = 0;
+            }
+        }
+        self.recoil = self.recoil * RECOIL_DECAY_PERCENT / 100;
+    }
+
+    /// Offset to add to the physics position when drawing
+    pub fn offset(&self) -> Vec2 {
+        let shake = match self.shake_remaining {
+            0 => 0,
+            n if n.is_multiple_of(2) => self.shake_amplitude,
+            _ => -self.shake_amplitude,
+        };
+        Vec2::new(shake.saturating_add(self.recoil), 0)
+    }
+
+    /// Whether any offset is still playing
+    pub fn is_active(&self) -> bool {
+        self.shake_remaining > 0 || self.recoil != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shake_alternates_and_ends() {
+        let mut p = Presentation::default();
+        p.shake(4, 1000);
+        assert_eq!(p.offset().x, 1000);
+        p.tick();
+        assert_eq!(p.offset().x, -1000);
+        for _ in 0..3 {
+            p.tick();
+        }
+        assert_eq!(p.offset(), Vec2::ZERO);
+        assert!(!p.is_active());
+    }
+
+    #[test]
+    fn test_recoil_eases_out() {
+        let mut p = Presentation::default();
+        p.recoil(-HIT_RECOIL_DISTANCE);
+        let mut last = p.offset().x;
+        while p.is_active() {
+            p.tick();
+            assert!(p.offset().x > last || p.offset().x == 0);
+            last = p.offset().x;
+        }
+        assert_eq!(last, 0);
+    }
+}
@@ -0,0 +1,329 @@
+//! Projectiles
+//!
+//! Projectiles are engine-owned objects lighter than fighters: no state
+//! machine or health, just a moving hitbox with a lifetime. Their hitboxes go
+//! through the regular collision and hit pipeline with the projectile as the
+//! attacker, so blocking, damage scaling and hit events work unchanged.
+//!
+//! A projectile disappears when it connects, when its lifetime runs out, or
+//! when it clashes with an opposing projectile. Explosive projectiles leave a
+//! brief residual hitbox behind when destroyed in a clash.
+//!
+//! Movement is described by a `Motion` program, so straight, lobbed and
+//! homing projectiles are all data rather than code.
+
+use crate::hitbox::{AttackData, CollisionBox, Team};
+use crate::types::{EntityId, Facing, Fixed, Rect, Vec2};
+
+/// Hitbox left behind by an explosive projectile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Residual {
+    /// Hitbox relative to the projectile position, authored facing right
+    pub hitbox: Rect,
+    pub attack: AttackData,
+    /// Frames the residual hitbox stays out
+    pub frames: u32,
+}
+
+/// How a projectile's velocity changes each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Motion {
+    /// Constant velocity
+    #[default]
+    Straight,
+    /// Velocity pulled down by `gravity` each frame; despawns on landing
+    Arc { gravity: i32 },
+    /// Steers toward the opponent at `speed`, changing each velocity
+    /// component by at most `turn_rate` per frame
+    Homing { speed: i32, turn_rate: i32 },
+}
+
+/// Authored description of a projectile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectileDef {
+    /// Hitbox relative to the projectile position, authored facing right
+    pub hitbox: Rect,
+    pub attack: AttackData,
+    /// Initial movement per frame, authored facing right
+    pub velocity: Vec2,
+    pub motion: Motion,
+    /// Frames before the projectile expires
+    pub lifetime: u32,
+    /// Explosion left behind when destroyed in a clash
+    pub residual: Option<Residual>,
+    /// Whether reflecting guards can send it back
+    pub reflectable: bool,
+    /// Whether absorbing guards can destroy it
+    pub absorbable: bool,
+}
+
+impl ProjectileDef {
+    pub fn new(hitbox: Rect, attack: AttackData, velocity: Vec2, lifetime: u32) -> Self {
+        Self {
+            hitbox,
+            attack,
+            velocity,
+            motion: Motion::Straight,
+            lifetime,
+            residual: None,
+            reflectable: true,
+            absorbable: true,
+        }
+    }
+
+    /// Pass through reflecting guards as a normal hit
+    pub fn unreflectable(mut self) -> Self {
+        self.reflectable = false;
+        self
+    }
+
+    /// Pass through absorbing guards as a normal hit
+    pub fn unabsorbable(mut self) -> Self {
+        self.absorbable = false;
+        self
+    }
+
+    /// Move with a motion program instead of a constant velocity
+    pub fn with_motion(mut self, motion: Motion) -> Self {
+        self.motion = motion;
+        self
+    }
+
+    /// Leave a residual hitbox behind when destroyed in a clash
+    pub fn with_residual(mut self, residual: Residual) -> Self {
+        self.residual = Some(residual);
+        self
+    }
+}
+
+/// A live projectile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projectile {
+    pub id: EntityId,
+    /// Fighter that threw or last reflected the projectile. The owner is
+    /// only safe from it through team checks, like the rest of its side
+    pub owner: EntityId,
+    /// Side the projectile fights for, set by the engine from its owner
+    pub team: Team,
+    pub facing: Facing,
+    pub position: Vec2,
+    /// Current movement per frame, in world space
+    pub velocity: Vec2,
+    pub def: ProjectileDef,
+    /// Frames since the projectile was spawned
+    pub age: u32,
+    /// Frames left of the residual hitbox (0 while flying)
+    pub residual_remaining: u32,
+}
+
+impl Projectile {
+    pub fn new(
+        id: EntityId,
+        owner: EntityId,
+        facing: Facing,
+        position: Vec2,
+        def: ProjectileDef,
+    ) -> Self {
+        Self {
+            id,
+            owner,
+            team: Team::Neutral,
+            facing,
+            position,
+            velocity: Vec2::new(def.velocity.x * facing.sign(), def.velocity.y),
+            def,
+            age: 0,
+            residual_remaining: 0,
+        }
+    }
+
+    /// Whether the projectile has become its residual explosion
+    pub fn is_residual(&self) -> bool {
+        self.residual_remaining > 0
+    }
+
+    /// Advance one frame toward `target` (the opponent, used by homing);
+    /// returns false once the projectile should despawn
+    pub fn update(&mut self, target: Option<Vec2>) -> bool {
+        if self.is_residual() {
+            self.residual_remaining -= 1;
+            return self.residual_remaining > 0;
+        }
+
+        self.position = self.position.add(self.velocity);
+        self.age += 1;
+
+        match self.def.motion {
+            Motion::Straight => {}
+            Motion::Arc { gravity } => {
+                self.velocity.y = self.velocity.y.saturating_add(gravity);
+                if self.position.y > 0 {
+                    return false;
+                }
+            }
+            Motion::Homing { speed, turn_rate } => {
+                if let Some(target) = target {
+                    let desired = aim(target.sub(self.position), speed);
+                    let turn = turn_rate.max(0);
+                    let steer = |velocity: i32, desired: i32| {
+                        let change = Fixed(desired).sub(Fixed(velocity)).clamp_abs(turn);
+                        Fixed(velocity).add(change).raw()
+                    };
+                    self.velocity = Vec2::new(
+                        steer(self.velocity.x, desired.x),
+                        steer(self.velocity.y, desired.y),
+                    );
+                }
+            }
+        }
+        self.age < self.def.lifetime
+    }
+
+    /// Send the projectile back, handing it to `new_owner` of `team`
+    pub fn reflect(&mut self, new_owner: EntityId, team: Team) {
+        self.owner = new_owner;
+        self.team = team;
+        self.facing = self.facing.opposite();
+        self.velocity.x = Fixed(self.velocity.x).mul(-1).raw();
+        self.age = 0;
+    }
+
+    /// Destroy the projectile in a clash; returns true if it lives on as a
+    /// residual explosion
+    pub fn destroy(&mut self) -> bool {
+        match self.def.residual {
+            Some(residual) if !self.is_residual() => {
+                self.residual_remaining = residual.frames;
+                residual.frames > 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Current hitbox in world space
+    pub fn hitbox(&self) -> CollisionBox {
+        let (mut bounds, attack) = match (self.def.residual, self.is_residual()) {
+            (Some(residual), true) => (residual.hitbox, residual.attack),
+            _ => (self.def.hitbox, self.def.attack),
+        };
+        if self.facing == Facing::Left {
+            bounds.x = -bounds.x - bounds.width;
+        }
+        CollisionBox::hitbox(self.id, bounds, attack)
+            .with_team(attack.team.unwrap_or(self.team))
+            .translate(self.position)
+    }
+}
+
+/// Velocity of length `speed` pointing along `delta`
+fn aim(delta: Vec2, speed: i32) -> Vec2 {
+    let (dx, dy) = (delta.x as i64, delta.y as i64);
+    let length = (dx * dx + dy * dy).unsigned_abs().isqrt() as i64;
+    if length == 0 {
+        return Vec2::ZERO;
+    }
+    Vec2::new(
+        (dx * speed as i64 / length) as i32,
+        (dy * speed as i64 / length) as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fireball() -> ProjectileDef {
+        ProjectileDef::new(
+            Rect::new(0, 0, 5000, 5000),
+            AttackData::new(40),
+            Vec2::new(1000, 0),
+            3,
+        )
+    }
+
+    #[test]
+    fn test_projectile_moves_with_facing_and_expires() {
+        let mut p = Projectile::new(
+            EntityId(9),
+            EntityId(1),
+            Facing::Left,
+            Vec2::ZERO,
+            fireball(),
+        );
+        assert!(p.update(None));
+        assert_eq!(p.position.x, -1000);
+        assert_eq!(p.hitbox().bounds.x, -6000);
+        assert!(p.update(None));
+        assert!(!p.update(None));
+    }
+
+    #[test]
+    fn test_arc_rises_then_lands() {
+        let lob = fireball().with_motion(Motion::Arc { gravity: 100 });
+        let mut def = lob;
+        def.velocity = Vec2::new(500, -1000);
+        def.lifetime = 1000;
+        let mut p = Projectile::new(EntityId(9), EntityId(0), Facing::Right, Vec2::ZERO, def);
+
+        let mut peak = 0;
+        let mut frames = 0;
+        while p.update(None) {
+            peak = peak.min(p.position.y);
+            frames += 1;
+        }
+        assert!(peak < -4000);
+        assert!(p.position.y > 0);
+        assert_eq!(frames, 21);
+    }
+
+    #[test]
+    fn test_homing_turns_with_capped_rate() {
+        let mut def = fireball().with_motion(Motion::Homing {
+            speed: 1000,
+            turn_rate: 100,
+        });
+        def.lifetime = 1000;
+        let mut p = Projectile::new(EntityId(9), EntityId(0), Facing::Right, Vec2::ZERO, def);
+
+        // Target straight above: the velocity bends by at most 100 a frame
+        let target = Some(Vec2::new(0, -50000));
+        p.update(target);
+        assert_eq!(p.velocity, Vec2::new(900, -100));
+        for _ in 0..30 {
+            p.update(target);
+        }
+        // Now climbing, and curving back toward the target's column
+        assert!(p.velocity.y < -900);
+        assert!(p.velocity.x < 0 && p.position.x > 0);
+    }
+
+    #[test]
+    fn test_explosive_projectile_leaves_residual() {
+        let residual = Residual {
+            hitbox: Rect::new(-5000, -5000, 15000, 15000),
+            attack: AttackData::new(20),
+            frames: 2,
+        };
+        let mut p = Projectile::new(
+            EntityId(9),
+            EntityId(0),
+            Facing::Right,
+            Vec2::ZERO,
+            fireball().with_residual(residual),
+        );
+        assert!(p.destroy());
+        assert_eq!(p.hitbox().attack_data.map(|a| a.damage), Some(20));
+        assert!(p.update(None));
+        assert!(!p.update(None));
+
+        // Plain projectiles just disappear
+        let mut plain = Projectile::new(
+            EntityId(9),
+            EntityId(0),
+            Facing::Right,
+            Vec2::ZERO,
+            fireball(),
+        );
+        assert!(!plain.destroy());
+    }
+}
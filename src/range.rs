@@ -0,0 +1,143 @@
+//! Range queries
+//!
+//! Distances are horizontal, between fighter positions, in internal units:
+//! the same measure the engine checks throws against. AI, training overlays
+//! and tutorials read range from here instead of redoing the box geometry.
+//!
+//! Hitbox queries place both fighters on the ground at the given distance,
+//! with the attacker facing the way it does now and the opponent standing.
+
+use crate::constants::THROW_RANGE;
+use crate::engine::Engine;
+use crate::state::{StateAction, StateId};
+use crate::types::{Facing, PlayerId, Rect};
+
+/// Whether fighters `distance` apart can start a throw on each other
+pub(crate) fn throw_reaches(distance: i32) -> bool {
+    distance <= THROW_RANGE
+}
+
+impl Engine {
+    /// Horizontal distance between the two fighters
+    pub fn distance_between_players(&self) -> Option<i32> {
+        let x = |player| self.get_player_entity(player).map(|e| e.physics.position.x);
+        x(PlayerId::PLAYER_1)
+            .zip(x(PlayerId::PLAYER_2))
+            .map(|(a, b)| a.saturating_sub(b).saturating_abs())
+    }
+
+    /// Whether the fighters are close enough for a throw to grab instead of
+    /// whiffing
+    pub fn in_throw_range(&self) -> bool {
+        self.distance_between_players().is_some_and(throw_reaches)
+    }
+
+    /// Whether any hitbox of `player`'s `state` touches the opponent
+    /// `distance` ahead
+    pub fn will_hitbox_reach(&self, player: PlayerId, state: StateId, distance: i32) -> bool {
+        let Some((hitboxes, body)) = self.range_geometry(player, state) else {
+            return false;
+        };
+        let target = Rect {
+            x: body.x.saturating_add(distance),
+            ..body
+        };
+        hitboxes.iter().any(|hitbox| hitbox.intersects(&target))
+    }
+
+    /// Farthest distance at which `player`'s `state` touches the opponent;
+    /// `None` if none of its hitboxes reach in front of the attacker
+    pub fn hitbox_reach(&self, player: PlayerId, state: StateId) -> Option<i32> {
+        let (hitboxes, body) = self.range_geometry(player, state)?;
+        hitboxes
+            .iter()
+            .filter(|hitbox| hitbox.top() < body.bottom() && hitbox.bottom() > body.top())
+            .map(|hitbox| hitbox.right() - body.left() - 1)
+            .max()
+            .filter(|&reach| reach >= 0)
+    }
+
+    /// Hitboxes of a state and the opponent's standing body, mirrored so the
+    /// attacker faces right from the origin
+    fn range_geometry(&self, player: PlayerId, state: StateId) -> Option<(Vec<Rect>, Rect)> {
+        let attacker = self.get_player_entity(player)?;
+        let opponent = self.get_player_entity(PlayerId(1 - player.0.min(1)))?;
+        let definitions = attacker.state_machine.definitions();
+        let hitboxes = definitions
+            .find(state)?
+            .frame_data
+            .iter()
+            .filter_map(|data| match data.action {
+                StateAction::Hitbox {
+                    x,
+                    y,
+                    width,
+                    height,
+                    ..
+                } => Some(Rect::new(x, y, width, height)),
+                _ => None,
+            })
+            .collect();
+
+        let mut body = opponent.state_machine.definitions().body.stand;
+        if attacker.facing == Facing::Left {
+            body.x = -body.x - body.width;
+        }
+        Some((hitboxes, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EngineConfig;
+    use crate::engine::tests::{engine_with_poke, poke_state};
+    use crate::input::InputState;
+
+    /// Engine with player 1 `distance` left of player 2, both knowing the
+    /// test poke (`engine_with_poke`)
+    fn engine_at(distance: i32) -> Engine {
+        let mut engine = engine_with_poke(EngineConfig::default());
+        let p2 = engine.entities[1].as_mut().unwrap();
+        p2.physics.position.x = distance;
+        p2.state_machine.register_state(poke_state());
+        engine
+    }
+
+    #[test]
+    fn test_distance_and_throw_range() {
+        let engine = engine_at(THROW_RANGE);
+        assert_eq!(engine.distance_between_players(), Some(THROW_RANGE));
+        assert!(engine.in_throw_range());
+        assert!(!engine_at(THROW_RANGE + 1).in_throw_range());
+        assert_eq!(Engine::new().distance_between_players(), None);
+    }
+
+    #[test]
+    fn test_hitbox_reach_matches_collisions() {
+        let engine = engine_at(30000);
+        let poke = StateId::SpecialMove;
+        // The default body isn't centered, so reach depends on the side
+        let reach = engine.hitbox_reach(PlayerId::PLAYER_1, poke).unwrap();
+        assert_eq!(reach, 29999);
+        assert!(engine.will_hitbox_reach(PlayerId::PLAYER_1, poke, reach));
+        assert!(!engine.will_hitbox_reach(PlayerId::PLAYER_1, poke, reach + 1));
+        assert_eq!(engine.hitbox_reach(PlayerId::PLAYER_2, poke), Some(39999));
+        assert_eq!(engine.hitbox_reach(PlayerId::PLAYER_1, StateId::Idle), None);
+        for distance in [i32::MIN, i32::MAX] {
+            assert!(!engine.will_hitbox_reach(PlayerId::PLAYER_1, poke, distance));
+        }
+
+        // The prediction agrees with a real hit at the edge of reach
+        for (distance, lands) in [(reach, true), (reach + 1, false)] {
+            let mut engine = engine_at(distance);
+            let p1 = engine.entities[0].as_mut().unwrap();
+            p1.state_machine.transition(poke);
+            for _ in 0..3 {
+                engine.tick(InputState::neutral(), InputState::neutral());
+            }
+            let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+            assert_eq!(p2.health.current < p2.health.maximum, lands);
+        }
+    }
+}
@@ -0,0 +1,550 @@
+//! Replay format and playback
+//!
+//! A replay is the stage, the match setup and both players' inputs for
+//! every frame; feeding them back through `Engine::advance` reproduces the
+//! match exactly.
+//! Inputs are recorded after latency compensation (`Engine::inputs`), so
+//! playback doesn't depend on the recording setup's device offsets.
+//! Netplay recordings also note the session's prediction policy; playback
+//! ignores it, but it explains how the match felt to the players.
+//!
+//! Byte layout (little endian):
+//!
+//! | Field          | Size                                      |
+//! |----------------|-------------------------------------------|
+//! | Magic `BGRP`   | 4                                         |
+//! | Format version | 2                                         |
+//! | Engine version | 2                                         |
+//! | Match setup    | 32 (conditions, then mutators)            |
+//! | Stage          | 12 (preset, edge, half width, ceiling)    |
+//! | Prediction     | 2 (policy kind, decay frames)             |
+//! | Frame count    | 4                                         |
+//! | Inputs         | 2 per frame (P1, P2 as `InputState` bits) |
+
+use crate::config::{EngineConfig, StageConfig, StageEdge, StagePreset};
+use crate::constants::*;
+use crate::engine::Engine;
+use crate::input::InputState;
+use crate::rollback::PredictionPolicy;
+use crate::setup::{MatchSetup, Mutators};
+use crate::snapshot::EngineSnapshot;
+use crate::types::PlayerId;
+
+const MAGIC: &[u8; 4] = b"BGRP";
+const SETUP_LEN: usize = 32;
+const STAGE_LEN: usize = 12;
+const PREDICTION_LEN: usize = 2;
+const HEADER_LEN: usize = 4 + 2 + 2 + SETUP_LEN + STAGE_LEN + PREDICTION_LEN + 4;
+
+/// Reasons a replay buffer can't be loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Buffer shorter than the header
+    TooShort,
+    /// Buffer doesn't start with the replay magic
+    BadMagic,
+    /// Written by an incompatible format version
+    UnsupportedVersion(u16),
+    /// Recorded under different simulation rules (`ENGINE_DATA_VERSION`)
+    EngineVersion(u16),
+    /// Frame count in the header doesn't match the input data
+    Truncated { expected: usize, found: usize },
+}
+
+/// Recorded match: starting conditions and per-frame inputs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub setup: MatchSetup,
+    /// Stage the match was played on
+    pub stage: StageConfig,
+    /// Prediction policy of the netplay session that recorded the match
+    /// (None for local play)
+    pub prediction: Option<PredictionPolicy>,
+    inputs: Vec<[InputState; MAX_PLAYERS]>,
+}
+
+impl Replay {
+    pub fn new(setup: MatchSetup) -> Self {
+        Self {
+            setup,
+            stage: StageConfig::default(),
+            prediction: None,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Note the stage the match is played on
+    pub fn with_stage(mut self, stage: StageConfig) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    /// Note the prediction policy of the recording netplay session
+    pub fn with_prediction(mut self, policy: PredictionPolicy) -> Self {
+        self.prediction = Some(policy);
+        self
+    }
+
+    /// Append one frame of inputs
+    pub fn push(&mut self, p1: InputState, p2: InputState) {
+        self.inputs.push([p1, p2]);
+    }
+
+    /// Append the inputs a live engine consumed on its last tick
+    pub fn record(&mut self, engine: &Engine) {
+        let [p1, p2] = engine.inputs;
+        self.push(p1, p2);
+    }
+
+    /// Drop every frame after the first `frames`
+    pub fn truncate(&mut self, frames: usize) {
+        self.inputs.truncate(frames);
+    }
+
+    /// Inputs for a frame
+    pub fn inputs(&self, frame: usize) -> Option<[InputState; MAX_PLAYERS]> {
+        self.inputs.get(frame).copied()
+    }
+
+    /// Number of recorded frames
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Serialize to the replay byte format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.inputs.len() * MAX_PLAYERS);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&REPLAY_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&ENGINE_DATA_VERSION.to_le_bytes());
+        write_setup(&mut bytes, &self.setup);
+        write_stage(&mut bytes, &self.stage);
+        write_prediction(&mut bytes, self.prediction);
+        bytes.extend_from_slice(&(self.inputs.len() as u32).to_le_bytes());
+        for frame in &self.inputs {
+            bytes.extend(frame.iter().map(InputState::to_bits));
+        }
+        bytes
+    }
+
+    /// Parse the replay byte format
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(ReplayError::TooShort);
+        }
+        if &bytes[0..4] != MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let engine_version = u16::from_le_bytes([bytes[6], bytes[7]]);
+        if engine_version != ENGINE_DATA_VERSION {
+            return Err(ReplayError::EngineVersion(engine_version));
+        }
+        let setup = read_setup(&bytes[8..8 + SETUP_LEN]);
+        let stage = read_stage(&bytes[8 + SETUP_LEN..]);
+        let prediction = read_prediction(&bytes[8 + SETUP_LEN + STAGE_LEN..]);
+        let count = read_u32(&bytes[HEADER_LEN - 4..]) as usize;
+
+        let data = &bytes[HEADER_LEN..];
+        // The count is untrusted and can overflow on 32-bit targets
+        if count.checked_mul(MAX_PLAYERS) != Some(data.len()) {
+            return Err(ReplayError::Truncated {
+                expected: count,
+                found: data.len() / MAX_PLAYERS,
+            });
+        }
+        let inputs = data
+            .chunks_exact(MAX_PLAYERS)
+            .map(|frame| {
+                [
+                    InputState::from_bits(frame[0]),
+                    InputState::from_bits(frame[1]),
+                ]
+            })
+            .collect();
+
+        Ok(Self {
+            setup,
+            stage,
+            prediction,
+            inputs,
+        })
+    }
+}
+
+/// Plays a replay through its own engine, with seeking
+///
+/// Snapshots are kept every `REPLAY_KEYFRAME_INTERVAL` frames as playback
+/// reaches them, so seeking only resimulates from the nearest keyframe.
+pub struct ReplayPlayer {
+    replay: Replay,
+    engine: Engine,
+    position: usize,
+    keyframes: Vec<EngineSnapshot>,
+}
+
+impl ReplayPlayer {
+    /// Play back on the replay's stage with otherwise default configuration
+    pub fn new(replay: Replay) -> Self {
+        let engine = Engine::with_config(EngineConfig {
+            stage: replay.stage,
+            ..Default::default()
+        });
+        Self::with_engine(replay, engine)
+    }
+
+    /// Play back using an engine with custom configuration; its stage is
+    /// used as is, not the replay's
+    pub fn with_engine(replay: Replay, mut engine: Engine) -> Self {
+        engine.init_match_with(replay.setup);
+        let keyframes = vec![engine.save_snapshot()];
+        Self {
+            replay,
+            engine,
+            position: 0,
+            keyframes,
+        }
+    }
+
+    /// Engine showing the current playback frame
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Frames played so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Whether every recorded frame has been played
+    pub fn finished(&self) -> bool {
+        self.position >= self.replay.len()
+    }
+
+    /// Play the next frame; returns false at the end of the replay
+    pub fn tick(&mut self) -> bool {
+        let Some([p1, p2]) = self.replay.inputs(self.position) else {
+            return false;
+        };
+        self.engine.advance(p1, p2);
+        self.position += 1;
+
+        if self.position.is_multiple_of(REPLAY_KEYFRAME_INTERVAL)
+            && self.position / REPLAY_KEYFRAME_INTERVAL == self.keyframes.len()
+        {
+            self.keyframes.push(self.engine.save_snapshot());
+        }
+        true
+    }
+
+    /// Jump to a frame (clamped to the replay length)
+    pub fn seek(&mut self, frame: usize) {
+        let target = frame.min(self.replay.len());
+        let keyframe = (target / REPLAY_KEYFRAME_INTERVAL).min(self.keyframes.len() - 1);
+
+        // Going forward from the current position is cheaper than reloading
+        if target < self.position || keyframe * REPLAY_KEYFRAME_INTERVAL > self.position {
+            self.keyframes[keyframe].restore(&mut self.engine);
+            self.position = keyframe * REPLAY_KEYFRAME_INTERVAL;
+        }
+        while self.position < target {
+            self.tick();
+        }
+    }
+}
+
+fn write_setup(bytes: &mut Vec<u8>, setup: &MatchSetup) {
+    bytes.extend_from_slice(&setup.seed.to_le_bytes());
+    bytes.push(match setup.swap_sides {
+        None => 0,
+        Some(false) => 1,
+        Some(true) => 2,
+    });
+    bytes.push(setup.randomize_sides as u8);
+    bytes.push(setup.distance.is_some() as u8);
+    bytes.push(setup.first_attacker.map(|p| p.0).unwrap_or(u8::MAX));
+    bytes.extend_from_slice(&setup.distance.unwrap_or(0).to_le_bytes());
+    bytes.extend_from_slice(&setup.distance_variation.to_le_bytes());
+    bytes.extend_from_slice(&setup.handicap_frames.to_le_bytes());
+    let mutators = setup.mutators;
+    bytes.push(mutators.speed);
+    bytes.push(0);
+    bytes.extend_from_slice(&mutators.gravity_percent.to_le_bytes());
+    bytes.extend_from_slice(&mutators.damage_percent.to_le_bytes());
+    bytes.extend_from_slice(&mutators.meter_gain_percent.to_le_bytes());
+}
+
+fn read_setup(bytes: &[u8]) -> MatchSetup {
+    let mut seed = [0; 8];
+    seed.copy_from_slice(&bytes[0..8]);
+    MatchSetup {
+        seed: u64::from_le_bytes(seed),
+        swap_sides: match bytes[8] {
+            0 => None,
+            1 => Some(false),
+            _ => Some(true),
+        },
+        randomize_sides: bytes[9] != 0,
+        distance: (bytes[10] != 0).then(|| read_u32(&bytes[12..]) as i32),
+        first_attacker: (bytes[11] != u8::MAX).then(|| PlayerId(bytes[11])),
+        distance_variation: read_u32(&bytes[16..]) as i32,
+        handicap_frames: read_u32(&bytes[20..]),
+        mutators: Mutators {
+            speed: bytes[24],
+            gravity_percent: read_u16(&bytes[26..]),
+            damage_percent: read_u16(&bytes[28..]),
+            meter_gain_percent: read_u16(&bytes[30..]),
+        },
+    }
+}
+
+fn write_stage(bytes: &mut Vec<u8>, stage: &StageConfig) {
+    bytes.push(match stage.preset {
+        StagePreset::Standard => 0,
+        StagePreset::Narrow => 1,
+        StagePreset::Wide => 2,
+        StagePreset::Corridor => 3,
+        StagePreset::Platform => 4,
+    });
+    bytes.push(match stage.edge {
+        StageEdge::Wall => 0,
+        StageEdge::Ledge => 1,
+    });
+    bytes.push(stage.ceiling.is_some() as u8);
+    bytes.push(0);
+    bytes.extend_from_slice(&stage.half_width.to_le_bytes());
+    bytes.extend_from_slice(&stage.ceiling.unwrap_or(0).to_le_bytes());
+}
+
+fn read_stage(bytes: &[u8]) -> StageConfig {
+    StageConfig {
+        preset: match bytes[0] {
+            1 => StagePreset::Narrow,
+            2 => StagePreset::Wide,
+            3 => StagePreset::Corridor,
+            4 => StagePreset::Platform,
+            _ => StagePreset::Standard,
+        },
+        edge: match bytes[1] {
+            1 => StageEdge::Ledge,
+            _ => StageEdge::Wall,
+        },
+        half_width: read_u32(&bytes[4..]) as i32,
+        ceiling: (bytes[2] != 0).then(|| read_u32(&bytes[8..]) as i32),
+    }
+}
+
+fn write_prediction(bytes: &mut Vec<u8>, prediction: Option<PredictionPolicy>) {
+    bytes.extend_from_slice(&match prediction {
+        None => [0, 0],
+        Some(PredictionPolicy::HoldLast) => [1, 0],
+        Some(PredictionPolicy::DecayToNeutral { frames }) => [2, frames],
+        Some(PredictionPolicy::Neutral) => [3, 0],
+    });
+}
+
+fn read_prediction(bytes: &[u8]) -> Option<PredictionPolicy> {
+    match bytes[0] {
+        1 => Some(PredictionPolicy::HoldLast),
+        2 => Some(PredictionPolicy::DecayToNeutral { frames: bytes[1] }),
+        3 => Some(PredictionPolicy::Neutral),
+        _ => None,
+    }
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Direction;
+    use crate::snapshot::assert_snapshots_match;
+
+    fn scripted_replay(frames: u32) -> Replay {
+        let mut replay = Replay::new(MatchSetup {
+            handicap_frames: 5,
+            mutators: Mutators {
+                speed: 2,
+                damage_percent: 150,
+                ..Default::default()
+            },
+            ..MatchSetup::seeded(3)
+        });
+        for frame in 0..frames {
+            let mut p1 = InputState::neutral();
+            p1.direction = Direction::Forward;
+            p1.light = frame.is_multiple_of(9);
+            let mut p2 = InputState::neutral();
+            p2.medium = frame.is_multiple_of(13);
+            replay.push(p1, p2);
+        }
+        replay
+    }
+
+    #[test]
+    fn test_replay_bytes_round_trip() {
+        let replay = scripted_replay(100);
+        let bytes = replay.to_bytes();
+        assert_eq!(bytes.len(), HEADER_LEN + 200);
+        assert_eq!(Replay::from_bytes(&bytes), Ok(replay));
+    }
+
+    #[test]
+    fn test_replay_keeps_stage() {
+        use crate::config::StagePreset;
+
+        for preset in [
+            StagePreset::Standard,
+            StagePreset::Corridor,
+            StagePreset::Platform,
+        ] {
+            let stage = StageConfig::preset(preset);
+            let replay = scripted_replay(3).with_stage(stage);
+            let loaded = Replay::from_bytes(&replay.to_bytes()).unwrap();
+            assert_eq!(loaded.stage, stage);
+        }
+    }
+
+    #[test]
+    fn test_replay_keeps_prediction_policy() {
+        assert_eq!(
+            Replay::from_bytes(&scripted_replay(3).to_bytes())
+                .unwrap()
+                .prediction,
+            None
+        );
+        for policy in [
+            PredictionPolicy::HoldLast,
+            PredictionPolicy::DecayToNeutral { frames: 8 },
+            PredictionPolicy::Neutral,
+        ] {
+            let replay = scripted_replay(3).with_prediction(policy);
+            let loaded = Replay::from_bytes(&replay.to_bytes()).unwrap();
+            assert_eq!(loaded.prediction, Some(policy));
+        }
+    }
+
+    #[test]
+    fn test_replay_rejects_bad_buffers() {
+        let bytes = scripted_replay(10).to_bytes();
+        assert_eq!(Replay::from_bytes(&bytes[..10]), Err(ReplayError::TooShort));
+        assert_eq!(
+            Replay::from_bytes(&bytes[..bytes.len() - 2]),
+            Err(ReplayError::Truncated {
+                expected: 10,
+                found: 9
+            })
+        );
+
+        let mut wrong = bytes.clone();
+        wrong[0] = b'X';
+        assert_eq!(Replay::from_bytes(&wrong), Err(ReplayError::BadMagic));
+        let mut wrong = bytes.clone();
+        wrong[4] = 99;
+        assert_eq!(
+            Replay::from_bytes(&wrong),
+            Err(ReplayError::UnsupportedVersion(99))
+        );
+        let mut wrong = bytes.clone();
+        wrong[6] = 99;
+        assert_eq!(
+            Replay::from_bytes(&wrong),
+            Err(ReplayError::EngineVersion(99))
+        );
+        let mut wrong = bytes;
+        wrong[HEADER_LEN - 4..HEADER_LEN].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            Replay::from_bytes(&wrong),
+            Err(ReplayError::Truncated {
+                expected: u32::MAX as usize,
+                found: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_playback_reproduces_match() {
+        let replay = scripted_replay(200);
+        let mut live = Engine::new();
+        live.init_match_with(replay.setup);
+        for frame in 0..replay.len() {
+            let [p1, p2] = replay.inputs(frame).unwrap();
+            live.tick(p1, p2);
+        }
+
+        let mut player = ReplayPlayer::new(replay);
+        while player.tick() {}
+        assert!(player.finished());
+        assert_snapshots_match(&live.save_snapshot(), &player.engine().save_snapshot());
+    }
+
+    #[test]
+    fn test_replay_records_compensated_inputs() {
+        use crate::config::EngineConfig;
+
+        let source = scripted_replay(120);
+        let mut config = EngineConfig::default();
+        config.input.latency_offset = [0, 3];
+        let mut live = Engine::with_config(config);
+        live.init_match_with(source.setup);
+
+        let mut recorded = Replay::new(source.setup);
+        for frame in 0..source.len() {
+            let [p1, p2] = source.inputs(frame).unwrap();
+            live.tick(p1, p2);
+            let [p1, p2] = live.inputs;
+            recorded.push(p1, p2);
+        }
+        assert_eq!(
+            recorded.inputs(3),
+            Some([source.inputs(3).unwrap()[0], source.inputs(0).unwrap()[1]])
+        );
+
+        // Played back without any offset, the match comes out the same
+        let mut player = ReplayPlayer::new(recorded);
+        while player.tick() {}
+        assert_snapshots_match(&live.save_snapshot(), &player.engine().save_snapshot());
+    }
+
+    #[test]
+    fn test_seek_matches_linear_playback() {
+        let mut linear = ReplayPlayer::new(scripted_replay(200));
+        for _ in 0..150 {
+            linear.tick();
+        }
+        let at_150 = linear.engine().save_snapshot();
+        for _ in 150..200 {
+            linear.tick();
+        }
+
+        // Backwards to a frame between keyframes
+        linear.seek(150);
+        assert_eq!(linear.position(), 150);
+        assert_snapshots_match(&at_150, &linear.engine().save_snapshot());
+
+        // Forward past frames never played
+        let mut fresh = ReplayPlayer::new(scripted_replay(200));
+        fresh.seek(150);
+        assert_snapshots_match(&at_150, &fresh.engine().save_snapshot());
+
+        fresh.seek(1000);
+        assert_eq!(fresh.position(), 200);
+    }
+}
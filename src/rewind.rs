@@ -0,0 +1,215 @@
+//! Frame-by-frame rewind for local play and training
+//!
+//! When enabled, the engine keeps the snapshot taken before each of its
+//! last frames together with the inputs it ran them with. Rewinding
+//! restores an older snapshot and hands back the inputs of the undone
+//! frames, so a player can step backwards and then replay them as they
+//! were or with changes. Netplay uses `RollbackSession`, which keeps its
+//! own window, instead.
+
+use std::collections::VecDeque;
+
+use crate::constants::*;
+use crate::engine::Engine;
+use crate::input::InputState;
+use crate::snapshot::EngineSnapshot;
+
+/// One recorded frame: the state before it and the inputs it ran with
+#[derive(Clone)]
+struct RewindFrame {
+    snapshot: EngineSnapshot,
+    inputs: [InputState; MAX_PLAYERS],
+}
+
+/// Snapshots of the most recent frames, oldest first
+#[derive(Clone)]
+pub struct RewindHistory {
+    capacity: usize,
+    frames: VecDeque<RewindFrame>,
+}
+
+impl RewindHistory {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.min(MAX_REWIND_FRAMES);
+        Self {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Most frames kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Frames currently available to rewind
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Record the state before a frame and its inputs
+    ///
+    /// Frames at or after the recorded one are dropped first, so history
+    /// stays consistent after rewinds and snapshot loads.
+    fn record(&mut self, snapshot: EngineSnapshot, inputs: [InputState; MAX_PLAYERS]) {
+        while self
+            .frames
+            .back()
+            .is_some_and(|f| f.snapshot.frame >= snapshot.frame)
+        {
+            self.frames.pop_back();
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(RewindFrame { snapshot, inputs });
+    }
+
+    fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+impl Engine {
+    /// Keep the last `frames` frames (up to `MAX_REWIND_FRAMES`) for
+    /// rewinding; 0 turns rewind off and frees the history
+    pub fn enable_rewind(&mut self, frames: usize) {
+        self.rewind = (frames > 0).then(|| RewindHistory::new(frames));
+    }
+
+    /// Frames that can be rewound right now
+    pub fn rewindable_frames(&self) -> usize {
+        self.rewind.as_ref().map_or(0, RewindHistory::len)
+    }
+
+    /// Step back up to `frames` frames
+    ///
+    /// Returns the inputs of the undone frames, oldest first; feeding them
+    /// back through `advance` (edited or not) replays from here. The
+    /// per-tick event lists are cleared since they described a frame that
+    /// no longer happened.
+    pub fn rewind(&mut self, frames: usize) -> Vec<[InputState; MAX_PLAYERS]> {
+        let Some(history) = &mut self.rewind else {
+            return Vec::new();
+        };
+        let frames = frames.min(history.frames.len());
+        if frames == 0 {
+            return Vec::new();
+        }
+        let undone: Vec<RewindFrame> = history
+            .frames
+            .drain(history.frames.len() - frames..)
+            .collect();
+        undone[0].snapshot.restore(self);
+
+        self.events.clear();
+        self.cues.clear();
+        self.clashes.clear();
+        self.finishes.clear();
+        self.game_events.clear();
+        undone.into_iter().map(|f| f.inputs).collect()
+    }
+
+    /// Record the state before the frame about to run
+    pub(crate) fn record_rewind(&mut self, inputs: [InputState; MAX_PLAYERS]) {
+        if self.rewind.is_none() {
+            return;
+        }
+        let snapshot = self.save_snapshot();
+        if let Some(history) = &mut self.rewind {
+            history.record(snapshot, inputs);
+        }
+    }
+
+    /// Forget recorded frames (a new match started)
+    pub(crate) fn clear_rewind(&mut self) {
+        if let Some(history) = &mut self.rewind {
+            history.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Direction;
+    use crate::snapshot::assert_snapshots_match;
+    use crate::state::StateId;
+
+    fn forward() -> InputState {
+        InputState {
+            direction: Direction::Forward,
+            ..InputState::neutral()
+        }
+    }
+
+    #[test]
+    fn test_rewind_steps_back_and_replays() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        engine.enable_rewind(20);
+        for _ in 0..10 {
+            engine.tick(forward(), InputState::neutral());
+        }
+        let before = engine.save_snapshot();
+        for _ in 0..5 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+
+        let undone = engine.rewind(5);
+        assert_eq!(undone.len(), 5);
+        assert_eq!(engine.frame.0, 10);
+        assert_eq!(engine.rewindable_frames(), 10);
+        assert_snapshots_match(&before, &engine.save_snapshot());
+
+        // Replaying the same inputs lands on the same state
+        let mut replayed = Engine::new();
+        replayed.init_match();
+        replayed.enable_rewind(20);
+        for _ in 0..10 {
+            replayed.tick(forward(), InputState::neutral());
+        }
+        for _ in 0..5 {
+            replayed.tick(InputState::neutral(), InputState::neutral());
+        }
+        for [p1, p2] in undone {
+            engine.advance(p1, p2);
+        }
+        assert_snapshots_match(&replayed.save_snapshot(), &engine.save_snapshot());
+
+        // Or with a change
+        engine.rewind(3);
+        let light = InputState {
+            light: true,
+            ..InputState::neutral()
+        };
+        engine.tick(light, InputState::neutral());
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.state_machine.current_state(), StateId::LightAttack);
+    }
+
+    #[test]
+    fn test_rewind_history_is_bounded() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        assert!(engine.rewind(1).is_empty());
+
+        engine.enable_rewind(4);
+        for _ in 0..10 {
+            engine.tick(InputState::neutral(), InputState::neutral());
+        }
+        assert_eq!(engine.rewindable_frames(), 4);
+        assert_eq!(engine.rewind(100).len(), 4);
+        assert_eq!(engine.frame.0, 6);
+
+        engine.init_match();
+        assert_eq!(engine.rewindable_frames(), 0);
+    }
+}
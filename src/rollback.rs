@@ -0,0 +1,646 @@
+//! Rollback session for netplay
+//!
+//! The session runs the engine ahead using predicted inputs for players
+//! whose inputs haven't arrived yet. When a late input disagrees with the
+//! prediction used for its frame, the session restores the snapshot taken
+//! before that frame and resimulates up to the present.
+//!
+//! How missing inputs are predicted is set by the session's
+//! `PredictionPolicy` (`with_prediction`). Repeating the previous input is
+//! the default; decaying to neutral keeps a long outage from leaving a
+//! character walking or holding block on stale input.
+//!
+//! Rollbacks can be timed by giving the session a clock (`with_clock`);
+//! the engine itself never reads the time, so the wasm build stays
+//! portable and simulation stays deterministic.
+//!
+//! A session can record its match as a replay (`with_recording`). Frames
+//! resimulated by a rollback overwrite what was recorded for them, and the
+//! replay notes the session's prediction policy.
+
+use crate::constants::*;
+use crate::engine::Engine;
+use crate::input::InputState;
+use crate::replay::Replay;
+use crate::setup::MatchSetup;
+use crate::snapshot::EngineSnapshot;
+
+const SLOT_COUNT: usize = ROLLBACK_WINDOW * 2;
+
+/// Reasons an input can't be added to the session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollbackError {
+    /// Player index out of range
+    InvalidPlayer(usize),
+    /// Frame is older than the rollback window
+    TooLate { frame: u64, oldest: u64 },
+    /// Frame is more than a window ahead of the simulation
+    TooEarly { frame: u64, newest: u64 },
+}
+
+/// How the session fills in inputs that haven't arrived yet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PredictionPolicy {
+    /// Repeat the player's input from the previous frame
+    #[default]
+    HoldLast,
+    /// Repeat the previous input, then predict neutral once more than
+    /// `frames` frames in a row have been predicted
+    DecayToNeutral { frames: u8 },
+    /// Always predict neutral
+    Neutral,
+}
+
+impl PredictionPolicy {
+    /// Input to use for a frame, given the previous frame's input and how
+    /// many frames in a row (this one included) are predicted
+    pub fn predict(self, previous: InputState, streak: u32) -> InputState {
+        match self {
+            PredictionPolicy::HoldLast => previous,
+            PredictionPolicy::DecayToNeutral { frames } if streak <= frames as u32 => previous,
+            PredictionPolicy::DecayToNeutral { .. } | PredictionPolicy::Neutral => {
+                InputState::neutral()
+            }
+        }
+    }
+}
+
+/// Rollback counters since the session started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RollbackStats {
+    /// Times the session rewound to correct a misprediction
+    pub rollbacks: u32,
+    /// Frames simulated again because of rollbacks
+    pub resimulated_frames: u32,
+    /// Deepest rollback so far, in frames
+    pub max_depth: u32,
+    /// Predicted inputs that were later confirmed
+    pub predictions: u32,
+    /// Confirmed inputs that differed from their prediction
+    pub mispredictions: u32,
+    /// Depth of the most recent rollback, in frames
+    pub last_depth: u32,
+    /// Time the most recent rollback took, in microseconds (needs a clock)
+    pub last_rollback_micros: u64,
+    /// Slowest rollback so far, in microseconds (needs a clock)
+    pub max_rollback_micros: u64,
+}
+
+impl RollbackStats {
+    /// Share of checked predictions that were right, in percent
+    pub fn prediction_accuracy(&self) -> Option<u32> {
+        // Widened so long sessions don't overflow the multiplication
+        let (predictions, misses) = (self.predictions as u64, self.mispredictions as u64);
+        (predictions > 0).then(|| ((predictions - misses) * 100 / predictions) as u32)
+    }
+}
+
+/// One player's input for one frame, as seen by the session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputRecord {
+    pub frame: u64,
+    /// Input the simulation used
+    pub input: InputState,
+    /// Whether the input came from the player rather than prediction
+    pub confirmed: bool,
+    /// Prediction the simulation ran with while the input was missing
+    /// (None if it arrived in time); kept after confirmation so input
+    /// displays can ghost it next to the real input
+    pub predicted: Option<InputState>,
+}
+
+impl InputRecord {
+    /// Whether the confirmed input differs from what was predicted
+    pub fn mispredicted(&self) -> bool {
+        self.confirmed && self.predicted.is_some_and(|p| p != self.input)
+    }
+}
+
+/// History kept for one frame of the window
+#[derive(Clone)]
+struct FrameSlot {
+    frame: u64,
+    /// Inputs received for this frame
+    confirmed: [Option<InputState>; MAX_PLAYERS],
+    /// Inputs the simulation actually used
+    used: [InputState; MAX_PLAYERS],
+    /// Predictions replaced by late inputs
+    predicted: [Option<InputState>; MAX_PLAYERS],
+    /// Consecutive predicted frames ending here (0 when confirmed)
+    streak: [u32; MAX_PLAYERS],
+    /// State before this frame was simulated
+    snapshot: Option<EngineSnapshot>,
+}
+
+impl Default for FrameSlot {
+    fn default() -> Self {
+        Self {
+            frame: 0,
+            confirmed: [None; MAX_PLAYERS],
+            used: [InputState::neutral(); MAX_PLAYERS],
+            predicted: [None; MAX_PLAYERS],
+            streak: [0; MAX_PLAYERS],
+            snapshot: None,
+        }
+    }
+}
+
+/// Engine wrapper that predicts missing inputs and rolls back on mismatch
+pub struct RollbackSession {
+    engine: Engine,
+    slots: Vec<FrameSlot>,
+    rollback_from: Option<u64>,
+    stats: RollbackStats,
+    clock: Option<fn() -> u64>,
+    prediction: PredictionPolicy,
+    recording: Option<Replay>,
+}
+
+impl RollbackSession {
+    /// Start a session from an engine whose match is already initialized
+    pub fn new(engine: Engine) -> Self {
+        Self {
+            engine,
+            // Past window for rollback plus the same again for early inputs
+            slots: vec![FrameSlot::default(); SLOT_COUNT],
+            rollback_from: None,
+            stats: RollbackStats::default(),
+            clock: None,
+            prediction: PredictionPolicy::default(),
+            recording: None,
+        }
+    }
+
+    /// Predict missing inputs with a different policy
+    pub fn with_prediction(mut self, policy: PredictionPolicy) -> Self {
+        self.prediction = policy;
+        self
+    }
+
+    pub fn prediction(&self) -> PredictionPolicy {
+        self.prediction
+    }
+
+    /// Time rollbacks with a monotonic microsecond clock
+    pub fn with_clock(mut self, clock: fn() -> u64) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Record the match as a replay, before the first `advance`
+    ///
+    /// `setup` is what the engine's match was started with.
+    pub fn with_recording(mut self, setup: MatchSetup) -> Self {
+        self.recording = Some(Replay::new(setup).with_stage(self.engine.config.stage));
+        self
+    }
+
+    /// Stop recording and hand over the replay, noting the prediction
+    /// policy; frames not yet confirmed keep the inputs that were predicted
+    pub fn take_replay(&mut self) -> Option<Replay> {
+        let policy = self.prediction;
+        self.recording
+            .take()
+            .map(|replay| replay.with_prediction(policy))
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn stats(&self) -> RollbackStats {
+        self.stats
+    }
+
+    /// Next frame to be simulated
+    pub fn current_frame(&self) -> u64 {
+        self.engine.frame.0
+    }
+
+    /// Provide a player's input for a frame (local or remote)
+    ///
+    /// Frames up to a window ahead are accepted, for local input delay.
+    /// Inputs for past frames inside the window trigger a rollback on the
+    /// next `advance` if they differ from the prediction that was used.
+    pub fn add_input(
+        &mut self,
+        player: usize,
+        frame: u64,
+        input: InputState,
+    ) -> Result<(), RollbackError> {
+        if player >= MAX_PLAYERS {
+            return Err(RollbackError::InvalidPlayer(player));
+        }
+        let current = self.current_frame();
+        let window = ROLLBACK_WINDOW as u64;
+        let oldest = current.saturating_sub(window - 1);
+        if frame < oldest {
+            return Err(RollbackError::TooLate { frame, oldest });
+        }
+        let newest = current + window;
+        if frame > newest {
+            return Err(RollbackError::TooEarly { frame, newest });
+        }
+
+        let slot = self.slot_mut(frame);
+        if slot.frame != frame {
+            *slot = FrameSlot {
+                frame,
+                ..FrameSlot::default()
+            };
+        }
+        let was_predicted = frame < current && slot.confirmed[player].is_none();
+        let mispredicted = frame < current && slot.used[player] != input;
+        slot.confirmed[player] = Some(input);
+        if was_predicted {
+            slot.predicted[player] = Some(slot.used[player]);
+        }
+
+        if was_predicted {
+            self.stats.predictions += 1;
+            if mispredicted {
+                self.stats.mispredictions += 1;
+            }
+        }
+        if mispredicted {
+            self.rollback_from = Some(self.rollback_from.map_or(frame, |f| f.min(frame)));
+        }
+        Ok(())
+    }
+
+    /// Correct any mispredictions, then simulate the current frame
+    pub fn advance(&mut self) {
+        if let Some(from) = self.rollback_from.take() {
+            self.rollback(from);
+        }
+        self.simulate();
+    }
+
+    /// Whether every player's input for `frame` has arrived
+    pub fn is_confirmed(&self, frame: u64) -> bool {
+        let slot = self.slot(frame);
+        slot.frame == frame && slot.confirmed.iter().all(Option::is_some)
+    }
+
+    /// A player's simulated inputs still in the window, oldest first
+    ///
+    /// Unconfirmed entries show the prediction the simulation used, both as
+    /// `input` and `predicted`.
+    pub fn input_history(&self, player: usize) -> Vec<InputRecord> {
+        if player >= MAX_PLAYERS {
+            return Vec::new();
+        }
+        let current = self.current_frame();
+        let oldest = current.saturating_sub(ROLLBACK_WINDOW as u64);
+        (oldest..current)
+            .map(|frame| self.slot(frame))
+            .map(|slot| {
+                let confirmed = slot.confirmed[player].is_some();
+                InputRecord {
+                    frame: slot.frame,
+                    input: slot.used[player],
+                    confirmed,
+                    predicted: if confirmed {
+                        slot.predicted[player]
+                    } else {
+                        Some(slot.used[player])
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn rollback(&mut self, from: u64) {
+        let current = self.current_frame();
+        let Some(snapshot) = self.slot(from).snapshot.clone() else {
+            return;
+        };
+        snapshot.restore(&mut self.engine);
+
+        let started = self.clock.map(|clock| clock());
+        while self.current_frame() < current {
+            // A corrected input can end the match early, after which the
+            // engine stops advancing frames
+            let frame = self.current_frame();
+            self.simulate();
+            if self.current_frame() == frame {
+                break;
+            }
+        }
+
+        let depth = (current - from) as u32;
+        self.stats.rollbacks += 1;
+        self.stats.resimulated_frames += depth;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        self.stats.last_depth = depth;
+        if let (Some(clock), Some(started)) = (self.clock, started) {
+            let elapsed = clock().saturating_sub(started);
+            self.stats.last_rollback_micros = elapsed;
+            self.stats.max_rollback_micros = self.stats.max_rollback_micros.max(elapsed);
+        }
+    }
+
+    /// Save state, pick inputs (confirmed or predicted), and tick once
+    fn simulate(&mut self) {
+        let frame = self.current_frame();
+        let (previous, previous_streak) = if frame > 0 {
+            let slot = self.slot(frame - 1);
+            (slot.used, slot.streak)
+        } else {
+            ([InputState::neutral(); MAX_PLAYERS], [0; MAX_PLAYERS])
+        };
+        let policy = self.prediction;
+        let snapshot = self.engine.save_snapshot();
+
+        let slot = self.slot_mut(frame);
+        if slot.frame != frame {
+            *slot = FrameSlot {
+                frame,
+                ..FrameSlot::default()
+            };
+        }
+        for player in 0..MAX_PLAYERS {
+            match slot.confirmed[player] {
+                Some(input) => {
+                    slot.used[player] = input;
+                    slot.streak[player] = 0;
+                }
+                None => {
+                    let streak = previous_streak[player] + 1;
+                    slot.used[player] = policy.predict(previous[player], streak);
+                    slot.streak[player] = streak;
+                }
+            }
+        }
+        slot.snapshot = Some(snapshot);
+        let [p1, p2] = slot.used;
+
+        self.engine.tick(p1, p2);
+        // A resimulated frame replaces what was recorded for it
+        let advanced = self.current_frame() != frame;
+        if let (Some(recording), true) = (&mut self.recording, advanced) {
+            recording.truncate(frame as usize);
+            recording.record(&self.engine);
+        }
+    }
+
+    fn slot(&self, frame: u64) -> &FrameSlot {
+        &self.slots[(frame % SLOT_COUNT as u64) as usize]
+    }
+
+    fn slot_mut(&mut self, frame: u64) -> &mut FrameSlot {
+        &mut self.slots[(frame % SLOT_COUNT as u64) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EngineConfig;
+    use crate::engine::GameResult;
+    use crate::input::Direction;
+    use crate::replay::ReplayPlayer;
+    use crate::snapshot::assert_snapshots_match;
+
+    fn session() -> RollbackSession {
+        let mut engine = Engine::new();
+        engine.init_match();
+        RollbackSession::new(engine)
+    }
+
+    fn remote_input(frame: u64) -> InputState {
+        let mut input = InputState::neutral();
+        input.direction = Direction::Forward;
+        input.light = frame.is_multiple_of(5);
+        input
+    }
+
+    #[test]
+    fn test_late_inputs_resimulate_to_match_local_play() {
+        let mut reference = Engine::new();
+        reference.init_match();
+        for frame in 0..40 {
+            reference.tick(InputState::neutral(), remote_input(frame));
+        }
+
+        // Remote inputs arrive 3 frames late
+        let mut session = session();
+        for frame in 0..40 {
+            session.add_input(0, frame, InputState::neutral()).unwrap();
+            if frame >= 3 {
+                session
+                    .add_input(1, frame - 3, remote_input(frame - 3))
+                    .unwrap();
+            }
+            session.advance();
+        }
+        for frame in 37..40 {
+            session.add_input(1, frame, remote_input(frame)).unwrap();
+        }
+        session.advance();
+        // The extra advance simulated frame 40 with predicted inputs
+        reference.tick(InputState::neutral(), remote_input(39));
+
+        assert_snapshots_match(
+            &reference.save_snapshot(),
+            &session.engine().save_snapshot(),
+        );
+        let stats = session.stats();
+        assert!(stats.rollbacks > 0);
+        assert!(stats.max_depth <= 3);
+    }
+
+    #[test]
+    fn test_correct_prediction_skips_rollback() {
+        let mut session = session();
+        for frame in 0..5 {
+            session.add_input(0, frame, InputState::neutral()).unwrap();
+            session.advance();
+            // Remote stays neutral, which matches the prediction
+            session.add_input(1, frame, InputState::neutral()).unwrap();
+        }
+        session.advance();
+        assert_eq!(session.stats().rollbacks, 0);
+        assert!(session.is_confirmed(4));
+        assert!(!session.is_confirmed(5));
+    }
+
+    #[test]
+    fn test_inputs_outside_window_are_rejected() {
+        let mut session = session();
+        for _ in 0..20 {
+            session.advance();
+        }
+        assert_eq!(
+            session.add_input(1, 2, InputState::neutral()),
+            Err(RollbackError::TooLate {
+                frame: 2,
+                oldest: 20 - ROLLBACK_WINDOW as u64 + 1
+            })
+        );
+        assert!(matches!(
+            session.add_input(1, 100, InputState::neutral()),
+            Err(RollbackError::TooEarly { .. })
+        ));
+        assert_eq!(
+            session.add_input(2, 20, InputState::neutral()),
+            Err(RollbackError::InvalidPlayer(2))
+        );
+    }
+
+    #[test]
+    fn test_input_history_marks_predictions() {
+        let mut session = session();
+        let forward = remote_input(1);
+        for frame in 0..4 {
+            session.add_input(0, frame, InputState::neutral()).unwrap();
+            session.advance();
+        }
+        let history = session.input_history(1);
+        assert_eq!(history.len(), 4);
+        assert!(history.iter().all(|r| !r.confirmed));
+
+        session.add_input(1, 0, InputState::neutral()).unwrap();
+        session.add_input(1, 1, forward).unwrap();
+        session.advance();
+
+        let history = session.input_history(1);
+        assert_eq!(history[0].frame, 0);
+        assert!(history[0].confirmed && history[1].confirmed);
+        // The predictions they replaced stay visible as ghosts
+        assert_eq!(history[1].predicted, Some(InputState::neutral()));
+        assert!(!history[0].mispredicted() && history[1].mispredicted());
+        // Frames after the correction now predict the corrected input
+        assert_eq!(history[2].input, forward);
+        assert!(!history[2].confirmed);
+
+        let stats = session.stats();
+        assert_eq!((stats.predictions, stats.mispredictions), (2, 1));
+        assert_eq!(stats.prediction_accuracy(), Some(50));
+        assert!(session.input_history(2).is_empty());
+
+        // Long sessions don't overflow
+        let long = RollbackStats {
+            predictions: u32::MAX,
+            mispredictions: u32::MAX / 4,
+            ..stats
+        };
+        assert_eq!(long.prediction_accuracy(), Some(75));
+    }
+
+    #[test]
+    fn test_prediction_policies() {
+        let forward = remote_input(1);
+        let predicted_after_outage = |policy| {
+            let mut session = session().with_prediction(policy);
+            session.add_input(1, 0, forward).unwrap();
+            for frame in 0..6 {
+                session.add_input(0, frame, InputState::neutral()).unwrap();
+                session.advance();
+            }
+            session
+                .input_history(1)
+                .iter()
+                .map(|r| r.input == forward)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            predicted_after_outage(PredictionPolicy::HoldLast),
+            [true; 6]
+        );
+        assert_eq!(
+            predicted_after_outage(PredictionPolicy::DecayToNeutral { frames: 3 }),
+            [true, true, true, true, false, false]
+        );
+        assert_eq!(
+            predicted_after_outage(PredictionPolicy::Neutral),
+            [true, false, false, false, false, false]
+        );
+        assert_eq!(
+            PredictionPolicy::DecayToNeutral { frames: 0 }.predict(forward, 1),
+            InputState::neutral()
+        );
+    }
+
+    #[test]
+    fn test_recorded_session_replays_to_the_same_state() {
+        let policy = PredictionPolicy::DecayToNeutral { frames: 2 };
+        let mut session = session()
+            .with_prediction(policy)
+            .with_recording(MatchSetup::default());
+        for frame in 0..40 {
+            session.add_input(0, frame, InputState::neutral()).unwrap();
+            if frame >= 3 {
+                session
+                    .add_input(1, frame - 3, remote_input(frame - 3))
+                    .unwrap();
+            }
+            session.advance();
+        }
+        assert!(session.stats().rollbacks > 0);
+
+        let replay = session.take_replay().unwrap();
+        assert_eq!((replay.len(), replay.prediction), (40, Some(policy)));
+        assert_eq!(session.take_replay(), None);
+        let mut player = ReplayPlayer::new(replay);
+        while player.tick() {}
+        assert_snapshots_match(
+            &session.engine().save_snapshot(),
+            &player.engine().save_snapshot(),
+        );
+    }
+
+    #[test]
+    fn test_deep_rollback_is_timed() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        // Fake clock advancing 10us per reading
+        static NOW: AtomicU64 = AtomicU64::new(0);
+        fn clock() -> u64 {
+            NOW.fetch_add(10, Ordering::Relaxed)
+        }
+
+        let mut session = session().with_clock(clock);
+        for frame in 0..8 {
+            session.add_input(0, frame, InputState::neutral()).unwrap();
+            session.advance();
+        }
+        session.add_input(1, 0, remote_input(0)).unwrap();
+        session.advance();
+
+        let stats = session.stats();
+        assert_eq!(stats.last_depth, 8);
+        assert_eq!(stats.last_rollback_micros, 10);
+        assert_eq!(stats.max_rollback_micros, 10);
+    }
+
+    #[test]
+    fn test_correction_that_ends_the_match_stops_resimulating() {
+        let mut config = EngineConfig::default();
+        config.game.rounds_to_win = 1;
+        config.game.outro_frames = 0;
+        let mut engine = Engine::with_config(config);
+        engine.init_match();
+        engine.entities[0].as_mut().unwrap().health.set(1);
+        let x = engine.entities[0].as_ref().unwrap().physics.position.x;
+        engine.entities[1].as_mut().unwrap().physics.position.x = x + 30000;
+        let mut session = RollbackSession::new(engine);
+
+        for frame in 0..8 {
+            session.add_input(0, frame, InputState::neutral()).unwrap();
+            session.advance();
+        }
+        assert_eq!(session.engine().game_result, GameResult::InProgress);
+
+        // The remote jab arrives late and lands a KO inside the window
+        let jab = InputState {
+            light: true,
+            ..InputState::neutral()
+        };
+        session.add_input(1, 0, jab).unwrap();
+        session.advance();
+        assert_eq!(session.engine().game_result, GameResult::Player2Wins);
+        assert!(session.current_frame() < 8);
+    }
+}
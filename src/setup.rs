@@ -0,0 +1,239 @@
+//! Seeded match setup
+//!
+//! A `MatchSetup` describes how a match should start. Any variation left as
+//! `None` is drawn from the seed, so the same setup always produces the same
+//! starting conditions.
+
+use crate::constants::*;
+use crate::types::{Fixed, PlayerId};
+
+/// Requested starting conditions for a match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchSetup {
+    /// Seed for every variation not fixed below
+    pub seed: u64,
+    /// Put player 1 on the right (`None` = seeded when `randomize_sides`)
+    pub swap_sides: Option<bool>,
+    /// Whether an unset `swap_sides` is drawn from the seed
+    pub randomize_sides: bool,
+    /// Distance between fighters (`None` = standard distance)
+    pub distance: Option<i32>,
+    /// Seeded variation added to the distance, up to this amount either way
+    pub distance_variation: i32,
+    /// Player allowed to attack first (`None` = seeded when handicapped)
+    pub first_attacker: Option<PlayerId>,
+    /// Frames the other player must wait before attacking each round
+    pub handicap_frames: u32,
+    /// Rule changes for the whole match
+    pub mutators: Mutators,
+}
+
+/// Party-mode and training rule changes, fixed for a whole match and
+/// recorded in its replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mutators {
+    /// Simulation ticks per rendered frame (2 = turbo), 1 to
+    /// `MAX_TICKS_PER_FRAME`; frontends read it through
+    /// `Engine::ticks_per_frame`
+    pub speed: u8,
+    /// Gravity, in percent
+    pub gravity_percent: u16,
+    /// Damage dealt, in percent
+    pub damage_percent: u16,
+    /// Meter gained, in percent
+    pub meter_gain_percent: u16,
+}
+
+impl Default for Mutators {
+    fn default() -> Self {
+        Self {
+            speed: 1,
+            gravity_percent: 100,
+            damage_percent: 100,
+            meter_gain_percent: 100,
+        }
+    }
+}
+
+impl Mutators {
+    /// Ticks to run per rendered frame, clamped to the supported range
+    pub fn ticks_per_frame(&self) -> u32 {
+        self.speed.clamp(1, MAX_TICKS_PER_FRAME) as u32
+    }
+
+    /// Damage after the damage mutator; every source of damage goes
+    /// through this, strikes, throws and cinematics alike
+    pub fn scale_damage(&self, damage: i32) -> i32 {
+        Fixed(damage).percent(self.damage_percent as i32).raw()
+    }
+}
+
+impl MatchSetup {
+    /// Setup with every variation drawn from `seed`
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            randomize_sides: true,
+            distance_variation: START_DISTANCE_VARIATION,
+            ..Default::default()
+        }
+    }
+
+    /// Resolve the setup into concrete starting conditions
+    pub fn resolve(&self) -> StartingConditions {
+        let mut rng = SplitMix64::new(self.seed);
+
+        let swap_sides = match self.swap_sides {
+            Some(swap) => swap,
+            None => self.randomize_sides && rng.next() & 1 == 1,
+        };
+
+        let variation = self.distance_variation.max(0);
+        let jitter = if variation > 0 {
+            (rng.below(variation as u64 * 2 + 1) as i64 - variation as i64) as i32
+        } else {
+            0
+        };
+        // Both come from replays and the C ABI, so extreme values saturate
+        let distance = self
+            .distance
+            .unwrap_or(START_DISTANCE)
+            .saturating_add(jitter)
+            .max(0);
+
+        let first_attacker = match self.first_attacker {
+            Some(player) => player,
+            None if self.handicap_frames > 0 => PlayerId(rng.below(MAX_PLAYERS as u64) as u8),
+            None => PlayerId::PLAYER_1,
+        };
+
+        StartingConditions {
+            swap_sides,
+            distance,
+            first_attacker,
+            handicap_frames: self.handicap_frames,
+        }
+    }
+}
+
+/// Concrete starting conditions, applied at the start of every round
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartingConditions {
+    pub swap_sides: bool,
+    pub distance: i32,
+    pub first_attacker: PlayerId,
+    pub handicap_frames: u32,
+}
+
+impl Default for StartingConditions {
+    fn default() -> Self {
+        MatchSetup::default().resolve()
+    }
+}
+
+impl StartingConditions {
+    /// Starting X position for a player
+    pub fn position_x(&self, player: PlayerId) -> i32 {
+        let left = player == PlayerId::PLAYER_1;
+        if left != self.swap_sides {
+            -self.distance / 2
+        } else {
+            self.distance / 2
+        }
+    }
+
+    /// Frames a player waits before attacking at the start of a round
+    pub fn attack_delay(&self, player: PlayerId) -> u32 {
+        if player == self.first_attacker {
+            0
+        } else {
+            self.handicap_frames
+        }
+    }
+}
+
+/// Small deterministic generator (SplitMix64)
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Value in `0..bound`
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_setup_matches_standard_start() {
+        let start = MatchSetup::default().resolve();
+        assert_eq!(start.position_x(PlayerId::PLAYER_1), -50000);
+        assert_eq!(start.position_x(PlayerId::PLAYER_2), 50000);
+        assert_eq!(start.attack_delay(PlayerId::PLAYER_2), 0);
+    }
+
+    #[test]
+    fn test_seeded_setup_is_deterministic() {
+        for seed in 0..20 {
+            assert_eq!(
+                MatchSetup::seeded(seed).resolve(),
+                MatchSetup::seeded(seed).resolve()
+            );
+        }
+        let varied = (0..20)
+            .map(|seed| MatchSetup::seeded(seed).resolve())
+            .any(|s| s != MatchSetup::seeded(0).resolve());
+        assert!(varied);
+    }
+
+    #[test]
+    fn test_explicit_values_override_seed() {
+        let setup = MatchSetup {
+            swap_sides: Some(true),
+            distance: Some(40000),
+            distance_variation: 0,
+            first_attacker: Some(PlayerId::PLAYER_2),
+            handicap_frames: 30,
+            ..MatchSetup::seeded(7)
+        };
+        let start = setup.resolve();
+        assert_eq!(start.position_x(PlayerId::PLAYER_1), 20000);
+        assert_eq!(start.position_x(PlayerId::PLAYER_2), -20000);
+        assert_eq!(start.attack_delay(PlayerId::PLAYER_1), 30);
+        assert_eq!(start.attack_delay(PlayerId::PLAYER_2), 0);
+    }
+
+    #[test]
+    fn test_extreme_distances_saturate() {
+        for seed in 0..20 {
+            let setup = MatchSetup {
+                distance: Some(i32::MAX),
+                distance_variation: i32::MAX,
+                ..MatchSetup::seeded(seed)
+            };
+            assert!(setup.resolve().distance >= 0);
+        }
+        let far = MatchSetup {
+            distance: Some(i32::MAX),
+            distance_variation: 0,
+            ..Default::default()
+        };
+        assert_eq!(far.resolve().distance, i32::MAX);
+    }
+}
@@ -0,0 +1,463 @@
+//! Engine snapshots for rollback, resimulation, and debugging
+//!
+//! A snapshot holds every piece of simulation state needed to resume a match
+//! from a given frame. Collision boxes and metrics are rebuilt each tick and
+//! are not included.
+
+use core::fmt;
+
+use crate::constants::*;
+use crate::engine::{Engine, GameResult};
+use crate::entity::Entity;
+use crate::input::InputManager;
+use crate::link::PairLink;
+use crate::projectile::Projectile;
+use crate::types::Frame;
+
+/// Reasons a snapshot can't be loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// Captured under different simulation rules (`ENGINE_DATA_VERSION`)
+    EngineVersion(u16),
+}
+
+/// Saved simulation state of an engine
+#[derive(Clone)]
+pub struct EngineSnapshot {
+    /// `ENGINE_DATA_VERSION` of the engine that captured it
+    pub data_version: u16,
+    pub frame: Frame,
+    pub entities: [Option<Entity>; MAX_ENTITIES],
+    pub entity_count: usize,
+    pub input_manager: InputManager,
+    pub game_result: GameResult,
+    pub round: u32,
+    pub round_wins: [u32; MAX_PLAYERS],
+    pub round_start_frame: Frame,
+    pub round_bonus: [i32; MAX_PLAYERS],
+    pub outro_remaining: u32,
+    pub link: Option<PairLink>,
+    pub projectiles: [Option<Projectile>; MAX_PROJECTILES],
+}
+
+/// A single field that differs between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// Path of the field, e.g. `entities[1].physics.position`
+    pub field: String,
+    /// Value in `self`
+    pub left: String,
+    /// Value in `other`
+    pub right: String,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} != {}", self.field, self.left, self.right)
+    }
+}
+
+impl EngineSnapshot {
+    /// Bytes a snapshot copies: its fixed layout, as state definitions are
+    /// shared through `Arc` rather than copied
+    pub const fn size_bytes() -> usize {
+        core::mem::size_of::<Self>()
+    }
+
+    /// Capture the simulation state of an engine
+    pub fn capture(engine: &Engine) -> Self {
+        Self {
+            data_version: ENGINE_DATA_VERSION,
+            frame: engine.frame,
+            entities: engine.entities.clone(),
+            entity_count: engine.entity_count,
+            input_manager: engine.input_manager.clone(),
+            game_result: engine.game_result,
+            round: engine.round,
+            round_wins: engine.round_wins,
+            round_start_frame: engine.round_start_frame,
+            round_bonus: engine.round_bonus,
+            outro_remaining: engine.outro_remaining,
+            link: engine.link,
+            projectiles: engine.projectiles,
+        }
+    }
+
+    /// Restore this snapshot into an engine, without a version check
+    ///
+    /// For snapshots the crate captured itself (rollback, rewind, replay
+    /// seeking); everything else goes through `Engine::load_snapshot`.
+    pub(crate) fn restore(&self, engine: &mut Engine) {
+        engine.frame = self.frame;
+        engine.entities = self.entities.clone();
+        engine.entity_count = self.entity_count;
+        engine.input_manager = self.input_manager.clone();
+        engine.game_result = self.game_result;
+        engine.round = self.round;
+        engine.round_wins = self.round_wins;
+        engine.round_start_frame = self.round_start_frame;
+        engine.round_bonus = self.round_bonus;
+        engine.outro_remaining = self.outro_remaining;
+        engine.link = self.link;
+        engine.projectiles = self.projectiles;
+        engine.validate_link();
+    }
+
+    /// List every gameplay-relevant field that differs from `other`
+    ///
+    /// An empty list means the two snapshots are equivalent.
+    pub fn diff(&self, other: &EngineSnapshot) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+
+        compare(
+            &mut diffs,
+            "data_version",
+            &self.data_version,
+            &other.data_version,
+        );
+        compare(&mut diffs, "frame", &self.frame.0, &other.frame.0);
+        compare(
+            &mut diffs,
+            "entity_count",
+            &self.entity_count,
+            &other.entity_count,
+        );
+        compare(
+            &mut diffs,
+            "game_result",
+            &self.game_result,
+            &other.game_result,
+        );
+        compare(&mut diffs, "round", &self.round, &other.round);
+        compare(
+            &mut diffs,
+            "round_wins",
+            &self.round_wins,
+            &other.round_wins,
+        );
+        compare(
+            &mut diffs,
+            "round_start_frame",
+            &self.round_start_frame.0,
+            &other.round_start_frame.0,
+        );
+        compare(
+            &mut diffs,
+            "round_bonus",
+            &self.round_bonus,
+            &other.round_bonus,
+        );
+        compare(
+            &mut diffs,
+            "outro_remaining",
+            &self.outro_remaining,
+            &other.outro_remaining,
+        );
+
+        compare(&mut diffs, "link", &self.link, &other.link);
+        for (i, (a, b)) in self.projectiles.iter().zip(&other.projectiles).enumerate() {
+            compare(&mut diffs, &format!("projectiles[{}]", i), a, b);
+        }
+
+        for i in 0..MAX_ENTITIES {
+            match (&self.entities[i], &other.entities[i]) {
+                (Some(a), Some(b)) => diff_entity(&mut diffs, i, a, b),
+                (None, None) => {}
+                (a, b) => diffs.push(FieldDiff {
+                    field: format!("entities[{}]", i),
+                    left: presence(a.is_some()),
+                    right: presence(b.is_some()),
+                }),
+            }
+        }
+
+        for player in 0..MAX_PLAYERS {
+            let a = &self.input_manager.player_inputs[player];
+            let b = &other.input_manager.player_inputs[player];
+            if a != b {
+                diffs.push(FieldDiff {
+                    field: format!("inputs[{}]", player),
+                    left: format!("{:?}", a.current()),
+                    right: format!("{:?}", b.current()),
+                });
+            }
+        }
+
+        diffs
+    }
+}
+
+impl Engine {
+    /// Capture the current simulation state
+    pub fn save_snapshot(&self) -> EngineSnapshot {
+        EngineSnapshot::capture(self)
+    }
+
+    /// Restore a previously captured simulation state
+    ///
+    /// Snapshots captured under another `ENGINE_DATA_VERSION` are refused
+    /// and leave the engine untouched.
+    pub fn load_snapshot(&mut self, snapshot: &EngineSnapshot) -> Result<(), SnapshotError> {
+        if snapshot.data_version != ENGINE_DATA_VERSION {
+            return Err(SnapshotError::EngineVersion(snapshot.data_version));
+        }
+        snapshot.restore(self);
+        Ok(())
+    }
+}
+
+/// Panic with a readable field list if two snapshots differ
+///
+/// Intended for rollback and resimulation tests.
+#[track_caller]
+pub fn assert_snapshots_match(expected: &EngineSnapshot, actual: &EngineSnapshot) {
+    let diffs = expected.diff(actual);
+    if !diffs.is_empty() {
+        let lines: Vec<String> = diffs.iter().map(|d| format!("  {}", d)).collect();
+        panic!(
+            "snapshots differ in {} field(s):\n{}",
+            diffs.len(),
+            lines.join("\n")
+        );
+    }
+}
+
+fn compare<T: PartialEq + fmt::Debug>(diffs: &mut Vec<FieldDiff>, field: &str, a: &T, b: &T) {
+    if a != b {
+        diffs.push(FieldDiff {
+            field: field.to_string(),
+            left: format!("{:?}", a),
+            right: format!("{:?}", b),
+        });
+    }
+}
+
+fn presence(present: bool) -> String {
+    if present { "present" } else { "absent" }.to_string()
+}
+
+fn diff_entity(diffs: &mut Vec<FieldDiff>, index: usize, a: &Entity, b: &Entity) {
+    let field = |name: &str| format!("entities[{}].{}", index, name);
+
+    compare(diffs, &field("id"), &a.id, &b.id);
+    compare(diffs, &field("facing"), &a.facing, &b.facing);
+    compare(
+        diffs,
+        &field("health"),
+        &a.health.current,
+        &b.health.current,
+    );
+    compare(
+        diffs,
+        &field("physics.position"),
+        &a.physics.position,
+        &b.physics.position,
+    );
+    compare(
+        diffs,
+        &field("physics.velocity"),
+        &a.physics.velocity,
+        &b.physics.velocity,
+    );
+    compare(
+        diffs,
+        &field("physics.momentum"),
+        &a.physics.momentum,
+        &b.physics.momentum,
+    );
+    compare(
+        diffs,
+        &field("physics.on_ground"),
+        &a.physics.on_ground,
+        &b.physics.on_ground,
+    );
+    compare(
+        diffs,
+        &field("presentation"),
+        &a.presentation,
+        &b.presentation,
+    );
+    compare(
+        diffs,
+        &field("state"),
+        &a.state_machine.current_state(),
+        &b.state_machine.current_state(),
+    );
+    compare(
+        diffs,
+        &field("state_frame"),
+        &a.state_machine.state_frame(),
+        &b.state_machine.state_frame(),
+    );
+    compare(
+        diffs,
+        &field("hitstun_remaining"),
+        &a.hitstun_remaining,
+        &b.hitstun_remaining,
+    );
+    compare(
+        diffs,
+        &field("blockstun_remaining"),
+        &a.blockstun_remaining,
+        &b.blockstun_remaining,
+    );
+    compare(
+        diffs,
+        &field("knockdown_remaining"),
+        &a.knockdown_remaining,
+        &b.knockdown_remaining,
+    );
+    compare(
+        diffs,
+        &field("invuln_remaining"),
+        &a.invuln_remaining,
+        &b.invuln_remaining,
+    );
+    compare(diffs, &field("invuln"), &a.invuln, &b.invuln);
+    compare(
+        diffs,
+        &field("armor_remaining"),
+        &a.armor_remaining,
+        &b.armor_remaining,
+    );
+    compare(diffs, &field("armor_hits"), &a.armor_hits, &b.armor_hits);
+    compare(
+        diffs,
+        &field("armor_damage_percent"),
+        &a.armor_damage_percent,
+        &b.armor_damage_percent,
+    );
+    compare(
+        diffs,
+        &field("attack_delay"),
+        &a.attack_delay,
+        &b.attack_delay,
+    );
+    compare(diffs, &field("meter"), &a.meter, &b.meter);
+    compare(diffs, &field("guard"), &a.guard, &b.guard);
+    compare(diffs, &field("dizzy"), &a.dizzy, &b.dizzy);
+    compare(
+        diffs,
+        &field("dizzy_remaining"),
+        &a.dizzy_remaining,
+        &b.dizzy_remaining,
+    );
+    compare(
+        diffs,
+        &field("crush_protection_remaining"),
+        &a.crush_protection_remaining,
+        &b.crush_protection_remaining,
+    );
+    compare(
+        diffs,
+        &field("frames_since_action"),
+        &a.frames_since_action,
+        &b.frames_since_action,
+    );
+    compare(
+        diffs,
+        &field("frozen_frames"),
+        &a.frozen_frames,
+        &b.frozen_frames,
+    );
+    compare(diffs, &field("install"), &a.install, &b.install);
+    compare(diffs, &field("resources"), &a.resources, &b.resources);
+    compare(diffs, &field("combo_hits"), &a.combo_hits, &b.combo_hits);
+    compare(
+        diffs,
+        &field("combo_damage"),
+        &a.combo_damage,
+        &b.combo_damage,
+    );
+    compare(diffs, &field("stale_moves"), &a.stale_moves, &b.stale_moves);
+    compare(diffs, &field("blockstring"), &a.blockstring, &b.blockstring);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputState;
+    use crate::types::Vec2;
+
+    fn scripted_input(frame: u32) -> InputState {
+        let mut input = InputState::neutral();
+        input.direction = crate::input::Direction::Forward;
+        input.light = frame.is_multiple_of(7);
+        input
+    }
+
+    #[test]
+    fn test_identical_snapshots_have_no_diff() {
+        let mut engine = Engine::new();
+        engine.init_match();
+
+        let a = engine.save_snapshot();
+        let b = engine.save_snapshot();
+        assert!(a.diff(&b).is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_changed_fields() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let before = engine.save_snapshot();
+
+        engine.entities[1].as_mut().unwrap().health.current = 500;
+        engine.entities[0].as_mut().unwrap().physics.position = Vec2::new(1, 2);
+        let after = engine.save_snapshot();
+
+        let diffs = before.diff(&after);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].field, "entities[0].physics.position");
+        assert_eq!(diffs[1].field, "entities[1].health");
+        assert_eq!(diffs[1].to_string(), "entities[1].health: 1000 != 500");
+    }
+
+    #[test]
+    fn test_load_refuses_other_engine_versions() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let mut saved = engine.save_snapshot();
+        saved.data_version = ENGINE_DATA_VERSION + 1;
+        saved.frame = Frame(500);
+
+        assert_eq!(
+            engine.load_snapshot(&saved),
+            Err(SnapshotError::EngineVersion(ENGINE_DATA_VERSION + 1))
+        );
+        assert_eq!(engine.frame, Frame::ZERO);
+    }
+
+    #[test]
+    fn test_resimulation_matches() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        for frame in 0..30 {
+            engine.tick(scripted_input(frame), InputState::neutral());
+        }
+
+        let saved = engine.save_snapshot();
+        for frame in 30..90 {
+            engine.tick(scripted_input(frame), InputState::neutral());
+        }
+        let first_run = engine.save_snapshot();
+
+        engine.load_snapshot(&saved).unwrap();
+        for frame in 30..90 {
+            engine.tick(scripted_input(frame), InputState::neutral());
+        }
+        let second_run = engine.save_snapshot();
+
+        assert_snapshots_match(&first_run, &second_run);
+    }
+
+    #[test]
+    #[should_panic(expected = "snapshots differ")]
+    fn test_assert_snapshots_match_panics_on_mismatch() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let before = engine.save_snapshot();
+        engine.tick(scripted_input(0), InputState::neutral());
+        assert_snapshots_match(&before, &engine.save_snapshot());
+    }
+}
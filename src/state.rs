@@ -1,8 +1,16 @@
 //! State machine system for character states
 //! Each state has frame data and can transition to other states
 
+use std::sync::{Arc, OnceLock};
+
+use crate::characters::CharacterDef;
+use crate::chardef::{self, DEFAULT_CHARACTER};
+use crate::checksum::StateHasher;
 use crate::constants::*;
-use crate::hitbox::AttackData;
+use crate::hitbox::{AttackData, Invulnerability};
+use crate::input::Button;
+use crate::projectile::ProjectileDef;
+use crate::types::Rect;
 
 /// State ID for character states
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -15,13 +23,80 @@ pub enum StateId {
     LightAttack,
     MediumAttack,
     HeavyAttack,
+    Sweep,
     SpecialMove,
     Hitstun,
     Blockstun,
     Knockdown,
+    TechNeutral,
+    TechBack,
+    TechForward,
+    HeavyCharge,
+    ChargedHeavy,
+    Held,
+    Throw,
+    Throwing,
+    ThrowWhiff,
+    BackHitstun,
+    ThrowTech,
+    ExSpecial,
+    SuperMove,
+    JumpLight,
+    JumpMedium,
+    JumpHeavy,
+    Landing,
+    Dizzy,
+    /// Outro of the match winner; the pose picked by the button held when
+    /// the match was decided (0 none, then light, medium, heavy, special)
+    WinPose(u8),
+    /// Outro of the match loser
+    LosePose,
     Custom(u16),
 }
 
+impl StateId {
+    /// Stable numeric code (win poses are 31-35, custom states start at
+    /// 100), shared by the C ABI and state checksums
+    pub fn to_u32(self) -> u32 {
+        match self {
+            StateId::Idle => 0,
+            StateId::Walk => 1,
+            StateId::WalkBack => 2,
+            StateId::Crouch => 3,
+            StateId::Jump => 4,
+            StateId::LightAttack => 5,
+            StateId::MediumAttack => 6,
+            StateId::HeavyAttack => 7,
+            StateId::SpecialMove => 8,
+            StateId::Hitstun => 9,
+            StateId::Blockstun => 10,
+            StateId::Knockdown => 11,
+            StateId::Sweep => 12,
+            StateId::TechNeutral => 13,
+            StateId::TechBack => 14,
+            StateId::TechForward => 15,
+            StateId::HeavyCharge => 16,
+            StateId::ChargedHeavy => 17,
+            StateId::Held => 18,
+            StateId::Throw => 19,
+            StateId::Throwing => 20,
+            StateId::ThrowWhiff => 21,
+            StateId::BackHitstun => 22,
+            StateId::ThrowTech => 23,
+            StateId::JumpLight => 24,
+            StateId::JumpMedium => 25,
+            StateId::JumpHeavy => 26,
+            StateId::Landing => 27,
+            StateId::ExSpecial => 28,
+            StateId::SuperMove => 29,
+            StateId::Dizzy => 30,
+            StateId::WinPose(pose) => 31 + pose.min(4) as u32,
+            StateId::LosePose => 36,
+            StateId::Custom(id) => 100 + id as u32,
+        }
+    }
+}
+
 /// State type classification
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StateType {
@@ -31,7 +106,7 @@ pub enum StateType {
     Attack,
     /// Hurt state (being hit)
     Hurt,
-    /// Invincible state
+    /// Invincible state (no hurtboxes for its whole duration)
     Invincible,
 }
 
@@ -50,6 +125,56 @@ pub enum StateAction {
     SetVelocity { x: i32, y: i32 },
     /// Add momentum
     AddMomentum { x: i32, y: i32 },
+    /// Scale gravity (percent) until the state ends, e.g. for float attacks
+    SetGravityScale { percent: i32 },
+    /// Cap falling speed until the state ends
+    ClampFallSpeed { max: i32 },
+    /// Stop all vertical movement and momentum, e.g. to hang in the air
+    HaltMomentum,
+    /// Hold the character in place for a number of frames
+    PinPosition { frames: u32 },
+    /// Suspend vertical movement and gravity for a number of frames
+    FreezeVertical { frames: u32 },
+    /// Place a linked victim relative to this character (throws)
+    MoveVictim { x: i32, y: i32 },
+    /// Let go of a linked victim
+    ReleaseVictim,
+    /// Put a linked victim in a state (cinematics); it stays frozen there
+    /// until the next `VictimState` or the release
+    VictimState { state: StateId },
+    /// Deal guaranteed damage to a linked victim (cinematics)
+    DamageVictim { damage: i32 },
+    /// Spend super meter (never below zero)
+    ConsumeMeter { amount: i32 },
+    /// Ignore the given kinds of attack for a number of frames, counting
+    /// this one (reversal startup)
+    SetInvincible {
+        frames: u32,
+        invulnerability: Invulnerability,
+    },
+    /// Absorb up to `hits` strikes (`u32::MAX` for hyper armor) for a
+    /// number of frames, counting this one: they deal `damage_percent` of
+    /// their damage without a hit reaction. Throws go through armor
+    SetArmor {
+        hits: u32,
+        frames: u32,
+        damage_percent: i32,
+    },
+    /// Launch a projectile from an offset relative to this character
+    SpawnProjectile {
+        x: i32,
+        y: i32,
+        projectile: ProjectileDef,
+    },
+    /// Freeze every other character and their projectiles for a number of
+    /// frames (super flash)
+    SuperFlash { frames: u32 },
+    /// Enter an install: moves started during the next `frames` updates
+    /// have their timing changed by `modifier`
+    Install {
+        frames: u32,
+        modifier: FrameDataModifier,
+    },
     /// Transition to another state
     Transition { target: StateId },
     /// No action
@@ -69,15 +194,379 @@ impl FrameData {
     }
 }
 
+/// Hold-to-charge behavior of a state
+///
+/// While the button is held the state keeps charging; releasing it picks the
+/// tap or charged follow-up depending on how long it was held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChargeData {
+    pub button: Button,
+    /// State entered when released before full charge
+    pub tap: StateId,
+    /// State entered when released at or after full charge
+    pub charged: StateId,
+    /// Frames the button must be held to fully charge
+    pub full_charge_frames: u32,
+}
+
+/// Held-button behavior of a state
+///
+/// The state lasts as long as the button is held, replaying frames from
+/// `loop_from` whenever it reaches its end, and moves to `release` as soon
+/// as the button comes up (flight modes, sustained beams, held projectiles).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HoldData {
+    pub button: Button,
+    /// State entered when the button is released
+    pub release: StateId,
+    /// Frame the state loops back to while the button stays held
+    pub loop_from: u32,
+}
+
+/// Where a move can be started from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Footing {
+    #[default]
+    Anywhere,
+    /// Only while standing on the ground
+    Grounded,
+    /// Only while in the air
+    Airborne,
+}
+
+/// How often a move can be started before it is available again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UseLimit {
+    /// Once until the character lands
+    OncePerAirtime,
+    /// Once per combo the character lands, available again back in neutral
+    OncePerCombo,
+}
+
+/// A state an attack can be canceled into, and when
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelRoute {
+    pub target: StateId,
+    /// First state frame the cancel is allowed
+    pub start: u32,
+    /// Last state frame the cancel is allowed
+    pub end: u32,
+    /// Only once the attack has hit or been blocked
+    pub on_contact: bool,
+}
+
+impl CancelRoute {
+    /// Cancel into `target` during frames `start..=end` after contact
+    pub fn new(target: StateId, start: u32, end: u32) -> Self {
+        Self {
+            target,
+            start,
+            end,
+            on_contact: true,
+        }
+    }
+
+    /// Also allow the cancel when the attack whiffed
+    pub fn on_whiff(mut self) -> Self {
+        self.on_contact = false;
+        self
+    }
+
+    /// Whether the route is open on `frame`, given whether the attack made contact
+    pub fn open(&self, frame: u32, contact: bool) -> bool {
+        frame >= self.start && frame <= self.end && (contact || !self.on_contact)
+    }
+}
+
+/// Change to the timing of attacks, applied when they start (installs)
+///
+/// Startup is stretched or squeezed up to the first active frame; active
+/// frames keep their length and recovery gains or loses frames at the end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameDataModifier {
+    /// Frames added to startup (negative for faster); never below 1 frame
+    pub startup: i32,
+    /// Frames added to recovery (negative for faster); never cuts into the
+    /// move's last action
+    pub recovery: i32,
+}
+
+impl FrameDataModifier {
+    pub fn new(startup: i32, recovery: i32) -> Self {
+        Self { startup, recovery }
+    }
+}
+
+/// Active install of a fighter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Install {
+    pub modifier: FrameDataModifier,
+    /// Updates left before the install ends
+    pub remaining: u32,
+}
+
+/// How the frames of a started state map onto its definition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Timing {
+    /// Defined first active frame (0 leaves startup alone)
+    startup: u32,
+    /// First active frame after the modifier
+    adjusted_startup: u32,
+    /// Frames added to the end
+    recovery: i32,
+    /// Earliest end that still plays every action
+    floor: u32,
+}
+
+impl Timing {
+    fn new(state: &State, modifier: FrameDataModifier) -> Self {
+        if state.state_type != StateType::Attack || modifier == FrameDataModifier::default() {
+            return Self::default();
+        }
+        let startup = state.first_active_frame().unwrap_or(0);
+        let adjusted_startup = if startup == 0 {
+            0
+        } else {
+            (startup as i32).saturating_add(modifier.startup).max(1) as u32
+        };
+        let mut timing = Self {
+            startup,
+            adjusted_startup,
+            recovery: modifier.recovery,
+            floor: 0,
+        };
+        let last_action = state.frame_data.iter().map(|f| f.frame).max();
+        timing.floor = last_action.map_or(0, |frame| timing.elapsed(frame) + 1);
+        timing
+    }
+
+    /// Frame of the started state a defined frame plays on
+    fn elapsed(&self, frame: u32) -> u32 {
+        if frame < self.startup {
+            frame * self.adjusted_startup / self.startup
+        } else {
+            frame - self.startup + self.adjusted_startup
+        }
+    }
+
+    /// Defined frame shown on a frame of the started state
+    fn definition(&self, elapsed: u32) -> u32 {
+        if elapsed < self.adjusted_startup {
+            elapsed * self.startup / self.adjusted_startup
+        } else {
+            elapsed - self.adjusted_startup + self.startup
+        }
+    }
+
+    /// Frame the started state ends on, from its defined end
+    fn end(&self, end: u32) -> u32 {
+        let end = self.elapsed(end);
+        if self.recovery >= 0 {
+            end + self.recovery as u32
+        } else {
+            end.saturating_sub(self.recovery.unsigned_abs())
+                .max(self.floor.min(end))
+        }
+    }
+}
+
+/// Moves normals can be special canceled into
+pub const SPECIAL_CANCELS: [StateId; 3] =
+    [StateId::SpecialMove, StateId::ExSpecial, StateId::SuperMove];
+
+/// When a limited resource is refilled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceScope {
+    /// Refilled at the start of every round
+    Round,
+    /// Never refilled during a match
+    Match,
+}
+
+/// Resource with a fixed number of uses (a burst, a one-time install)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitedResource {
+    /// Uses left
+    pub remaining: u8,
+    /// Uses when full
+    pub uses: u8,
+    pub scope: ResourceScope,
+}
+
+impl LimitedResource {
+    pub fn new(uses: u8, scope: ResourceScope) -> Self {
+        Self {
+            remaining: uses,
+            uses,
+            scope,
+        }
+    }
+
+    /// Spend one use, if any is left
+    pub fn spend(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        true
+    }
+}
+
+/// Counter stance behavior of a state
+///
+/// Qualifying attacks landing during the active window are negated and the
+/// defender transitions into the punish state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterData {
+    /// First state frame the counter is active
+    pub start: u32,
+    /// Last state frame the counter is active
+    pub end: u32,
+    pub catches_low: bool,
+    pub catches_mid: bool,
+    pub catches_overhead: bool,
+    /// State entered after catching an attack
+    pub punish: StateId,
+}
+
+impl CounterData {
+    /// Counter catching mid and overhead attacks (a standing counter)
+    pub fn new(start: u32, end: u32, punish: StateId) -> Self {
+        Self {
+            start,
+            end,
+            catches_low: false,
+            catches_mid: true,
+            catches_overhead: true,
+            punish,
+        }
+    }
+
+    /// Also catch low attacks
+    pub fn with_low(mut self) -> Self {
+        self.catches_low = true;
+        self
+    }
+
+    /// Check whether an attack is caught on the given state frame
+    pub fn catches(&self, frame: u32, attack: &AttackData) -> bool {
+        let in_window = frame >= self.start && frame <= self.end;
+        let category = if attack.is_low {
+            self.catches_low
+        } else if attack.is_overhead {
+            self.catches_overhead
+        } else {
+            self.catches_mid
+        };
+        in_window && category
+    }
+}
+
+/// Renderer timeline of a state
+///
+/// Sprite (or mesh frame) indices hold from their key frame until the next
+/// key; cues fire once on their frame as `Cue::Animation`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Animation {
+    /// Animation clip the renderer plays
+    pub id: u16,
+    /// (first state frame, sprite index) keys, sorted by frame
+    pub sprites: Vec<(u32, u16)>,
+    /// (state frame, cue ID) triggers, sorted by frame
+    pub cues: Vec<(u32, u16)>,
+}
+
+impl Animation {
+    pub fn new(id: u16) -> Self {
+        Self {
+            id,
+            ..Default::default()
+        }
+    }
+
+    /// Show `sprite` from `frame` on
+    pub fn with_sprite(mut self, frame: u32, sprite: u16) -> Self {
+        let at = self.sprites.partition_point(|&(f, _)| f <= frame);
+        self.sprites.insert(at, (frame, sprite));
+        self
+    }
+
+    /// Raise cue `id` on `frame`
+    pub fn with_cue(mut self, frame: u32, id: u16) -> Self {
+        let at = self.cues.partition_point(|&(f, _)| f <= frame);
+        self.cues.insert(at, (frame, id));
+        self
+    }
+
+    /// Sprite index shown on a state frame (0 before the first key)
+    pub fn sprite_at(&self, frame: u32) -> u16 {
+        let at = self.sprites.partition_point(|&(f, _)| f <= frame);
+        at.checked_sub(1).map(|i| self.sprites[i].1).unwrap_or(0)
+    }
+
+    /// Cues raised on a state frame
+    pub fn cues_at(&self, frame: u32) -> impl Iterator<Item = u16> + '_ {
+        self.cues
+            .iter()
+            .filter(move |&&(f, _)| f == frame)
+            .map(|&(_, id)| id)
+    }
+}
+
+/// What the renderer should show for the current state frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationFrame {
+    pub animation: u16,
+    pub sprite: u16,
+}
+
+/// What a projectile guard does to projectiles it catches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardKind {
+    /// Send reflectable projectiles back, now owned by the reflector
+    Reflect,
+    /// Destroy absorbable projectiles without taking a hit
+    Absorb,
+}
+
+/// Window of a state during which projectiles are reflected or absorbed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectileGuard {
+    /// First state frame the guard is active
+    pub start: u32,
+    /// Last state frame the guard is active
+    pub end: u32,
+    pub kind: GuardKind,
+}
+
+impl ProjectileGuard {
+    pub fn new(start: u32, end: u32, kind: GuardKind) -> Self {
+        Self { start, end, kind }
+    }
+}
+
 /// State definition with frame data
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct State {
     pub id: StateId,
     pub state_type: StateType,
-    pub duration: u32,                                             // Total frames
-    pub can_cancel: bool,                                          // Can cancel to other states?
-    pub frame_data: [Option<FrameData>; MAX_FRAME_DATA_PER_STATE], // Frame-specific actions
-    pub frame_data_count: usize,
+    pub duration: u32,                             // Total frames
+    pub can_cancel: bool,                          // Free to start any move (neutral-like states)?
+    pub cancels: Vec<CancelRoute>,                 // Moves this state can be canceled into
+    pub frame_data: Vec<FrameData>,                // Frame-specific actions, in insertion order
+    pub mash_reduction: u32, // Frames removed per button press (0 = not mashable)
+    pub mash_min_duration: u32, // Mashing can't shorten the state below this
+    pub contact_duration: u32, // Total frames after contact (0 = same as duration)
+    pub charge: Option<ChargeData>, // Hold-to-charge behavior
+    pub hold: Option<HoldData>, // Lasts while a button is held
+    pub counter: Option<CounterData>, // Counter stance behavior
+    pub projectile_guard: Option<ProjectileGuard>, // Reflect or absorb window
+    pub animation: Option<Animation>, // Renderer timeline
+    pub back_turned: bool,   // Facing away from the opponent
+    pub footing: Footing,    // Where the move can be started from
+    pub use_limit: Option<UseLimit>, // Starts allowed before it resets
+    pub resource_cost: Option<usize>, // Limited resource slot spent to start the move
+    pub meter_required: i32, // Super meter needed to start the move
 }
 
 impl State {
@@ -87,49 +576,285 @@ impl State {
             state_type,
             duration,
             can_cancel: false,
-            frame_data: [None; MAX_FRAME_DATA_PER_STATE],
-            frame_data_count: 0,
+            cancels: Vec::new(),
+            frame_data: Vec::new(),
+            mash_reduction: 0,
+            mash_min_duration: 0,
+            contact_duration: 0,
+            charge: None,
+            hold: None,
+            counter: None,
+            projectile_guard: None,
+            animation: None,
+            back_turned: false,
+            footing: Footing::Anywhere,
+            use_limit: None,
+            resource_cost: None,
+            meter_required: 0,
         }
     }
 
+    /// Let any move start from this state, as from idle (walks, crouch, jump)
     pub fn with_cancel(mut self) -> Self {
         self.can_cancel = true;
         self
     }
 
+    /// Allow canceling into another state
+    pub fn with_cancel_route(mut self, route: CancelRoute) -> Self {
+        self.cancels.push(route);
+        self
+    }
+
+    /// Allow canceling into each of `targets` during frames `start..=end`
+    /// once the attack has made contact (chains and special cancels)
+    pub fn cancels_on_contact(mut self, targets: &[StateId], start: u32, end: u32) -> Self {
+        self.cancels.extend(
+            targets
+                .iter()
+                .map(|&target| CancelRoute::new(target, start, end)),
+        );
+        self
+    }
+
+    /// Let button presses shorten this state (throws, dizzies, grab supers)
+    pub fn with_mash(mut self, reduction_per_press: u32, min_duration: u32) -> Self {
+        self.mash_reduction = reduction_per_press;
+        self.mash_min_duration = min_duration;
+        self
+    }
+
+    /// Use a different total duration when the attack hits or is blocked,
+    /// letting recovery differ between whiff and contact
+    pub fn with_contact_recovery(mut self, duration: u32) -> Self {
+        self.contact_duration = duration;
+        self
+    }
+
+    /// Keep this state going while `hold.button` is held
+    pub fn with_hold(mut self, hold: HoldData) -> Self {
+        self.hold = Some(hold);
+        self
+    }
+
+    /// Make this a charging state released into `charge.tap` or `charge.charged`
+    pub fn with_charge(mut self, charge: ChargeData) -> Self {
+        self.charge = Some(charge);
+        self
+    }
+
+    /// Make this a counter stance
+    pub fn with_counter(mut self, counter: CounterData) -> Self {
+        self.counter = Some(counter);
+        self
+    }
+
+    /// Reflect or absorb projectiles during a window of this state
+    pub fn with_projectile_guard(mut self, guard: ProjectileGuard) -> Self {
+        self.projectile_guard = Some(guard);
+        self
+    }
+
+    /// Turn the character's back to the opponent: hits from the front land
+    /// on their back and can't be blocked
+    pub fn with_back_turned(mut self) -> Self {
+        self.back_turned = true;
+        self
+    }
+
+    /// Only allow starting this move on the ground
+    pub fn grounded_only(mut self) -> Self {
+        self.footing = Footing::Grounded;
+        self
+    }
+
+    /// Only allow starting this move in the air
+    pub fn airborne_only(mut self) -> Self {
+        self.footing = Footing::Airborne;
+        self
+    }
+
+    /// Limit how often this move can be started
+    pub fn with_use_limit(mut self, limit: UseLimit) -> Self {
+        self.use_limit = Some(limit);
+        self
+    }
+
+    /// Spend a use of the character's limited resource `slot` to start
+    /// this move; it can't be started once the resource is used up
+    pub fn spends_resource(mut self, slot: usize) -> Self {
+        self.resource_cost = Some(slot);
+        self
+    }
+
+    /// Require at least `meter` super meter to start this move; spending
+    /// it is left to a `ConsumeMeter` action
+    pub fn requires_meter(mut self, meter: i32) -> Self {
+        self.meter_required = meter;
+        self
+    }
+
+    /// Attach a renderer timeline
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = Some(animation);
+        self
+    }
+
+    /// Duration after removing mashed frames
+    pub fn mashed_duration(&self, presses: u32) -> u32 {
+        self.end_frame(presses, false)
+    }
+
+    /// Frame the state ends on, given mash presses and whether it made contact
+    pub fn end_frame(&self, presses: u32, contact: bool) -> u32 {
+        let duration = if contact && self.contact_duration > 0 {
+            self.contact_duration
+        } else {
+            self.duration
+        };
+        let floor = self.mash_min_duration.min(duration);
+        let reduction = presses.saturating_mul(self.mash_reduction);
+        duration.saturating_sub(reduction).max(floor)
+    }
+
+    /// First frame with a hitbox or projectile spawn
+    pub fn first_active_frame(&self) -> Option<u32> {
+        self.frame_data
+            .iter()
+            .filter(|f| {
+                matches!(
+                    f.action,
+                    StateAction::Hitbox { .. } | StateAction::SpawnProjectile { .. }
+                )
+            })
+            .map(|f| f.frame)
+            .min()
+    }
+
     /// Add frame data to this state
     pub fn add_frame_data(mut self, data: FrameData) -> Self {
-        if self.frame_data_count < MAX_FRAME_DATA_PER_STATE {
-            self.frame_data[self.frame_data_count] = Some(data);
-            self.frame_data_count += 1;
-        }
+        self.frame_data.push(data);
         self
     }
 
-    /// Get actions for a specific frame
-    pub fn get_actions(&self, frame: u32) -> [Option<StateAction>; MAX_ACTIONS_PER_FRAME] {
-        let mut actions = [None; MAX_ACTIONS_PER_FRAME];
-        let mut action_count = 0;
+    /// Iterate over actions for a specific frame, in the order they were added
+    pub fn actions_at(&self, frame: u32) -> impl Iterator<Item = &StateAction> {
+        self.frame_data
+            .iter()
+            .filter(move |data| data.frame == frame)
+            .map(|data| &data.action)
+    }
 
-        for i in 0..self.frame_data_count {
-            if let Some(data) = &self.frame_data[i] {
-                if data.frame == frame && action_count < MAX_ACTIONS_PER_FRAME {
-                    actions[action_count] = Some(data.action);
-                    action_count += 1;
-                }
-            }
+    /// Get all actions for a specific frame, in the order they were added
+    pub fn get_actions(&self, frame: u32) -> Vec<StateAction> {
+        self.actions_at(frame).copied().collect()
+    }
+}
+
+/// Collision body of a character by posture, relative to its position;
+/// used as the hurtbox, with the standing body as the pushbox
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Body {
+    pub stand: Rect,
+    /// While in `StateId::Crouch`
+    pub crouch: Rect,
+}
+
+impl Default for Body {
+    /// Plain box for sets built in code; character files set their own
+    fn default() -> Self {
+        let body = Rect::new(0, 0, 10000, 25000);
+        Self {
+            stand: body,
+            crouch: body,
+        }
+    }
+}
+
+/// Immutable collection of state definitions, shared by every entity
+/// playing the same character
+#[derive(Clone, Default)]
+pub struct StateSet {
+    states: Vec<State>,
+    resources: [Option<LimitedResource>; MAX_RESOURCES],
+    pub body: Body,
+}
+
+impl StateSet {
+    pub fn new() -> Self {
+        Self {
+            states: Vec::new(),
+            resources: [None; MAX_RESOURCES],
+            body: Body::default(),
+        }
+    }
+
+    /// Declare a limited resource in `slot`; out of range slots are ignored
+    pub fn set_resource(&mut self, slot: usize, resource: LimitedResource) {
+        if let Some(entry) = self.resources.get_mut(slot) {
+            *entry = Some(resource);
+        }
+    }
+
+    /// Limited resources the character starts a round with
+    pub fn resources(&self) -> [Option<LimitedResource>; MAX_RESOURCES] {
+        self.resources
+    }
+
+    /// Register a state, replacing any existing definition with the same ID
+    pub fn register(&mut self, state: State) {
+        if let Some(existing) = self.states.iter_mut().find(|s| s.id == state.id) {
+            *existing = state;
+        } else if self.states.len() < MAX_STATES {
+            self.states.push(state);
         }
+    }
 
-        actions
+    /// Find a state by ID
+    pub fn find(&self, id: StateId) -> Option<&State> {
+        self.states.iter().find(|state| state.id == id)
+    }
+
+    /// Registration slot of a state, below `MAX_STATES`
+    pub fn index_of(&self, id: StateId) -> Option<usize> {
+        self.states.iter().position(|state| state.id == id)
+    }
+
+    /// Number of registered states
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
     }
 }
 
 /// State machine that tracks current state and transitions
+///
+/// Only the runtime position (state ID, frame, per-activation counters) is
+/// owned by the machine; definitions live in a shared `StateSet`, so cloning a
+/// machine for a snapshot is cheap.
+#[derive(Clone)]
 pub struct StateMachine {
     current_state: StateId,
-    state_frame: u32, // Current frame within the state
-    states: [Option<State>; MAX_STATES],
-    state_count: usize,
+    /// Current frame within the state
+    state_frame: u32,
+    /// Button presses counted in the current state
+    mash_presses: u32,
+    /// Bitmask of hit groups that connected this activation
+    hit_groups: u32,
+    /// Hold state button held on the current frame
+    held: bool,
+    /// Bitmask (by state slot) of once-per-airtime moves used
+    airtime_uses: u64,
+    /// Bitmask (by state slot) of once-per-combo moves used
+    combo_uses: u64,
+    /// Applied to attacks as they start
+    modifier: FrameDataModifier,
+    /// Modifier applied to the current state
+    timing: Timing,
+    definitions: Arc<StateSet>,
 }
 
 impl Default for StateMachine {
@@ -140,20 +865,99 @@ impl Default for StateMachine {
 
 impl StateMachine {
     pub fn new() -> Self {
+        Self::with_states(Arc::new(StateSet::new()))
+    }
+
+    /// Create a machine driven by shared state definitions
+    pub fn with_states(definitions: Arc<StateSet>) -> Self {
         Self {
             current_state: StateId::Idle,
             state_frame: 0,
-            states: [None; MAX_STATES],
-            state_count: 0,
+            mash_presses: 0,
+            hit_groups: 0,
+            held: false,
+            airtime_uses: 0,
+            combo_uses: 0,
+            modifier: FrameDataModifier::default(),
+            timing: Timing::default(),
+            definitions,
         }
     }
 
     /// Register a state
+    ///
+    /// If the definitions are shared with other machines, this machine gets
+    /// its own copy first so the others are unaffected.
     pub fn register_state(&mut self, state: State) {
-        if self.state_count < MAX_STATES {
-            self.states[self.state_count] = Some(state);
-            self.state_count += 1;
-        }
+        Arc::make_mut(&mut self.definitions).register(state);
+    }
+
+    /// Shared state definitions driving this machine
+    pub fn definitions(&self) -> &Arc<StateSet> {
+        &self.definitions
+    }
+
+    /// Check if a state is defined
+    pub fn has_state(&self, id: StateId) -> bool {
+        self.find_state(id).is_some()
+    }
+
+    /// Charge behavior of the current state, if any
+    pub fn current_charge(&self) -> Option<ChargeData> {
+        self.find_state(self.current_state).and_then(|s| s.charge)
+    }
+
+    /// Held-button behavior of the current state, if any
+    pub fn current_hold(&self) -> Option<HoldData> {
+        self.find_state(self.current_state).and_then(|s| s.hold)
+    }
+
+    /// Record whether the current hold state's button is held this frame
+    pub fn set_held(&mut self, held: bool) {
+        self.held = held;
+    }
+
+    /// Whether the current state's counter catches `attack` right now
+    pub fn counter_catches(&self, attack: &AttackData) -> Option<StateId> {
+        let counter = self.find_state(self.current_state)?.counter?;
+        counter
+            .catches(self.definition_frame(), attack)
+            .then_some(counter.punish)
+    }
+
+    /// Whether the current state is an attack that has not yet reached
+    /// its first hitbox or projectile spawn frame
+    pub fn in_startup(&self) -> bool {
+        let Some(state) = self.find_state(self.current_state) else {
+            return false;
+        };
+        state.state_type == StateType::Attack
+            && state
+                .first_active_frame()
+                .is_some_and(|frame| self.definition_frame() < frame)
+    }
+
+    /// Animation clip and sprite for the current state frame
+    pub fn animation_frame(&self) -> Option<AnimationFrame> {
+        let animation = self.find_state(self.current_state)?.animation.as_ref()?;
+        Some(AnimationFrame {
+            animation: animation.id,
+            sprite: animation.sprite_at(self.definition_frame()),
+        })
+    }
+
+    /// Projectile guard of the current state, if active on this frame
+    pub fn active_projectile_guard(&self) -> Option<GuardKind> {
+        let guard = self.find_state(self.current_state)?.projectile_guard?;
+        let frame = self.definition_frame();
+        (frame >= guard.start && frame <= guard.end).then_some(guard.kind)
+    }
+
+    /// Whether the current state has the character's back turned
+    pub fn is_back_turned(&self) -> bool {
+        self.find_state(self.current_state)
+            .map(|s| s.back_turned)
+            .unwrap_or(false)
     }
 
     /// Get current state
@@ -166,14 +970,130 @@ impl StateMachine {
         self.state_frame
     }
 
+    /// Frame of the state definition playing now; differs from
+    /// `state_frame` when a frame data modifier changed the state's timing
+    pub fn definition_frame(&self) -> u32 {
+        self.timing.definition(self.state_frame)
+    }
+
+    /// Whether the action defined on `frame` plays on the current frame
+    pub fn plays_on_current_frame(&self, frame: u32) -> bool {
+        self.timing.elapsed(frame) == self.state_frame
+    }
+
+    /// `plays_on_current_frame` without borrowing the machine, for callers
+    /// that change it while running the current frame's actions
+    pub fn current_frame_filter(&self) -> impl Fn(u32) -> bool {
+        let (timing, state_frame) = (self.timing, self.state_frame);
+        move |frame| timing.elapsed(frame) == state_frame
+    }
+
+    /// Change the timing of attacks started from now on
+    pub fn set_modifier(&mut self, modifier: FrameDataModifier) {
+        self.modifier = modifier;
+    }
+
     /// Transition to a new state
     pub fn transition(&mut self, new_state: StateId) {
         if new_state != self.current_state {
+            self.timing = self
+                .find_state(new_state)
+                .map(|state| Timing::new(state, self.modifier))
+                .unwrap_or_default();
             self.current_state = new_state;
             self.state_frame = 0;
+            self.mash_presses = 0;
+            self.hit_groups = 0;
+            self.held = false;
         }
     }
 
+    /// Whether a move's footing and use limit allow starting it now
+    pub fn can_start(&self, id: StateId, on_ground: bool) -> bool {
+        let (Some(state), Some(slot)) = (self.find_state(id), self.definitions.index_of(id)) else {
+            return false;
+        };
+        let footing = match state.footing {
+            Footing::Anywhere => true,
+            Footing::Grounded => on_ground,
+            Footing::Airborne => !on_ground,
+        };
+        let unused = match state.use_limit {
+            None => true,
+            Some(UseLimit::OncePerAirtime) => self.airtime_uses & (1 << slot) == 0,
+            Some(UseLimit::OncePerCombo) => self.combo_uses & (1 << slot) == 0,
+        };
+        footing && unused
+    }
+
+    /// Start a move, spending its use until the limit resets
+    pub fn start(&mut self, id: StateId) {
+        let limit = self.find_state(id).and_then(|s| s.use_limit);
+        if let (Some(limit), Some(slot)) = (limit, self.definitions.index_of(id)) {
+            match limit {
+                UseLimit::OncePerAirtime => self.airtime_uses |= 1 << slot,
+                UseLimit::OncePerCombo => self.combo_uses |= 1 << slot,
+            }
+        }
+        self.transition(id);
+    }
+
+    /// Make moves spent under a use limit available again
+    pub fn reset_uses(&mut self, limit: UseLimit) {
+        match limit {
+            UseLimit::OncePerAirtime => self.airtime_uses = 0,
+            UseLimit::OncePerCombo => self.combo_uses = 0,
+        }
+    }
+
+    /// Check if a hit group already connected during this state
+    pub fn has_hit_group(&self, group: u8) -> bool {
+        self.hit_groups & (1 << group.min(31)) != 0
+    }
+
+    /// Record that a hit group connected, disarming it until the state ends
+    pub fn mark_hit_group(&mut self, group: u8) {
+        self.hit_groups |= 1 << group.min(31);
+    }
+
+    /// Whether any hitbox of the current state has hit or been blocked
+    pub fn hit_confirmed(&self) -> bool {
+        self.hit_groups != 0
+    }
+
+    /// Count button presses towards escaping a mashable state
+    pub fn register_mash(&mut self, presses: u32) {
+        let mashable = self
+            .find_state(self.current_state)
+            .map(|s| s.mash_reduction > 0)
+            .unwrap_or(false);
+        if mashable {
+            self.mash_presses += presses;
+        }
+    }
+
+    /// Button presses counted in the current state
+    pub fn mash_presses(&self) -> u32 {
+        self.mash_presses
+    }
+
+    /// Feed the machine's progress (not its definitions) to a state hash
+    pub(crate) fn hash_state(&self, h: &mut StateHasher) {
+        h.write_u32(self.current_state.to_u32());
+        h.write_u32(self.state_frame);
+        h.write_u32(self.mash_presses);
+        h.write_u32(self.hit_groups);
+        h.write_bool(self.held);
+        h.write_u64(self.airtime_uses);
+        h.write_u64(self.combo_uses);
+        h.write_i32(self.modifier.startup);
+        h.write_i32(self.modifier.recovery);
+        h.write_u32(self.timing.startup);
+        h.write_u32(self.timing.adjusted_startup);
+        h.write_i32(self.timing.recovery);
+        h.write_u32(self.timing.floor);
+    }
+
     /// Check if we can cancel current state
     pub fn can_cancel(&self) -> bool {
         self.find_state(self.current_state)
@@ -181,38 +1101,69 @@ impl StateMachine {
             .unwrap_or(false)
     }
 
+    /// Whether the current state has cancel routes to try
+    pub fn has_cancel_routes(&self) -> bool {
+        self.find_state(self.current_state)
+            .is_some_and(|s| !s.cancels.is_empty())
+    }
+
+    /// Whether `target` can start now: from idle, a free state, or an open
+    /// cancel route of the current state
+    pub fn can_cancel_into(&self, target: StateId) -> bool {
+        if self.current_state == StateId::Idle {
+            return true;
+        }
+        let Some(state) = self.find_state(self.current_state) else {
+            return false;
+        };
+        state.can_cancel
+            || state.cancels.iter().any(|route| {
+                route.target == target && route.open(self.definition_frame(), self.hit_confirmed())
+            })
+    }
+
     /// Advance to next frame
     pub fn advance_frame(&mut self) {
         self.state_frame += 1;
 
         // Auto-transition at end of state
         if let Some(state) = self.find_state(self.current_state) {
-            if self.state_frame >= state.duration {
-                // Default behavior: return to idle
-                self.transition(StateId::Idle);
+            let end = self
+                .timing
+                .end(state.end_frame(self.mash_presses, self.hit_confirmed()));
+            if self.state_frame >= end {
+                match state.hold {
+                    // Held states replay their loop instead of ending
+                    Some(hold) if self.held => {
+                        self.state_frame = self
+                            .timing
+                            .elapsed(hold.loop_from)
+                            .min(end.saturating_sub(1))
+                    }
+                    // Default behavior: return to idle
+                    _ => self.transition(StateId::Idle),
+                }
             }
         }
     }
 
-    /// Get actions for current frame
-    pub fn get_current_actions(&self) -> [Option<StateAction>; MAX_ACTIONS_PER_FRAME] {
-        if let Some(state) = self.find_state(self.current_state) {
-            state.get_actions(self.state_frame)
-        } else {
-            [None; MAX_ACTIONS_PER_FRAME]
-        }
+    /// Iterate over actions for the current frame
+    pub fn current_actions(&self) -> impl Iterator<Item = &StateAction> {
+        self.find_state(self.current_state)
+            .into_iter()
+            .flat_map(|state| &state.frame_data)
+            .filter(|data| self.plays_on_current_frame(data.frame))
+            .map(|data| &data.action)
+    }
+
+    /// Get all actions for the current frame
+    pub fn get_current_actions(&self) -> Vec<StateAction> {
+        self.current_actions().copied().collect()
     }
 
     /// Find a state by ID
     fn find_state(&self, id: StateId) -> Option<&State> {
-        for i in 0..self.state_count {
-            if let Some(state) = &self.states[i] {
-                if state.id == id {
-                    return Some(state);
-                }
-            }
-        }
-        None
+        self.definitions.find(id)
     }
 }
 
@@ -220,90 +1171,289 @@ impl StateMachine {
 pub mod states {
     use super::*;
 
+    /// Create the default character's full state set: the engine's
+    /// reaction states plus the bundled `DEFAULT_CHARACTER` file
+    pub fn default_set() -> StateSet {
+        with_default_character(reaction_set())
+    }
+
+    fn with_default_character(set: StateSet) -> StateSet {
+        let mut character = CharacterDef::new(set);
+        chardef::parse_into(DEFAULT_CHARACTER, &mut character)
+            .expect("the bundled default character is valid");
+        Arc::unwrap_or_clone(character.states)
+    }
+
+    /// State of the bundled default character
+    fn bundled(id: StateId) -> State {
+        // Parsed on first use, then shared by every lookup
+        static BUNDLED: OnceLock<StateSet> = OnceLock::new();
+        BUNDLED
+            .get_or_init(|| with_default_character(StateSet::new()))
+            .find(id)
+            .cloned()
+            .expect("state defined by the default character")
+    }
+
+    /// States every character shares, driven by engine rules rather than
+    /// character data
+    fn reaction_set() -> StateSet {
+        let mut set = StateSet::new();
+        set.register(landing());
+        set.register(hitstun(20));
+        set.register(back_hitstun(20));
+        set.register(blockstun(15));
+        // Knockdown length is driven by the entity's timer, not the state duration
+        set.register(knockdown(u32::MAX));
+        // Held length is driven by the attacker's throw state
+        set.register(held());
+        // Dizzy length is driven by the entity's timer
+        set.register(dizzy());
+        // Characters add the other win poses they have
+        set.register(win_pose(0));
+        set.register(lose_pose());
+        set.register(throw());
+        set.register(throwing());
+        set.register(throw_whiff());
+        set.register(tech_roll(
+            StateId::ThrowTech,
+            -THROW_TECH_SPEED,
+            THROW_TECH_FRAMES,
+        ));
+        set.register(tech_roll(StateId::TechNeutral, 0, TECH_NEUTRAL_FRAMES));
+        set.register(tech_roll(
+            StateId::TechBack,
+            -TECH_ROLL_SPEED,
+            TECH_ROLL_FRAMES,
+        ));
+        set.register(tech_roll(
+            StateId::TechForward,
+            TECH_ROLL_SPEED,
+            TECH_ROLL_FRAMES,
+        ));
+        set
+    }
+
     /// Create idle state
     pub fn idle() -> State {
-        State::new(StateId::Idle, StateType::Normal, 1)
+        bundled(StateId::Idle)
     }
 
     /// Create walk state
     pub fn walk() -> State {
-        State::new(StateId::Walk, StateType::Normal, 1)
-            .add_frame_data(FrameData::new(0, StateAction::SetVelocity { x: 300, y: 0 }))
+        bundled(StateId::Walk)
     }
 
     /// Create walk back state (backward movement)
     pub fn walk_back() -> State {
-        State::new(StateId::WalkBack, StateType::Normal, 1).add_frame_data(FrameData::new(
-            0,
-            StateAction::SetVelocity { x: -200, y: 0 },
-        ))
+        bundled(StateId::WalkBack)
+    }
+
+    /// Create crouch state (attacks can be started from it)
+    pub fn crouch() -> State {
+        bundled(StateId::Crouch)
     }
 
-    /// Create jump state
+    /// Create jump state (takeoff velocity gives ~30 frames of airtime)
     pub fn jump() -> State {
-        State::new(StateId::Jump, StateType::Normal, 30).add_frame_data(FrameData::new(
-            0,
-            StateAction::SetVelocity { x: 0, y: -300 },
-        ))
+        bundled(StateId::Jump)
     }
 
+    /// Create jumping light attack (fast, hits below and ahead)
+    pub fn jump_light() -> State {
+        bundled(StateId::JumpLight)
+    }
+
+    /// Create jumping medium attack
+    pub fn jump_medium() -> State {
+        bundled(StateId::JumpMedium)
+    }
+
+    /// Create jumping heavy attack (the main jump-in)
+    pub fn jump_heavy() -> State {
+        bundled(StateId::JumpHeavy)
+    }
+
+    /// Create landing recovery after an air attack
+    pub fn landing() -> State {
+        State::new(StateId::Landing, StateType::Normal, LANDING_RECOVERY_FRAMES)
+    }
+
+    /// Normals a light chains into on contact
+    pub const LIGHT_CHAINS: [StateId; 4] = [
+        StateId::MediumAttack,
+        StateId::HeavyAttack,
+        StateId::HeavyCharge,
+        StateId::Sweep,
+    ];
+
+    /// Normals a medium chains into on contact
+    pub const MEDIUM_CHAINS: [StateId; 3] =
+        [StateId::HeavyAttack, StateId::HeavyCharge, StateId::Sweep];
+
     /// Create basic light attack (fast, low damage)
     pub fn light_attack() -> State {
-        State::new(StateId::LightAttack, StateType::Attack, 18)
-            .with_cancel()
-            .add_frame_data(FrameData::new(
-                5,
-                StateAction::Hitbox {
-                    x: 15000,
-                    y: 10000,
-                    width: 12000,
-                    height: 8000,
-                    attack: AttackData::new(50).with_stun(8, 6).with_knockback(400, 0),
-                },
-            ))
+        bundled(StateId::LightAttack)
     }
 
     /// Create medium attack (balanced)
     pub fn medium_attack() -> State {
-        State::new(StateId::MediumAttack, StateType::Attack, 24)
-            .with_cancel()
+        bundled(StateId::MediumAttack)
+    }
+
+    /// Create heavy attack (slow, high damage)
+    pub fn heavy_attack() -> State {
+        bundled(StateId::HeavyAttack)
+    }
+
+    /// Create held (victim of a throw or cinematic)
+    pub fn held() -> State {
+        State::new(StateId::Held, StateType::Hurt, u32::MAX)
+    }
+
+    /// Create dizzy (helpless after the dizzy meter fills)
+    pub fn dizzy() -> State {
+        State::new(StateId::Dizzy, StateType::Hurt, u32::MAX)
+    }
+
+    /// Create a win pose, held until the match restarts
+    pub fn win_pose(pose: u8) -> State {
+        State::new(StateId::WinPose(pose), StateType::Normal, u32::MAX)
+    }
+
+    /// Create the lose pose, held until the match restarts
+    pub fn lose_pose() -> State {
+        State::new(StateId::LosePose, StateType::Normal, u32::MAX)
+    }
+
+    /// Create throw (grab attempt started within `THROW_RANGE`)
+    ///
+    /// The grab links into `throwing`; if it finds nobody (the opponent
+    /// jumped or is invulnerable) the attempt falls into the whiff recovery.
+    pub fn throw() -> State {
+        State::new(StateId::Throw, StateType::Attack, 4)
+            .grounded_only()
             .add_frame_data(FrameData::new(
-                8,
+                1,
                 StateAction::Hitbox {
-                    x: 18000,
-                    y: 10000,
-                    width: 15000,
-                    height: 10000,
-                    attack: AttackData::new(100).with_stun(12, 8).with_knockback(800, 0),
+                    // Reaches into the body of an opponent at the edge of range
+                    x: 0,
+                    y: 5000,
+                    width: THROW_RANGE + 5000,
+                    height: 15000,
+                    attack: AttackData::new(THROW_DAMAGE)
+                        .unblockable()
+                        .hit_grab(StateId::Throwing)
+                        .techable(),
+                },
+            ))
+            .add_frame_data(FrameData::new(
+                3,
+                StateAction::Transition {
+                    target: StateId::ThrowWhiff,
                 },
             ))
     }
 
-    /// Create heavy attack (slow, high damage)
-    pub fn heavy_attack() -> State {
-        State::new(StateId::HeavyAttack, StateType::Attack, 36).add_frame_data(FrameData::new(
-            12,
+    /// Create throwing (linked throw sequence driving the victim)
+    pub fn throwing() -> State {
+        State::new(StateId::Throwing, StateType::Attack, 30)
+            .add_frame_data(FrameData::new(0, StateAction::MoveVictim { x: 8000, y: 0 }))
+            .add_frame_data(FrameData::new(
+                12,
+                StateAction::MoveVictim { x: 16000, y: 0 },
+            ))
+            .add_frame_data(FrameData::new(14, StateAction::ReleaseVictim))
+    }
+
+    /// Create throw whiff (punishable recovery of a throw out of range)
+    pub fn throw_whiff() -> State {
+        State::new(StateId::ThrowWhiff, StateType::Attack, THROW_WHIFF_FRAMES).grounded_only()
+    }
+
+    /// Create heavy charge (hold Heavy; release early for a normal heavy)
+    ///
+    /// Not part of the default set; characters opt in by registering it
+    /// together with `charged_heavy`.
+    pub fn heavy_charge() -> State {
+        State::new(
+            StateId::HeavyCharge,
+            StateType::Normal,
+            HEAVY_MAX_CHARGE_FRAMES + 1,
+        )
+        .grounded_only()
+        .with_charge(ChargeData {
+            button: Button::Heavy,
+            tap: StateId::HeavyAttack,
+            charged: StateId::ChargedHeavy,
+            full_charge_frames: HEAVY_FULL_CHARGE_FRAMES,
+        })
+        // Holding past the limit releases automatically
+        .add_frame_data(FrameData::new(
+            HEAVY_MAX_CHARGE_FRAMES,
+            StateAction::Transition {
+                target: StateId::ChargedHeavy,
+            },
+        ))
+    }
+
+    /// Create charged heavy (released after full charge, unblockable)
+    pub fn charged_heavy() -> State {
+        State::new(StateId::ChargedHeavy, StateType::Attack, 36).add_frame_data(FrameData::new(
+            6,
             StateAction::Hitbox {
                 x: 20000,
                 y: 10000,
-                width: 18000,
+                width: 20000,
                 height: 12000,
-                attack: AttackData::new(200)
-                    .with_stun(18, 12)
-                    .with_knockback(1500, -500), // Launcher
+                attack: AttackData::new(300)
+                    .with_stun(24, 16)
+                    .with_knockback(1800, -600)
+                    .with_block_pushback(900)
+                    .unblockable()
+                    .hard_knockdown(),
             },
         ))
     }
 
+    /// Create crouching sweep (Down + Heavy, low, hard knockdown)
+    pub fn sweep() -> State {
+        bundled(StateId::Sweep)
+    }
+
     /// Create hitstun state
     pub fn hitstun(duration: u32) -> State {
         State::new(StateId::Hitstun, StateType::Hurt, duration)
     }
 
+    /// Create hitstun reaction for hits landing on the back
+    pub fn back_hitstun(duration: u32) -> State {
+        State::new(StateId::BackHitstun, StateType::Hurt, duration)
+    }
+
     /// Create blockstun state
     pub fn blockstun(duration: u32) -> State {
         State::new(StateId::Blockstun, StateType::Hurt, duration)
     }
+
+    /// Create ground tech roll (quick getup with optional displacement)
+    pub fn tech_roll(id: StateId, speed: i32, duration: u32) -> State {
+        let mut state = State::new(id, StateType::Normal, duration);
+        if speed != 0 {
+            for frame in 0..duration {
+                state = state.add_frame_data(FrameData::new(
+                    frame,
+                    StateAction::SetVelocity { x: speed, y: 0 },
+                ));
+            }
+        }
+        state
+    }
+
+    /// Create knockdown state (lying on the ground)
+    pub fn knockdown(duration: u32) -> State {
+        State::new(StateId::Knockdown, StateType::Hurt, duration)
+    }
 }
 
 #[cfg(test)]
@@ -335,13 +1485,309 @@ mod tests {
         assert_eq!(sm.state_frame(), 5);
     }
 
+    #[test]
+    fn test_mashing_shortens_state() {
+        let stagger = State::new(StateId::Custom(1), StateType::Hurt, 60).with_mash(5, 20);
+        assert_eq!(stagger.mashed_duration(0), 60);
+        assert_eq!(stagger.mashed_duration(4), 40);
+        assert_eq!(stagger.mashed_duration(100), 20);
+
+        let mut sm = StateMachine::new();
+        sm.register_state(states::idle());
+        sm.register_state(stagger);
+        sm.transition(StateId::Custom(1));
+
+        // 8 presses: 60 - 40 = 20 frames
+        sm.register_mash(8);
+        for _ in 0..19 {
+            sm.advance_frame();
+        }
+        assert_eq!(sm.current_state(), StateId::Custom(1));
+        sm.advance_frame();
+        assert_eq!(sm.current_state(), StateId::Idle);
+        assert_eq!(sm.mash_presses(), 0);
+    }
+
+    #[test]
+    fn test_hit_groups_reset_on_transition() {
+        let mut sm = StateMachine::new();
+        sm.register_state(states::idle());
+        sm.register_state(states::light_attack());
+        sm.transition(StateId::LightAttack);
+
+        sm.mark_hit_group(0);
+        sm.mark_hit_group(2);
+        assert!(sm.has_hit_group(0));
+        assert!(!sm.has_hit_group(1));
+        assert!(sm.has_hit_group(2));
+
+        sm.transition(StateId::Idle);
+        assert!(!sm.has_hit_group(0));
+    }
+
+    #[test]
+    fn test_animation_timeline_follows_state_frame() {
+        let animation = Animation::new(7)
+            .with_sprite(4, 2)
+            .with_sprite(0, 1)
+            .with_cue(3, 40);
+        assert_eq!(animation.sprite_at(0), 1);
+        assert_eq!(animation.sprite_at(3), 1);
+        assert_eq!(animation.sprite_at(9), 2);
+        assert_eq!(animation.cues_at(3).collect::<Vec<_>>(), vec![40]);
+
+        let mut sm = StateMachine::new();
+        sm.register_state(states::idle());
+        sm.register_state(
+            State::new(StateId::Custom(3), StateType::Attack, 10).with_animation(animation),
+        );
+        assert_eq!(sm.animation_frame(), None);
+
+        sm.transition(StateId::Custom(3));
+        for _ in 0..5 {
+            sm.advance_frame();
+        }
+        assert_eq!(
+            sm.animation_frame(),
+            Some(AnimationFrame {
+                animation: 7,
+                sprite: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_recovery_differs_on_whiff_and_contact() {
+        let poke =
+            || State::new(StateId::Custom(2), StateType::Attack, 20).with_contact_recovery(14);
+        let run = |contact: bool| {
+            let mut sm = StateMachine::new();
+            sm.register_state(states::idle());
+            sm.register_state(poke());
+            sm.transition(StateId::Custom(2));
+            if contact {
+                sm.mark_hit_group(0);
+            }
+            assert_eq!(sm.hit_confirmed(), contact);
+
+            let mut frames = 0;
+            while sm.current_state() == StateId::Custom(2) {
+                sm.advance_frame();
+                frames += 1;
+            }
+            frames
+        };
+
+        assert_eq!(run(false), 20);
+        assert_eq!(run(true), 14);
+        assert_eq!(poke().end_frame(0, true), 14);
+        assert_eq!(
+            State::new(StateId::Idle, StateType::Normal, 5).end_frame(0, true),
+            5
+        );
+    }
+
+    #[test]
+    fn test_cancel_routes_respect_window_and_contact() {
+        let mut sm = StateMachine::new();
+        sm.register_state(
+            State::new(StateId::LightAttack, StateType::Attack, 20)
+                .cancels_on_contact(&[StateId::MediumAttack], 3, 6)
+                .with_cancel_route(CancelRoute::new(StateId::SpecialMove, 0, 10).on_whiff()),
+        );
+        sm.transition(StateId::LightAttack);
+
+        assert!(sm.can_cancel_into(StateId::SpecialMove));
+        assert!(!sm.can_cancel_into(StateId::MediumAttack));
+        sm.mark_hit_group(0);
+        assert!(!sm.can_cancel_into(StateId::MediumAttack));
+        for _ in 0..3 {
+            sm.advance_frame();
+        }
+        assert!(sm.can_cancel_into(StateId::MediumAttack));
+        assert!(!sm.can_cancel_into(StateId::HeavyAttack));
+        for _ in 0..4 {
+            sm.advance_frame();
+        }
+        assert!(!sm.can_cancel_into(StateId::MediumAttack));
+    }
+
+    #[test]
+    fn test_footing_and_use_limits() {
+        let dive = StateId::Custom(1);
+        let launcher = StateId::Custom(2);
+        let mut sm = StateMachine::new();
+        sm.register_state(
+            State::new(dive, StateType::Attack, 10)
+                .airborne_only()
+                .with_use_limit(UseLimit::OncePerAirtime),
+        );
+        sm.register_state(
+            State::new(launcher, StateType::Attack, 10)
+                .grounded_only()
+                .with_use_limit(UseLimit::OncePerCombo),
+        );
+
+        assert!(!sm.can_start(dive, true));
+        assert!(sm.can_start(dive, false));
+        sm.start(dive);
+        assert_eq!(sm.current_state(), dive);
+        assert!(!sm.can_start(dive, false));
+        sm.reset_uses(UseLimit::OncePerAirtime);
+        assert!(sm.can_start(dive, false));
+
+        // Limits are tracked separately
+        assert!(!sm.can_start(launcher, false));
+        sm.start(launcher);
+        sm.reset_uses(UseLimit::OncePerAirtime);
+        assert!(!sm.can_start(launcher, true));
+        sm.reset_uses(UseLimit::OncePerCombo);
+        assert!(sm.can_start(launcher, true));
+        assert!(!sm.can_start(StateId::Custom(3), true));
+    }
+
+    #[test]
+    fn test_counter_window_and_categories() {
+        let counter = CounterData::new(3, 10, StateId::Custom(21));
+        let mid = AttackData::new(10);
+        let low = AttackData::new(10).low();
+        let overhead = AttackData::new(10).overhead();
+
+        assert!(!counter.catches(2, &mid));
+        assert!(counter.catches(3, &mid));
+        assert!(counter.catches(10, &overhead));
+        assert!(!counter.catches(11, &mid));
+        assert!(!counter.catches(5, &low));
+        assert!(counter.with_low().catches(5, &low));
+    }
+
+    #[test]
+    fn test_mash_ignored_in_normal_states() {
+        let mut sm = StateMachine::new();
+        sm.register_state(states::light_attack());
+        sm.transition(StateId::LightAttack);
+        sm.register_mash(10);
+        assert_eq!(sm.mash_presses(), 0);
+    }
+
+    #[test]
+    fn test_actions_beyond_former_cap_are_kept_in_order() {
+        let mut state = State::new(StateId::Custom(2), StateType::Normal, 10);
+        for i in 0..12 {
+            state =
+                state.add_frame_data(FrameData::new(3, StateAction::AddMomentum { x: i, y: 0 }));
+        }
+
+        let actions = state.get_actions(3);
+        assert_eq!(actions.len(), 12);
+        for (i, action) in actions.iter().enumerate() {
+            match action {
+                StateAction::AddMomentum { x, .. } => assert_eq!(*x, i as i32),
+                _ => panic!("Expected momentum action"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_states_keep_all_frame_data() {
+        let mut state = State::new(StateId::Custom(3), StateType::Attack, 200);
+        for frame in 0..120 {
+            state = state.add_frame_data(FrameData::new(
+                frame,
+                StateAction::SetVelocity { x: 100, y: 0 },
+            ));
+        }
+
+        assert_eq!(state.frame_data.len(), 120);
+        assert_eq!(state.get_actions(119).len(), 1);
+    }
+
+    #[test]
+    fn test_machines_share_definitions() {
+        let shared = Arc::new(states::default_set());
+        let mut a = StateMachine::with_states(shared.clone());
+        let b = StateMachine::with_states(shared.clone());
+        assert!(Arc::ptr_eq(a.definitions(), b.definitions()));
+
+        // Runtime state is independent
+        a.transition(StateId::LightAttack);
+        assert_eq!(b.current_state(), StateId::Idle);
+
+        // Registering on one machine copies its definitions, leaving others intact
+        a.register_state(State::new(StateId::Custom(9), StateType::Normal, 5));
+        assert!(!Arc::ptr_eq(a.definitions(), b.definitions()));
+        assert_eq!(a.definitions().len(), shared.len() + 1);
+        assert_eq!(b.definitions().len(), shared.len());
+    }
+
+    #[test]
+    fn test_frame_data_modifier_changes_timing() {
+        let poke = states::light_attack()
+            .with_cancel_route(CancelRoute::new(StateId::SpecialMove, 7, 8).on_whiff())
+            .add_frame_data(FrameData::new(2, StateAction::HaltMomentum));
+        let run = |modifier: FrameDataModifier| {
+            let mut sm = StateMachine::new();
+            sm.register_state(poke.clone());
+            sm.set_modifier(modifier);
+            sm.transition(StateId::LightAttack);
+            // Installs only change moves as they start
+            sm.set_modifier(FrameDataModifier::default());
+            let (mut actions, mut cancels) = (Vec::new(), Vec::new());
+            while sm.current_state() == StateId::LightAttack {
+                if sm.current_actions().next().is_some() {
+                    actions.push(sm.state_frame());
+                }
+                if sm.can_cancel_into(StateId::SpecialMove) {
+                    cancels.push(sm.state_frame());
+                }
+                sm.advance_frame();
+            }
+            (actions, cancels)
+        };
+        let end = |modifier| {
+            let mut sm = StateMachine::new();
+            sm.register_state(poke.clone());
+            sm.set_modifier(modifier);
+            sm.transition(StateId::LightAttack);
+            let mut frames = 0;
+            while sm.current_state() == StateId::LightAttack {
+                sm.advance_frame();
+                frames += 1;
+            }
+            frames
+        };
+
+        assert_eq!(run(FrameDataModifier::default()).0, vec![2, 5]);
+        assert_eq!(end(FrameDataModifier::default()), 18);
+
+        // Faster startup keeps the active frames and everything after them
+        // in step; startup actions are squeezed along
+        let (actions, cancels) = run(FrameDataModifier::new(-2, 0));
+        assert_eq!(actions, vec![1, 3]);
+        assert_eq!(cancels, vec![5, 6]);
+        assert_eq!(end(FrameDataModifier::new(-2, -3)), 13);
+        assert_eq!(end(FrameDataModifier::new(3, 2)), 23);
+
+        // Neither startup nor recovery can skip the move's actions
+        assert_eq!(run(FrameDataModifier::new(-10, 0)).0, vec![0, 1]);
+        assert_eq!(end(FrameDataModifier::new(0, -30)), 6);
+
+        // Non-attacks are left alone
+        let mut sm = StateMachine::new();
+        sm.register_state(states::idle());
+        sm.set_modifier(FrameDataModifier::new(-2, -2));
+        sm.transition(StateId::Custom(1));
+        sm.transition(StateId::Idle);
+        assert_eq!(sm.timing, Timing::default());
+    }
+
     #[test]
     fn test_state_actions() {
         let state = states::light_attack();
         let actions = state.get_actions(5);
 
-        assert!(actions[0].is_some());
-        if let Some(StateAction::Hitbox { attack, .. }) = actions[0] {
+        assert_eq!(actions.len(), 1);
+        if let StateAction::Hitbox { attack, .. } = actions[0] {
             assert_eq!(attack.damage, 50);
         } else {
             panic!("Expected hitbox action");
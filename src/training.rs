@@ -0,0 +1,133 @@
+//! Training mode information
+//!
+//! Read-only per-player data that training overlays display to help players
+//! practice specific situations.
+
+use crate::checksum::StateHasher;
+use crate::constants::BLOCKSTRING_GAP_WINDOW;
+
+/// Training overlay data for one player
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrainingInfo {
+    /// Frames left before the player gets up from a knockdown
+    pub knockdown_remaining: u32,
+    /// Delay (in frames) added to the most recent wakeup, 0 if none
+    pub wakeup_delay: u32,
+    /// Pressure analysis of the blockstring this player is (or was) blocking
+    pub blockstring: Blockstring,
+}
+
+/// Gap analysis of consecutive blocked hits
+///
+/// A gap is the number of actionable frames the defender had between leaving
+/// blockstun and the next hit. A string with no gaps is a "true" blockstring.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Blockstring {
+    /// Blocked hits in the current or most recent blockstring
+    pub hits: u32,
+    /// Gap before the most recent blocked hit
+    pub last_gap: u32,
+    /// Largest gap in the blockstring
+    pub max_gap: u32,
+    /// Gap the defender was hit in, if the string ended on a frame trap
+    pub frame_trap_gap: Option<u32>,
+    idle_frames: u32,
+    active: bool,
+}
+
+impl Blockstring {
+    /// Whether the string had at least two hits and no gaps
+    pub fn is_true(&self) -> bool {
+        self.hits > 1 && self.max_gap == 0
+    }
+
+    /// Feed the analysis to a state hash
+    pub(crate) fn hash_state(&self, h: &mut StateHasher) {
+        h.write_u32(self.hits);
+        h.write_u32(self.last_gap);
+        h.write_u32(self.max_gap);
+        h.write_u32(self.frame_trap_gap.unwrap_or(u32::MAX));
+        h.write_u32(self.idle_frames);
+        h.write_bool(self.active);
+    }
+
+    /// Record a blocked hit
+    pub(crate) fn record_block(&mut self, in_blockstun: bool) {
+        let gap = if in_blockstun { 0 } else { self.idle_frames };
+        if !self.active || gap > BLOCKSTRING_GAP_WINDOW {
+            *self = Self::default();
+        }
+
+        self.hits += 1;
+        if self.hits > 1 {
+            self.last_gap = gap;
+            self.max_gap = self.max_gap.max(gap);
+        }
+        self.idle_frames = 0;
+        self.active = true;
+    }
+
+    /// Record a clean hit, which ends the string
+    pub(crate) fn record_hit(&mut self, in_blockstun: bool) {
+        if self.active && !in_blockstun && self.idle_frames <= BLOCKSTRING_GAP_WINDOW {
+            self.frame_trap_gap = Some(self.idle_frames);
+        }
+        self.active = false;
+    }
+
+    /// Count an actionable frame outside blockstun
+    pub(crate) fn tick_actionable(&mut self) {
+        if self.active {
+            self.idle_frames = self.idle_frames.saturating_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gapless_string_is_true() {
+        let mut string = Blockstring::default();
+        string.record_block(false);
+        string.record_block(true);
+        string.record_block(true);
+
+        assert_eq!(string.hits, 3);
+        assert_eq!(string.max_gap, 0);
+        assert!(string.is_true());
+    }
+
+    #[test]
+    fn test_gaps_are_measured() {
+        let mut string = Blockstring::default();
+        string.record_block(false);
+        for _ in 0..3 {
+            string.tick_actionable();
+        }
+        string.record_block(false);
+
+        assert_eq!(string.hits, 2);
+        assert_eq!(string.last_gap, 3);
+        assert!(!string.is_true());
+
+        string.tick_actionable();
+        string.tick_actionable();
+        string.record_hit(false);
+        assert_eq!(string.frame_trap_gap, Some(2));
+    }
+
+    #[test]
+    fn test_long_gap_starts_new_string() {
+        let mut string = Blockstring::default();
+        string.record_block(false);
+        for _ in 0..=BLOCKSTRING_GAP_WINDOW {
+            string.tick_actionable();
+        }
+        string.record_block(false);
+
+        assert_eq!(string.hits, 1);
+        assert_eq!(string.max_gap, 0);
+    }
+}
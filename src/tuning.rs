@@ -0,0 +1,223 @@
+//! Live tuning
+//!
+//! `Engine::tune` changes a character parameter between frames of a running
+//! match: the fighter feels it on the next tick and the player keeps it for
+//! later matches. Every change is journaled, and `Engine::export_tuning`
+//! writes what a session changed as a character file patch (see `chardef`)
+//! to apply over the character's data.
+//!
+//! Tuning edits definitions, not match state, so rewinding or loading a
+//! snapshot doesn't undo it.
+
+use crate::characters::CharacterDef;
+use crate::chardef::write_state;
+use crate::constants::ENGINE_DATA_VERSION;
+use crate::engine::Engine;
+use crate::state::StateId;
+use crate::types::{Fixed, Frame, PlayerId};
+
+/// A tunable character parameter and its new value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tweak {
+    /// Forward walk speed, per frame
+    WalkSpeed(i32),
+    /// Backward walk speed, per frame
+    BackWalkSpeed(i32),
+    /// Upward takeoff speed of a jump
+    JumpVelocity(i32),
+    /// Damage of every hitbox of a move
+    MoveDamage { state: StateId, damage: i32 },
+    /// Gravity, in percent of the stage's
+    Gravity(i32),
+    /// Pushbox weight
+    Weight(i32),
+}
+
+impl Tweak {
+    /// State the tweak edits, if it edits one
+    fn state(&self) -> Option<StateId> {
+        match *self {
+            Tweak::WalkSpeed(_) => Some(StateId::Walk),
+            Tweak::BackWalkSpeed(_) => Some(StateId::WalkBack),
+            Tweak::JumpVelocity(_) => Some(StateId::Jump),
+            Tweak::MoveDamage { state, .. } => Some(state),
+            Tweak::Gravity(_) | Tweak::Weight(_) => None,
+        }
+    }
+
+    fn apply(self, character: CharacterDef) -> CharacterDef {
+        match self {
+            Tweak::WalkSpeed(speed) => character.with_walk_speed(speed),
+            Tweak::BackWalkSpeed(speed) => character.with_back_walk_speed(speed),
+            Tweak::JumpVelocity(velocity) => character.with_jump_velocity(velocity),
+            Tweak::MoveDamage { state, damage } => character.with_move_damage(state, damage),
+            Tweak::Gravity(percent) => character.with_gravity_percent(percent),
+            Tweak::Weight(weight) => character.with_weight(weight),
+        }
+    }
+}
+
+/// One journaled change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TuningEntry {
+    /// Frame the change took effect before
+    pub frame: Frame,
+    pub player: PlayerId,
+    pub tweak: Tweak,
+}
+
+impl Engine {
+    /// Change a player's character parameter, live
+    ///
+    /// Returns false, changing nothing, for an unknown player or a
+    /// `MoveDamage` on a move without hitboxes.
+    pub fn tune(&mut self, player: PlayerId, tweak: Tweak) -> bool {
+        let index = player.0 as usize;
+        let Some(character) = self.characters.get(index) else {
+            return false;
+        };
+        if let Tweak::MoveDamage { state, .. } = tweak {
+            if character.move_damage(state).is_none() {
+                return false;
+            }
+        }
+        let character = tweak.apply(character.clone());
+
+        let gravity = self.fighter_gravity(&character);
+        if let Some(fighter) = &mut self.entities[index] {
+            if let Some(state) = tweak.state().and_then(|id| character.states.find(id)) {
+                fighter.state_machine.register_state(state.clone());
+            }
+            fighter.weight = character.weight;
+            fighter.physics.gravity = gravity;
+        }
+        self.characters[index] = character;
+        self.tuning.push(TuningEntry {
+            frame: self.frame,
+            player,
+            tweak,
+        });
+        true
+    }
+
+    /// Every change made with `tune`, oldest first
+    pub fn tuning_journal(&self) -> &[TuningEntry] {
+        &self.tuning
+    }
+
+    /// Forget the journal; the changes themselves stay
+    pub fn clear_tuning(&mut self) {
+        self.tuning.clear();
+    }
+
+    /// Character file patch with the current value of everything the
+    /// journal changed for `player`
+    ///
+    /// States the text format can't express are listed in comments.
+    pub fn export_tuning(&self, player: PlayerId) -> String {
+        let Some(character) = self.characters.get(player.0 as usize) else {
+            return String::new();
+        };
+        let tweaks: Vec<Tweak> = self
+            .tuning
+            .iter()
+            .filter(|entry| entry.player == player)
+            .map(|entry| entry.tweak)
+            .collect();
+
+        let mut out = format!(
+            "# Tuning for player {}\nversion {}\n",
+            player.0 + 1,
+            ENGINE_DATA_VERSION
+        );
+        if tweaks.iter().any(|t| matches!(t, Tweak::Gravity(_))) {
+            out += &format!("gravity {}\n", character.gravity_percent);
+        }
+        if tweaks.iter().any(|t| matches!(t, Tweak::Weight(_))) {
+            out += &format!("weight {}\n", character.weight);
+        }
+        let mut written = Vec::new();
+        for id in tweaks.iter().filter_map(Tweak::state) {
+            if written.contains(&id) {
+                continue;
+            }
+            written.push(id);
+            out += "\n";
+            match character.states.find(id).and_then(write_state) {
+                Some(block) => out += &block,
+                None => out += &format!("# {id:?} can't be written as text\n"),
+            }
+        }
+        out
+    }
+
+    /// Gravity of a fighter playing `character` in the current match
+    pub(crate) fn fighter_gravity(&self, character: &CharacterDef) -> i32 {
+        Fixed(self.config.physics.gravity)
+            .percent(character.gravity_percent)
+            .percent(self.mutators.gravity_percent as i32)
+            .raw()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chardef::parse_character;
+    use crate::input::{Direction, InputState};
+
+    #[test]
+    fn test_tuning_applies_live_and_exports_a_patch() {
+        let mut engine = Engine::new();
+        engine.init_match();
+        let forward = InputState {
+            direction: Direction::Forward,
+            ..InputState::neutral()
+        };
+        let step = |engine: &mut Engine| {
+            let before = engine.entities[0].as_ref().unwrap().physics.position.x;
+            engine.tick(forward, InputState::neutral());
+            engine.entities[0].as_ref().unwrap().physics.position.x - before
+        };
+        for _ in 0..3 {
+            step(&mut engine);
+        }
+        assert_eq!(step(&mut engine), 300);
+
+        assert!(engine.tune(PlayerId::PLAYER_1, Tweak::WalkSpeed(420)));
+        assert_eq!(step(&mut engine), 420);
+        assert!(engine.tune(PlayerId::PLAYER_1, Tweak::Gravity(150)));
+        let light = Tweak::MoveDamage {
+            state: StateId::LightAttack,
+            damage: 55,
+        };
+        assert!(engine.tune(PlayerId::PLAYER_1, light));
+        assert!(!engine.tune(
+            PlayerId::PLAYER_1,
+            Tweak::MoveDamage {
+                state: StateId::Idle,
+                damage: 1
+            }
+        ));
+        assert_eq!(engine.tuning_journal().len(), 3);
+        assert_eq!(engine.tuning_journal()[0].frame, Frame(4));
+
+        // Player 2 is untouched, player 1 keeps the changes next match
+        assert_eq!(engine.characters[1].walk_speed(), 300);
+        engine.init_match();
+        let p1 = engine.entities[0].as_ref().unwrap();
+        assert_eq!(p1.physics.gravity, engine.config.physics.gravity * 3 / 2);
+
+        let patch = engine.export_tuning(PlayerId::PLAYER_1);
+        assert!(patch.contains("gravity 150\n"));
+        assert!(patch.contains("state Walk normal 1\n  frame 0 velocity 420 0\nend\n"));
+        let patched = parse_character(&patch).unwrap();
+        assert_eq!(patched.walk_speed(), 420);
+        assert_eq!(patched.move_damage(StateId::LightAttack), Some(55));
+        assert_eq!(patched.gravity_percent, 150);
+        assert_eq!(
+            engine.export_tuning(PlayerId::PLAYER_2),
+            "# Tuning for player 2\nversion 1\n"
+        );
+    }
+}
@@ -1,10 +1,77 @@
 //! Core types for the Bagarre fighting game engine
 //! Zero dependencies - all implementations are custom
 
+/// A scalar in internal units with an explicit overflow policy.
+///
+/// Simulation arithmetic saturates at the `i32` bounds rather than
+/// panicking in debug builds and wrapping in release, so extreme values
+/// (stacked knockback, huge damage) resolve identically on every build.
+///
+/// # Examples
+///
+/// ```
+/// use bagarre::types::Fixed;
+///
+/// assert_eq!(Fixed::MAX.add(Fixed::new(1)), Fixed::MAX);
+/// assert_eq!(Fixed::new(200).percent(50), Fixed::new(100));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fixed(pub i32);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const MAX: Fixed = Fixed(i32::MAX);
+    pub const MIN: Fixed = Fixed(i32::MIN);
+
+    pub const fn new(value: i32) -> Self {
+        Self(value)
+    }
+
+    /// Raw value in internal units
+    pub const fn raw(self) -> i32 {
+        self.0
+    }
+
+    /// Saturating addition
+    pub const fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(other.0))
+    }
+
+    /// Saturating subtraction
+    pub const fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(other.0))
+    }
+
+    /// Saturating multiplication by an integer
+    pub const fn mul(self, scalar: i32) -> Fixed {
+        Fixed(self.0.saturating_mul(scalar))
+    }
+
+    /// Scale by a percentage, rounding toward zero
+    ///
+    /// The intermediate product is computed in 64 bits, so only the final
+    /// result saturates.
+    pub fn percent(self, percent: i32) -> Fixed {
+        Fixed::from_wide(self.0 as i64 * percent as i64 / 100)
+    }
+
+    /// Limit the magnitude to `limit`
+    pub fn clamp_abs(self, limit: i32) -> Fixed {
+        let limit = limit.saturating_abs();
+        Fixed(self.0.clamp(-limit, limit))
+    }
+
+    /// Saturate a 64-bit intermediate into range
+    pub fn from_wide(value: i64) -> Fixed {
+        Fixed(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
 /// A 2D vector for positions, velocities, and other 2D quantities.
 ///
 /// Uses fixed-point integer math for deterministic gameplay. All values are in
 /// "internal units" - divide by 1000 to convert to display units.
+/// Arithmetic saturates like [`Fixed`].
 ///
 /// # Examples
 ///
@@ -55,8 +122,8 @@ impl Vec2 {
     /// ```
     pub fn add(&self, other: Vec2) -> Vec2 {
         Vec2 {
-            x: self.x + other.x,
-            y: self.y + other.y,
+            x: Fixed(self.x).add(Fixed(other.x)).raw(),
+            y: Fixed(self.y).add(Fixed(other.y)).raw(),
         }
     }
 
@@ -73,8 +140,8 @@ impl Vec2 {
     /// ```
     pub fn sub(&self, other: Vec2) -> Vec2 {
         Vec2 {
-            x: self.x - other.x,
-            y: self.y - other.y,
+            x: Fixed(self.x).sub(Fixed(other.x)).raw(),
+            y: Fixed(self.y).sub(Fixed(other.y)).raw(),
         }
     }
 
@@ -90,8 +157,8 @@ impl Vec2 {
     /// ```
     pub fn scale(&self, scalar: i32) -> Vec2 {
         Vec2 {
-            x: self.x * scalar,
-            y: self.y * scalar,
+            x: Fixed(self.x).mul(scalar).raw(),
+            y: Fixed(self.y).mul(scalar).raw(),
         }
     }
 
@@ -107,7 +174,8 @@ impl Vec2 {
     /// assert_eq!(v1.dot(v2), 250); // 10*5 + 20*10
     /// ```
     pub fn dot(&self, other: Vec2) -> i32 {
-        self.x * other.x + self.y * other.y
+        let wide = self.x as i64 * other.x as i64 + self.y as i64 * other.y as i64;
+        Fixed::from_wide(wide).raw()
     }
 
     /// Returns the squared length of the vector.
@@ -124,7 +192,7 @@ impl Vec2 {
     /// assert_eq!(v.length_squared(), 25); // 3² + 4² = 9 + 16
     /// ```
     pub fn length_squared(&self) -> i32 {
-        self.x * self.x + self.y * self.y
+        self.dot(*self)
     }
 }
 
@@ -202,7 +270,7 @@ impl Rect {
 
     /// Returns the X coordinate of the right edge.
     pub fn right(&self) -> i32 {
-        self.x + self.width
+        Fixed(self.x).add(Fixed(self.width)).raw()
     }
 
     /// Returns the Y coordinate of the top edge.
@@ -212,7 +280,7 @@ impl Rect {
 
     /// Returns the Y coordinate of the bottom edge.
     pub fn bottom(&self) -> i32 {
-        self.y + self.height
+        Fixed(self.y).add(Fixed(self.height)).raw()
     }
 
     /// Returns the center point of the rectangle.
@@ -249,6 +317,31 @@ impl Rect {
             && self.top() < other.bottom()
             && self.bottom() > other.top()
     }
+
+    /// Returns the overlapping region of two rectangles, if they intersect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bagarre::types::Rect;
+    ///
+    /// let r1 = Rect::new(0, 0, 10, 10);
+    /// let r2 = Rect::new(5, 5, 10, 10);
+    /// assert_eq!(r1.intersection(&r2), Some(Rect::new(5, 5, 5, 5)));
+    /// assert_eq!(r1.intersection(&Rect::new(20, 20, 10, 10)), None);
+    /// ```
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        let left = self.left().max(other.left());
+        let top = self.top().max(other.top());
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        Some(Rect::new(left, top, right - left, bottom - top))
+    }
 }
 
 /// The direction a character or entity is facing.
@@ -416,6 +509,20 @@ mod tests {
         assert_eq!(v1.dot(v2), 250); // 10*5 + 20*10
     }
 
+    #[test]
+    fn test_arithmetic_saturates() {
+        let big = Vec2::new(i32::MAX - 10, i32::MIN + 10);
+        assert_eq!(big.add(Vec2::new(100, -100)), Vec2::new(i32::MAX, i32::MIN));
+        assert_eq!(big.scale(3), Vec2::new(i32::MAX, i32::MIN));
+        assert_eq!(Vec2::new(50000, 50000).length_squared(), i32::MAX);
+
+        assert_eq!(Fixed::MIN.sub(Fixed::new(1)), Fixed::MIN);
+        assert_eq!(Fixed::MAX.percent(200), Fixed::MAX);
+        // Percent keeps full precision in the intermediate product
+        assert_eq!(Fixed::new(i32::MAX).percent(50), Fixed::new(i32::MAX / 2));
+        assert_eq!(Fixed::new(-7).clamp_abs(5), Fixed::new(-5));
+    }
+
     #[test]
     fn test_rect_collision() {
         let r1 = Rect::new(0, 0, 10, 10);
@@ -426,6 +533,19 @@ mod tests {
         assert!(!r1.intersects(&r3));
     }
 
+    #[test]
+    fn test_rect_intersection() {
+        let r1 = Rect::new(0, 0, 10, 10);
+        let r2 = Rect::new(4, 6, 10, 10);
+
+        let overlap = r1.intersection(&r2).unwrap();
+        assert_eq!(overlap, Rect::new(4, 6, 6, 4));
+        assert_eq!(overlap.center(), Vec2::new(7, 8));
+
+        // Touching edges do not intersect
+        assert_eq!(r1.intersection(&Rect::new(10, 0, 10, 10)), None);
+    }
+
     #[test]
     fn test_facing() {
         assert_eq!(Facing::Left.opposite(), Facing::Right);
@@ -5,13 +5,53 @@
 //!
 //! To use with wasm-bindgen (recommended), enable it in Cargo.toml
 
-use crate::engine::{Engine, GameResult};
-use crate::input::{Button, Direction, InputState};
-use crate::types::{Facing, PlayerId};
+use crate::config::{EngineConfig, StageConfig, StageEdge, StagePreset};
+use crate::engine::Engine;
+use crate::events::{FinishKind, GameEvent};
+use crate::ffi::{encode_result, encode_state};
+use crate::input::InputState;
+use crate::limits::Limits;
+use crate::replay::{Replay, ReplayError, ReplayPlayer};
+use crate::rollback::{PredictionPolicy, RollbackError, RollbackSession};
+use crate::setup::{MatchSetup, Mutators};
+use crate::snapshot::EngineSnapshot;
+use crate::types::PlayerId;
 
 /// Global engine instance for WASM
 static mut ENGINE: Option<Engine> = None;
 
+/// Replay being watched; getters read from it instead of `ENGINE` while set
+static mut REPLAY: Option<ReplayPlayer> = None;
+
+/// Local match being recorded, from `replay_record_start` until
+/// `replay_record_stop`
+static mut RECORDING: Option<Replay> = None;
+
+/// Staging area JavaScript writes replay bytes into before `replay_load`,
+/// and reads a finished recording from
+static mut REPLAY_BUFFER: Vec<u8> = Vec::new();
+
+/// Rollback netplay session; getters read from it while set
+static mut SESSION: Option<RollbackSession> = None;
+
+/// Number of save-state slots available to `save_state`/`load_state`
+const SAVE_SLOTS: usize = 8;
+
+/// Saved engine states for front-end driven rollback
+static mut SAVED: Vec<Option<EngineSnapshot>> = Vec::new();
+
+/// Engine the getters report on: a replay or netplay session if active,
+/// else the local match
+unsafe fn active_engine() -> Option<&'static Engine> {
+    if let Some(player) = &REPLAY {
+        return Some(player.engine());
+    }
+    if let Some(session) = &SESSION {
+        return Some(session.engine());
+    }
+    ENGINE.as_ref()
+}
+
 /// Initialize the engine
 #[no_mangle]
 pub extern "C" fn init() {
@@ -19,6 +59,144 @@ pub extern "C" fn init() {
         let mut engine = Engine::new();
         engine.init_match();
         ENGINE = Some(engine);
+        RECORDING = None;
+    }
+}
+
+/// Initialize the engine on a bundled stage
+/// Presets: 0 standard, 1 narrow, 2 wide, 3 corridor, 4 platform (others fall back to standard)
+#[no_mangle]
+pub extern "C" fn init_stage(preset: u32) {
+    let preset = match preset {
+        1 => StagePreset::Narrow,
+        2 => StagePreset::Wide,
+        3 => StagePreset::Corridor,
+        4 => StagePreset::Platform,
+        _ => StagePreset::Standard,
+    };
+    unsafe {
+        let mut engine = Engine::with_config(EngineConfig {
+            stage: StageConfig::preset(preset),
+            ..Default::default()
+        });
+        engine.init_match();
+        ENGINE = Some(engine);
+        RECORDING = None;
+    }
+}
+
+/// Restart the local match with party-mode mutators (percentages, 100 =
+/// unchanged)
+/// Returns 0 on success, -1 if no local match is set up
+#[no_mangle]
+pub extern "C" fn init_mutators(
+    speed: u32,
+    gravity_percent: u32,
+    damage_percent: u32,
+    meter_gain_percent: u32,
+) -> i32 {
+    unsafe {
+        let Some(engine) = &mut ENGINE else {
+            return -1;
+        };
+        let mutators = Mutators {
+            speed: speed.min(u8::MAX as u32) as u8,
+            gravity_percent: gravity_percent.min(u16::MAX as u32) as u16,
+            damage_percent: damage_percent.min(u16::MAX as u32) as u16,
+            meter_gain_percent: meter_gain_percent.min(u16::MAX as u32) as u16,
+        };
+        engine.init_match_with(MatchSetup {
+            mutators,
+            ..Default::default()
+        });
+        RECORDING = None;
+        0
+    }
+}
+
+/// Number of times to call `tick` per rendered frame (2 or more in turbo)
+#[no_mangle]
+pub extern "C" fn get_ticks_per_frame() -> u32 {
+    unsafe { active_engine().map(|e| e.ticks_per_frame()).unwrap_or(1) }
+}
+
+/// Get a build-time limit, in the field order of `Limits`:
+/// 0 max players, 1 max entities, 2 max projectiles, 3 max hitboxes,
+/// 4 max hurtboxes, 5 max collisions per frame, 6 max states,
+/// 7 max resources, 8 input buffer size, 9 motion detection window,
+/// 10 max input delay, 11 rollback window, 12 max rewind frames,
+/// 13 units per pixel, 14 max coordinate, 15 ticks per second,
+/// 16 engine data version, 17 replay format version
+/// Returns -1 for unknown codes
+#[no_mangle]
+pub extern "C" fn get_limit(kind: u32) -> i32 {
+    let limits = Limits::current();
+    match kind {
+        0 => limits.max_players as i32,
+        1 => limits.max_entities as i32,
+        2 => limits.max_projectiles as i32,
+        3 => limits.max_hitboxes as i32,
+        4 => limits.max_hurtboxes as i32,
+        5 => limits.max_collisions_per_frame as i32,
+        6 => limits.max_states as i32,
+        7 => limits.max_resources as i32,
+        8 => limits.input_buffer_size as i32,
+        9 => limits.motion_detection_window as i32,
+        10 => limits.max_input_delay as i32,
+        11 => limits.rollback_window as i32,
+        12 => limits.max_rewind_frames as i32,
+        13 => limits.units_per_pixel,
+        14 => limits.max_coordinate,
+        15 => limits.ticks_per_second as i32,
+        16 => limits.engine_data_version as i32,
+        17 => limits.replay_format_version as i32,
+        _ => -1,
+    }
+}
+
+/// Get the horizontal distance between the fighters (-1 without a match)
+#[no_mangle]
+pub extern "C" fn get_player_distance() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(Engine::distance_between_players)
+            .unwrap_or(-1)
+    }
+}
+
+/// Whether the fighters are close enough to throw (1) or not (0)
+#[no_mangle]
+pub extern "C" fn get_in_throw_range() -> u32 {
+    unsafe { active_engine().is_some_and(Engine::in_throw_range) as u32 }
+}
+
+/// Get the distance from the stage center to each edge
+#[no_mangle]
+pub extern "C" fn get_stage_half_width() -> i32 {
+    unsafe {
+        active_engine()
+            .map(|e| e.config.stage.half_width)
+            .unwrap_or(0)
+    }
+}
+
+/// Get the stage ceiling height above the ground (0 when open sky)
+#[no_mangle]
+pub extern "C" fn get_stage_ceiling() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.config.stage.ceiling)
+            .unwrap_or(0)
+    }
+}
+
+/// Whether the stage edges are walls (1) or ledges (0)
+#[no_mangle]
+pub extern "C" fn get_stage_walled() -> u32 {
+    unsafe {
+        active_engine()
+            .map(|e| (e.config.stage.edge == StageEdge::Wall) as u32)
+            .unwrap_or(0)
     }
 }
 
@@ -33,9 +211,64 @@ pub extern "C" fn init() {
 pub extern "C" fn tick(p1_input: u32, p2_input: u32) {
     unsafe {
         if let Some(engine) = &mut ENGINE {
-            let p1 = decode_input(p1_input, Facing::Right);
-            let p2 = decode_input(p2_input, Facing::Left);
+            let p1 = decode_input(p1_input);
+            let p2 = decode_input(p2_input);
+            let frame = engine.frame;
             engine.tick(p1, p2);
+            // Paused and finished matches don't advance, so there's nothing to record
+            if let Some(recording) = &mut RECORDING {
+                if engine.frame != frame {
+                    recording.record(engine);
+                }
+            }
+        }
+    }
+}
+
+/// Pause the local match; ticks are ignored until `resume`
+#[no_mangle]
+pub extern "C" fn pause() {
+    unsafe {
+        if let Some(engine) = &mut ENGINE {
+            engine.pause();
+        }
+    }
+}
+
+/// Resume the local match
+#[no_mangle]
+pub extern "C" fn resume() {
+    unsafe {
+        if let Some(engine) = &mut ENGINE {
+            engine.resume();
+        }
+    }
+}
+
+/// Keep the last `frames` frames of the local match for rewinding (0 = off)
+#[no_mangle]
+pub extern "C" fn rewind_enable(frames: u32) {
+    unsafe {
+        if let Some(engine) = &mut ENGINE {
+            engine.enable_rewind(frames as usize);
+        }
+    }
+}
+
+/// Step the local match back up to `frames` frames; the frontend replays
+/// them by ticking again. Returns the number of frames undone
+#[no_mangle]
+pub extern "C" fn rewind(frames: u32) -> u32 {
+    unsafe {
+        match &mut ENGINE {
+            Some(engine) => {
+                let undone = engine.rewind(frames as usize).len() as u32;
+                if let Some(recording) = &mut RECORDING {
+                    recording.truncate(engine.frame.0 as usize);
+                }
+                undone
+            }
+            None => 0,
         }
     }
 }
@@ -43,15 +276,14 @@ pub extern "C" fn tick(p1_input: u32, p2_input: u32) {
 /// Get current frame number
 #[no_mangle]
 pub extern "C" fn get_frame() -> u64 {
-    unsafe { ENGINE.as_ref().map(|e| e.frame.0).unwrap_or(0) }
+    unsafe { active_engine().map(|e| e.frame.0).unwrap_or(0) }
 }
 
 /// Get player 1 position X
 #[no_mangle]
 pub extern "C" fn get_p1_x() -> i32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
             .map(|p| p.physics.position.x)
             .unwrap_or(0)
@@ -62,32 +294,112 @@ pub extern "C" fn get_p1_x() -> i32 {
 #[no_mangle]
 pub extern "C" fn get_p1_y() -> i32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
             .map(|p| p.physics.position.y)
             .unwrap_or(0)
     }
 }
 
+/// Get player 1 hit shake and recoil offset X (draw only, not simulated)
+#[no_mangle]
+pub extern "C" fn get_p1_offset_x() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
+            .map(|p| p.presentation.offset().x)
+            .unwrap_or(0)
+    }
+}
+
+/// Get player 1 animation clip ID (0xFFFF when the state declares none)
+#[no_mangle]
+pub extern "C" fn get_p1_animation() -> u32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
+            .and_then(|p| p.state_machine.animation_frame())
+            .map(|a| a.animation as u32)
+            .unwrap_or(0xFFFF)
+    }
+}
+
+/// Get player 1 sprite index within the animation clip
+#[no_mangle]
+pub extern "C" fn get_p1_sprite() -> u32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
+            .and_then(|p| p.state_machine.animation_frame())
+            .map(|a| a.sprite as u32)
+            .unwrap_or(0)
+    }
+}
+
 /// Get player 1 health
 #[no_mangle]
 pub extern "C" fn get_p1_health() -> i32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
             .map(|p| p.health.current)
             .unwrap_or(0)
     }
 }
 
+/// Get player 1 super meter
+#[no_mangle]
+pub extern "C" fn get_p1_meter() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
+            .map(|p| p.meter)
+            .unwrap_or(0)
+    }
+}
+
+/// Get player 1 guard meter (0 when the guard meter is disabled)
+#[no_mangle]
+pub extern "C" fn get_p1_guard() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
+            .map(|p| p.guard)
+            .unwrap_or(0)
+    }
+}
+
+/// Get player 1 dizzy meter (0 when dizzy is disabled)
+#[no_mangle]
+pub extern "C" fn get_p1_dizzy() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
+            .map(|p| p.dizzy)
+            .unwrap_or(0)
+    }
+}
+
+/// Get player 1 lifebar segments out of `LIFEBAR_SCALE`:
+/// remaining life in the high 16 bits, recently lost in the low 16 bits
+#[no_mangle]
+pub extern "C" fn get_p1_lifebar() -> u32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
+            .map(|p| {
+                let bar = p.health.segments();
+                (bar.life as u32) << 16 | bar.recent as u32
+            })
+            .unwrap_or(0)
+    }
+}
+
 /// Get player 1 state (encoded as integer)
 #[no_mangle]
 pub extern "C" fn get_p1_state() -> u32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
             .map(|p| encode_state(p.state_machine.current_state()))
             .unwrap_or(0)
@@ -98,8 +410,7 @@ pub extern "C" fn get_p1_state() -> u32 {
 #[no_mangle]
 pub extern "C" fn get_p1_facing() -> i32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_1))
             .map(|p| p.facing.sign())
             .unwrap_or(1)
@@ -110,8 +421,7 @@ pub extern "C" fn get_p1_facing() -> i32 {
 #[no_mangle]
 pub extern "C" fn get_p2_x() -> i32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
             .map(|p| p.physics.position.x)
             .unwrap_or(0)
@@ -122,32 +432,112 @@ pub extern "C" fn get_p2_x() -> i32 {
 #[no_mangle]
 pub extern "C" fn get_p2_y() -> i32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
             .map(|p| p.physics.position.y)
             .unwrap_or(0)
     }
 }
 
+/// Get player 2 hit shake and recoil offset X (draw only, not simulated)
+#[no_mangle]
+pub extern "C" fn get_p2_offset_x() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
+            .map(|p| p.presentation.offset().x)
+            .unwrap_or(0)
+    }
+}
+
+/// Get player 2 animation clip ID (0xFFFF when the state declares none)
+#[no_mangle]
+pub extern "C" fn get_p2_animation() -> u32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
+            .and_then(|p| p.state_machine.animation_frame())
+            .map(|a| a.animation as u32)
+            .unwrap_or(0xFFFF)
+    }
+}
+
+/// Get player 2 sprite index within the animation clip
+#[no_mangle]
+pub extern "C" fn get_p2_sprite() -> u32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
+            .and_then(|p| p.state_machine.animation_frame())
+            .map(|a| a.sprite as u32)
+            .unwrap_or(0)
+    }
+}
+
 /// Get player 2 health
 #[no_mangle]
 pub extern "C" fn get_p2_health() -> i32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
             .map(|p| p.health.current)
             .unwrap_or(0)
     }
 }
 
+/// Get player 2 super meter
+#[no_mangle]
+pub extern "C" fn get_p2_meter() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
+            .map(|p| p.meter)
+            .unwrap_or(0)
+    }
+}
+
+/// Get player 2 guard meter (0 when the guard meter is disabled)
+#[no_mangle]
+pub extern "C" fn get_p2_guard() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
+            .map(|p| p.guard)
+            .unwrap_or(0)
+    }
+}
+
+/// Get player 2 dizzy meter (0 when dizzy is disabled)
+#[no_mangle]
+pub extern "C" fn get_p2_dizzy() -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
+            .map(|p| p.dizzy)
+            .unwrap_or(0)
+    }
+}
+
+/// Get player 2 lifebar segments out of `LIFEBAR_SCALE`:
+/// remaining life in the high 16 bits, recently lost in the low 16 bits
+#[no_mangle]
+pub extern "C" fn get_p2_lifebar() -> u32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
+            .map(|p| {
+                let bar = p.health.segments();
+                (bar.life as u32) << 16 | bar.recent as u32
+            })
+            .unwrap_or(0)
+    }
+}
+
 /// Get player 2 state (encoded as integer)
 #[no_mangle]
 pub extern "C" fn get_p2_state() -> u32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
             .map(|p| encode_state(p.state_machine.current_state()))
             .unwrap_or(0)
@@ -158,8 +548,7 @@ pub extern "C" fn get_p2_state() -> u32 {
 #[no_mangle]
 pub extern "C" fn get_p2_facing() -> i32 {
     unsafe {
-        ENGINE
-            .as_ref()
+        active_engine()
             .and_then(|e| e.get_player_entity(PlayerId::PLAYER_2))
             .map(|p| p.facing.sign())
             .unwrap_or(-1)
@@ -170,92 +559,536 @@ pub extern "C" fn get_p2_facing() -> i32 {
 #[no_mangle]
 pub extern "C" fn get_result() -> u32 {
     unsafe {
-        ENGINE
-            .as_ref()
-            .map(|e| match e.game_result {
-                GameResult::InProgress => 0,
-                GameResult::Player1Wins => 1,
-                GameResult::Player2Wins => 2,
-                GameResult::Draw => 3,
+        active_engine()
+            .map(|e| encode_result(e.game_result))
+            .unwrap_or(0)
+    }
+}
+
+/// Get the X position of the projectile in a slot (0 when the slot is empty)
+#[no_mangle]
+pub extern "C" fn get_projectile_x(slot: u32) -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.projectiles.get(slot as usize).copied().flatten())
+            .map(|p| p.position.x)
+            .unwrap_or(0)
+    }
+}
+
+/// Get the Y position of the projectile in a slot
+#[no_mangle]
+pub extern "C" fn get_projectile_y(slot: u32) -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.projectiles.get(slot as usize).copied().flatten())
+            .map(|p| p.position.y)
+            .unwrap_or(0)
+    }
+}
+
+/// Whether a projectile slot is live (0 = empty, 1 = flying, 2 = residual)
+#[no_mangle]
+pub extern "C" fn get_projectile_state(slot: u32) -> u32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.projectiles.get(slot as usize).copied().flatten())
+            .map(|p| if p.is_residual() { 2 } else { 1 })
+            .unwrap_or(0)
+    }
+}
+
+/// Number of projectile clashes during the last tick
+#[no_mangle]
+pub extern "C" fn get_clash_count() -> u32 {
+    unsafe {
+        active_engine()
+            .map(|e| e.clashes().len() as u32)
+            .unwrap_or(0)
+    }
+}
+
+/// Special finishes of a round won during the last tick, as bit flags:
+/// bit 0 super, bit 1 wall break, bit 2 perfect
+#[no_mangle]
+pub extern "C" fn get_finish_flags() -> u32 {
+    unsafe {
+        active_engine()
+            .map(|e| {
+                e.finishes().iter().fold(0, |flags, f| {
+                    flags
+                        | match f.kind {
+                            FinishKind::Super => 1,
+                            FinishKind::WallBreak => 2,
+                            FinishKind::Perfect => 4,
+                        }
+                })
             })
             .unwrap_or(0)
     }
 }
 
-/// Decode input from bitfield
-fn decode_input(input: u32, facing: Facing) -> InputState {
-    let dir_value = (input & 0xF) as u8;
-    let direction = match dir_value {
-        5 | 0 => Direction::Neutral,
-        2 => Direction::Down,
-        1 => Direction::DownBack,
-        4 => Direction::Back,
-        7 => Direction::UpBack,
-        8 => Direction::Up,
-        9 => Direction::UpForward,
-        6 => Direction::Forward,
-        3 => Direction::DownForward,
-        _ => Direction::Neutral,
+/// Number of typed events queued during the last tick
+#[no_mangle]
+pub extern "C" fn get_game_event_count() -> u32 {
+    unsafe {
+        active_engine()
+            .map(|e| e.game_events().len() as u32)
+            .unwrap_or(0)
+    }
+}
+
+/// Typed event from the last tick, packed as: kind in bits 0-7
+/// (1 round start, 2 hit landed, 3 blocked, 4 counter hit, 5 KO,
+/// 6 state changed, 7 parry, 8 armor absorb), entity in bits 8-15 (the
+/// defender for hits) and a
+/// payload in bits 16-31 (round number, damage, or new state code);
+/// 0 if out of range
+#[no_mangle]
+pub extern "C" fn get_game_event(index: u32) -> u32 {
+    let pack = |kind: u32, entity: u32, payload: u32| {
+        kind | (entity & 0xFF) << 8 | (payload & 0xFFFF) << 16
+    };
+    unsafe {
+        active_engine()
+            .and_then(|e| e.game_events().get(index as usize))
+            .map(|event| match *event {
+                GameEvent::RoundStart { round, .. } => pack(1, 0, round),
+                GameEvent::HitLanded(hit) => pack(2, hit.defender.0, hit.damage.max(0) as u32),
+                GameEvent::Blocked(hit) => pack(3, hit.defender.0, 0),
+                GameEvent::CounterHit(hit) => pack(4, hit.defender.0, hit.damage.max(0) as u32),
+                GameEvent::Ko { entity, .. } => pack(5, entity.0, 0),
+                GameEvent::StateChanged { entity, to, .. } => pack(6, entity.0, encode_state(to)),
+                GameEvent::Parry(hit) => pack(7, hit.defender.0, 0),
+                GameEvent::ArmorAbsorb(hit) => pack(8, hit.defender.0, hit.damage.max(0) as u32),
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Counter hits landed during the last tick, as bit flags:
+/// bit 0 by player 1, bit 1 by player 2
+#[no_mangle]
+pub extern "C" fn get_counter_hit_flags() -> u32 {
+    unsafe {
+        active_engine()
+            .map(|e| {
+                let p2 = e.get_player_entity(PlayerId::PLAYER_2).map(|p| p.id);
+                e.events()
+                    .iter()
+                    .filter(|hit| hit.counter)
+                    .fold(0, |flags, hit| {
+                        flags | if Some(hit.defender) == p2 { 1 } else { 2 }
+                    })
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Spark X position of a clash from the last tick
+#[no_mangle]
+pub extern "C" fn get_clash_x(index: u32) -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.clashes().get(index as usize))
+            .map(|c| c.position.x)
+            .unwrap_or(0)
+    }
+}
+
+/// Spark Y position of a clash from the last tick
+#[no_mangle]
+pub extern "C" fn get_clash_y(index: u32) -> i32 {
+    unsafe {
+        active_engine()
+            .and_then(|e| e.clashes().get(index as usize))
+            .map(|c| c.position.y)
+            .unwrap_or(0)
+    }
+}
+
+/// Allocate the replay staging buffer and return a pointer to write into
+#[no_mangle]
+pub extern "C" fn replay_buffer(len: u32) -> *mut u8 {
+    unsafe {
+        REPLAY_BUFFER = vec![0; len as usize];
+        REPLAY_BUFFER.as_mut_ptr()
+    }
+}
+
+/// Pointer to the staging buffer, for reading a finished recording
+#[no_mangle]
+pub extern "C" fn replay_buffer_ptr() -> *const u8 {
+    unsafe { REPLAY_BUFFER.as_ptr() }
+}
+
+/// Restart the local match, keeping its stage and mutators, and record it
+/// as a replay
+/// Returns 0 on success, -1 if no local match is set up
+#[no_mangle]
+pub extern "C" fn replay_record_start() -> i32 {
+    unsafe {
+        let Some(engine) = &mut ENGINE else {
+            return -1;
+        };
+        let setup = MatchSetup {
+            mutators: engine.mutators,
+            ..Default::default()
+        };
+        engine.init_match_with(setup);
+        RECORDING = Some(Replay::new(setup).with_stage(engine.config.stage));
+        0
+    }
+}
+
+/// Frames recorded so far
+#[no_mangle]
+pub extern "C" fn replay_record_length() -> u32 {
+    unsafe { RECORDING.as_ref().map(|r| r.len() as u32).unwrap_or(0) }
+}
+
+/// Stop recording and write the replay bytes to the staging buffer
+/// Returns the byte length to read from `replay_buffer_ptr` (0 if nothing
+/// was being recorded)
+#[no_mangle]
+pub extern "C" fn replay_record_stop() -> u32 {
+    unsafe {
+        match RECORDING.take() {
+            Some(recording) => {
+                REPLAY_BUFFER = recording.to_bytes();
+                REPLAY_BUFFER.len() as u32
+            }
+            None => 0,
+        }
+    }
+}
+
+/// Load the replay written to the staging buffer
+/// Returns 0 on success, or a negative error code:
+/// -1 too short, -2 bad magic, -3 unsupported version, -4 truncated,
+/// -5 recorded by another engine version
+#[no_mangle]
+pub extern "C" fn replay_load() -> i32 {
+    unsafe {
+        let result = Replay::from_bytes(&REPLAY_BUFFER);
+        REPLAY_BUFFER = Vec::new();
+        match result {
+            Ok(replay) => {
+                REPLAY = Some(ReplayPlayer::new(replay));
+                0
+            }
+            Err(ReplayError::TooShort) => -1,
+            Err(ReplayError::BadMagic) => -2,
+            Err(ReplayError::UnsupportedVersion(_)) => -3,
+            Err(ReplayError::Truncated { .. }) => -4,
+            Err(ReplayError::EngineVersion(_)) => -5,
+        }
+    }
+}
+
+/// Play the next replay frame (1 = advanced, 0 = finished or no replay)
+#[no_mangle]
+pub extern "C" fn replay_tick() -> u32 {
+    unsafe {
+        match &mut REPLAY {
+            Some(player) => player.tick() as u32,
+            None => 0,
+        }
+    }
+}
+
+/// Jump to a replay frame
+#[no_mangle]
+pub extern "C" fn replay_seek(frame: u32) {
+    unsafe {
+        if let Some(player) = &mut REPLAY {
+            player.seek(frame as usize);
+        }
+    }
+}
+
+/// Frames played in the current replay
+#[no_mangle]
+pub extern "C" fn replay_position() -> u32 {
+    unsafe { REPLAY.as_ref().map(|p| p.position() as u32).unwrap_or(0) }
+}
+
+/// Total frames in the current replay
+#[no_mangle]
+pub extern "C" fn replay_length() -> u32 {
+    unsafe {
+        REPLAY
+            .as_ref()
+            .map(|p| p.replay().len() as u32)
+            .unwrap_or(0)
+    }
+}
+
+/// Stop watching the replay; getters report the live match again
+#[no_mangle]
+pub extern "C" fn replay_unload() {
+    unsafe {
+        REPLAY = None;
+    }
+}
+
+/// Save the local match state into a slot (0 = ok, -1 = bad slot or no match)
+#[no_mangle]
+pub extern "C" fn save_state(slot: u32) -> i32 {
+    unsafe {
+        let slot = slot as usize;
+        let Some(engine) = ENGINE.as_ref() else {
+            return -1;
+        };
+        if slot >= SAVE_SLOTS {
+            return -1;
+        }
+        if SAVED.len() < SAVE_SLOTS {
+            SAVED.resize(SAVE_SLOTS, None);
+        }
+        SAVED[slot] = Some(engine.save_snapshot());
+        0
+    }
+}
+
+/// Restore the local match from a slot (0 = ok, -1 = empty or bad slot,
+/// -2 = saved by another engine version)
+#[no_mangle]
+pub extern "C" fn load_state(slot: u32) -> i32 {
+    unsafe {
+        let Some(Some(snapshot)) = SAVED.get(slot as usize) else {
+            return -1;
+        };
+        match &mut ENGINE {
+            Some(engine) => match engine.load_snapshot(snapshot) {
+                Ok(()) => {
+                    // Keep recording only if the loaded state is part of it
+                    let frame = engine.frame.0 as usize;
+                    match &mut RECORDING {
+                        Some(recording) if frame <= recording.len() => recording.truncate(frame),
+                        _ => RECORDING = None,
+                    }
+                    0
+                }
+                Err(_) => -2,
+            },
+            None => -1,
+        }
+    }
+}
+
+/// Low 32 bits of the gameplay state checksum, for desync detection
+#[no_mangle]
+pub extern "C" fn get_state_hash_low() -> u32 {
+    unsafe { active_engine().map(|e| e.state_hash() as u32).unwrap_or(0) }
+}
+
+/// High 32 bits of the gameplay state checksum
+#[no_mangle]
+pub extern "C" fn get_state_hash_high() -> u32 {
+    unsafe {
+        active_engine()
+            .map(|e| (e.state_hash() >> 32) as u32)
+            .unwrap_or(0)
+    }
+}
+
+/// Start a rollback netplay session on a fresh match
+#[no_mangle]
+pub extern "C" fn netplay_start() {
+    unsafe {
+        let mut engine = Engine::new();
+        engine.init_match();
+        SESSION = Some(RollbackSession::new(engine));
+    }
+}
+
+/// Choose how the session predicts missing inputs:
+/// 1 hold last input, 2 decay to neutral after `frames`, 3 neutral
+/// Returns 0 on success, -1 unknown policy, -4 no session
+#[no_mangle]
+pub extern "C" fn netplay_set_prediction(policy: u32, frames: u32) -> i32 {
+    let policy = match policy {
+        1 => PredictionPolicy::HoldLast,
+        2 => PredictionPolicy::DecayToNeutral {
+            frames: frames.min(u8::MAX as u32) as u8,
+        },
+        3 => PredictionPolicy::Neutral,
+        _ => return -1,
     };
+    unsafe {
+        match SESSION.take() {
+            Some(session) => {
+                SESSION = Some(session.with_prediction(policy));
+                0
+            }
+            None => -4,
+        }
+    }
+}
 
-    InputState {
-        direction,
-        light: (input & 0x10) != 0,
-        medium: (input & 0x20) != 0,
-        heavy: (input & 0x40) != 0,
-        special: (input & 0x80) != 0,
+/// Provide a player's input for a frame (local or remote)
+/// Returns 0 on success, -1 invalid player, -2 too late, -3 too early,
+/// -4 no session
+#[no_mangle]
+pub extern "C" fn netplay_add_input(player: u32, frame: u32, input: u32) -> i32 {
+    unsafe {
+        let Some(session) = &mut SESSION else {
+            return -4;
+        };
+        let input = decode_input(input);
+        match session.add_input(player as usize, frame as u64, input) {
+            Ok(()) => 0,
+            Err(RollbackError::InvalidPlayer(_)) => -1,
+            Err(RollbackError::TooLate { .. }) => -2,
+            Err(RollbackError::TooEarly { .. }) => -3,
+        }
     }
 }
 
-/// Encode state to integer
-fn encode_state(state: crate::state::StateId) -> u32 {
-    use crate::state::StateId;
-    match state {
-        StateId::Idle => 0,
-        StateId::Walk => 1,
-        StateId::WalkBack => 2,
-        StateId::Crouch => 3,
-        StateId::Jump => 4,
-        StateId::LightAttack => 5,
-        StateId::MediumAttack => 6,
-        StateId::HeavyAttack => 7,
-        StateId::SpecialMove => 8,
-        StateId::Hitstun => 9,
-        StateId::Blockstun => 10,
-        StateId::Knockdown => 11,
-        StateId::Custom(id) => 100 + id as u32,
+/// Resimulate any mispredicted frames, then advance one frame
+#[no_mangle]
+pub extern "C" fn netplay_advance() {
+    unsafe {
+        if let Some(session) = &mut SESSION {
+            session.advance();
+        }
     }
 }
 
+/// Next frame the session will simulate
+#[no_mangle]
+pub extern "C" fn netplay_frame() -> u32 {
+    unsafe {
+        SESSION
+            .as_ref()
+            .map(|s| s.current_frame() as u32)
+            .unwrap_or(0)
+    }
+}
+
+/// Rollbacks performed since the session started
+#[no_mangle]
+pub extern "C" fn netplay_rollbacks() -> u32 {
+    unsafe { SESSION.as_ref().map(|s| s.stats().rollbacks).unwrap_or(0) }
+}
+
+/// Share of predictions that turned out right, in percent (100 before any)
+#[no_mangle]
+pub extern "C" fn netplay_prediction_accuracy() -> u32 {
+    unsafe {
+        SESSION
+            .as_ref()
+            .and_then(|s| s.stats().prediction_accuracy())
+            .unwrap_or(100)
+    }
+}
+
+/// Input the session used for a player and frame, with bit 8 set when
+/// confirmed (0 if the frame is outside the window)
+#[no_mangle]
+pub extern "C" fn netplay_input_at(player: u32, frame: u32) -> u32 {
+    unsafe {
+        SESSION
+            .as_ref()
+            .and_then(|s| {
+                s.input_history(player as usize)
+                    .into_iter()
+                    .find(|r| r.frame == frame as u64)
+            })
+            .map(|r| r.input.to_bits() as u32 | (r.confirmed as u32) << 8)
+            .unwrap_or(0)
+    }
+}
+
+/// Prediction the session ran with for a player and frame, with bit 8 set
+/// when there was one (0 if the input arrived in time or the frame is
+/// outside the window); compare with `netplay_input_at` to ghost it
+#[no_mangle]
+pub extern "C" fn netplay_predicted_at(player: u32, frame: u32) -> u32 {
+    unsafe {
+        SESSION
+            .as_ref()
+            .and_then(|s| {
+                s.input_history(player as usize)
+                    .into_iter()
+                    .find(|r| r.frame == frame as u64)
+            })
+            .and_then(|r| r.predicted)
+            .map(|p| p.to_bits() as u32 | 1 << 8)
+            .unwrap_or(0)
+    }
+}
+
+/// End the netplay session; getters report the local match again
+#[no_mangle]
+pub extern "C" fn netplay_stop() {
+    unsafe {
+        SESSION = None;
+    }
+}
+
+/// Decode input from bitfield (`InputState::to_bits` layout)
+fn decode_input(input: u32) -> InputState {
+    InputState::from_bits(input as u8)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::Direction;
 
     #[test]
     fn test_input_encoding() {
         // Test neutral input
         let input = 0x00; // All bits off
-        let decoded = decode_input(input, Facing::Right);
+        let decoded = decode_input(input);
         assert_eq!(decoded.direction, Direction::Neutral);
         assert!(!decoded.light);
 
         // Test light button
         let input = 0x10; // Light button bit
-        let decoded = decode_input(input, Facing::Right);
+        let decoded = decode_input(input);
         assert!(decoded.light);
 
         // Test forward + light
         let input = 0x16; // Direction 6 (forward) + light
-        let decoded = decode_input(input, Facing::Right);
+        let decoded = decode_input(input);
         assert_eq!(decoded.direction, Direction::Forward);
         assert!(decoded.light);
     }
 
+    #[test]
+    fn test_replay_round_trip_on_preset_stage() {
+        // Walk P1 back into the narrow stage's wall so the layout matters
+        init_stage(1);
+        assert_eq!(replay_record_start(), 0);
+        for _ in 0..240 {
+            tick(0x04, 0x00);
+        }
+        let (live_x, live_p2_x) = (get_p1_x(), get_p2_x());
+        let len = replay_record_stop();
+        let bytes = unsafe { REPLAY_BUFFER.clone() };
+        assert_eq!(bytes.len(), len as usize);
+
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), replay_buffer(len), bytes.len());
+        }
+        assert_eq!(replay_load(), 0);
+        while replay_tick() == 1 {}
+        assert_eq!(
+            get_stage_half_width(),
+            StageConfig::preset(StagePreset::Narrow).half_width
+        );
+        assert_eq!((get_p1_x(), get_p2_x()), (live_x, live_p2_x));
+        replay_unload();
+    }
+
     #[test]
     fn test_state_encoding() {
         use crate::state::StateId;
         assert_eq!(encode_state(StateId::Idle), 0);
-        assert_eq!(encode_state(StateId::LightAttack), 4);
+        assert_eq!(encode_state(StateId::LightAttack), 5);
         assert_eq!(encode_state(StateId::Custom(5)), 105);
     }
 }
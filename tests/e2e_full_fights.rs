@@ -3,7 +3,7 @@
 //! These tests simulate complete, realistic fights between two characters
 //! with guaranteed damage, strategic gameplay, and varied outcomes.
 
-use bagarre::{Button, Direction, Engine, GameResult, InputState, PlayerId};
+use bagarre::{Button, Direction, Engine, EngineConfig, GameResult, InputState, PlayerId};
 
 /// Helper to create input with direction
 fn dir_input(dir: Direction) -> InputState {
@@ -772,17 +772,21 @@ fn test_full_fight_defensive_masterclass() {
 fn test_full_fight_timeout_scenario() {
     println!("\n=== FULL FIGHT: Timeout Scenario ===");
 
-    let mut engine = Engine::new();
+    // Short one-round timer so the cautious fight runs out the clock
+    let mut config = EngineConfig::default();
+    config.game.time_limit_frames = 600;
+    config.game.rounds_to_win = 1;
+    let mut engine = Engine::with_config(config);
     engine.init_match();
 
     position_players_close(&mut engine);
 
-    println!("  Both players will be cautious, potentially leading to timeout");
+    println!("  Both players will be cautious, leading to timeout");
 
-    // Simulate many frames with minimal action
-    let max_frames = 1000; // ~16 seconds at 60 FPS
+    let health = |engine: &Engine, player| engine.get_player_entity(player).unwrap().health.current;
 
-    for frame in 0..max_frames {
+    let mut frame = 0;
+    while engine.get_state().result == GameResult::InProgress {
         // Occasional pokes but mostly neutral
         if frame % 100 == 0 {
             execute_attack(&mut engine, true, Button::Light, 18);
@@ -798,52 +802,33 @@ fn test_full_fight_timeout_scenario() {
         }
 
         if frame % 200 == 0 {
-            let p1_health = engine
-                .get_player_entity(PlayerId::PLAYER_1)
-                .unwrap()
-                .health
-                .current;
-            let p2_health = engine
-                .get_player_entity(PlayerId::PLAYER_2)
-                .unwrap()
-                .health
-                .current;
             println!(
                 "  Frame {}: P1={} HP, P2={} HP",
-                frame, p1_health, p2_health
+                frame,
+                health(&engine, PlayerId::PLAYER_1),
+                health(&engine, PlayerId::PLAYER_2)
             );
         }
-
-        let state = engine.get_state();
-        if state.result != GameResult::InProgress {
-            println!("\n  Fight ended at frame {}", frame);
-            println!("  Result: {:?}", state.result);
-            return;
-        }
+        frame += 1;
     }
 
-    println!(
-        "\n  Timeout scenario: Both fighters survived {} frames",
-        max_frames
-    );
-    let p1_health = engine
-        .get_player_entity(PlayerId::PLAYER_1)
-        .unwrap()
-        .health
-        .current;
-    let p2_health = engine
-        .get_player_entity(PlayerId::PLAYER_2)
-        .unwrap()
-        .health
-        .current;
-    println!("  Final: P1={} HP, P2={} HP", p1_health, p2_health);
-
-    // Determine winner by health
-    if p1_health > p2_health {
-        println!("  ⏱️  P1 wins by timeout! ⏱️");
-    } else if p2_health > p1_health {
-        println!("  ⏱️  P2 wins by timeout! ⏱️");
-    } else {
-        println!("  ⏱️  Draw! ⏱️");
+    // Nobody was knocked out, so the clock decided the round
+    assert!(engine.frame.0 <= 600);
+    let state = engine.get_state();
+    println!("  Result: {:?}", state.result);
+    match state.result {
+        GameResult::Player1Wins => {
+            println!("  ⏱️  P1 wins by timeout! ⏱️");
+            assert!(state.p1_health > state.p2_health);
+        }
+        GameResult::Player2Wins => {
+            println!("  ⏱️  P2 wins by timeout! ⏱️");
+            assert!(state.p2_health > state.p1_health);
+        }
+        _ => {
+            println!("  ⏱️  Draw! ⏱️");
+            assert_eq!(state.result, GameResult::Draw);
+            assert_eq!(state.p1_health, state.p2_health);
+        }
     }
 }
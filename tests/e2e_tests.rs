@@ -704,3 +704,53 @@ fn test_complete_fight_simultaneous_ko() {
             || state.result == GameResult::InProgress
     );
 }
+
+#[test]
+fn test_complete_fight_sweep_knockdown_and_wakeup() {
+    let mut engine = Engine::new();
+    engine.init_match();
+
+    println!("=== E2E: Sweep -> Hard Knockdown -> Wakeup ===");
+
+    // Place the players within sweep range
+    engine.entities[0].as_mut().unwrap().physics.position.x = 0;
+    engine.entities[1].as_mut().unwrap().physics.position.x = 20000;
+
+    // Down + Heavy performs the crouching sweep
+    let mut sweep_input = input_with_direction(Direction::Down);
+    sweep_input.heavy = true;
+    engine.tick(sweep_input, InputState::neutral());
+    assert_eq!(engine.get_state().p1_state, "Sweep");
+
+    for _ in 0..10 {
+        engine.tick(InputState::neutral(), InputState::neutral());
+    }
+
+    let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+    assert_eq!(engine.get_state().p2_state, "Down");
+    assert!(p2.is_knocked_down());
+    let health_after_sweep = p2.health.current;
+    assert!(health_after_sweep < 1000);
+
+    // Wait for the sweep to recover, then swing at the downed opponent
+    for _ in 0..25 {
+        engine.tick(InputState::neutral(), InputState::neutral());
+    }
+    engine.tick(input_with_button(Button::Medium), InputState::neutral());
+    for _ in 0..12 {
+        engine.tick(InputState::neutral(), InputState::neutral());
+    }
+
+    // Medium is not OTG-capable, so the downed opponent takes no damage
+    let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+    assert!(p2.is_knocked_down());
+    assert_eq!(p2.health.current, health_after_sweep);
+
+    // Eventually P2 wakes up and can act again
+    for _ in 0..30 {
+        engine.tick(InputState::neutral(), InputState::neutral());
+    }
+    let p2 = engine.get_player_entity(PlayerId::PLAYER_2).unwrap();
+    assert!(!p2.is_knocked_down());
+    assert_eq!(engine.get_state().p2_state, "Idle");
+}
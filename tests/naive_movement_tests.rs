@@ -59,7 +59,7 @@ fn test_player1_movement_and_jump() {
         engine.tick(InputState::neutral(), InputState::neutral());
     }
 
-    // Test another direction - move in a different direction
+    // Test another direction - down-forward crouches
     println!("Testing another direction (down-forward)...");
     for _ in 0..30 {
         engine.tick(
@@ -77,10 +77,10 @@ fn test_player1_movement_and_jump() {
         "P1 after diagonal movement: ({}, {})",
         p1_after_diagonal.x, p1_after_diagonal.y
     );
-    // Diagonal forward should move more forward
+    // Down-forward crouches instead of walking
     assert!(
-        p1_after_diagonal.x > p1_after_forward.x,
-        "P1 should have moved more forward with diagonal input"
+        p1_after_diagonal.x == p1_after_forward.x,
+        "P1 should crouch in place on down-forward"
     );
 
     // Return to neutral
@@ -172,7 +172,7 @@ fn test_player2_movement_and_jump() {
         engine.tick(InputState::neutral(), InputState::neutral());
     }
 
-    // Test another direction - move in a different direction
+    // Test another direction - down-forward crouches
     println!("Testing another direction (down-forward)...");
     for _ in 0..30 {
         engine.tick(
@@ -190,10 +190,10 @@ fn test_player2_movement_and_jump() {
         "P2 after diagonal movement: ({}, {})",
         p2_after_diagonal.x, p2_after_diagonal.y
     );
-    // Diagonal forward should move more forward (toward P1, so x decreases)
+    // Down-forward crouches instead of walking
     assert!(
-        p2_after_diagonal.x < p2_after_forward.x,
-        "P2 should have moved more forward with diagonal input"
+        p2_after_diagonal.x == p2_after_forward.x,
+        "P2 should crouch in place on down-forward"
     );
 
     // Return to neutral